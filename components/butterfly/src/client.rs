@@ -8,22 +8,31 @@ use zmq;
 
 use crate::{error::{Error,
                     Result},
+            identity::MemberIdentity,
             message,
             rumor::{departure::Departure,
+                    purge::Purge,
                     service_config::ServiceConfig,
                     service_file::ServiceFile,
                     Rumor},
             ZMQ_CONTEXT};
 
-/// Holds a ZMQ Push socket, and an optional ring encryption key.
+/// Holds a ZMQ Push socket, an optional ring encryption key, and an
+/// optional member identity to sign the traffic and the rumors it
+/// issues with.
 pub struct Client {
     socket:   zmq::Socket,
     ring_key: Option<SymKey>,
+    identity: Option<MemberIdentity>,
 }
 
 impl Client {
-    /// Connect this client to the address, and optionally encrypt the traffic.
-    pub fn new(addr: &str, ring_key: Option<SymKey>) -> Result<Client> {
+    /// Connect this client to the address, and optionally encrypt and
+    /// sign the traffic.
+    pub fn new(addr: &str,
+               ring_key: Option<SymKey>,
+               identity: Option<MemberIdentity>)
+               -> Result<Client> {
         let socket = (**ZMQ_CONTEXT).as_mut()
                                     .socket(zmq::PUSH)
                                     .expect("Failure to create the ZMQ push socket");
@@ -39,15 +48,29 @@ impl Client {
               .expect("Failure to set the ZMQ send timeout");
         let to_addr = format!("tcp://{}", addr);
         socket.connect(&to_addr).map_err(Error::ZmqConnectError)?;
-        Ok(Client { socket, ring_key })
+        Ok(Client { socket,
+                    ring_key,
+                    identity })
     }
 
     /// Create a departure notification and send it to the server.
     pub fn send_departure(&mut self, member_id: &str) -> Result<()> {
-        let departure = Departure::new(member_id);
+        let mut departure = Departure::new(member_id);
+        if let Some(ref identity) = self.identity {
+            departure.sign(identity)?;
+        }
         self.send(&departure)
     }
 
+    /// Create a purge of the service group's rumors and send it to the server.
+    pub fn send_purge(&mut self, service_group: &str) -> Result<()> {
+        let mut purge = Purge::new(service_group);
+        if let Some(ref identity) = self.identity {
+            purge.sign(identity)?;
+        }
+        self.send(&purge)
+    }
+
     /// Create a service configuration and send it to the server.
     pub fn send_service_config(&mut self,
                                service_group: ServiceGroup,
@@ -58,6 +81,9 @@ impl Client {
         let mut sc = ServiceConfig::new("butterflyclient", service_group, config.to_vec());
         sc.incarnation = incarnation;
         sc.encrypted = encrypted;
+        if let Some(ref identity) = self.identity {
+            sc.sign(identity)?;
+        }
         self.send(&sc)
     }
 
@@ -74,6 +100,9 @@ impl Client {
         let mut sf = ServiceFile::new("butterflyclient", service_group, filename, body.to_vec());
         sf.incarnation = incarnation;
         sf.encrypted = encrypted;
+        if let Some(ref identity) = self.identity {
+            sf.sign(identity)?;
+        }
         self.send(&sf)
     }
 
@@ -82,7 +111,8 @@ impl Client {
         where T: Rumor
     {
         let bytes = rumor.write_to_bytes()?;
-        let wire_msg = message::generate_wire(bytes, self.ring_key.as_ref())?;
+        let wire_msg =
+            message::generate_wire(bytes, self.ring_key.as_ref(), self.identity.as_ref())?;
         self.socket.send(&wire_msg, 0).map_err(Error::ZmqSendError)
     }
 }
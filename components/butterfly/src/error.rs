@@ -17,6 +17,7 @@ pub type Result<T> = result::Result<T, Error>;
 pub enum Error {
     BadDataPath(PathBuf, io::Error),
     BadDatFile(PathBuf, io::Error),
+    BadMemberSignature(String),
     CannotBind(io::Error),
     DatFileIO(PathBuf, io::Error),
     DecodeError(prost::DecodeError),
@@ -25,7 +26,10 @@ pub enum Error {
     IncarnationIO(PathBuf, io::Error),
     IncarnationParse(PathBuf, num::ParseIntError),
     InvalidRumorShareLimit,
+    InvalidTiming(String),
+    MemberKeyDir(PathBuf, io::Error),
     NonExistentRumor(String, String),
+    PrimaryRingKey(String),
     ProtocolMismatch(&'static str),
     ServiceConfigDecode(String, toml::de::Error),
     ServiceConfigNotUtf8(String, str::Utf8Error),
@@ -34,6 +38,8 @@ pub enum Error {
     SocketSetWriteTimeout(io::Error),
     Timeout(String),
     UnknownMember(String),
+    UnknownRingKey(String),
+    UntrustedMember(String),
     ZmqConnectError(zmq::Error),
     ZmqSendError(zmq::Error),
     UnknownIOError(io::Error),
@@ -52,6 +58,10 @@ impl fmt::Display for Error {
                         path.display(),
                         err)
             }
+            Error::BadMemberSignature(ref member_id) => {
+                format!("Message is not signed by a trusted key of member {}",
+                        member_id)
+            }
             Error::CannotBind(ref err) => format!("Cannot bind to port: {:?}", err),
             Error::DatFileIO(ref path, ref err) => {
                 format!("Error reading or writing to DatFile, {}, {}",
@@ -75,10 +85,19 @@ impl fmt::Display for Error {
             Error::InvalidRumorShareLimit => {
                 "Rumor share limit should be a positive integer".to_string()
             }
+            Error::InvalidTiming(ref msg) => format!("Invalid gossip timing: {}", msg),
+            Error::MemberKeyDir(ref path, ref err) => {
+                format!("Unable to read member keys from {}, {}",
+                        path.display(),
+                        err)
+            }
             Error::NonExistentRumor(ref member_id, ref rumor_id) => {
                 format!("Non existent rumor asked to be written to bytes: {} {}",
                         member_id, rumor_id)
             }
+            Error::PrimaryRingKey(ref key) => {
+                format!("Cannot retire {}, it is the primary ring key", key)
+            }
             Error::ProtocolMismatch(ref field) => {
                 format!("Received an unsupported or bad protocol message. Missing field: {}",
                         field)
@@ -98,6 +117,10 @@ impl fmt::Display for Error {
             }
             Error::Timeout(ref msg) => format!("Timed out {}", msg),
             Error::UnknownMember(ref member_id) => format!("Unknown member ID: {}", member_id),
+            Error::UnknownRingKey(ref key) => format!("Unknown ring key: {}", key),
+            Error::UntrustedMember(ref member_id) => {
+                format!("No trusted member key for member {}", member_id)
+            }
             Error::ZmqConnectError(ref err) => format!("Cannot connect ZMQ socket: {}", err),
             Error::ZmqSendError(ref err) => {
                 format!("Cannot send message through ZMQ socket: {}", err)
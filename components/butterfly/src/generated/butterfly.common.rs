@@ -7,4 +7,10 @@ pub struct Wire {
     pub nonce: ::std::option::Option<std::vec::Vec<u8>>,
     #[prost(bytes, optional, tag="3")]
     pub payload: ::std::option::Option<std::vec::Vec<u8>>,
+    #[prost(string, optional, tag="4")]
+    pub ring_key: ::std::option::Option<std::string::String>,
+    #[prost(string, optional, tag="5")]
+    pub member_id: ::std::option::Option<std::string::String>,
+    #[prost(bytes, optional, tag="6")]
+    pub signature: ::std::option::Option<std::vec::Vec<u8>>,
 }
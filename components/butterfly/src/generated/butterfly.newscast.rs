@@ -13,6 +13,16 @@ pub struct Election {
     pub status: ::std::option::Option<i32>,
     #[prost(string, repeated, tag="6")]
     pub votes: ::std::vec::Vec<std::string::String>,
+    /// Whether the candidate is in the zone its service group prefers a leader in
+    #[prost(bool, optional, tag="7", default="false")]
+    pub in_preferred_zone: ::std::option::Option<bool>,
+    /// How many zones the alive members must span for the election to have quorum
+    #[prost(uint32, optional, tag="8")]
+    pub min_zones: ::std::option::Option<u32>,
+    /// Signature of the candidacy by the candidate, when members have identities; once the election
+    /// is finished, it also covers the status
+    #[prost(bytes, optional, tag="9")]
+    pub signature: ::std::option::Option<std::vec::Vec<u8>>,
 }
 pub mod election {
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
@@ -41,6 +51,15 @@ pub struct Service {
     pub cfg: ::std::option::Option<std::vec::Vec<u8>>,
     #[prost(message, optional, tag="12")]
     pub sys: ::std::option::Option<SysInfo>,
+    #[prost(bool, optional, tag="13")]
+    pub health_check_passed: ::std::option::Option<bool>,
+    #[prost(bool, optional, tag="14")]
+    pub ready: ::std::option::Option<bool>,
+    #[prost(string, repeated, tag="15")]
+    pub blocked_releases: ::std::vec::Vec<std::string::String>,
+    /// Signature of the rest of the rumor by its member, when members have identities
+    #[prost(bytes, optional, tag="16")]
+    pub signature: ::std::option::Option<std::vec::Vec<u8>>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -53,6 +72,12 @@ pub struct ServiceConfig {
     pub encrypted: ::std::option::Option<bool>,
     #[prost(bytes, optional, tag="4")]
     pub config: ::std::option::Option<std::vec::Vec<u8>>,
+    /// The member that issued the configuration, and its signature of the rest of the rumor, when
+    /// members have identities
+    #[prost(string, optional, tag="5")]
+    pub signed_by: ::std::option::Option<std::string::String>,
+    #[prost(bytes, optional, tag="6")]
+    pub signature: ::std::option::Option<std::vec::Vec<u8>>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -67,6 +92,12 @@ pub struct ServiceFile {
     pub filename: ::std::option::Option<std::string::String>,
     #[prost(bytes, optional, tag="5")]
     pub body: ::std::option::Option<std::vec::Vec<u8>>,
+    /// The member that issued the file, and its signature of the rest of the rumor, when members
+    /// have identities
+    #[prost(string, optional, tag="6")]
+    pub signed_by: ::std::option::Option<std::string::String>,
+    #[prost(bytes, optional, tag="7")]
+    pub signature: ::std::option::Option<std::vec::Vec<u8>>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -93,6 +124,52 @@ pub struct SysInfo {
 pub struct Departure {
     #[prost(string, optional, tag="1")]
     pub member_id: ::std::option::Option<std::string::String>,
+    /// The member that issued the departure, and its signature of the rest of the rumor, when
+    /// members have identities
+    #[prost(string, optional, tag="2")]
+    pub signed_by: ::std::option::Option<std::string::String>,
+    #[prost(bytes, optional, tag="3")]
+    pub signature: ::std::option::Option<std::vec::Vec<u8>>,
+}
+/// Drops every rumor of a service group, ring-wide
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct Purge {
+    #[prost(string, optional, tag="1")]
+    pub service_group: ::std::option::Option<std::string::String>,
+    /// When the purge was requested, in seconds since the Unix epoch
+    #[prost(uint64, optional, tag="2")]
+    pub purged_at: ::std::option::Option<u64>,
+    /// The member that issued the purge, and its signature of the rest of the rumor, when members
+    /// have identities
+    #[prost(string, optional, tag="3")]
+    pub signed_by: ::std::option::Option<std::string::String>,
+    #[prost(bytes, optional, tag="4")]
+    pub signature: ::std::option::Option<std::vec::Vec<u8>>,
+}
+/// The keys and versions of the rumors a member holds, for anti-entropy
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct Digest {
+    #[prost(message, repeated, tag="1")]
+    pub entries: ::std::vec::Vec<digest::Entry>,
+    /// Whether the entries cover every rumor the sender holds, rather than only the ones it wants
+    #[prost(bool, optional, tag="2")]
+    pub complete: ::std::option::Option<bool>,
+}
+pub mod digest {
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    #[derive(Serialize, Deserialize)]
+    pub struct Entry {
+        #[prost(enumeration="super::rumor::Type", optional, tag="1")]
+        pub r#type: ::std::option::Option<i32>,
+        #[prost(string, optional, tag="2")]
+        pub key: ::std::option::Option<std::string::String>,
+        #[prost(string, optional, tag="3")]
+        pub id: ::std::option::Option<std::string::String>,
+        #[prost(uint64, optional, tag="4")]
+        pub version: ::std::option::Option<u64>,
+    }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -103,7 +180,7 @@ pub struct Rumor {
     pub tag: ::std::vec::Vec<std::string::String>,
     #[prost(string, optional, tag="3")]
     pub from_id: ::std::option::Option<std::string::String>,
-    #[prost(oneof="rumor::Payload", tags="4, 5, 6, 7, 8, 9")]
+    #[prost(oneof="rumor::Payload", tags="4, 5, 6, 7, 8, 9, 10, 11")]
     pub payload: ::std::option::Option<rumor::Payload>,
 }
 pub mod rumor {
@@ -120,6 +197,8 @@ pub mod rumor {
         Fake2 = 7,
         ElectionUpdate = 8,
         Departure = 9,
+        Purge = 10,
+        Digest = 11,
     }
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     #[allow(clippy::large_enum_variant)]
@@ -137,5 +216,9 @@ pub mod rumor {
         Election(super::Election),
         #[prost(message, tag="9")]
         Departure(super::Departure),
+        #[prost(message, tag="10")]
+        Purge(super::Purge),
+        #[prost(message, tag="11")]
+        Digest(super::Digest),
     }
 }
@@ -16,6 +16,13 @@ pub struct Member {
     pub persistent: ::std::option::Option<bool>,
     #[prost(bool, optional, tag="7", default="false")]
     pub departed: ::std::option::Option<bool>,
+    /// Operator-defined metadata, e.g. zone=us-east-1a
+    #[prost(map="string, string", tag="8")]
+    pub tags: ::std::collections::HashMap<std::string::String, std::string::String>,
+    /// Signature of the member's own fields by the member, when members have identities; the
+    /// address and departed flag are filled in by others, and are not covered
+    #[prost(bytes, optional, tag="9")]
+    pub signature: ::std::option::Option<std::vec::Vec<u8>>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -48,6 +55,10 @@ pub struct Membership {
     pub member: ::std::option::Option<Member>,
     #[prost(enumeration="membership::Health", optional, tag="2")]
     pub health: ::std::option::Option<i32>,
+    /// The members that have suspected a suspect member, each by failing to
+    /// get an ack from it themselves
+    #[prost(string, repeated, tag="3")]
+    pub suspected_by: ::std::vec::Vec<std::string::String>,
 }
 pub mod membership {
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
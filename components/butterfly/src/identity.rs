@@ -0,0 +1,166 @@
+//! Per-member identity for gossip traffic.
+//!
+//! A ring key only proves that a message came from *some* holder of the
+//! ring key. When members have identities, every message is also signed
+//! with the key pair of the member that sent it, and only members whose
+//! public keys are trusted are admitted to the ring.
+//!
+//! Member keys are signing key pairs, named after the member ID they
+//! identify, and live in a single directory: this member's key pair,
+//! plus the public keys of every member it admits.
+
+use crate::error::{Error,
+                   Result};
+use habitat_core::crypto::{keys::PairType,
+                           SigKeyPair,
+                           PUBLIC_KEY_SUFFIX};
+use std::{collections::HashMap,
+          ffi::OsStr,
+          fmt,
+          fs,
+          path::Path};
+
+#[derive(Clone)]
+pub struct MemberIdentity {
+    /// This member's key pair; its name is this member's ID
+    key: SigKeyPair,
+    /// The public keys of admitted members, by member ID
+    trusted: HashMap<String, Vec<SigKeyPair>>,
+}
+
+impl fmt::Debug for MemberIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut trusted = self.trusted.keys().collect::<Vec<_>>();
+        trusted.sort();
+        f.debug_struct("MemberIdentity")
+         .field("key", &self.key.name_with_rev())
+         .field("trusted", &trusted)
+         .finish()
+    }
+}
+
+impl MemberIdentity {
+    /// Identify as the member `key` is named after, admitting members
+    /// with any of the `trusted` keys. This member is always admitted.
+    pub fn new(key: SigKeyPair, trusted: Vec<SigKeyPair>) -> Self {
+        let mut identity = MemberIdentity { key:     key.clone(),
+                                            trusted: HashMap::new(), };
+        for pair in trusted.into_iter().chain(Some(key)) {
+            identity.trust(pair);
+        }
+        identity
+    }
+
+    /// Load the identity of `member_id` from `path`, generating its key
+    /// pair there if it doesn't exist yet, and trust every member
+    /// public key in `path`.
+    pub fn load(member_id: &str, path: &Path) -> Result<Self> {
+        let key = match SigKeyPair::get_latest_pair_for(member_id, path, Some(&PairType::Secret)) {
+            Ok(key) => key,
+            Err(_) => {
+                let key = SigKeyPair::generate_pair_for_member(member_id)?;
+                key.to_pair_files(path)?;
+                key
+            }
+        };
+        let mut trusted = Vec::new();
+        let entries = fs::read_dir(path).map_err(|e| Error::MemberKeyDir(path.to_path_buf(), e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| Error::MemberKeyDir(path.to_path_buf(), e))?;
+            let key_path = entry.path();
+            if key_path.extension() != Some(PUBLIC_KEY_SUFFIX.as_ref()) {
+                continue;
+            }
+            let name_with_rev = match key_path.file_stem().and_then(OsStr::to_str) {
+                Some(name_with_rev) => name_with_rev.to_string(),
+                None => continue,
+            };
+            // Box keys share the public key suffix, and are skipped here.
+            match SigKeyPair::get_pair_for(&name_with_rev, path) {
+                Ok(pair) if pair.public().is_ok() => trusted.push(pair),
+                _ => debug!("Not a member key: {}", key_path.display()),
+            }
+        }
+        Ok(Self::new(key, trusted))
+    }
+
+    fn trust(&mut self, pair: SigKeyPair) {
+        let keys = self.trusted.entry(pair.name.clone()).or_default();
+        if keys.iter()
+               .all(|k| k.name_with_rev() != pair.name_with_rev())
+        {
+            keys.push(pair);
+        }
+    }
+
+    pub fn member_id(&self) -> &str { &self.key.name }
+
+    /// The name with revision of this member's key pair.
+    pub fn name_with_rev(&self) -> String { self.key.name_with_rev() }
+
+    /// Sign `data` as this member.
+    pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>> { Ok(self.key.sign_detached(data)?) }
+
+    /// Check that `signature` is a signature of `data` by a trusted key
+    /// of `member_id`.
+    pub fn verify(&self, member_id: &str, data: &[u8], signature: &[u8]) -> Result<()> {
+        let keys = self.trusted
+                       .get(member_id)
+                       .ok_or_else(|| Error::UntrustedMember(member_id.to_string()))?;
+        if keys.iter()
+               .any(|key| key.verify_detached(data, signature).is_ok())
+        {
+            Ok(())
+        } else {
+            Err(Error::BadMemberSignature(member_id.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mktemp::Temp;
+
+    #[test]
+    fn trusted_members_are_verified() {
+        let wolverine = SigKeyPair::generate_pair_for_member("wolverine").unwrap();
+        let cyclops = SigKeyPair::generate_pair_for_member("cyclops").unwrap();
+        let magneto = SigKeyPair::generate_pair_for_member("magneto").unwrap();
+        let ours = MemberIdentity::new(wolverine, vec![cyclops.clone()]);
+        let theirs = MemberIdentity::new(cyclops, Vec::new());
+        let enemy = MemberIdentity::new(magneto, Vec::new());
+
+        let signature = theirs.sign(b"hello").unwrap();
+        assert!(ours.verify("cyclops", b"hello", &signature).is_ok());
+        assert!(ours.verify("cyclops", b"goodbye", &signature).is_err());
+        assert!(ours.verify("wolverine", b"hello", &signature).is_err());
+
+        let signature = enemy.sign(b"hello").unwrap();
+        assert!(ours.verify("magneto", b"hello", &signature).is_err());
+        assert!(ours.verify("cyclops", b"hello", &signature).is_err());
+
+        let signature = ours.sign(b"hello").unwrap();
+        assert!(ours.verify("wolverine", b"hello", &signature).is_ok());
+    }
+
+    #[test]
+    fn load_generates_a_key_and_trusts_the_keys_beside_it() {
+        let dir = Temp::new_dir().expect("Could not create temp dir");
+        let cyclops = SigKeyPair::generate_pair_for_member("cyclops").unwrap();
+        cyclops.to_pair_files(dir.as_ref()).unwrap();
+
+        let identity = MemberIdentity::load("wolverine", dir.as_ref()).unwrap();
+        assert_eq!(identity.member_id(), "wolverine");
+        assert!(dir.as_ref()
+                   .join(format!("{}.sig.key", identity.name_with_rev()))
+                   .exists());
+
+        let theirs = MemberIdentity::new(cyclops, Vec::new());
+        let signature = theirs.sign(b"hello").unwrap();
+        assert!(identity.verify("cyclops", b"hello", &signature).is_ok());
+
+        let reloaded = MemberIdentity::load("wolverine", dir.as_ref()).unwrap();
+        assert_eq!(reloaded.name_with_rev(), identity.name_with_rev());
+    }
+}
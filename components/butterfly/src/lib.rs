@@ -25,6 +25,9 @@
 //! 1. A 'pull' thread, which takes messages from any push source and applies them locally.
 //!
 //! Start exploring the code base by following the thread of execution in the `server` module.
+//!
+//! The `server::sim` module runs the same protocols without threads or sockets, on a simulated
+//! clock and an in-memory network, so that large rings can be tested deterministically.
 
 #[macro_use]
 extern crate lazy_static;
@@ -39,6 +42,7 @@ extern crate serde_derive;
 
 pub mod client;
 pub mod error;
+pub mod identity;
 pub mod member;
 pub mod message;
 pub mod protocol;
@@ -3,21 +3,29 @@
 pub use crate::protocol::swim::Health;
 use crate::{error::{Error,
                     Result},
+            identity::MemberIdentity,
             protocol::{self,
                        newscast,
                        swim as proto,
                        FromProto},
             rumor::{RumorKey,
                     RumorPayload,
-                    RumorType}};
+                    RumorType},
+            server::timing::Clock};
+use bytes::BytesMut;
 use habitat_common::sync::{Lock,
                            ReadGuard,
                            WriteGuard};
 use habitat_core::util::ToI64;
-use prometheus::IntGaugeVec;
-use rand::{seq::{IteratorRandom,
+use prometheus::{IntCounter,
+                 IntCounterVec,
+                 IntGaugeVec};
+use prost::Message as ProstMessage;
+use rand::{rngs::StdRng,
+           seq::{IteratorRandom,
                  SliceRandom},
-           thread_rng};
+           thread_rng,
+           SeedableRng};
 use serde::{de,
             ser::{SerializeMap,
                   SerializeStruct},
@@ -26,27 +34,40 @@ use serde::{de,
             Serialize,
             Serializer};
 use std::{collections::{hash_map,
-                        HashMap},
+                        BTreeMap,
+                        BTreeSet,
+                        HashMap,
+                        HashSet},
           fmt,
           net::SocketAddr,
           num::ParseIntError,
           ops::Add,
           result,
           str::FromStr,
-          sync::atomic::{AtomicUsize,
-                         Ordering}};
+          sync::{atomic::{AtomicUsize,
+                          Ordering},
+                 Mutex}};
 use time::{Duration,
            SteadyTime};
 use uuid::Uuid;
 
-/// How many nodes do we target when we need to run PingReq.
-const PINGREQ_TARGETS: usize = 5;
+/// The member tag naming the zone a Supervisor is in, for zone-aware
+/// elections.
+pub const ZONE_TAG: &str = "zone";
 
 lazy_static! {
     static ref PEER_HEALTH_COUNT: IntGaugeVec =
         register_int_gauge_vec!("hab_butterfly_peer_health_total",
                                 "Number of butterfly peers",
                                 &["health"]).unwrap();
+    static ref SUSPICIONS: IntCounter =
+        register_int_counter!("hab_butterfly_swim_suspicions_total",
+                              "Number of times an alive member came to be suspected").unwrap();
+    static ref REFUTED_SUSPICIONS: IntCounterVec =
+        register_int_counter_vec!("hab_butterfly_swim_refuted_suspicions_total",
+                                  "Number of suspect or confirmed members that turned out to be \
+                                   alive; the false positives of failure detection",
+                                  &["health"]).unwrap();
 }
 
 /// Wraps a `u64` to represent the "incarnation number" of a
@@ -136,6 +157,10 @@ pub struct Member {
     pub gossip_port: u16,
     pub persistent:  bool,
     pub departed:    bool,
+    pub tags:        BTreeMap<String, String>,
+    /// Signed by the member when members have identities
+    #[serde(skip)]
+    pub signature:   Option<Vec<u8>>,
 }
 
 impl Member {
@@ -154,6 +179,41 @@ impl Member {
             }
         }
     }
+
+    /// Sign this record as its member.
+    pub fn sign(&mut self, identity: &MemberIdentity) -> Result<()> {
+        self.signature = Some(identity.sign(&self.signed_bytes()?)?);
+        Ok(())
+    }
+
+    /// Check that this record was signed by its member.
+    pub fn verify(&self, identity: &MemberIdentity) -> Result<()> {
+        let signature = self.signature
+                            .as_ref()
+                            .ok_or(Error::ProtocolMismatch("signature"))?;
+        identity.verify(&self.id, &self.signed_bytes()?, signature)
+    }
+
+    /// The fields only the member itself sets, as they are encoded on the wire. The address is
+    /// filled in from the network by whoever hears from the member, and the departed flag by
+    /// whoever departs it, so neither is covered. Tags are encoded separately, in order, as the
+    /// protobuf map encoding has none.
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let mut unsigned = proto::Member::from(self.clone());
+        unsigned.address = None;
+        unsigned.departed = None;
+        unsigned.tags.clear();
+        unsigned.signature = None;
+        let mut buf = BytesMut::with_capacity(unsigned.encoded_len());
+        unsigned.encode(&mut buf)?;
+        for (key, value) in self.tags.iter() {
+            for field in &[key, value] {
+                buf.extend_from_slice(&(field.len() as u64).to_be_bytes());
+                buf.extend_from_slice(field.as_bytes());
+            }
+        }
+        Ok(buf.to_vec())
+    }
 }
 
 impl Default for Member {
@@ -169,7 +229,9 @@ impl Default for Member {
                  swim_port:   0,
                  gossip_port: 0,
                  persistent:  false,
-                 departed:    false, }
+                 departed:    false,
+                 tags:        BTreeMap::new(),
+                 signature:   None, }
     }
 }
 
@@ -193,20 +255,26 @@ impl From<Member> for proto::Member {
                         swim_port:   Some(value.swim_port.into()),
                         gossip_port: Some(value.gossip_port.into()),
                         persistent:  Some(value.persistent),
-                        departed:    Some(value.departed), }
+                        departed:    Some(value.departed),
+                        tags:        value.tags.into_iter().collect(),
+                        signature:   value.signature, }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Membership {
-    pub member: Member,
-    pub health: Health,
+    pub member:       Member,
+    pub health:       Health,
+    /// The members that have each failed to get an ack from a suspect member. The more of them
+    /// there are, the sooner the member is confirmed.
+    #[serde(default)]
+    pub suspected_by: BTreeSet<String>,
 }
 
 impl fmt::Display for Membership {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f,
-               "Member i/{} m/{} ad/{} sp/{} gp/{} p/{} d/{} h/{:?}",
+               "Member i/{} m/{} ad/{} sp/{} gp/{} p/{} d/{} t/{:?} h/{:?}",
                self.member.incarnation,
                self.member.id,
                self.member.address,
@@ -214,11 +282,18 @@ impl fmt::Display for Membership {
                self.member.gossip_port,
                self.member.persistent,
                self.member.departed,
+               self.member.tags,
                self.health)
     }
 }
 
 impl Membership {
+    pub fn new(member: Member, health: Health) -> Self {
+        Membership { member,
+                     health,
+                     suspected_by: BTreeSet::new() }
+    }
+
     /// See MemberList::insert
     fn newer_or_less_healthy_than(&self,
                                   other_incarnation: Incarnation,
@@ -235,8 +310,9 @@ impl protocol::Message<proto::Membership> for Membership {
 
 impl From<Membership> for proto::Membership {
     fn from(value: Membership) -> Self {
-        proto::Membership { member: Some(value.member.into()),
-                            health: Some(value.health as i32), }
+        proto::Membership { member:       Some(value.member.into()),
+                            health:       Some(value.health as i32),
+                            suspected_by: value.suspected_by.into_iter().collect(), }
     }
 }
 
@@ -304,18 +380,21 @@ impl FromProto<proto::Member> for Member {
                                       .and_then(as_port)
                                       .ok_or(Error::ProtocolMismatch("gossip-port"))?,
                     persistent:  proto.persistent.unwrap_or(false),
-                    departed:    proto.departed.unwrap_or(false), })
+                    departed:    proto.departed.unwrap_or(false),
+                    tags:        proto.tags.into_iter().collect(),
+                    signature:   proto.signature, })
     }
 }
 
 impl FromProto<proto::Membership> for Membership {
     fn from_proto(proto: proto::Membership) -> Result<Self> {
-        Ok(Membership { member: proto.member
-                                     .ok_or(Error::ProtocolMismatch("member"))
-                                     .and_then(Member::from_proto)?,
-                        health: proto.health
-                                     .and_then(Health::from_i32)
-                                     .unwrap_or(Health::Alive), })
+        Ok(Membership { member:       proto.member
+                                           .ok_or(Error::ProtocolMismatch("member"))
+                                           .and_then(Member::from_proto)?,
+                        health:       proto.health
+                                           .and_then(Health::from_i32)
+                                           .unwrap_or(Health::Alive),
+                        suspected_by: proto.suspected_by.into_iter().collect(), })
     }
 }
 
@@ -333,17 +412,43 @@ mod member_list {
     pub struct Entry {
         pub member:            super::Member,
         pub health:            super::Health,
+        pub suspected_by:      super::BTreeSet<String>,
         pub health_updated_at: super::SteadyTime,
     }
 }
 
+impl<'a> From<&'a member_list::Entry> for Membership {
+    fn from(entry: &'a member_list::Entry) -> Self {
+        Membership { member:       entry.member.clone(),
+                     health:       entry.health,
+                     suspected_by: entry.suspected_by.clone(), }
+    }
+}
+
+/// Count the suspicions that start, and the ones that turn out to be wrong, as a member's health
+/// goes from `from` to `to`.
+fn count_suspicion_outcome(from: Health, to: Health) {
+    match (from, to) {
+        (Health::Alive, Health::Suspect) => SUSPICIONS.inc(),
+        (Health::Suspect, Health::Alive) | (Health::Confirmed, Health::Alive) => {
+            REFUTED_SUSPICIONS.with_label_values(&[&from.to_string()])
+                              .inc()
+        }
+        _ => {}
+    }
+}
+
 /// Tracks lists of members, their health, and how long they have been
 /// suspect or confirmed.
 #[derive(Debug)]
 pub struct MemberList {
-    entries:         Lock<HashMap<UuidSimple, member_list::Entry>>,
+    entries: Lock<HashMap<UuidSimple, member_list::Entry>>,
     initial_members: Lock<Vec<Member>>,
-    update_counter:  AtomicUsize,
+    update_counter: AtomicUsize,
+    clock: Clock,
+    /// Replaces the thread's RNG in simulations, so that a run can be
+    /// repeated from its seed.
+    rng: Option<Mutex<StdRng>>,
 }
 
 impl Serialize for MemberList {
@@ -380,7 +485,17 @@ impl MemberList {
     pub fn new() -> MemberList {
         MemberList { entries:         Lock::new(HashMap::new()),
                      initial_members: Lock::new(Vec::new()),
-                     update_counter:  AtomicUsize::new(0), }
+                     update_counter:  AtomicUsize::new(0),
+                     clock:           Clock::default(),
+                     rng:             None, }
+    }
+
+    /// Creates a new, empty, MemberList that takes its time from `clock` and
+    /// makes its random choices from `seed`.
+    pub fn simulated(clock: Clock, seed: u64) -> MemberList {
+        MemberList { clock,
+                     rng: Some(Mutex::new(StdRng::seed_from_u64(seed))),
+                     ..MemberList::new() }
     }
 
     /// # Locking (see locking.md)
@@ -497,22 +612,36 @@ impl MemberList {
     /// * `MemberList::entries` (write)
     // TODO (CM): why don't we just insert a membership record here?
     pub fn insert_mlw(&self, incoming_member: Member, incoming_health: Health) -> bool {
-        self.insert_membership_mlw(Membership { member: incoming_member,
-                                                health: incoming_health, })
+        self.insert_membership_mlw(Membership::new(incoming_member, incoming_health))
     }
 
+    /// Inserts a membership record, by the rules of `insert_mlw`. A rumor
+    /// that a member we already suspect is suspect at the same incarnation
+    /// is also accepted, if it was suspected by members we didn't know of;
+    /// each is another independent confirmation of the suspicion.
+    ///
     /// # Locking (see locking.md)
     /// * `MemberList::entries` (write)
-    fn insert_membership_mlw(&self, incoming: Membership) -> bool {
+    pub fn insert_membership_mlw(&self, incoming: Membership) -> bool {
         // Is this clone necessary, or can a key be a reference to a field contained in the value?
         // Maybe the members we store should not contain the ID to reduce the duplication?
         let modified = match self.write_entries().entry(incoming.member.id.clone()) {
             hash_map::Entry::Occupied(mut entry) => {
                 let val = entry.get_mut();
                 if incoming.newer_or_less_healthy_than(val.member.incarnation, val.health) {
+                    count_suspicion_outcome(val.health, incoming.health);
                     *val = member_list::Entry { member:            incoming.member,
                                                 health:            incoming.health,
-                                                health_updated_at: SteadyTime::now(), };
+                                                suspected_by:      incoming.suspected_by,
+                                                health_updated_at: self.clock.now(), };
+                    true
+                } else if incoming.member.incarnation == val.member.incarnation
+                          && incoming.health == Health::Suspect
+                          && val.health == Health::Suspect
+                          && !incoming.suspected_by.is_subset(&val.suspected_by)
+                {
+                    // The suspicion keeps the time it started at
+                    val.suspected_by.extend(incoming.suspected_by);
                     true
                 } else {
                     false
@@ -521,7 +650,8 @@ impl MemberList {
             hash_map::Entry::Vacant(entry) => {
                 entry.insert(member_list::Entry { member:            incoming.member,
                                                   health:            incoming.health,
-                                                  health_updated_at: SteadyTime::now(), });
+                                                  suspected_by:      incoming.suspected_by,
+                                                  health_updated_at: self.clock.now(), });
                 true
             }
         };
@@ -587,6 +717,16 @@ impl MemberList {
             .map(|member_list::Entry { health, .. }| *health)
     }
 
+    /// Returns the zone of the member, if the member exists and is tagged with one.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (read)
+    pub fn zone_of_by_id_mlr(&self, member_id: &str) -> Option<String> {
+        self.read_entries()
+            .get(member_id)
+            .and_then(|member_list::Entry { member, .. }| member.tags.get(ZONE_TAG).cloned())
+    }
+
     /// Returns the health of the member, blocking for a limited timeout
     ///
     /// # Locking (see locking.md)
@@ -641,12 +781,7 @@ impl MemberList {
     /// # Locking (see locking.md)
     /// * `MemberList::entries` (read)
     pub fn membership_for_mlr(&self, member_id: &str) -> Option<Membership> {
-        self.read_entries()
-            .get(member_id)
-            .map(|member_list::Entry { member, health, .. }| {
-                Membership { member: member.clone(),
-                             health: *health, }
-            })
+        self.read_entries().get(member_id).map(Membership::from)
     }
 
     /// Returns the number of entries.
@@ -659,6 +794,17 @@ impl MemberList {
     /// * `MemberList::entries` (read)
     pub fn is_empty_mlr(&self) -> bool { self.read_entries().is_empty() }
 
+    /// Returns the number of alive members.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (read)
+    pub fn len_alive_mlr(&self) -> usize {
+        self.read_entries()
+            .values()
+            .filter(|entry| entry.health == Health::Alive)
+            .count()
+    }
+
     /// A randomized list of members to check.
     ///
     /// # Locking (see locking.md)
@@ -670,11 +816,20 @@ impl MemberList {
                                       .filter(|member| member.id != exclude_id)
                                       .cloned()
                                       .collect();
-        members.shuffle(&mut thread_rng());
+        match self.rng {
+            Some(ref rng) => {
+                // The entries are in no particular order, so sort them
+                // before a seeded shuffle
+                members.sort_by(|a, b| a.id.cmp(&b.id));
+                members.shuffle(&mut *rng.lock().expect("MemberList rng lock poisoned"));
+            }
+            None => members.shuffle(&mut thread_rng()),
+        }
         members
     }
 
-    /// Takes a function whose first argument is a member, and calls it for every pingreq target.
+    /// Takes a function whose first argument is a member, and calls it for up to `count` pingreq
+    /// targets.
     ///
     /// # Locking (see locking.md)
     /// * `MemberList::entries` (read)
@@ -683,17 +838,27 @@ impl MemberList {
     pub fn with_pingreq_targets_mlr(&self,
                                     sending_member_id: &str,
                                     target_member_id: &str,
+                                    count: usize,
                                     mut with_closure: impl FnMut(&Member)) {
-        for member_list::Entry { member, .. } in
-            self.read_entries()
-                .values()
-                .filter(|member_list::Entry { member, health, .. }| {
-                    member.id != sending_member_id
-                    && member.id != target_member_id
-                    && *health == Health::Alive
-                })
-                .choose_multiple(&mut thread_rng(), PINGREQ_TARGETS)
-        {
+        let entries = self.read_entries();
+        let candidates = entries.values()
+                                .filter(|member_list::Entry { member, health, .. }| {
+                                    member.id != sending_member_id
+                                    && member.id != target_member_id
+                                    && *health == Health::Alive
+                                })
+                                .map(|member_list::Entry { member, .. }| member);
+        let targets = match self.rng {
+            Some(ref rng) => {
+                let mut candidates: Vec<_> = candidates.collect();
+                candidates.sort_by(|a, b| a.id.cmp(&b.id));
+                candidates.into_iter()
+                          .choose_multiple(&mut *rng.lock().expect("MemberList rng lock poisoned"),
+                                           count)
+            }
+            None => candidates.choose_multiple(&mut thread_rng(), count),
+        };
+        for member in targets {
             with_closure(member);
         }
     }
@@ -720,13 +885,7 @@ impl MemberList {
                                             mut with_closure: impl FnMut(Membership) -> Result<T>)
                                             -> Result<T> {
         let mut ok = Ok(T::default());
-        for membership in self.read_entries()
-                              .values()
-                              .map(|member_list::Entry { member, health, .. }| {
-                                  Membership { member: member.clone(),
-                                               health: *health, }
-                              })
-        {
+        for membership in self.read_entries().values().map(Membership::from) {
             ok = Ok(with_closure(membership)?);
         }
         ok
@@ -737,9 +896,15 @@ impl MemberList {
     /// appropriately, and a list of newly-Confirmed Member IDs is
     /// returned.
     ///
+    /// `timeout` is given how many members have independently
+    /// suspected each Suspect member, and returns how long that member
+    /// stays Suspect.
+    ///
     /// # Locking (see locking.md)
     /// * `MemberList::entries` (write)
-    pub fn members_expired_to_confirmed_mlw(&self, timeout: Duration) -> Vec<String> {
+    pub fn members_expired_to_confirmed_mlw(&self,
+                                            timeout: impl Fn(usize) -> Duration)
+                                            -> Vec<String> {
         self.members_expired_to_mlw(Health::Confirmed, timeout)
     }
 
@@ -750,7 +915,7 @@ impl MemberList {
     /// # Locking (see locking.md)
     /// * `MemberList::entries` (write)
     pub fn members_expired_to_departed_mlw(&self, timeout: Duration) -> Vec<String> {
-        self.members_expired_to_mlw(Health::Departed, timeout)
+        self.members_expired_to_mlw(Health::Departed, |_| timeout)
     }
 
     /// Return the member IDs of all members that have "timed out" to
@@ -758,18 +923,22 @@ impl MemberList {
     ///
     /// For instance,
     ///
-    ///   members_expired_to(Health::Departed, timeout)
+    ///   members_expired_to(Health::Departed, |_| timeout)
     ///
     /// will return the IDs of those members that have been
-    /// `Confirmed` for longer than the given `timeout`.
+    /// `Confirmed` for longer than the given `timeout`. The timeout
+    /// is given how many members suspected the member.
     ///
     /// The newly-updated health status is recorded properly.
     ///
     /// # Locking (see locking.md)
     /// * `MemberList::entries` (write)
     // TODO (CM): Better return type than Vec<String>
-    fn members_expired_to_mlw(&self, expiring_to: Health, timeout: Duration) -> Vec<String> {
-        let now = SteadyTime::now();
+    fn members_expired_to_mlw(&self,
+                              expiring_to: Health,
+                              timeout: impl Fn(usize) -> Duration)
+                              -> Vec<String> {
+        let now = self.clock.now();
         let precursor_health = match expiring_to {
             Health::Confirmed => Health::Suspect,
             Health::Departed => Health::Confirmed,
@@ -781,9 +950,12 @@ impl MemberList {
                 .iter_mut()
                 .filter_map(|(id, v)| {
                     let member_list::Entry { health,
+                                             suspected_by,
                                              health_updated_at,
                                              .. } = v;
-                    if *health == precursor_health && now >= *health_updated_at + timeout {
+                    if *health == precursor_health
+                       && now >= *health_updated_at + timeout(suspected_by.len())
+                    {
                         *health = expiring_to;
                         *health_updated_at = now;
                         Some(id.clone())
@@ -800,6 +972,23 @@ impl MemberList {
         expired
     }
 
+    /// Return the member IDs of all members that have been `Confirmed`
+    /// or `Departed` for longer than the given `timeout`.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (read)
+    pub fn members_gone_longer_than_mlr(&self, timeout: Duration) -> HashSet<String> {
+        let now = self.clock.now();
+        self.read_entries()
+            .iter()
+            .filter(|(_, entry)| {
+                (entry.health == Health::Confirmed || entry.health == Health::Departed)
+                && now >= entry.health_updated_at + timeout
+            })
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
     /// # Locking (see locking.md)
     /// * `MemberList::entries` (read)
     pub fn contains_member_mlr(&self, member_id: &str) -> bool {
@@ -844,12 +1033,13 @@ impl<'a> Serialize for MemberProxy<'a> {
     fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
         where S: Serializer
     {
-        let mut strukt = serializer.serialize_struct("member", 6)?;
+        let mut strukt = serializer.serialize_struct("member", 7)?;
         strukt.serialize_field("address", &self.0.address)?;
         strukt.serialize_field("gossip_port", &self.0.gossip_port)?;
         strukt.serialize_field("incarnation", &self.0.incarnation)?;
         strukt.serialize_field("persistent", &self.0.persistent)?;
         strukt.serialize_field("swim_port", &self.0.swim_port)?;
+        strukt.serialize_field("tags", &self.0.tags)?;
         strukt.serialize_field("health", &self.1)?;
         strukt.end()
     }
@@ -891,15 +1081,18 @@ mod tests {
     }
 
     mod membership {
-        use crate::{member::{Health,
+        use crate::{identity::MemberIdentity,
+                    member::{Health,
                              Member,
                              Membership},
                     protocol::Message};
+        use habitat_core::crypto::SigKeyPair;
+
         #[test]
         fn encode_decode_roundtrip() {
             let member = Member::default();
-            let membership = Membership { member,
-                                          health: Health::Suspect };
+            let mut membership = Membership::new(member, Health::Suspect);
+            membership.suspected_by.insert("suspecter".to_string());
 
             let bytes = membership.clone()
                                   .write_to_bytes()
@@ -909,15 +1102,56 @@ mod tests {
 
             assert_eq!(&membership.member, &from_bytes.member);
             assert_eq!(&membership.health, &from_bytes.health);
+            assert_eq!(&membership.suspected_by, &from_bytes.suspected_by);
+        }
+
+        #[test]
+        fn tags_survive_encode_decode_roundtrip() {
+            let mut member = Member::default();
+            member.tags
+                  .insert("zone".to_string(), "us-east-1a".to_string());
+            member.tags.insert("rack".to_string(), "12".to_string());
+            let membership = Membership::new(member, Health::Alive);
+
+            let bytes = membership.clone()
+                                  .write_to_bytes()
+                                  .expect("Could not write membership to bytes!");
+            let from_bytes =
+                Membership::from_bytes(&bytes).expect("Could not decode membership from bytes!");
+
+            assert_eq!(from_bytes.member.tags, membership.member.tags);
+            assert_eq!(from_bytes.member.tags["zone"], "us-east-1a");
+        }
+
+        #[test]
+        fn signature_covers_what_only_the_member_sets() {
+            let mut member = Member::default();
+            member.tags
+                  .insert("zone".to_string(), "us-east-1a".to_string());
+            let key = SigKeyPair::generate_pair_for_member(&member.id).unwrap();
+            let identity = MemberIdentity::new(key, Vec::new());
+            member.sign(&identity).unwrap();
+
+            let bytes = Membership::new(member, Health::Alive).write_to_bytes()
+                                                               .unwrap();
+            let mut member = Membership::from_bytes(&bytes).unwrap().member;
+            member.address = "10.0.0.1".to_string();
+            member.departed = true;
+            assert!(member.verify(&identity).is_ok());
+
+            member.tags
+                  .insert("zone".to_string(), "us-west-2a".to_string());
+            assert!(member.verify(&identity).is_err());
         }
     }
 
     mod member_list {
-        use crate::member::{Health,
-                            Member,
-                            MemberList,
-                            Membership,
-                            PINGREQ_TARGETS};
+        use crate::{member::{Health,
+                             Member,
+                             MemberList,
+                             Membership},
+                    server::timing::Timing};
+        use time::Duration;
 
         fn populated_member_list(size: u64) -> MemberList {
             let ml = MemberList::new();
@@ -971,23 +1205,25 @@ mod tests {
         #[test]
         fn pingreq_targets() {
             let ml = populated_member_list(10);
+            let targets = Timing::default().pingreq_targets;
             ml.with_member_iter(|mut i| {
                   let from = i.nth(0).unwrap();
                   let target = i.nth(1).unwrap();
                   let mut counter: usize = 0;
-                  ml.with_pingreq_targets_mlr(&from.id, &target.id, |_m| counter += 1);
-                  assert_eq!(counter, PINGREQ_TARGETS);
+                  ml.with_pingreq_targets_mlr(&from.id, &target.id, targets, |_m| counter += 1);
+                  assert_eq!(counter, targets);
               });
         }
 
         #[test]
         fn pingreq_targets_excludes_pinging_member() {
             let ml = populated_member_list(3);
+            let targets = Timing::default().pingreq_targets;
             ml.with_member_iter(|mut i| {
                   let from = i.nth(0).unwrap();
                   let target = i.nth(1).unwrap();
                   let mut excluded_appears: bool = false;
-                  ml.with_pingreq_targets_mlr(&from.id, &target.id, |m| {
+                  ml.with_pingreq_targets_mlr(&from.id, &target.id, targets, |m| {
                         if m.id == from.id {
                             excluded_appears = true
                         }
@@ -999,11 +1235,12 @@ mod tests {
         #[test]
         fn pingreq_targets_excludes_target_member() {
             let ml = populated_member_list(3);
+            let targets = Timing::default().pingreq_targets;
             ml.with_member_iter(|mut i| {
                   let from = i.nth(0).unwrap();
                   let target = i.nth(1).unwrap();
                   let mut excluded_appears: bool = false;
-                  ml.with_pingreq_targets_mlr(&from.id, &target.id, |m| {
+                  ml.with_pingreq_targets_mlr(&from.id, &target.id, targets, |m| {
                         if m.id == target.id {
                             excluded_appears = true
                         }
@@ -1015,11 +1252,12 @@ mod tests {
         #[test]
         fn pingreq_targets_minimum_viable_pingreq_size_is_three() {
             let ml = populated_member_list(3);
+            let targets = Timing::default().pingreq_targets;
             ml.with_member_iter(|mut i| {
                   let from = i.nth(0).unwrap();
                   let target = i.nth(1).unwrap();
                   let mut counter: isize = 0;
-                  ml.with_pingreq_targets_mlr(&from.id, &target.id, |_m| counter += 1);
+                  ml.with_pingreq_targets_mlr(&from.id, &target.id, targets, |_m| counter += 1);
                   assert_eq!(counter, 1);
               });
         }
@@ -1033,6 +1271,47 @@ mod tests {
             assert_eq!(ml.health_of_mlr(&mcheck), Some(Health::Alive));
         }
 
+        fn suspected_by(member: &Member, suspecter: &str) -> Membership {
+            let mut membership = Membership::new(member.clone(), Health::Suspect);
+            membership.suspected_by.insert(suspecter.to_string());
+            membership
+        }
+
+        #[test]
+        fn independent_suspicions_are_merged() {
+            let ml = MemberList::new();
+            let member = Member::default();
+            assert!(ml.insert_mlw(member.clone(), Health::Alive));
+            assert!(ml.insert_membership_mlw(suspected_by(&member, "a")));
+            assert!(ml.insert_membership_mlw(suspected_by(&member, "b")));
+            assert!(!ml.insert_membership_mlw(suspected_by(&member, "a")),
+                    "A suspicion we already know of should not be news");
+
+            let membership = ml.membership_for_mlr(&member.id).unwrap();
+            assert_eq!(membership.health, Health::Suspect);
+            assert_eq!(membership.suspected_by.len(), 2);
+        }
+
+        #[test]
+        fn suspicion_timeout_is_given_the_number_of_suspecters() {
+            let ml = MemberList::new();
+            let lone = Member::default();
+            let confirmed = Member::default();
+            assert!(ml.insert_membership_mlw(suspected_by(&lone, "a")));
+            assert!(ml.insert_membership_mlw(suspected_by(&confirmed, "a")));
+            assert!(ml.insert_membership_mlw(suspected_by(&confirmed, "b")));
+
+            let newly_confirmed = ml.members_expired_to_confirmed_mlw(|suspecters| {
+                                        if suspecters >= 2 {
+                                            Duration::zero()
+                                        } else {
+                                            Duration::hours(1)
+                                        }
+                                    });
+            assert_eq!(newly_confirmed, vec![confirmed.id.clone()]);
+            assert_eq!(ml.health_of_mlr(&lone), Some(Health::Suspect));
+        }
+
         /// Tests of MemberList::insert
         mod insert {
             use crate::member::{Health,
@@ -1422,27 +1701,27 @@ mod tests {
                 let large_timeout =
                     Duration::from_std(StdDuration::from_secs(large_seconds)).unwrap();
 
-                assert!(ml.members_expired_to_confirmed_mlw(small_timeout)
+                assert!(ml.members_expired_to_confirmed_mlw(|_| small_timeout)
                           .is_empty(),
                         "An empty MemberList shouldn't have anything that's timing out to being \
                          Confirmed");
 
                 assert!(ml.insert_mlw(member_one.clone(), Health::Alive));
 
-                assert!(ml.members_expired_to_confirmed_mlw(small_timeout)
+                assert!(ml.members_expired_to_confirmed_mlw(|_| small_timeout)
                           .is_empty(),
                         "Should be no newly Confirmed members when they're all Alive");
 
                 assert!(ml.insert_mlw(member_one.clone(), Health::Suspect));
 
-                assert!(ml.members_expired_to_confirmed_mlw(large_timeout)
+                assert!(ml.members_expired_to_confirmed_mlw(|_| large_timeout)
                           .is_empty(),
                         "Nothing should have timed out to Confirmed with a large timeout");
 
                 // Allow the Suspect to age
                 thread::sleep(StdDuration::from_secs(small_seconds));
 
-                let newly_confirmed = ml.members_expired_to_confirmed_mlw(small_timeout);
+                let newly_confirmed = ml.members_expired_to_confirmed_mlw(|_| small_timeout);
                 assert!(newly_confirmed.contains(&member_one.id),
                         "Member should be newly Confirmed after timing out");
 
@@ -1511,7 +1790,7 @@ mod tests {
 
                 let timeout = Duration::from_std(StdDuration::from_secs(2)).unwrap();
 
-                let newly_confirmed = ml.members_expired_to_confirmed_mlw(timeout);
+                let newly_confirmed = ml.members_expired_to_confirmed_mlw(|_| timeout);
                 assert!(newly_confirmed.contains(&member_1.id),
                         "Member 1 should be newly Confirmed after timing out");
                 assert!(newly_confirmed.contains(&member_2.id),
@@ -4,31 +4,276 @@ use prost::Message;
 
 use crate::{error::{Error,
                     Result},
+            identity::MemberIdentity,
             protocol::Wire};
 
-pub fn generate_wire(payload: Vec<u8>, ring_key: Option<&SymKey>) -> Result<Vec<u8>> {
+/// The ring keys a member gossips with. Messages are sent encrypted
+/// with the primary key, and accepted if they were encrypted with any
+/// accepted key, so that a ring can move to a new key one member at a
+/// time:
+///
+/// 1. every member accepts the new key;
+/// 1. every member promotes the new key to be its primary key;
+/// 1. every member retires the old key.
+#[derive(Clone, Debug, Default)]
+pub struct RingKeys {
+    primary: Option<SymKey>,
+    /// Accepted keys other than the primary key
+    accepted: Vec<SymKey>,
+}
+
+impl RingKeys {
+    pub fn new(primary: Option<SymKey>) -> Self {
+        RingKeys { primary,
+                   accepted: Vec::new() }
+    }
+
+    /// The key messages are sent with, if they are encrypted.
+    pub fn primary(&self) -> Option<&SymKey> { self.primary.as_ref() }
+
+    /// Every accepted key, the primary key first.
+    pub fn iter(&self) -> impl Iterator<Item = &SymKey> {
+        self.primary.iter().chain(self.accepted.iter())
+    }
+
+    fn get(&self, name_with_rev: &str) -> Option<&SymKey> {
+        self.iter().find(|key| key.name_with_rev() == name_with_rev)
+    }
+
+    /// Accept messages encrypted with `key`, in addition to those
+    /// already accepted.
+    pub fn accept(&mut self, key: SymKey) {
+        if self.get(&key.name_with_rev()).is_none() {
+            self.accepted.push(key);
+        }
+    }
+
+    /// Send messages with an accepted key. The previous primary key is
+    /// still accepted.
+    pub fn promote(&mut self, name_with_rev: &str) -> Result<()> {
+        if self.primary
+               .as_ref()
+               .map_or(false, |key| key.name_with_rev() == name_with_rev)
+        {
+            return Ok(());
+        }
+        let position = self.accepted
+                           .iter()
+                           .position(|key| key.name_with_rev() == name_with_rev)
+                           .ok_or_else(|| Error::UnknownRingKey(name_with_rev.to_string()))?;
+        let key = self.accepted.remove(position);
+        if let Some(previous) = self.primary.replace(key) {
+            self.accepted.push(previous);
+        }
+        Ok(())
+    }
+
+    /// Stop accepting messages encrypted with a key. The primary key
+    /// cannot be retired.
+    pub fn retire(&mut self, name_with_rev: &str) -> Result<()> {
+        if self.primary
+               .as_ref()
+               .map_or(false, |key| key.name_with_rev() == name_with_rev)
+        {
+            return Err(Error::PrimaryRingKey(name_with_rev.to_string()));
+        }
+        let position = self.accepted
+                           .iter()
+                           .position(|key| key.name_with_rev() == name_with_rev)
+                           .ok_or_else(|| Error::UnknownRingKey(name_with_rev.to_string()))?;
+        self.accepted.remove(position);
+        Ok(())
+    }
+}
+
+/// Wrap `payload` for the wire, encrypting it with `ring_key` and
+/// signing it as `identity`, if given.
+pub fn generate_wire(payload: Vec<u8>,
+                     ring_key: Option<&SymKey>,
+                     identity: Option<&MemberIdentity>)
+                     -> Result<Vec<u8>> {
     let mut wire = Wire::default();
     if let Some(ring_key) = ring_key {
         wire.encrypted = Some(true);
         let (nonce, encrypted_payload) = ring_key.encrypt(&payload)?;
         wire.nonce = Some(nonce);
         wire.payload = Some(encrypted_payload);
+        wire.ring_key = Some(ring_key.name_with_rev());
     } else {
         wire.payload = Some(payload);
     }
+    if let Some(identity) = identity {
+        wire.signature = wire.payload
+                             .as_ref()
+                             .map(|payload| identity.sign(payload))
+                             .transpose()?;
+        wire.member_id = Some(identity.member_id().to_string());
+    }
     let mut buf = BytesMut::with_capacity(wire.encoded_len());
     wire.encode(&mut buf)?;
     Ok(buf.to_vec())
 }
 
-pub fn unwrap_wire(payload: &[u8], ring_key: Option<&SymKey>) -> Result<Vec<u8>> {
+/// Unwrap a payload from the wire, decrypting it with `ring_keys`.
+///
+/// When `identity` is given, the payload must be signed by a trusted
+/// member, whose ID is returned alongside the payload.
+pub fn unwrap_wire(payload: &[u8],
+                   ring_keys: &RingKeys,
+                   identity: Option<&MemberIdentity>)
+                   -> Result<(Vec<u8>, Option<String>)> {
     let wire = Wire::decode(payload)?;
     let payload = wire.payload
                       .ok_or(Error::ProtocolMismatch("missing payload"))?;
-    if let Some(ring_key) = ring_key {
-        let nonce = wire.nonce.ok_or(Error::ProtocolMismatch("missing nonce"))?;
-        Ok(ring_key.decrypt(&nonce, &payload)?)
-    } else {
-        Ok(payload)
+    let signed_by = match identity {
+        Some(identity) => {
+            let member_id = wire.member_id
+                                .ok_or(Error::ProtocolMismatch("missing member id"))?;
+            let signature = wire.signature
+                                .ok_or(Error::ProtocolMismatch("missing signature"))?;
+            identity.verify(&member_id, &payload, &signature)?;
+            Some(member_id)
+        }
+        None => None,
+    };
+    if ring_keys.primary().is_none() && !wire.encrypted.unwrap_or(false) {
+        return Ok((payload, signed_by));
+    }
+    let nonce = wire.nonce.ok_or(Error::ProtocolMismatch("missing nonce"))?;
+    let payload = match wire.ring_key {
+        Some(name_with_rev) => {
+            let ring_key = ring_keys.get(&name_with_rev)
+                                    .ok_or(Error::UnknownRingKey(name_with_rev))?;
+            ring_key.decrypt(&nonce, &payload)?
+        }
+        // Older members don't name the key they encrypted with.
+        None => {
+            let mut decrypted = Err(Error::ProtocolMismatch("ring key"));
+            for ring_key in ring_keys.iter() {
+                decrypted = ring_key.decrypt(&nonce, &payload).map_err(Error::from);
+                if decrypted.is_ok() {
+                    break;
+                }
+            }
+            decrypted?
+        }
+    };
+    Ok((payload, signed_by))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use habitat_core::crypto::SigKeyPair;
+
+    fn ring_key(name: &str) -> SymKey {
+        SymKey::generate_pair_for_ring(name).expect("Failed to generate an in memory symkey")
+    }
+
+    #[test]
+    fn messages_are_accepted_with_any_accepted_key() {
+        let old = ring_key("old");
+        let new = ring_key("new");
+        let mut ring_keys = RingKeys::new(Some(old.clone()));
+        ring_keys.accept(new.clone());
+
+        for key in &[old, new] {
+            let wire = generate_wire(b"hello".to_vec(), Some(key), None).unwrap();
+            assert_eq!(unwrap_wire(&wire, &ring_keys, None).unwrap().0, b"hello");
+        }
+        let wire = generate_wire(b"hello".to_vec(), Some(&ring_key("other")), None).unwrap();
+        assert!(unwrap_wire(&wire, &ring_keys, None).is_err());
+    }
+
+    #[test]
+    fn messages_from_members_that_do_not_name_their_key_are_accepted() {
+        let old = ring_key("old");
+        let new = ring_key("new");
+        let mut ring_keys = RingKeys::new(Some(old));
+        ring_keys.accept(new.clone());
+
+        let mut wire =
+            Wire::decode(&generate_wire(b"hello".to_vec(), Some(&new), None).unwrap()[..]).unwrap();
+        wire.ring_key = None;
+        let mut buf = BytesMut::with_capacity(wire.encoded_len());
+        wire.encode(&mut buf).unwrap();
+        assert_eq!(unwrap_wire(&buf, &ring_keys, None).unwrap().0, b"hello");
+    }
+
+    #[test]
+    fn unencrypted_messages_are_rejected_once_there_is_a_primary_key() {
+        let wire = generate_wire(b"hello".to_vec(), None, None).unwrap();
+        assert_eq!(unwrap_wire(&wire, &RingKeys::default(), None).unwrap().0,
+                   b"hello");
+        assert!(unwrap_wire(&wire, &RingKeys::new(Some(ring_key("ring"))), None).is_err());
+    }
+
+    #[test]
+    fn promoting_a_key_keeps_the_previous_primary_key_accepted() {
+        let old = ring_key("old");
+        let new = ring_key("new");
+        let mut ring_keys = RingKeys::new(Some(old.clone()));
+        assert!(ring_keys.promote(&new.name_with_rev()).is_err());
+
+        ring_keys.accept(new.clone());
+        ring_keys.promote(&new.name_with_rev()).unwrap();
+        assert_eq!(ring_keys.primary().map(SymKey::name_with_rev),
+                   Some(new.name_with_rev()));
+        assert_eq!(ring_keys.iter()
+                            .map(SymKey::name_with_rev)
+                            .collect::<Vec<_>>(),
+                   vec![new.name_with_rev(), old.name_with_rev()]);
+    }
+
+    #[test]
+    fn the_primary_key_cannot_be_retired() {
+        let old = ring_key("old");
+        let new = ring_key("new");
+        let mut ring_keys = RingKeys::new(Some(old.clone()));
+        ring_keys.accept(new.clone());
+
+        assert!(ring_keys.retire(&old.name_with_rev()).is_err());
+        ring_keys.promote(&new.name_with_rev()).unwrap();
+        ring_keys.retire(&old.name_with_rev()).unwrap();
+        assert_eq!(ring_keys.iter().count(), 1);
+        assert!(ring_keys.retire(&old.name_with_rev()).is_err());
+    }
+
+    #[test]
+    fn messages_must_be_signed_by_a_trusted_member_when_members_have_identities() {
+        let wolverine = SigKeyPair::generate_pair_for_member("wolverine").unwrap();
+        let cyclops = SigKeyPair::generate_pair_for_member("cyclops").unwrap();
+        let magneto = SigKeyPair::generate_pair_for_member("magneto").unwrap();
+        let ours = MemberIdentity::new(wolverine, vec![cyclops.clone()]);
+        let theirs = MemberIdentity::new(cyclops, Vec::new());
+        let enemy = MemberIdentity::new(magneto, Vec::new());
+        let ring_keys = RingKeys::new(Some(ring_key("ring")));
+
+        let wire = generate_wire(b"hello".to_vec(), ring_keys.primary(), Some(&theirs)).unwrap();
+        let (payload, signed_by) = unwrap_wire(&wire, &ring_keys, Some(&ours)).unwrap();
+        assert_eq!(payload, b"hello");
+        assert_eq!(signed_by, Some("cyclops".to_string()));
+
+        let wire = generate_wire(b"hello".to_vec(), ring_keys.primary(), Some(&enemy)).unwrap();
+        assert!(unwrap_wire(&wire, &ring_keys, Some(&ours)).is_err());
+
+        let wire = generate_wire(b"hello".to_vec(), ring_keys.primary(), None).unwrap();
+        assert!(unwrap_wire(&wire, &ring_keys, Some(&ours)).is_err());
+    }
+
+    #[test]
+    fn a_signature_cannot_be_claimed_by_another_member() {
+        let wolverine = SigKeyPair::generate_pair_for_member("wolverine").unwrap();
+        let cyclops = SigKeyPair::generate_pair_for_member("cyclops").unwrap();
+        let ours = MemberIdentity::new(wolverine, vec![cyclops.clone()]);
+        let theirs = MemberIdentity::new(cyclops, Vec::new());
+
+        let mut wire =
+            Wire::decode(&generate_wire(b"hello".to_vec(), None, Some(&theirs)).unwrap()[..])
+            .unwrap();
+        wire.member_id = Some("wolverine".to_string());
+        let mut buf = BytesMut::with_capacity(wire.encoded_len());
+        wire.encode(&mut buf).unwrap();
+        assert!(unwrap_wire(&buf, &RingKeys::default(), Some(&ours)).is_err());
     }
 }
@@ -3,6 +3,7 @@ use std::fmt;
 use crate::rumor::{departure::Departure as CDeparture,
                    election::{Election as CElection,
                               ElectionUpdate as CElectionUpdate},
+                   purge::Purge as CPurge,
                    service::Service as CService,
                    service_config::ServiceConfig as CServiceConfig,
                    service_file::ServiceFile as CServiceFile};
@@ -24,6 +25,8 @@ impl fmt::Display for RumorType {
             RumorType::Fake2 => "fake2",
             RumorType::ElectionUpdate => "election-update",
             RumorType::Departure => "departure",
+            RumorType::Purge => "purge",
+            RumorType::Digest => "digest",
         };
 
         write!(f, "{}", value)
@@ -32,22 +35,33 @@ impl fmt::Display for RumorType {
 
 impl From<CDeparture> for Rumor {
     fn from(value: CDeparture) -> Self {
-        let payload = Departure { member_id: Some(value.member_id), };
         Rumor { r#type:  RumorType::Departure as i32,
                 tag:     Vec::default(),
                 from_id: Some("butterflyclient".to_string()),
-                payload: Some(RumorPayload::Departure(payload)), }
+                payload: Some(RumorPayload::Departure(value.into())), }
+    }
+}
+
+impl From<CPurge> for Rumor {
+    fn from(value: CPurge) -> Self {
+        Rumor { r#type:  RumorType::Purge as i32,
+                tag:     Vec::default(),
+                from_id: Some("butterflyclient".to_string()),
+                payload: Some(RumorPayload::Purge(value.into())), }
     }
 }
 
 impl From<CElection> for Rumor {
     fn from(value: CElection) -> Self {
-        let payload = Election { member_id:     Some(value.member_id.clone()),
-                                 service_group: Some(value.service_group.to_string()),
-                                 term:          Some(value.term),
-                                 suitability:   Some(value.suitability),
-                                 status:        Some(value.status as i32),
-                                 votes:         value.votes.clone(), };
+        let payload = Election { member_id:         Some(value.member_id.clone()),
+                                 service_group:     Some(value.service_group.to_string()),
+                                 term:              Some(value.term),
+                                 suitability:       Some(value.suitability),
+                                 status:            Some(value.status as i32),
+                                 votes:             value.votes.clone(),
+                                 in_preferred_zone: Some(value.in_preferred_zone),
+                                 min_zones:         Some(value.min_zones),
+                                 signature:         value.signature.clone(), };
         Rumor { r#type:  RumorType::Election as i32,
                 tag:     Vec::default(),
                 from_id: Some(value.member_id),
@@ -57,12 +71,15 @@ impl From<CElection> for Rumor {
 
 impl From<CElectionUpdate> for Rumor {
     fn from(value: CElectionUpdate) -> Self {
-        let payload = Election { member_id:     Some(value.member_id.clone()),
-                                 service_group: Some(value.service_group.to_string()),
-                                 term:          Some(value.term),
-                                 suitability:   Some(value.suitability),
-                                 status:        Some(value.status as i32),
-                                 votes:         value.votes.clone(), };
+        let payload = Election { member_id:         Some(value.member_id.clone()),
+                                 service_group:     Some(value.service_group.to_string()),
+                                 term:              Some(value.term),
+                                 suitability:       Some(value.suitability),
+                                 status:            Some(value.status as i32),
+                                 votes:             value.votes.clone(),
+                                 in_preferred_zone: Some(value.in_preferred_zone),
+                                 min_zones:         Some(value.min_zones),
+                                 signature:         value.signature.clone(), };
         Rumor { r#type:  RumorType::ElectionUpdate as i32,
                 tag:     Vec::default(),
                 from_id: Some(value.member_id.clone()),
@@ -72,13 +89,17 @@ impl From<CElectionUpdate> for Rumor {
 
 impl From<CService> for Rumor {
     fn from(value: CService) -> Self {
-        let payload = Service { member_id:     Some(value.member_id.clone()),
-                                service_group: Some(value.service_group.to_string()),
-                                incarnation:   Some(value.incarnation),
-                                initialized:   Some(value.initialized),
-                                pkg:           Some(value.pkg),
-                                cfg:           Some(value.cfg),
-                                sys:           Some(value.sys.into()), };
+        let payload = Service { member_id:           Some(value.member_id.clone()),
+                                service_group:       Some(value.service_group.to_string()),
+                                incarnation:         Some(value.incarnation),
+                                initialized:         Some(value.initialized),
+                                pkg:                 Some(value.pkg),
+                                cfg:                 Some(value.cfg),
+                                sys:                 Some(value.sys.into()),
+                                health_check_passed: Some(value.health_check_passed),
+                                ready:               Some(value.ready),
+                                blocked_releases:    value.blocked_releases,
+                                signature:           value.signature, };
         Rumor { r#type:  RumorType::Service as i32,
                 tag:     Vec::default(),
                 from_id: Some(value.member_id),
@@ -91,7 +112,9 @@ impl From<CServiceConfig> for Rumor {
         let payload = ServiceConfig { service_group: Some(value.service_group.to_string()),
                                       incarnation:   Some(value.incarnation),
                                       encrypted:     Some(value.encrypted),
-                                      config:        Some(value.config), };
+                                      config:        Some(value.config),
+                                      signed_by:     value.signed_by,
+                                      signature:     value.signature, };
         Rumor { r#type:  RumorType::ServiceConfig as i32,
                 tag:     Vec::default(),
                 from_id: Some(value.from_id),
@@ -105,7 +128,9 @@ impl From<CServiceFile> for Rumor {
                                     incarnation:   Some(value.incarnation),
                                     encrypted:     Some(value.encrypted),
                                     filename:      Some(value.filename),
-                                    body:          Some(value.body), };
+                                    body:          Some(value.body),
+                                    signed_by:     value.signed_by,
+                                    signature:     value.signature, };
         Rumor { r#type:  RumorType::ServiceFile as i32,
                 tag:     Vec::default(),
                 from_id: Some(value.from_id),
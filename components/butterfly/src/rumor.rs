@@ -8,8 +8,10 @@
 
 pub mod dat_file;
 pub mod departure;
+pub mod digest;
 pub mod election;
 pub mod heat;
+pub mod purge;
 pub mod service;
 pub mod service_config;
 pub mod service_file;
@@ -34,8 +36,11 @@ use std::{collections::{hash_map::Entry,
                  Arc}};
 
 pub use self::{departure::Departure,
+               digest::{Digest,
+                        DigestEntry},
                election::{Election,
                           ElectionUpdate},
+               purge::Purge,
                service::Service,
                service_config::ServiceConfig,
                service_file::ServiceFile,
@@ -55,9 +60,11 @@ lazy_static! {
 #[derive(Debug, Clone, Serialize)]
 pub enum RumorKind {
     Departure(Departure),
+    Digest(Digest),
     Election(Election),
     ElectionUpdate(ElectionUpdate),
     Membership(Membership),
+    Purge(Purge),
     Service(Box<Service>), // Boxed due to clippy::large_enum_variant
     ServiceConfig(ServiceConfig),
     ServiceFile(ServiceFile),
@@ -67,9 +74,11 @@ impl From<RumorKind> for RumorPayload {
     fn from(value: RumorKind) -> Self {
         match value {
             RumorKind::Departure(departure) => RumorPayload::Departure(departure.into()),
+            RumorKind::Digest(digest) => RumorPayload::Digest(digest.into()),
             RumorKind::Election(election) => RumorPayload::Election(election.into()),
             RumorKind::ElectionUpdate(election) => RumorPayload::Election(election.into()),
             RumorKind::Membership(membership) => RumorPayload::Member(membership.into()),
+            RumorKind::Purge(purge) => RumorPayload::Purge(purge.into()),
             RumorKind::Service(service) => RumorPayload::Service((*service).into()),
             RumorKind::ServiceConfig(service_config) => {
                 RumorPayload::ServiceConfig(service_config.into())
@@ -87,7 +96,7 @@ type RumorKeyKey = String;
 type RumorKeyId = String;
 
 /// The description of a `RumorKey`.
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct RumorKey {
     pub kind: RumorType,
     pub id:   RumorKeyId,
@@ -115,6 +124,20 @@ impl fmt::Display for RumorKey {
     }
 }
 
+/// What a member knows of a rumor, for inspecting the state of a ring.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RumorSummary {
+    pub key: RumorKey,
+    /// The incarnation of service, service config and service file rumors
+    pub incarnation: Option<u64>,
+    /// The term and status of election rumors
+    pub election: Option<(u64, election::ElectionStatus)>,
+    /// How many times this member has shared the rumor
+    pub shared: usize,
+    /// How many members the rumor has cooled off for
+    pub cooled: usize,
+}
+
 /// A representation of a Rumor; implemented by all the concrete types we share as rumors. The
 /// exception is the Membership rumor, since it's not actually a rumor in the same vein.
 pub trait Rumor: Message<ProtoRumor> + Sized {
@@ -122,6 +145,10 @@ pub trait Rumor: Message<ProtoRumor> + Sized {
     fn key(&self) -> &str;
     fn id(&self) -> &str;
     fn merge(&mut self, other: Self) -> bool;
+
+    /// How up to date the rumor is, for anti-entropy: a member holding the same rumor at a lower
+    /// version is behind. Rumors that never change once they are made keep the default.
+    fn version(&self) -> u64 { 0 }
 }
 
 pub trait ConstKeyRumor: Rumor {
@@ -183,13 +210,47 @@ mod storage {
         }
     }
 
+    /// The rumors in a `RumorStore`, along with how many bytes each takes up encoded. The length
+    /// is worked out once, when the rumor is stored, so digest exchanges can count the bytes
+    /// they found were already shared without encoding rumors under the lock.
+    #[derive(Debug)]
+    pub struct Rumors<T> {
+        map:          RumorMap<T>,
+        encoded_lens: RumorMap<usize>,
+    }
+
+    impl<T> Rumors<T> {
+        fn remove(&mut self, key: &str, id: &str) {
+            self.map.get_mut(key).and_then(|r| r.remove(id));
+            self.encoded_lens.get_mut(key).and_then(|r| r.remove(id));
+        }
+
+        fn remove_key(&mut self, key: &str) -> bool {
+            self.encoded_lens.remove(key);
+            self.map.remove(key).is_some()
+        }
+    }
+
+    impl<T> Default for Rumors<T> {
+        fn default() -> Self {
+            Rumors { map:          HashMap::new(),
+                     encoded_lens: HashMap::new(), }
+        }
+    }
+
+    impl<T> std::ops::Deref for Rumors<T> {
+        type Target = RumorMap<T>;
+
+        fn deref(&self) -> &Self::Target { &self.map }
+    }
+
     pub struct IterableGuard<'a, T>(ReadGuard<'a, T>);
 
     // This impl block covers a `ReadGuard` over a `RumorMap` structure, but none of these
     // functions require the contained value to be a rumor, so we use T, not R. Rumor-specific
     // functionality is a different impl block.
-    impl<'a, T> IterableGuard<'a, RumorMap<T>> {
-        fn read(lock: &'a Lock<RumorMap<T>>) -> Self { IterableGuard(lock.read()) }
+    impl<'a, T> IterableGuard<'a, Rumors<T>> {
+        fn read(lock: &'a Lock<Rumors<T>>) -> Self { IterableGuard(lock.read()) }
 
         /// Allows iterator access to the rumors in to the `RumorMap` while holding its lock:
         /// ```
@@ -218,9 +279,20 @@ mod storage {
                         -> Option<OUT> {
             self.service_group(key).0.and_then(|m| m.get(id).map(f))
         }
+
+        /// How many bytes the rumor for the given key takes up encoded, or 0 if no such rumor is
+        /// present.
+        pub fn encoded_len(&self, RumorKey { key, id, .. }: &RumorKey) -> usize {
+            self.0
+                .encoded_lens
+                .get(key)
+                .and_then(|m| m.get(id))
+                .cloned()
+                .unwrap_or(0)
+        }
     }
 
-    impl<'a, R: Rumor> IterableGuard<'a, RumorMap<R>> {
+    impl<'a, R: Rumor> IterableGuard<'a, Rumors<R>> {
         pub fn contains_rumor(&self, rumor: &R) -> bool {
             let RumorKey { key, id, .. } = rumor.into();
 
@@ -237,9 +309,18 @@ mod storage {
                     Err(Error::NonExistentRumor(String::from(&key.id), String::from(&key.key)))
                 })
         }
+
+        /// The version of the rumor for the given key, if present.
+        pub fn version_of(&self, key: &RumorKey) -> Option<u64> { self.map_key(key, R::version) }
+
+        /// The key and version of every rumor, for a digest.
+        pub fn digest_entries(&self) -> impl Iterator<Item = DigestEntry> + '_ {
+            self.rumors()
+                .map(|rumor| DigestEntry::new(RumorKey::from(rumor), rumor.version()))
+        }
     }
 
-    impl<'a, C: ConstKeyRumor> IterableGuard<'a, RumorMap<C>> {
+    impl<'a, C: ConstKeyRumor> IterableGuard<'a, Rumors<C>> {
         pub fn contains_id(&self, member_id: &str) -> bool {
             self.get(C::const_key())
                 .map(|rumors| rumors.contains_key(member_id))
@@ -247,7 +328,7 @@ mod storage {
         }
     }
 
-    impl<'a, E: ElectionRumor> IterableGuard<'a, RumorMap<E>> {
+    impl<'a, E: ElectionRumor> IterableGuard<'a, Rumors<E>> {
         pub fn get_term(&self, service_group: &str) -> Option<u64> {
             self.get(service_group)
                 .map(|sg| sg.get(E::const_id()).map(ElectionRumor::term))
@@ -262,10 +343,10 @@ mod storage {
     /// let rs: RumorStore<Departure> = RumorStore::default();
     /// assert_eq!(rs.lock_rsr().len(), 0);
     /// ```
-    impl<'a, R> std::ops::Deref for IterableGuard<'a, RumorMap<R>> {
+    impl<'a, R> std::ops::Deref for IterableGuard<'a, Rumors<R>> {
         type Target = RumorMap<R>;
 
-        fn deref(&self) -> &Self::Target { &self.0 }
+        fn deref(&self) -> &Self::Target { &self.0.map }
     }
 
     /// Storage for Rumors. It takes a rumor and stores it according to the member that produced it,
@@ -274,7 +355,7 @@ mod storage {
     /// Generic over the type of rumor it stores.
     #[derive(Debug, Clone)]
     pub struct RumorStore<T> {
-        list:           Arc<Lock<RumorMap<T>>>,
+        list:           Arc<Lock<Rumors<T>>>,
         update_counter: Arc<AtomicUsize>,
     }
 
@@ -294,13 +375,22 @@ mod storage {
         ///   held until the `IterableGuard` goes out of scope. In general, it's best to avoid
         ///   binding the return of `lock_rsr` in favor of using it as the first link in a chain of
         ///   functions that will be consumed by an iterator adapter or `for` loop.
-        pub fn lock_rsr(&self) -> IterableGuard<RumorMap<T>> { IterableGuard::read(&self.list) }
+        pub fn lock_rsr(&self) -> IterableGuard<Rumors<T>> { IterableGuard::read(&self.list) }
 
         /// # Locking (see locking.md)
         /// * `RumorStore::list` (write)
-        pub fn remove_rsw(&self, key: &str, id: &str) {
-            let mut list = self.list.write();
-            list.get_mut(key).and_then(|r| r.remove(id));
+        pub fn remove_rsw(&self, key: &str, id: &str) { self.list.write().remove(key, id); }
+
+        /// Remove every rumor under the key. Returns true if there were any.
+        ///
+        /// # Locking (see locking.md)
+        /// * `RumorStore::list` (write)
+        pub fn remove_key_rsw(&self, key: &str) -> bool {
+            let removed = self.list.write().remove_key(key);
+            if removed {
+                self.increment_update_counter();
+            }
+            removed
         }
     }
 
@@ -312,12 +402,14 @@ mod storage {
         /// * `RumorStore::list` (write)
         pub fn insert_rsw(&self, rumor: R) -> bool {
             let mut list = self.list.write();
-            let rumors = list.entry(String::from(rumor.key()))
-                             .or_insert_with(HashMap::new);
+            let list = &mut *list;
+            let key = String::from(rumor.key());
+            let id = String::from(rumor.id());
+            let rumors = list.map.entry(key.clone()).or_insert_with(HashMap::new);
             let kind_ignored_count =
                 IGNORED_RUMOR_COUNT.with_label_values(&[&rumor.kind().to_string()]);
             // Result reveals if there was a change so we can increment the counter if needed.
-            let result = match rumors.entry(rumor.id().into()) {
+            let result = match rumors.entry(id.clone()) {
                 Entry::Occupied(mut entry) => entry.get_mut().merge(rumor),
                 Entry::Vacant(entry) => {
                     entry.insert(rumor);
@@ -325,6 +417,13 @@ mod storage {
                 }
             };
             if result {
+                let encoded_len = rumors.get(&id)
+                                        .and_then(|rumor| rumor.write_to_bytes().ok())
+                                        .map_or(0, |bytes| bytes.len());
+                list.encoded_lens
+                    .entry(key)
+                    .or_insert_with(HashMap::new)
+                    .insert(id, encoded_len);
                 self.increment_update_counter();
             } else {
                 // If we get here, it means nothing changed, which means we effectively ignored the
@@ -333,6 +432,37 @@ mod storage {
             }
             result
         }
+
+        /// Remove every rumor for which `expired` returns true, along with any key left without
+        /// rumors. Returns the keys of the removed rumors.
+        ///
+        /// # Locking (see locking.md)
+        /// * `RumorStore::list` (write)
+        pub fn remove_expired_rsw(&self, expired: impl Fn(&R) -> bool) -> Vec<RumorKey> {
+            let mut list = self.list.write();
+            let mut removed = Vec::new();
+            for rumors in list.map.values_mut() {
+                rumors.retain(|_, rumor| {
+                          if expired(rumor) {
+                              removed.push(RumorKey::from(&*rumor));
+                              false
+                          } else {
+                              true
+                          }
+                      });
+            }
+            list.map.retain(|_, rumors| !rumors.is_empty());
+            for key in &removed {
+                list.encoded_lens
+                    .get_mut(&key.key)
+                    .and_then(|lens| lens.remove(&key.id));
+            }
+            list.encoded_lens.retain(|_, lens| !lens.is_empty());
+            if !removed.is_empty() {
+                self.increment_update_counter();
+            }
+            removed
+        }
     }
 
     impl<T> Default for RumorStore<T> {
@@ -350,7 +480,7 @@ mod storage {
             where S: Serializer
         {
             let mut strukt = serializer.serialize_struct("rumor_store", 2)?;
-            strukt.serialize_field("list", &*(self.list.read()))?;
+            strukt.serialize_field("list", &self.list.read().map)?;
             strukt.serialize_field("update_counter", &self.get_update_counter())?;
             strukt.end()
         }
@@ -485,11 +615,13 @@ impl RumorEnvelope {
                            .ok_or(Error::ProtocolMismatch("from-id"))?;
         let kind = match r#type {
             RumorType::Departure => RumorKind::Departure(Departure::from_proto(proto)?),
+            RumorType::Digest => RumorKind::Digest(Digest::from_proto(proto)?),
             RumorType::Election => RumorKind::Election(Election::from_proto(proto)?),
             RumorType::ElectionUpdate => {
                 RumorKind::ElectionUpdate(ElectionUpdate::from_proto(proto)?)
             }
             RumorType::Member => RumorKind::Membership(Membership::from_proto(proto)?),
+            RumorType::Purge => RumorKind::Purge(Purge::from_proto(proto)?),
             RumorType::Service => RumorKind::Service(Box::new(Service::from_proto(proto)?)),
             RumorType::ServiceConfig => RumorKind::ServiceConfig(ServiceConfig::from_proto(proto)?),
             RumorType::ServiceFile => RumorKind::ServiceFile(ServiceFile::from_proto(proto)?),
@@ -641,6 +773,19 @@ mod tests {
                        b"foo-bar".to_vec());
         }
 
+        #[test]
+        fn encoded_len_is_kept_until_the_rumor_is_removed() {
+            let rs = RumorStore::default();
+            let f = FakeRumor { id:  "foo".to_string(),
+                                key: "bar".to_string(), };
+            let key = RumorKey::from(&f);
+            rs.insert_rsw(f);
+
+            assert_eq!(rs.lock_rsr().encoded_len(&key), b"foo-bar".len());
+            rs.remove_rsw("bar", "foo");
+            assert_eq!(rs.lock_rsr().encoded_len(&key), 0);
+        }
+
         #[test]
         fn encode_non_existant() {
             let rs = RumorStore::<FakeRumor>::default();
@@ -710,5 +855,42 @@ mod tests {
               .service_group(&key)
               .map_rumor(&member_id, |o| assert_eq!(o.id, member_id));
         }
+
+        #[test]
+        fn remove_key_removes_every_rumor_under_the_key() {
+            let rs = RumorStore::default();
+            let f1 = FakeRumor::default();
+            let key = f1.key.clone();
+            rs.insert_rsw(f1);
+            rs.insert_rsw(FakeRumor::default());
+            assert!(rs.remove_key_rsw(&key));
+            assert_eq!(rs.get_update_counter(), 3);
+            assert!(rs.lock_rsr().get(&key).is_none());
+            assert_eq!(rs.remove_key_rsw(&key), false);
+            assert_eq!(rs.get_update_counter(), 3);
+        }
+
+        #[test]
+        fn remove_expired_removes_matching_rumors_and_empty_keys() {
+            let rs = RumorStore::default();
+            let f1 = FakeRumor { id:  "foo".to_string(),
+                                 key: "bar".to_string(), };
+            let f2 = FakeRumor { id:  "baz".to_string(),
+                                 key: "bar".to_string(), };
+            let f3 = FakeRumor { id:  "foo".to_string(),
+                                 key: "qux".to_string(), };
+            let k1 = RumorKey::from(&f1);
+            let k3 = RumorKey::from(&f3);
+            rs.insert_rsw(f1);
+            rs.insert_rsw(f2);
+            rs.insert_rsw(f3);
+
+            let mut removed = rs.remove_expired_rsw(|r| r.id == "foo");
+            removed.sort();
+            assert_eq!(removed, vec![k1, k3]);
+            assert_eq!(rs.lock_rsr().len(), 1);
+            assert!(rs.lock_rsr().service_group("bar").contains_id("baz"));
+            assert!(rs.remove_expired_rsw(|r| r.id == "foo").is_empty());
+        }
     }
 }
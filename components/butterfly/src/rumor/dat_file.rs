@@ -123,7 +123,7 @@ impl DatFileReader {
     /// * `RumorHeat::inner` (write)
     /// * `ManagerServices::inner` (read)
     pub fn read_into_rsw_mlw_rhw_msr(&mut self, server: &Server) -> Result<()> {
-        for Membership { member, health } in self.read_members()? {
+        for Membership { member, health, .. } in self.read_members()? {
             server.insert_member_mlw_rhw(member, health);
         }
 
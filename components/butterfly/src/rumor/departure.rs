@@ -6,6 +6,7 @@
 
 use crate::{error::{Error,
                     Result},
+            identity::MemberIdentity,
             protocol::{self,
                        newscast::{self,
                                   Rumor as ProtoRumor},
@@ -14,12 +15,19 @@ use crate::{error::{Error,
                     Rumor,
                     RumorPayload,
                     RumorType}};
+use bytes::BytesMut;
+use prost::Message as ProstMessage;
 use std::{cmp::Ordering,
           fmt};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Departure {
     pub member_id: String,
+    /// The member that issued the departure, when members have identities
+    #[serde(skip)]
+    pub signed_by: Option<String>,
+    #[serde(skip)]
+    pub signature: Option<Vec<u8>>,
 }
 
 impl fmt::Display for Departure {
@@ -29,7 +37,39 @@ impl fmt::Display for Departure {
 }
 
 impl Departure {
-    pub fn new(member_id: &str) -> Self { Departure { member_id: member_id.to_string(), } }
+    pub fn new(member_id: &str) -> Self {
+        Departure { member_id: member_id.to_string(),
+                    signed_by: None,
+                    signature: None, }
+    }
+
+    /// Sign this rumor as the member that issued it.
+    pub fn sign(&mut self, identity: &MemberIdentity) -> Result<()> {
+        self.signed_by = Some(identity.member_id().to_string());
+        self.signature = Some(identity.sign(&self.signed_bytes()?)?);
+        Ok(())
+    }
+
+    /// Check that this rumor was signed by the trusted member that issued it. Any member may
+    /// depart another.
+    pub fn verify(&self, identity: &MemberIdentity) -> Result<()> {
+        let signed_by = self.signed_by
+                            .as_ref()
+                            .ok_or(Error::ProtocolMismatch("signed-by"))?;
+        let signature = self.signature
+                            .as_ref()
+                            .ok_or(Error::ProtocolMismatch("signature"))?;
+        identity.verify(signed_by, &self.signed_bytes()?, signature)
+    }
+
+    /// Everything but the signature, as it is encoded on the wire.
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let mut unsigned = newscast::Departure::from(self.clone());
+        unsigned.signature = None;
+        let mut buf = BytesMut::with_capacity(unsigned.encoded_len());
+        unsigned.encode(&mut buf)?;
+        Ok(buf.to_vec())
+    }
 }
 
 impl protocol::Message<ProtoRumor> for Departure {
@@ -43,12 +83,18 @@ impl FromProto<ProtoRumor> for Departure {
             _ => panic!("from-bytes departure"),
         };
         Ok(Departure { member_id: payload.member_id
-                                         .ok_or(Error::ProtocolMismatch("member-id"))?, })
+                                         .ok_or(Error::ProtocolMismatch("member-id"))?,
+                       signed_by: payload.signed_by,
+                       signature: payload.signature, })
     }
 }
 
 impl From<Departure> for newscast::Departure {
-    fn from(value: Departure) -> Self { newscast::Departure { member_id: Some(value.member_id), } }
+    fn from(value: Departure) -> Self {
+        newscast::Departure { member_id: Some(value.member_id),
+                              signed_by: value.signed_by,
+                              signature: value.signature, }
+    }
 }
 
 impl Rumor for Departure {
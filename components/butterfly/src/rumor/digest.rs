@@ -0,0 +1,119 @@
+//! The Digest message.
+//!
+//! A digest lists the rumors a member holds by key and version, without their contents, so that
+//! two members can work out which rumors one of them is missing or behind on, and send only
+//! those. It travels the ring like a rumor, but is never stored.
+
+use crate::{error::{Error,
+                    Result},
+            protocol::{newscast::{self,
+                                  Rumor as ProtoRumor},
+                       FromProto},
+            rumor::{RumorKey,
+                    RumorPayload,
+                    RumorType}};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DigestEntry {
+    pub kind:    RumorType,
+    pub key:     String,
+    pub id:      String,
+    pub version: u64,
+}
+
+impl DigestEntry {
+    pub fn new(rumor_key: RumorKey, version: u64) -> Self {
+        DigestEntry { kind: rumor_key.kind,
+                      key: rumor_key.key,
+                      id: rumor_key.id,
+                      version }
+    }
+
+    pub fn rumor_key(&self) -> RumorKey { RumorKey::new(self.kind, &*self.id, &*self.key) }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Digest {
+    pub entries:  Vec<DigestEntry>,
+    /// Whether the entries cover every rumor the sender holds. If not, they are only the rumors
+    /// the sender wants.
+    pub complete: bool,
+}
+
+impl FromProto<ProtoRumor> for Digest {
+    fn from_proto(rumor: ProtoRumor) -> Result<Self> {
+        let payload = match rumor.payload.ok_or(Error::ProtocolMismatch("payload"))? {
+            RumorPayload::Digest(payload) => payload,
+            _ => panic!("from-bytes digest"),
+        };
+        let mut entries = Vec::with_capacity(payload.entries.len());
+        for entry in payload.entries {
+            // A member that knows of more kinds of rumor than we do may list them; we
+            // couldn't make use of them anyway.
+            let kind = match entry.r#type.and_then(RumorType::from_i32) {
+                Some(kind) => kind,
+                None => continue,
+            };
+            entries.push(DigestEntry { kind,
+                                       key: entry.key.ok_or(Error::ProtocolMismatch("key"))?,
+                                       id: entry.id.ok_or(Error::ProtocolMismatch("id"))?,
+                                       version: entry.version.unwrap_or(0) });
+        }
+        Ok(Digest { entries,
+                    complete: payload.complete.unwrap_or(false) })
+    }
+}
+
+impl From<Digest> for newscast::Digest {
+    fn from(value: Digest) -> Self {
+        let entries = value.entries
+                           .into_iter()
+                           .map(|entry| {
+                               newscast::digest::Entry { r#type:  Some(entry.kind as i32),
+                                                         key:     Some(entry.key),
+                                                         id:      Some(entry.id),
+                                                         version: Some(entry.version), }
+                           })
+                           .collect();
+        newscast::Digest { entries,
+                           complete: Some(value.complete) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Digest,
+                DigestEntry};
+    use crate::rumor::{RumorEnvelope,
+                       RumorKey,
+                       RumorKind,
+                       RumorType};
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let digest =
+            Digest { entries:  vec![DigestEntry::new(RumorKey::new(RumorType::Service,
+                                                                   "member",
+                                                                   "redis.prod"),
+                                                     3),
+                                    DigestEntry::new(RumorKey::new(RumorType::ServiceConfig,
+                                                                   "service_config",
+                                                                   "redis.prod"),
+                                                     1),],
+                     complete: true, };
+        let envelope = RumorEnvelope { r#type:  RumorType::Digest,
+                                       from_id: "member".to_string(),
+                                       kind:    RumorKind::Digest(digest.clone()), };
+        let bytes = envelope.encode().unwrap();
+        match RumorEnvelope::decode(&bytes).unwrap().kind {
+            RumorKind::Digest(decoded) => assert_eq!(decoded, digest),
+            kind => panic!("Decoded a digest as {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn entry_has_the_rumor_key() {
+        let key = RumorKey::new(RumorType::ServiceFile, "config.toml", "redis.prod");
+        assert_eq!(DigestEntry::new(key.clone(), 7).rumor_key(), key);
+    }
+}
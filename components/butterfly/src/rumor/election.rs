@@ -12,6 +12,7 @@ pub use crate::protocol::newscast::{election::Status as ElectionStatus,
                                     Election as ProtoElection};
 use crate::{error::{Error,
                     Result},
+            identity::MemberIdentity,
             protocol::{self,
                        newscast::{self,
                                   Rumor as ProtoRumor},
@@ -20,7 +21,10 @@ use crate::{error::{Error,
                     Rumor,
                     RumorPayload,
                     RumorType}};
-use std::{fmt,
+use bytes::BytesMut;
+use prost::Message as ProstMessage;
+use std::{cmp,
+          fmt,
           ops::{Deref,
                 DerefMut}};
 
@@ -30,18 +34,45 @@ pub trait ElectionRumor: ConstIdRumor {
     fn is_finished(&self) -> bool;
 
     fn term(&self) -> u64;
+
+    fn min_zones(&self) -> u32;
 }
 
 pub type Term = u64;
 
+/// How a leader-topology service group elects its leader, beyond
+/// suitability. Zones come from the `zone` tag of each member (see
+/// `member::ZONE_TAG`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ElectionPolicy {
+    /// Prefer a leader whose Supervisor is in this zone, over any more
+    /// suitable member outside of it.
+    pub preferred_zone: Option<String>,
+    /// Only have quorum when the alive members of the service group
+    /// span at least this many zones.
+    pub min_zones: u32,
+}
+
+impl ElectionPolicy {
+    /// Whether a member in `zone` is in the preferred zone.
+    pub fn prefers(&self, zone: Option<&String>) -> bool {
+        self.preferred_zone.is_some() && self.preferred_zone.as_ref() == zone
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct Election {
-    pub member_id:     String,
-    pub service_group: String,
-    pub term:          u64,
-    pub suitability:   u64,
-    pub status:        ElectionStatus,
-    pub votes:         Vec<String>,
+    pub member_id:         String,
+    pub service_group:     String,
+    pub term:              u64,
+    pub suitability:       u64,
+    pub status:            ElectionStatus,
+    pub votes:             Vec<String>,
+    pub in_preferred_zone: bool,
+    pub min_zones:         u32,
+    /// Signed by the candidate when members have identities
+    #[serde(skip)]
+    pub signature:         Option<Vec<u8>>,
 }
 
 impl fmt::Display for Election {
@@ -73,9 +104,24 @@ impl Election {
                    } else {
                        ElectionStatus::NoQuorum
                    },
-                   votes: vec![from_id] }
+                   votes: vec![from_id],
+                   in_preferred_zone: false,
+                   min_zones: 0,
+                   signature: None }
+    }
+
+    /// Apply a service group's election policy to this member's
+    /// candidacy, given the zone this member is in.
+    pub fn with_policy(mut self, policy: &ElectionPolicy, zone: Option<&String>) -> Self {
+        self.in_preferred_zone = policy.prefers(zone);
+        self.min_zones = policy.min_zones;
+        self
     }
 
+    /// Candidates in the preferred zone win over more suitable ones
+    /// outside of it.
+    fn rank(&self) -> (bool, u64) { (self.in_preferred_zone, self.suitability) }
+
     /// Insert a vote for the election.
     pub fn insert_vote(&mut self, member_id: &str) {
         if !self.votes.contains(&String::from(member_id)) {
@@ -98,6 +144,35 @@ impl Election {
 
     /// Sets the status of the election to "NoQuorum"
     pub fn no_quorum(&mut self) { self.status = ElectionStatus::NoQuorum; }
+
+    /// Sign this rumor as its candidate.
+    pub fn sign(&mut self, identity: &MemberIdentity) -> Result<()> {
+        self.signature = Some(identity.sign(&self.signed_bytes()?)?);
+        Ok(())
+    }
+
+    /// Check that this rumor was signed by its candidate.
+    pub fn verify(&self, identity: &MemberIdentity) -> Result<()> {
+        let signature = self.signature
+                            .as_ref()
+                            .ok_or(Error::ProtocolMismatch("signature"))?;
+        identity.verify(&self.member_id, &self.signed_bytes()?, signature)
+    }
+
+    /// The candidacy, as it is encoded on the wire. Every member that hears of an election adds
+    /// its vote, and sets the status it sees while it runs, so the votes are not covered, and the
+    /// status only once the candidate has finished the election.
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let mut unsigned = newscast::Election::from(self.clone());
+        if !self.is_finished() {
+            unsigned.status = None;
+        }
+        unsigned.votes.clear();
+        unsigned.signature = None;
+        let mut buf = BytesMut::with_capacity(unsigned.encoded_len());
+        unsigned.encode(&mut buf)?;
+        Ok(buf.to_vec())
+    }
 }
 
 impl ElectionRumor for Election {
@@ -106,6 +181,8 @@ impl ElectionRumor for Election {
     fn is_finished(&self) -> bool { self.status == ElectionStatus::Finished }
 
     fn term(&self) -> u64 { self.term }
+
+    fn min_zones(&self) -> u32 { self.min_zones }
 }
 
 impl PartialEq for Election {
@@ -117,6 +194,8 @@ impl PartialEq for Election {
         && self.votes == other.votes
         && self.status == other.status
         && self.term == other.term
+        && self.in_preferred_zone == other.in_preferred_zone
+        && self.min_zones == other.min_zones
     }
 }
 
@@ -131,26 +210,32 @@ impl FromProto<ProtoRumor> for Election {
             _ => panic!("from-bytes election"),
         };
         let from_id = rumor.from_id.ok_or(Error::ProtocolMismatch("from-id"))?;
-        Ok(Election { member_id:     from_id.clone(),
-                      service_group: payload.service_group
-                                            .ok_or(Error::ProtocolMismatch("service-group"))?,
-                      term:          payload.term.unwrap_or(0),
-                      suitability:   payload.suitability.unwrap_or(0),
-                      status:        payload.status
-                                            .and_then(ElectionStatus::from_i32)
-                                            .unwrap_or(ElectionStatus::Running),
-                      votes:         payload.votes, })
+        Ok(Election { member_id:         from_id.clone(),
+                      service_group:     payload.service_group
+                                                .ok_or(Error::ProtocolMismatch("service-group"))?,
+                      term:              payload.term.unwrap_or(0),
+                      suitability:       payload.suitability.unwrap_or(0),
+                      status:            payload.status
+                                                .and_then(ElectionStatus::from_i32)
+                                                .unwrap_or(ElectionStatus::Running),
+                      votes:             payload.votes,
+                      in_preferred_zone: payload.in_preferred_zone.unwrap_or(false),
+                      min_zones:         payload.min_zones.unwrap_or(0),
+                      signature:         payload.signature, })
     }
 }
 
 impl From<Election> for newscast::Election {
     fn from(value: Election) -> Self {
-        newscast::Election { member_id:     Some(value.member_id),
-                             service_group: Some(value.service_group.to_string()),
-                             term:          Some(value.term),
-                             suitability:   Some(value.suitability),
-                             status:        Some(value.status as i32),
-                             votes:         value.votes, }
+        newscast::Election { member_id:         Some(value.member_id),
+                             service_group:     Some(value.service_group.to_string()),
+                             term:              Some(value.term),
+                             suitability:       Some(value.suitability),
+                             status:            Some(value.status as i32),
+                             votes:             value.votes,
+                             in_preferred_zone: Some(value.in_preferred_zone),
+                             min_zones:         Some(value.min_zones),
+                             signature:         value.signature, }
     }
 }
 
@@ -172,11 +257,11 @@ impl Rumor for Election {
         } else if self.term > other.term {
             debug!("stored rumor represents a newer term than received; keep sharing it");
             true
-        } else if self.suitability > other.suitability {
+        } else if self.rank() > other.rank() {
             debug!("stored rumor is more suitable; take received rumor's votes and share");
             self.steal_votes(&mut other);
             true
-        } else if other.suitability > self.suitability {
+        } else if other.rank() > self.rank() {
             debug!("received rumor is more suitable; take stored rumor's votes, replace stored \
                     and share");
             other.steal_votes(self);
@@ -197,6 +282,19 @@ impl Rumor for Election {
 
     fn kind(&self) -> RumorType { RumorType::Election }
 
+    /// Later terms are ahead of earlier ones, and within a term, a finished election is ahead of
+    /// a running one, which is further ahead the more votes it has.
+    fn version(&self) -> u64 {
+        let term = cmp::min(self.term, u64::from(u32::max_value()));
+        let finished = if self.status == ElectionStatus::Finished {
+            1
+        } else {
+            0
+        };
+        let votes = cmp::min(self.votes.len() as u64, u64::from(u32::max_value() >> 1));
+        (term << 32) | (finished << 31) | votes
+    }
+
     fn id(&self) -> &str { Self::const_id() }
 
     fn key(&self) -> &str { self.service_group.as_ref() }
@@ -241,6 +339,8 @@ impl ElectionRumor for ElectionUpdate {
     fn is_finished(&self) -> bool { self.status == ElectionStatus::Finished }
 
     fn term(&self) -> u64 { self.term }
+
+    fn min_zones(&self) -> u32 { self.min_zones }
 }
 
 impl Deref for ElectionUpdate {
@@ -276,6 +376,8 @@ impl Rumor for ElectionUpdate {
 
     fn kind(&self) -> RumorType { RumorType::ElectionUpdate }
 
+    fn version(&self) -> u64 { self.0.version() }
+
     fn id(&self) -> &str { Self::const_id() }
 
     fn key(&self) -> &str { self.0.key() }
@@ -287,13 +389,17 @@ impl ConstIdRumor for ElectionUpdate {
 
 #[cfg(test)]
 mod tests {
-    use crate::rumor::{election::{Election,
-                                  ElectionUpdate,
-                                  Term},
-                       ConstIdRumor as _,
-                       Rumor,
-                       RumorStore};
-    use habitat_core::service::ServiceGroup;
+    use crate::{identity::MemberIdentity,
+                protocol::Message,
+                rumor::{election::{Election,
+                                   ElectionPolicy,
+                                   ElectionUpdate,
+                                   Term},
+                        ConstIdRumor as _,
+                        Rumor,
+                        RumorStore}};
+    use habitat_core::{crypto::SigKeyPair,
+                       service::ServiceGroup};
 
     fn create_election_rumor_store() -> RumorStore<Election> { RumorStore::default() }
 
@@ -315,6 +421,32 @@ mod tests {
                             true /* has_quorum */)
     }
 
+    #[test]
+    fn signature_covers_the_candidacy_and_a_finish() {
+        let key = SigKeyPair::generate_pair_for_member("a").unwrap();
+        let identity = MemberIdentity::new(key, Vec::new());
+        let mut e1 = create_election("a", 1);
+        e1.sign(&identity).unwrap();
+
+        // Other members vote, and set the status they see
+        e1.insert_vote("b");
+        e1.no_quorum();
+        assert!(e1.verify(&identity).is_ok());
+
+        let mut forged = e1.clone();
+        forged.suitability = 100;
+        assert!(forged.verify(&identity).is_err());
+
+        let mut forged = e1.clone();
+        forged.finish();
+        assert!(forged.verify(&identity).is_err());
+
+        e1.finish();
+        e1.sign(&identity).unwrap();
+        let e2 = Election::from_bytes(&e1.clone().write_to_bytes().unwrap()).unwrap();
+        assert!(e2.verify(&identity).is_ok());
+    }
+
     #[test]
     fn only_the_latest_election_is_kept() {
         let rs = create_election_rumor_store();
@@ -347,6 +479,21 @@ mod tests {
         assert!(sub_list.get(ElectionUpdate::const_id()).is_some());
     }
 
+    #[test]
+    fn version_orders_terms_then_finished_then_votes() {
+        let running = create_election("a", 0);
+        let mut more_votes = running.clone();
+        more_votes.insert_vote("b");
+        let mut finished = running.clone();
+        finished.finish();
+        let mut next_term = running.clone();
+        next_term.term += 1;
+
+        assert!(more_votes.version() > running.version());
+        assert!(finished.version() > more_votes.version());
+        assert!(next_term.version() > finished.version());
+    }
+
     #[test]
     fn merge_two_identical_elections_returns_false() {
         let mut e1 = create_election("a", 0);
@@ -367,6 +514,40 @@ mod tests {
         assert_eq!(e1.votes.len(), 4);
     }
 
+    #[test]
+    fn merge_prefers_a_candidate_in_the_preferred_zone() {
+        let policy = ElectionPolicy { preferred_zone: Some("east".to_string()),
+                                      min_zones:      2, };
+        let east = "east".to_string();
+        let west = "west".to_string();
+        let mut e1 = create_election("a", 10).with_policy(&policy, Some(&west));
+        let e2 = create_election("b", 0).with_policy(&policy, Some(&east));
+        let e3 = create_election("c", 5).with_policy(&policy, None);
+        assert_eq!(e1.merge(e2), true);
+        assert_eq!(e1.merge(e3), true);
+        assert_eq!(e1.member_id, "b");
+        assert!(e1.in_preferred_zone);
+        assert_eq!(e1.min_zones, 2);
+        assert_eq!(e1.votes.len(), 3);
+    }
+
+    #[test]
+    fn no_zone_is_preferred_without_a_preferred_zone() {
+        let policy = ElectionPolicy::default();
+        assert!(!policy.prefers(Some(&"east".to_string())));
+        assert!(!policy.prefers(None));
+    }
+
+    #[test]
+    fn policy_survives_encode_decode_roundtrip() {
+        let policy = ElectionPolicy { preferred_zone: Some("east".to_string()),
+                                      min_zones:      3, };
+        let e1 = create_election("a", 1).with_policy(&policy, Some(&"east".to_string()));
+        let bytes = e1.clone().write_to_bytes().unwrap();
+        let e2 = Election::from_bytes(&bytes).unwrap();
+        assert_eq!(e1, e2);
+    }
+
     #[test]
     fn merge_four() {
         let mut e1 = create_election("a", 0);
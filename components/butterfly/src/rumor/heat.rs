@@ -73,12 +73,31 @@ pub(crate) mod sync {
                     .filter(|&(_, heat)| heat < RumorShareLimit::configured_value().0)
                     .collect();
 
-            // Reverse sorting by heat; 0s come last!
-            rumor_heat.sort_by(|&(_, ref h1), &(_, ref h2)| h2.cmp(h1));
+            // Reverse sorting by heat; 0s come last! Rumors of equal heat are
+            // ordered by key, so that the same heat always gives the same list.
+            rumor_heat.sort_by(|&(ref k1, ref h1), &(ref k2, ref h2)| {
+                          h2.cmp(h1).then_with(|| k1.cmp(k2))
+                      });
 
             // We don't need the heat anymore, just return the rumors.
             rumor_heat.into_iter().map(|(k, _)| k).collect()
         }
+
+        /// Return how many times a rumor has been shared in total,
+        /// and the number of members it has cooled off for.
+        ///
+        /// # Locking (see locking.md)
+        /// * `RumorHeat::inner` (read)
+        pub fn heat_of(&self, rumor: &RumorKey) -> (usize, usize) {
+            let limit = RumorShareLimit::configured_value().0;
+            self.0
+                .get(rumor)
+                .map(|heat_map| {
+                    (heat_map.values().sum(),
+                     heat_map.values().filter(|&&heat| heat >= limit).count())
+                })
+                .unwrap_or((0, 0))
+        }
     }
 
     pub struct RumorHeatWriteGuard<'a>(WriteGuard<'a, RumorHeatInner>);
@@ -166,6 +185,21 @@ pub(crate) mod sync {
             }
             debug!("Purged {} heat count entries for {:?}", count, id);
         }
+
+        /// Stop sharing a rumor that has been removed from its store.
+        ///
+        /// # Locking (see locking.md)
+        /// * `RumorHeat::inner` (write)
+        pub fn forget(&mut self, rumor: &RumorKey) { self.0.remove(rumor); }
+
+        /// Stop sharing every rumor of a service group that has been
+        /// purged.
+        ///
+        /// # Locking (see locking.md)
+        /// * `RumorHeat::inner` (write)
+        pub fn forget_service_group(&mut self, service_group: &str) {
+            self.0.retain(|k, _| k.key != service_group);
+        }
     }
 
     /// Tracks the number of times a given rumor has been sent to each
@@ -577,4 +611,23 @@ mod tests {
         let expected_hot_rumors = &[warm_key.clone(), hot_key.clone()];
         assert_eq!(rumors, expected_hot_rumors);
     }
+
+    #[test]
+    fn heat_counts_shares_and_cooled_members() {
+        let l = lock_rumor_limit();
+        l.unset();
+
+        let heat = RumorHeat::default();
+        let rumor = FakeRumor::default();
+        let key = RumorKey::from(&rumor);
+        assert_eq!(heat.lock_rhr().heat_of(&key), (0, 0));
+
+        heat.lock_rhw().start_hot_rumor(&rumor);
+        heat.lock_rhw().cool_rumors("test_member_1", &[key.clone()]);
+        assert_eq!(heat.lock_rhr().heat_of(&key), (1, 0));
+
+        cool_rumor_completely_rhw(&heat, "test_member_2", &rumor);
+        assert_eq!(heat.lock_rhr().heat_of(&key),
+                   (1 + RumorShareLimit::default().0, 1));
+    }
 }
@@ -0,0 +1,241 @@
+//! The Purge rumor.
+//!
+//! Purge rumors drop every rumor of a service group ring-wide, for service groups that have been
+//! retired. While a member holds the purge, it turns away rumors of the service group that are
+//! still making their way around the ring; once the purge is older than the rumor TTL, it is
+//! dropped itself and the service group can be used again.
+//!
+//! The age of a purge is measured against the wall clock of each member from the time on the
+//! clock of the member it was requested on, so members whose clocks are skewed expire it
+//! correspondingly earlier or later. This is harmless as long as the skew is small next to the
+//! TTL, since members turn away purges that they consider expired, as well as purges dated more
+//! than the TTL ahead of their clock, which would otherwise outlive it.
+
+use crate::{error::{Error,
+                    Result},
+            identity::MemberIdentity,
+            protocol::{self,
+                       newscast::{self,
+                                  Rumor as ProtoRumor},
+                       FromProto},
+            rumor::{ConstKeyRumor,
+                    Rumor,
+                    RumorPayload,
+                    RumorType}};
+use bytes::BytesMut;
+use prost::Message as ProstMessage;
+use std::{fmt,
+          time::{Duration,
+                 SystemTime,
+                 UNIX_EPOCH}};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Purge {
+    pub service_group: String,
+    /// When the purge was requested, in seconds since the Unix epoch.
+    pub purged_at:     u64,
+    /// The member that issued the purge, when members have identities
+    #[serde(skip)]
+    pub signed_by:     Option<String>,
+    #[serde(skip)]
+    pub signature:     Option<Vec<u8>>,
+}
+
+impl fmt::Display for Purge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Purge sg/{} at {}", self.service_group, self.purged_at)
+    }
+}
+
+impl Purge {
+    /// A purge of the service group, requested now.
+    pub fn new(service_group: &str) -> Self {
+        Purge { service_group: service_group.to_string(),
+                purged_at:     now_secs().unwrap_or(0),
+                signed_by:     None,
+                signature:     None, }
+    }
+
+    /// Whether the purge was requested more than `ttl` ago, by our clock.
+    pub fn is_expired(&self, ttl: Duration) -> bool {
+        now_secs().map(|now| now > self.purged_at.saturating_add(ttl.as_secs()))
+                  .unwrap_or(false)
+    }
+
+    /// Whether the purge claims to have been requested more than `max_skew` from now, by our
+    /// clock.
+    pub fn is_future(&self, max_skew: Duration) -> bool {
+        now_secs().map(|now| self.purged_at > now.saturating_add(max_skew.as_secs()))
+                  .unwrap_or(false)
+    }
+
+    /// Sign this rumor as the member that issued it.
+    pub fn sign(&mut self, identity: &MemberIdentity) -> Result<()> {
+        self.signed_by = Some(identity.member_id().to_string());
+        self.signature = Some(identity.sign(&self.signed_bytes()?)?);
+        Ok(())
+    }
+
+    /// Check that this rumor was signed by the trusted member that issued it. Any member may
+    /// purge a service group.
+    pub fn verify(&self, identity: &MemberIdentity) -> Result<()> {
+        let signed_by = self.signed_by
+                            .as_ref()
+                            .ok_or(Error::ProtocolMismatch("signed-by"))?;
+        let signature = self.signature
+                            .as_ref()
+                            .ok_or(Error::ProtocolMismatch("signature"))?;
+        identity.verify(signed_by, &self.signed_bytes()?, signature)
+    }
+
+    /// Everything but the signature, as it is encoded on the wire.
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let mut unsigned = newscast::Purge::from(self.clone());
+        unsigned.signature = None;
+        let mut buf = BytesMut::with_capacity(unsigned.encoded_len());
+        unsigned.encode(&mut buf)?;
+        Ok(buf.to_vec())
+    }
+}
+
+/// The time by our clock, in seconds since the Unix epoch.
+fn now_secs() -> Option<u64> {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+                     .map(|now| now.as_secs())
+                     .ok()
+}
+
+impl protocol::Message<ProtoRumor> for Purge {
+    const MESSAGE_ID: &'static str = "Purge";
+}
+
+impl FromProto<ProtoRumor> for Purge {
+    fn from_proto(rumor: ProtoRumor) -> Result<Self> {
+        let payload = match rumor.payload.ok_or(Error::ProtocolMismatch("payload"))? {
+            RumorPayload::Purge(payload) => payload,
+            _ => panic!("from-bytes purge"),
+        };
+        Ok(Purge { service_group: payload.service_group
+                                         .ok_or(Error::ProtocolMismatch("service-group"))?,
+                   purged_at:     payload.purged_at.unwrap_or(0),
+                   signed_by:     payload.signed_by,
+                   signature:     payload.signature, })
+    }
+}
+
+impl From<Purge> for newscast::Purge {
+    fn from(value: Purge) -> Self {
+        newscast::Purge { service_group: Some(value.service_group),
+                          purged_at:     Some(value.purged_at),
+                          signed_by:     value.signed_by,
+                          signature:     value.signature, }
+    }
+}
+
+impl Rumor for Purge {
+    /// A later purge of the same service group replaces an earlier one, so that a service group
+    /// can be purged again.
+    fn merge(&mut self, other: Purge) -> bool {
+        if other.purged_at > self.purged_at {
+            *self = other;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn kind(&self) -> RumorType { RumorType::Purge }
+
+    fn version(&self) -> u64 { self.purged_at }
+
+    fn key(&self) -> &str { Self::const_key() }
+
+    fn id(&self) -> &str { &self.service_group }
+}
+
+impl ConstKeyRumor for Purge {
+    fn const_key() -> &'static str { "purge" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Purge;
+    use crate::{identity::MemberIdentity,
+                protocol::Message,
+                rumor::{ConstKeyRumor as _,
+                        Rumor,
+                        RumorStore}};
+    use habitat_core::crypto::SigKeyPair;
+    use std::time::Duration;
+
+    fn create_purge(service_group: &str, purged_at: u64) -> Purge {
+        Purge { purged_at,
+                ..Purge::new(service_group) }
+    }
+
+    #[test]
+    fn multiple_purges_are_all_under_the_same_key() {
+        let rs: RumorStore<Purge> = RumorStore::default();
+        rs.insert_rsw(create_purge("redis.prod", 1));
+        rs.insert_rsw(create_purge("redis.dev", 1));
+
+        let list = rs.lock_rsr();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.get(Purge::const_key()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn merge_takes_the_later_purge() {
+        let mut p1 = create_purge("redis.prod", 10);
+        assert_eq!(p1.merge(create_purge("redis.prod", 5)), false);
+        assert_eq!(p1.purged_at, 10);
+        assert_eq!(p1.merge(create_purge("redis.prod", 10)), false);
+        assert_eq!(p1.merge(create_purge("redis.prod", 20)), true);
+        assert_eq!(p1.purged_at, 20);
+    }
+
+    #[test]
+    fn purge_expires_after_the_ttl() {
+        let p = Purge::new("redis.prod");
+        assert!(!p.is_expired(Duration::from_secs(60)));
+        assert!(create_purge("redis.prod", 0).is_expired(Duration::from_secs(60)));
+        assert!(!create_purge("redis.prod", u64::max_value()).is_expired(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn purge_from_beyond_the_skew_is_future() {
+        let p = Purge::new("redis.prod");
+        assert!(!p.is_future(Duration::from_secs(60)));
+        assert!(!create_purge("redis.prod", p.purged_at + 30).is_future(Duration::from_secs(60)));
+        assert!(create_purge("redis.prod", p.purged_at + 120).is_future(Duration::from_secs(60)));
+        assert!(create_purge("redis.prod", u64::max_value()).is_future(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn signature_covers_the_service_group_and_time() {
+        let key = SigKeyPair::generate_pair_for_member("a").unwrap();
+        let identity = MemberIdentity::new(key, Vec::new());
+        let mut p1 = create_purge("redis.prod", 1234);
+        assert!(p1.verify(&identity).is_err());
+        p1.sign(&identity).unwrap();
+
+        let p2 = Purge::from_bytes(&p1.clone().write_to_bytes().unwrap()).unwrap();
+        assert!(p2.verify(&identity).is_ok());
+
+        let mut forged = p1.clone();
+        forged.service_group = "redis.dev".to_string();
+        assert!(forged.verify(&identity).is_err());
+
+        let mut forged = p1.clone();
+        forged.purged_at = 5678;
+        assert!(forged.verify(&identity).is_err());
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let p1 = create_purge("redis.prod", 1234);
+        let bytes = p1.clone().write_to_bytes().unwrap();
+        let p2 = Purge::from_bytes(&bytes).unwrap();
+        assert_eq!(p1, p2);
+    }
+}
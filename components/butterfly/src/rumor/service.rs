@@ -4,14 +4,17 @@
 
 use crate::{error::{Error,
                     Result},
+            identity::MemberIdentity,
             protocol::{self,
                        newscast,
                        FromProto},
             rumor::{Rumor,
                     RumorPayload,
                     RumorType}};
+use bytes::BytesMut;
 use habitat_core::{package::Identifiable,
                    service::ServiceGroup};
+use prost::Message as ProstMessage;
 use serde::{ser::SerializeStruct,
             Serialize,
             Serializer};
@@ -24,13 +27,18 @@ use toml;
 
 #[derive(Debug, Clone)]
 pub struct Service {
-    pub member_id:     String,
+    pub member_id: String,
     pub service_group: ServiceGroup,
-    pub incarnation:   u64,
-    pub initialized:   bool,
-    pub pkg:           String,
-    pub cfg:           Vec<u8>,
-    pub sys:           SysInfo,
+    pub incarnation: u64,
+    pub initialized: bool,
+    pub pkg: String,
+    pub cfg: Vec<u8>,
+    pub sys: SysInfo,
+    pub health_check_passed: bool,
+    pub ready: bool,
+    pub blocked_releases: Vec<String>,
+    /// Signed by the member when members have identities
+    pub signature: Option<Vec<u8>>,
 }
 
 impl fmt::Display for Service {
@@ -46,7 +54,7 @@ impl Serialize for Service {
     fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
         where S: Serializer
     {
-        let mut strukt = serializer.serialize_struct("service", 7)?;
+        let mut strukt = serializer.serialize_struct("service", 10)?;
         let cfg: toml::value::Table = toml::from_slice(&self.cfg).unwrap_or_default();
         strukt.serialize_field("member_id", &self.member_id)?;
         strukt.serialize_field("service_group", &self.service_group)?;
@@ -55,6 +63,9 @@ impl Serialize for Service {
         strukt.serialize_field("cfg", &cfg)?;
         strukt.serialize_field("sys", &self.sys)?;
         strukt.serialize_field("initialized", &self.initialized)?;
+        strukt.serialize_field("health_check_passed", &self.health_check_passed)?;
+        strukt.serialize_field("ready", &self.ready)?;
+        strukt.serialize_field("blocked_releases", &self.blocked_releases)?;
         strukt.end()
     }
 }
@@ -98,6 +109,10 @@ impl Service {
                   service_group,
                   incarnation: 0,
                   initialized: false,
+                  health_check_passed: false,
+                  ready: true,
+                  blocked_releases: Vec::new(),
+                  signature: None,
                   pkg: package.to_string(),
                   sys,
                   cfg: cfg.map(|v| {
@@ -111,6 +126,31 @@ impl Service {
     }
 }
 
+impl Service {
+    /// Sign this rumor as its member.
+    pub fn sign(&mut self, identity: &MemberIdentity) -> Result<()> {
+        self.signature = Some(identity.sign(&self.signed_bytes()?)?);
+        Ok(())
+    }
+
+    /// Check that this rumor was signed by its member.
+    pub fn verify(&self, identity: &MemberIdentity) -> Result<()> {
+        let signature = self.signature
+                            .as_ref()
+                            .ok_or(Error::ProtocolMismatch("signature"))?;
+        identity.verify(&self.member_id, &self.signed_bytes()?, signature)
+    }
+
+    /// Everything but the signature, as it is encoded on the wire.
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let mut unsigned = newscast::Service::from(self.clone());
+        unsigned.signature = None;
+        let mut buf = BytesMut::with_capacity(unsigned.encoded_len());
+        unsigned.encode(&mut buf)?;
+        Ok(buf.to_vec())
+    }
+}
+
 impl protocol::Message<newscast::Rumor> for Service {
     const MESSAGE_ID: &'static str = "Service";
 }
@@ -121,31 +161,41 @@ impl FromProto<newscast::Rumor> for Service {
             RumorPayload::Service(payload) => payload,
             _ => panic!("from-bytes service"),
         };
-        Ok(Service { member_id:     payload.member_id
-                                           .ok_or(Error::ProtocolMismatch("member-id"))?,
+        // Supervisors that predate readiness checks do not send
+        // `ready`; their members are always ready.
+        Ok(Service { member_id:           payload.member_id
+                                                 .ok_or(Error::ProtocolMismatch("member-id"))?,
                      service_group:
                          payload.service_group
                                 .ok_or(Error::ProtocolMismatch("service-group"))
                                 .and_then(|s| ServiceGroup::from_str(&s).map_err(Error::from))?,
-                     incarnation:   payload.incarnation.unwrap_or(0),
-                     initialized:   payload.initialized.unwrap_or(false),
-                     pkg:           payload.pkg.ok_or(Error::ProtocolMismatch("pkg"))?,
-                     cfg:           payload.cfg.unwrap_or_default(),
-                     sys:           payload.sys
-                                           .ok_or(Error::ProtocolMismatch("sys"))
-                                           .and_then(SysInfo::from_proto)?, })
+                     incarnation:         payload.incarnation.unwrap_or(0),
+                     initialized:         payload.initialized.unwrap_or(false),
+                     pkg:                 payload.pkg.ok_or(Error::ProtocolMismatch("pkg"))?,
+                     cfg:                 payload.cfg.unwrap_or_default(),
+                     sys:                 payload.sys
+                                                 .ok_or(Error::ProtocolMismatch("sys"))
+                                                 .and_then(SysInfo::from_proto)?,
+                     health_check_passed: payload.health_check_passed.unwrap_or(false),
+                     ready:               payload.ready.unwrap_or(true),
+                     blocked_releases:    payload.blocked_releases,
+                     signature:           payload.signature, })
     }
 }
 
 impl From<Service> for newscast::Service {
     fn from(value: Service) -> Self {
-        newscast::Service { member_id:     Some(value.member_id),
-                            service_group: Some(value.service_group.to_string()),
-                            incarnation:   Some(value.incarnation),
-                            initialized:   Some(value.initialized),
-                            pkg:           Some(value.pkg),
-                            cfg:           Some(value.cfg),
-                            sys:           Some(value.sys.into()), }
+        newscast::Service { member_id:           Some(value.member_id),
+                            service_group:       Some(value.service_group.to_string()),
+                            incarnation:         Some(value.incarnation),
+                            initialized:         Some(value.initialized),
+                            pkg:                 Some(value.pkg),
+                            cfg:                 Some(value.cfg),
+                            sys:                 Some(value.sys.into()),
+                            health_check_passed: Some(value.health_check_passed),
+                            ready:               Some(value.ready),
+                            blocked_releases:    value.blocked_releases,
+                            signature:           value.signature, }
     }
 }
 
@@ -163,6 +213,8 @@ impl Rumor for Service {
 
     fn kind(&self) -> RumorType { RumorType::Service }
 
+    fn version(&self) -> u64 { self.incarnation }
+
     fn id(&self) -> &str { &self.member_id }
 
     fn key(&self) -> &str { self.service_group.as_ref() }
@@ -230,8 +282,12 @@ mod tests {
                        service::ServiceGroup};
 
     use super::Service;
-    use crate::rumor::{service::SysInfo,
-                       Rumor};
+    use crate::{protocol::{newscast,
+                           FromProto,
+                           Message},
+                rumor::{service::SysInfo,
+                        Rumor,
+                        RumorPayload}};
 
     fn create_service(member_id: &str) -> Service {
         let pkg = PackageIdent::from_str("core/neurosis/1.2.3/20161208121212").unwrap();
@@ -329,6 +385,23 @@ mod tests {
                      None);
     }
 
+    #[test]
+    fn health_check_passed_survives_encoding() {
+        let mut service = create_service("adam");
+        service.health_check_passed = true;
+        let bytes = service.write_to_bytes().unwrap();
+        assert!(Service::from_bytes(&bytes).unwrap().health_check_passed);
+    }
+
+    #[test]
+    fn services_without_readiness_are_ready() {
+        let mut rumor = newscast::Rumor::from(create_service("adam"));
+        if let Some(RumorPayload::Service(ref mut payload)) = rumor.payload {
+            payload.ready = None;
+        }
+        assert!(Service::from_proto(rumor).unwrap().ready);
+    }
+
     #[test]
     fn service_cfg_serialization() {
         let package: PackageIdent = "core/foo/1.0.0/20180701125610".parse().unwrap();
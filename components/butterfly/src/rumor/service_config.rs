@@ -4,6 +4,7 @@
 
 use crate::{error::{Error,
                     Result},
+            identity::MemberIdentity,
             protocol::{self,
                        newscast::{self,
                                   Rumor as ProtoRumor},
@@ -12,9 +13,11 @@ use crate::{error::{Error,
                     Rumor,
                     RumorPayload,
                     RumorType}};
+use bytes::BytesMut;
 use habitat_core::{crypto::{keys::box_key_pair::WrappedSealedBox,
                             BoxKeyPair},
                    service::ServiceGroup};
+use prost::Message as ProstMessage;
 use std::{cmp::Ordering,
           fmt,
           mem,
@@ -30,6 +33,11 @@ pub struct ServiceConfig {
     pub incarnation:   u64,
     pub encrypted:     bool,
     pub config:        Vec<u8>, // TODO: make this a String
+    /// The member that issued the configuration, when members have identities
+    #[serde(skip)]
+    pub signed_by:     Option<String>,
+    #[serde(skip)]
+    pub signature:     Option<Vec<u8>>,
 }
 
 impl fmt::Display for ServiceConfig {
@@ -68,7 +76,37 @@ impl ServiceConfig {
                         service_group,
                         incarnation: 0,
                         encrypted: false,
-                        config }
+                        config,
+                        signed_by: None,
+                        signature: None }
+    }
+
+    /// Sign this rumor as the member that issued it.
+    pub fn sign(&mut self, identity: &MemberIdentity) -> Result<()> {
+        self.signed_by = Some(identity.member_id().to_string());
+        self.signature = Some(identity.sign(&self.signed_bytes()?)?);
+        Ok(())
+    }
+
+    /// Check that this rumor was signed by the trusted member that issued it. Any member may
+    /// issue a configuration for any service group.
+    pub fn verify(&self, identity: &MemberIdentity) -> Result<()> {
+        let signed_by = self.signed_by
+                            .as_ref()
+                            .ok_or(Error::ProtocolMismatch("signed-by"))?;
+        let signature = self.signature
+                            .as_ref()
+                            .ok_or(Error::ProtocolMismatch("signature"))?;
+        identity.verify(signed_by, &self.signed_bytes()?, signature)
+    }
+
+    /// Everything but the signature, as it is encoded on the wire.
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let mut unsigned = newscast::ServiceConfig::from(self.clone());
+        unsigned.signature = None;
+        let mut buf = BytesMut::with_capacity(unsigned.encoded_len());
+        unsigned.encode(&mut buf)?;
+        Ok(buf.to_vec())
     }
 
     pub fn encrypt(&mut self, user_pair: &BoxKeyPair, service_pair: &BoxKeyPair) -> Result<()> {
@@ -78,6 +116,24 @@ impl ServiceConfig {
         Ok(())
     }
 
+    /// The name with revision of the user key an encrypted configuration was encrypted by.
+    pub fn author(&self) -> Option<String> {
+        if !self.encrypted {
+            return None;
+        }
+        let payload = WrappedSealedBox::from_bytes(&self.config).ok()?;
+        BoxKeyPair::secret_metadata(&payload).ok()
+                                             .map(|secret| secret.sender.to_string())
+    }
+
+    /// The payload of an encrypted configuration, as it was encrypted.
+    pub fn sealed(&self) -> Option<String> {
+        if !self.encrypted {
+            return None;
+        }
+        String::from_utf8(self.config.clone()).ok()
+    }
+
     pub fn config(&self, cache_key_path: &Path) -> Result<toml::value::Table> {
         let config = if self.encrypted {
             let bytes = BoxKeyPair::decrypt_with_path(
@@ -127,7 +183,9 @@ impl FromProto<ProtoRumor> for ServiceConfig {
                                       })?,
                            incarnation:   payload.incarnation.unwrap_or(0),
                            encrypted:     payload.encrypted.unwrap_or(false),
-                           config:        payload.config.unwrap_or_default(), })
+                           config:        payload.config.unwrap_or_default(),
+                           signed_by:     payload.signed_by,
+                           signature:     payload.signature, })
     }
 }
 
@@ -136,7 +194,9 @@ impl From<ServiceConfig> for newscast::ServiceConfig {
         newscast::ServiceConfig { service_group: Some(value.service_group.to_string()),
                                   incarnation:   Some(value.incarnation),
                                   encrypted:     Some(value.encrypted),
-                                  config:        Some(value.config), }
+                                  config:        Some(value.config),
+                                  signed_by:     value.signed_by,
+                                  signature:     value.signature, }
     }
 }
 
@@ -154,6 +214,8 @@ impl Rumor for ServiceConfig {
 
     fn kind(&self) -> RumorType { RumorType::ServiceConfig }
 
+    fn version(&self) -> u64 { self.incarnation }
+
     fn id(&self) -> &str { Self::const_id() }
 
     fn key(&self) -> &str { &self.service_group }
@@ -169,7 +231,8 @@ mod tests {
     use crate::rumor::{ConstIdRumor as _,
                        Rumor,
                        RumorStore};
-    use habitat_core::service::ServiceGroup;
+    use habitat_core::{crypto::BoxKeyPair,
+                       service::ServiceGroup};
     use std::{cmp::Ordering,
               str::FromStr};
     use toml;
@@ -271,4 +334,17 @@ mod tests {
         assert_eq!(s1.config(&mock_cache_key_path).unwrap(),
                    toml::from_str::<toml::value::Table>("yep=1").unwrap());
     }
+
+    #[test]
+    fn encrypted_config_names_its_author() {
+        let user_pair = BoxKeyPair::generate_pair_for_user("ops").unwrap();
+        let service_pair =
+            BoxKeyPair::generate_pair_for_service("acme", "neurosis.production").unwrap();
+        let mut s1 = create_service_config("adam", "yep=1");
+        assert_eq!(s1.author(), None);
+        assert_eq!(s1.sealed(), None);
+        s1.encrypt(&user_pair, &service_pair).unwrap();
+        assert_eq!(s1.author(), Some(user_pair.name_with_rev()));
+        assert_eq!(s1.sealed().map(String::into_bytes), Some(s1.config.clone()));
+    }
 }
@@ -4,6 +4,7 @@
 
 use crate::{error::{Error,
                     Result},
+            identity::MemberIdentity,
             protocol::{self,
                        newscast::{self,
                                   Rumor as ProtoRumor},
@@ -11,9 +12,11 @@ use crate::{error::{Error,
             rumor::{Rumor,
                     RumorPayload,
                     RumorType}};
+use bytes::BytesMut;
 use habitat_core::{crypto::{keys::box_key_pair::WrappedSealedBox,
                             BoxKeyPair},
                    service::ServiceGroup};
+use prost::Message as ProstMessage;
 use std::{cmp::Ordering,
           fmt,
           mem,
@@ -28,6 +31,11 @@ pub struct ServiceFile {
     pub encrypted:     bool,
     pub filename:      String,
     pub body:          Vec<u8>, // TODO: make this a String
+    /// The member that issued the file, when members have identities
+    #[serde(skip)]
+    pub signed_by:     Option<String>,
+    #[serde(skip)]
+    pub signature:     Option<Vec<u8>>,
 }
 
 impl fmt::Display for ServiceFile {
@@ -73,7 +81,9 @@ impl ServiceFile {
                       incarnation: 0,
                       encrypted: false,
                       filename: filename.into(),
-                      body }
+                      body,
+                      signed_by: None,
+                      signature: None }
     }
 
     /// Encrypt the contents of the service file
@@ -98,6 +108,34 @@ impl ServiceFile {
             Ok(self.body.to_vec())
         }
     }
+
+    /// Sign this rumor as the member that issued it.
+    pub fn sign(&mut self, identity: &MemberIdentity) -> Result<()> {
+        self.signed_by = Some(identity.member_id().to_string());
+        self.signature = Some(identity.sign(&self.signed_bytes()?)?);
+        Ok(())
+    }
+
+    /// Check that this rumor was signed by the trusted member that issued it. Any member may
+    /// issue a file for any service group.
+    pub fn verify(&self, identity: &MemberIdentity) -> Result<()> {
+        let signed_by = self.signed_by
+                            .as_ref()
+                            .ok_or(Error::ProtocolMismatch("signed-by"))?;
+        let signature = self.signature
+                            .as_ref()
+                            .ok_or(Error::ProtocolMismatch("signature"))?;
+        identity.verify(signed_by, &self.signed_bytes()?, signature)
+    }
+
+    /// Everything but the signature, as it is encoded on the wire.
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let mut unsigned = newscast::ServiceFile::from(self.clone());
+        unsigned.signature = None;
+        let mut buf = BytesMut::with_capacity(unsigned.encoded_len());
+        unsigned.encode(&mut buf)?;
+        Ok(buf.to_vec())
+    }
 }
 
 impl protocol::Message<ProtoRumor> for ServiceFile {
@@ -119,7 +157,9 @@ impl FromProto<ProtoRumor> for ServiceFile {
                          encrypted:     payload.encrypted.unwrap_or(false),
                          filename:      payload.filename
                                                .ok_or(Error::ProtocolMismatch("filename"))?,
-                         body:          payload.body.unwrap_or_default(), })
+                         body:          payload.body.unwrap_or_default(),
+                         signed_by:     payload.signed_by,
+                         signature:     payload.signature, })
     }
 }
 
@@ -129,7 +169,9 @@ impl From<ServiceFile> for newscast::ServiceFile {
                                 incarnation:   Some(value.incarnation),
                                 encrypted:     Some(value.encrypted),
                                 filename:      Some(value.filename),
-                                body:          Some(value.body), }
+                                body:          Some(value.body),
+                                signed_by:     value.signed_by,
+                                signature:     value.signature, }
     }
 }
 
@@ -147,6 +189,8 @@ impl Rumor for ServiceFile {
 
     fn kind(&self) -> RumorType { RumorType::ServiceFile }
 
+    fn version(&self) -> u64 { self.incarnation }
+
     fn id(&self) -> &str { &self.filename }
 
     fn key(&self) -> &str { &self.service_group }
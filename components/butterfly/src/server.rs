@@ -4,32 +4,45 @@
 //! Winds up with 5 separate threads - inbound (incoming connections), outbound (the Probe
 //! protocol), expire (turning Suspect members into Confirmed members), push (the fan-out rumors),
 //! and pull (the inbound receipt of rumors.).
+//!
+//! Failure detection follows Lifeguard: probes slow down while our own local health is poor,
+//! suspicions time out sooner the more members independently confirm them, and a member that
+//! hears it is suspected refutes it in its very next ack.
 
+mod anti_entropy;
 mod expire;
 mod inbound;
 mod incarnation_store;
+mod local_health;
 mod outbound;
 mod pull;
 mod push;
+pub mod sim;
 pub mod timing;
 
 use self::{incarnation_store::IncarnationStore,
            sync::Myself};
 use crate::{error::{Error,
                     Result},
+            identity::MemberIdentity,
             member::{Health,
                      Incarnation,
                      Member,
                      MemberList,
-                     MemberListProxy},
-            message,
+                     MemberListProxy,
+                     Membership,
+                     ZONE_TAG},
+            message::{self,
+                      RingKeys},
             rumor::{dat_file::{DatFileReader,
                                DatFileWriter},
                     departure::Departure,
                     election::{Election,
+                               ElectionPolicy,
                                ElectionRumor,
                                ElectionUpdate},
                     heat::sync::RumorHeat,
+                    purge::Purge,
                     service::Service,
                     service_config::ServiceConfig,
                     service_file::ServiceFile,
@@ -38,6 +51,7 @@ use crate::{error::{Error,
                     RumorKey,
                     RumorStore,
                     RumorStoreProxy,
+                    RumorSummary,
                     RumorType},
             swim::Ack};
 use habitat_common::{liveliness_checker,
@@ -72,6 +86,7 @@ use std::{collections::{HashMap,
           thread,
           time::{Duration,
                  Instant}};
+use time::Duration as TimeDuration;
 
 /// The maximum number of other members we should notify when we shut
 /// down and leave the ring.
@@ -99,8 +114,25 @@ impl fmt::Debug for ElectionTimer {
 type AckReceiver = mpsc::Receiver<(SocketAddr, Ack)>;
 type AckSender = mpsc::Sender<(SocketAddr, Ack)>;
 
+/// Where SWIM messages are sent from. A running server sends over its UDP
+/// socket; a simulated one sends over an in-memory network.
+pub(crate) trait SwimSocket {
+    fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize>;
+}
+
+impl SwimSocket for UdpSocket {
+    fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+        UdpSocket::send_to(self, buf, addr)
+    }
+}
+
 pub trait Suitability: Debug + Send + Sync {
     fn suitability_for_msr(&self, service_group: &str) -> u64;
+
+    /// The election policy this member's service group was loaded with.
+    fn election_policy_for_msr(&self, _service_group: &str) -> ElectionPolicy {
+        ElectionPolicy::default()
+    }
 }
 
 pub(crate) mod sync {
@@ -140,6 +172,8 @@ pub(crate) mod sync {
         pub fn mark_departed(&mut self) { self.0.mark_departed() }
 
         pub fn set_persistent(&mut self) { self.0.set_persistent() }
+
+        pub fn set_identity(&mut self, identity: MemberIdentity) { self.0.set_identity(identity) }
     }
 
     /// Encapsulate a `Member` with the added understanding that this
@@ -165,7 +199,8 @@ pub(crate) mod sync {
         /// signals a difference between testing and "real life".
         pub fn new(member: Member, incarnation_store: Option<IncarnationStore>) -> Self {
             let inner = MyselfInner { member,
-                                      incarnation_store };
+                                      incarnation_store,
+                                      identity: None };
             Self { inner: Lock::new(inner), }
         }
 
@@ -182,6 +217,8 @@ pub(crate) mod sync {
         // TODO (CM): This is only optional because the current
         // implementation of Server requires it. See note there for more.
         incarnation_store: Option<incarnation_store::IncarnationStore>,
+        /// Signs the member record whenever it changes, when members have identities
+        identity: Option<MemberIdentity>,
     }
 
     impl MyselfInner {
@@ -193,6 +230,7 @@ pub(crate) mod sync {
             self.member.incarnation = value;
             INCARNATION.set(value.to_i64());
             debug!("Setting incarnation number to {}", self.member.incarnation);
+            self.sign();
             Ok(())
         }
 
@@ -248,6 +286,7 @@ pub(crate) mod sync {
                            self.member.incarnation, e);
                 }
             }
+            self.sign();
         }
 
         /// Returns the current incarnation number.
@@ -263,7 +302,24 @@ pub(crate) mod sync {
         // thing that should be mutable, once you actually have a fully
         // set-up Butterfly server, is the incarnation number, which is
         // accounted for in `Myself::increment_incarnation`.
-        fn set_persistent(&mut self) { self.member.persistent = true; }
+        fn set_persistent(&mut self) {
+            self.member.persistent = true;
+            self.sign();
+        }
+
+        fn set_identity(&mut self, identity: MemberIdentity) {
+            self.identity = Some(identity);
+            self.sign();
+        }
+
+        /// Re-sign the member record, if we have an identity.
+        fn sign(&mut self) {
+            if let Some(ref identity) = self.identity {
+                if let Err(e) = self.member.sign(identity) {
+                    error!("Could not sign our member record: {}", e);
+                }
+            }
+        }
     }
 }
 
@@ -272,11 +328,13 @@ pub(crate) mod sync {
 pub struct Server {
     name:      Arc<String>,
     member_id: Arc<String>,
+    zone:      Arc<Option<String>>,
     // TODO (CM): This is currently public because butterfly tests
     // depends on it being so. Refactor so it can be private.
     myself:                   Arc<Myself>,
     pub member_list:          Arc<MemberList>,
-    ring_key:                 Arc<Option<SymKey>>,
+    ring_keys:                Arc<Lock<RingKeys>>,
+    member_identity:          Arc<Option<MemberIdentity>>,
     rumor_heat:               Arc<RumorHeat>,
     pub service_store:        RumorStore<Service>,
     pub service_config_store: RumorStore<ServiceConfig>,
@@ -284,6 +342,7 @@ pub struct Server {
     pub election_store:       RumorStore<Election>,
     pub update_store:         RumorStore<ElectionUpdate>,
     pub departure_store:      RumorStore<Departure>,
+    pub purge_store:          RumorStore<Purge>,
     swim_addr:                SocketAddr,
     gossip_addr:              SocketAddr,
     suitability_lookup:       Arc<dyn Suitability>,
@@ -291,6 +350,9 @@ pub struct Server {
     dat_file:                 Option<Arc<Mutex<DatFileWriter>>>,
     socket:                   Option<UdpSocket>,
     departed:                 Arc<AtomicBool>,
+    /// The timing the server was started with.
+    timing:                   timing::Timing,
+    local_health:             Arc<local_health::LocalHealth>,
     // These are all here for testing support
     pause:           Arc<AtomicBool>,
     swim_rounds:     Arc<AtomicIsize>,
@@ -303,9 +365,11 @@ impl Clone for Server {
     fn clone(&self) -> Server {
         Server { name:                 self.name.clone(),
                  member_id:            self.member_id.clone(),
+                 zone:                 self.zone.clone(),
                  myself:               self.myself.clone(),
                  member_list:          self.member_list.clone(),
-                 ring_key:             self.ring_key.clone(),
+                 ring_keys:            self.ring_keys.clone(),
+                 member_identity:      self.member_identity.clone(),
                  rumor_heat:           self.rumor_heat.clone(),
                  service_store:        self.service_store.clone(),
                  service_config_store: self.service_config_store.clone(),
@@ -313,12 +377,15 @@ impl Clone for Server {
                  election_store:       self.election_store.clone(),
                  update_store:         self.update_store.clone(),
                  departure_store:      self.departure_store.clone(),
+                 purge_store:          self.purge_store.clone(),
                  swim_addr:            self.swim_addr,
                  gossip_addr:          self.gossip_addr,
                  suitability_lookup:   self.suitability_lookup.clone(),
                  data_path:            self.data_path.clone(),
                  dat_file:             self.dat_file.clone(),
                  departed:             self.departed.clone(),
+                 timing:               self.timing.clone(),
+                 local_health:         self.local_health.clone(),
                  pause:                self.pause.clone(),
                  swim_rounds:          self.swim_rounds.clone(),
                  gossip_rounds:        self.gossip_rounds.clone(),
@@ -353,6 +420,7 @@ impl Server {
                 member.gossip_port = gossip_socket_addr.port();
 
                 let member_id = member.id.clone();
+                let zone = member.tags.get(ZONE_TAG).cloned();
 
                 // TODO (CM): This None really wants to go away. Not
                 // currently setting a IncarnationStore, because it
@@ -365,9 +433,11 @@ impl Server {
                             // TODO (CM): could replace this with an accessor
                             // on member, if we have a better type
                             member_id: Arc::new(member_id),
+                            zone: Arc::new(zone),
                             myself: Arc::new(myself),
                             member_list: Arc::new(MemberList::new()),
-                            ring_key: Arc::new(ring_key),
+                            ring_keys: Arc::new(Lock::new(RingKeys::new(ring_key))),
+                            member_identity: Arc::new(None),
                             rumor_heat: Arc::default(),
                             service_store: RumorStore::default(),
                             service_config_store: RumorStore::default(),
@@ -375,12 +445,15 @@ impl Server {
                             election_store: RumorStore::default(),
                             update_store: RumorStore::default(),
                             departure_store: RumorStore::default(),
+                            purge_store: RumorStore::default(),
                             swim_addr: swim_socket_addr,
                             gossip_addr: gossip_socket_addr,
                             suitability_lookup,
                             data_path: data_path.as_ref().map(|p| p.into()),
                             dat_file: None,
                             departed: Arc::new(AtomicBool::new(false)),
+                            timing: timing::Timing::default(),
+                            local_health: Arc::default(),
                             pause: Arc::new(AtomicBool::new(false)),
                             swim_rounds: Arc::new(AtomicIsize::new(0)),
                             gossip_rounds: Arc::new(AtomicIsize::new(0)),
@@ -457,6 +530,7 @@ impl Server {
     /// * Returns `Error::SocketSetWriteTimeout` if the socket write timeout cannot be set
     pub fn start_rsw_mlw_smw_rhw_msr(&mut self, timing: &timing::Timing) -> Result<()> {
         debug!("entering habitat_butterfly::server::Server::start");
+        self.timing = timing.clone();
         let (tx_outbound, rx_inbound) = channel();
         if let Some(ref path) = self.data_path {
             if let Some(err) = fs::create_dir_all(path).err() {
@@ -491,7 +565,13 @@ impl Server {
                 // persisted previously.
                 let mut store = incarnation_store::IncarnationStore::new(path.join("INCARNATION"));
                 store.initialize()?;
-                self.myself.lock_smw().sync_incarnation(store)?;
+                let mut myself = self.myself.lock_smw();
+                myself.sync_incarnation(store)?;
+                // Our tags, and so our zone, may have changed since we
+                // last ran. The rest of the ring still holds the record
+                // we last gossiped at the persisted incarnation, and only
+                // takes ours over it if it is newer.
+                myself.increment_incarnation();
             }
         }
 
@@ -524,6 +604,10 @@ impl Server {
                            self.clone(),
                            timing.clone())?;
 
+        anti_entropy::spawn_thread(format!("anti-entropy-{}", self.name()),
+                                   self.clone(),
+                                   timing.clone())?;
+
         if self.dat_file.is_some() {
             spawn_persist_thread(format!("persist-{}", self.name()), self.clone())?;
         }
@@ -613,62 +697,83 @@ impl Server {
     /// * `Server::member` (write)
     /// * `RumorHeat::inner` (write)
     pub fn set_departed_mlw_smw_rhw(&self) {
-        if self.socket.is_some() {
-            self.myself.lock_smw().increment_incarnation();
-            // TODO (CM): It's not clear that this operation is actually needed.
-            self.myself.lock_smw().mark_departed();
-            self.member_list.set_departed_mlw(&self.member_id);
-            // We need to mark this as "hot" in order to propagate it.
-            //
-            // TODO (CM): This exact code is present numerous places;
-            // factor it out to facilitate further code consolidation.
-
-            // NOT calling RumorHeat::purge here because we'll be
-            // shutting down soon anyway.
-            self.rumor_heat
-                .lock_rhw()
-                .start_hot_rumor(RumorKey::new(RumorType::Member, &*self.member_id, ""));
-
-            let check_list = self.member_list.check_list_mlr(&self.member_id);
-
-            // TODO (CM): Even though we marked the rumor as hot
-            // above, when we gossip, we send out the 5 "coolest but
-            // still warm" rumors. Sending to 10 members increases the
-            // chances that we'll get to this hot one now, but I don't
-            // think that we can strictly guarantee that this
-            // departure health update actually gets out in all cases.
-            for member in check_list.iter().take(SELF_DEPARTURE_RUMOR_FANOUT) {
-                let addr = member.swim_socket_address();
-                // Safe because we checked above
-                outbound::ack_mlr_smr_rhw(&self, self.socket.as_ref().unwrap(), member, addr, None);
-            }
-        } else {
-            debug!("No socket present; server was never started, so nothing to depart");
+        match self.socket {
+            Some(ref socket) => self.announce_departure_mlw_smw_rhw(socket),
+            None => debug!("No socket present; server was never started, so nothing to depart"),
         }
     }
 
-    /// Given a membership record and some health, insert it into the Member List.
+    /// Mark ourselves departed and send acks carrying that news over `socket`.
     ///
     /// # Locking (see locking.md)
     /// * `MemberList::entries` (write)
     /// * `Server::member` (write)
     /// * `RumorHeat::inner` (write)
-    fn insert_member_from_rumor_mlw_smw_rhw(&self, member: Member, mut health: Health) {
-        let rk: RumorKey = RumorKey::from(&member);
+    pub(crate) fn announce_departure_mlw_smw_rhw(&self, socket: &dyn SwimSocket) {
+        self.myself.lock_smw().increment_incarnation();
+        // TODO (CM): It's not clear that this operation is actually needed.
+        self.myself.lock_smw().mark_departed();
+        self.member_list.set_departed_mlw(&self.member_id);
+        // We need to mark this as "hot" in order to propagate it.
+        //
+        // TODO (CM): This exact code is present numerous places;
+        // factor it out to facilitate further code consolidation.
+
+        // NOT calling RumorHeat::purge here because we'll be
+        // shutting down soon anyway.
+        self.rumor_heat
+            .lock_rhw()
+            .start_hot_rumor(RumorKey::new(RumorType::Member, &*self.member_id, ""));
+
+        let check_list = self.member_list.check_list_mlr(&self.member_id);
+
+        // TODO (CM): Even though we marked the rumor as hot
+        // above, when we gossip, we send out the 5 "coolest but
+        // still warm" rumors. Sending to 10 members increases the
+        // chances that we'll get to this hot one now, but I don't
+        // think that we can strictly guarantee that this
+        // departure health update actually gets out in all cases.
+        for member in check_list.iter().take(SELF_DEPARTURE_RUMOR_FANOUT) {
+            let addr = member.swim_socket_address();
+            outbound::ack_mlr_smr_rhw(&self, socket, member, addr, None);
+        }
+    }
 
-        if member.id == self.member_id()
-           && health != Health::Alive
-           && member.incarnation >= self.myself.lock_smr().incarnation()
+    /// Given a membership record from a rumor, insert it into the Member List. A record that
+    /// says we are anything but alive is refuted; having to do so counts against our local
+    /// health, since it likely means others are not hearing from us in time. When members have
+    /// identities, the record must be signed by the member it is about; its health is reported by
+    /// others, and is not.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (write)
+    /// * `Server::member` (write)
+    /// * `RumorHeat::inner` (write)
+    fn insert_member_from_rumor_mlw_smw_rhw(&self, mut membership: Membership) {
+        if let Some(identity) = self.member_identity() {
+            if let Err(e) = membership.member.verify(identity) {
+                warn!("Rejecting {}: {}", membership, e);
+                return;
+            }
+        }
+        let rk: RumorKey = RumorKey::from(&membership.member);
+
+        if membership.member.id == self.member_id()
+           && membership.health != Health::Alive
+           && membership.member.incarnation >= self.myself.lock_smr().incarnation()
         {
             self.myself
                 .lock_smw()
-                .refute_incarnation(member.incarnation);
-            health = Health::Alive;
+                .refute_incarnation(membership.member.incarnation);
+            membership.health = Health::Alive;
+            membership.suspected_by.clear();
+            self.local_health.fault(self.timing.local_health_max);
         }
 
-        let member_id = member.id.clone();
+        let member_id = membership.member.id.clone();
+        let health = membership.health;
 
-        if self.member_list.insert_mlw(member, health) {
+        if self.member_list.insert_membership_mlw(membership) {
             if member_id != self.member_id() && health == Health::Departed {
                 self.rumor_heat.lock_rhw().purge(&member_id);
             }
@@ -676,6 +781,23 @@ impl Server {
         }
     }
 
+    /// Mark a member we failed to reach as suspect, recording that we are the one suspecting it.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (write)
+    /// * `RumorHeat::inner` (write)
+    pub(crate) fn suspect_member_mlw_rhw(&self, member: Member) {
+        let rk = RumorKey::from(&member);
+        let mut membership = Membership::new(member, Health::Suspect);
+        membership.suspected_by.insert(self.member_id().to_string());
+        if self.member_list.insert_membership_mlw(membership) {
+            self.rumor_heat.lock_rhw().start_hot_rumor(rk);
+        }
+    }
+
+    /// How much evidence there is that we, rather than our peers, are struggling.
+    pub fn local_health_score(&self) -> usize { self.local_health.score() }
+
     /// Insert a service rumor into the service store.
     /// If we're adding a new service group member, we want to avoid the
     /// situation where we could lose quorum due to Confirmed but not yet
@@ -692,7 +814,36 @@ impl Server {
     /// * `RumorStore::list` (write)
     /// * `MemberList::entries` (write)
     /// * `RumorHeat::inner` (write)
-    pub fn insert_service_rsw_mlw_rhw(&self, service: Service) {
+    pub fn insert_service_rsw_mlw_rhw(&self, mut service: Service) {
+        if let Some(identity) = self.member_identity() {
+            if service.member_id == identity.member_id() {
+                if let Err(e) = service.sign(identity) {
+                    error!("Could not sign {}: {}", service, e);
+                    return;
+                }
+            }
+        }
+        Self::insert_service_impl(service,
+                                  &self.service_store,
+                                  &self.member_list,
+                                  &self.rumor_heat,
+                                  |k| self.check_quorum_mlr(k))
+    }
+
+    /// Insert a service rumor received from another member. When members
+    /// have identities, the rumor must be signed by the member it is about.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `MemberList::entries` (write)
+    /// * `RumorHeat::inner` (write)
+    pub fn insert_service_from_rumor_rsw_mlw_rhw(&self, service: Service) {
+        if let Some(identity) = self.member_identity() {
+            if let Err(e) = service.verify(identity) {
+                warn!("Rejecting {}: {}", service, e);
+                return;
+            }
+        }
         Self::insert_service_impl(service,
                                   &self.service_store,
                                   &self.member_list,
@@ -743,37 +894,96 @@ impl Server {
         }
     }
 
-    /// Insert a service config rumor into the service store.
+    /// Insert a service config rumor into the service store. When members have identities, an
+    /// unsigned rumor is signed as issued by us.
     ///
     /// # Locking (see locking.md)
     /// * `RumorStore::list` (write)
     /// * `RumorHeat::inner` (write)
-    pub fn insert_service_config_rsw_rhw(&self, service_config: ServiceConfig) {
+    pub fn insert_service_config_rsw_rhw(&self, mut service_config: ServiceConfig) {
+        if let Some(identity) = self.member_identity() {
+            if service_config.signature.is_none() {
+                if let Err(e) = service_config.sign(identity) {
+                    error!("Could not sign {}: {}", service_config, e);
+                    return;
+                }
+            }
+        }
         let rk = RumorKey::from(&service_config);
         if self.service_config_store.insert_rsw(service_config) {
             self.rumor_heat.lock_rhw().start_hot_rumor(rk);
         }
     }
 
-    /// Insert a service file rumor into the service file store.
+    /// Insert a service config rumor received from another member. When members have
+    /// identities, the rumor must be signed by the trusted member that issued it.
     ///
     /// # Locking (see locking.md)
     /// * `RumorStore::list` (write)
     /// * `RumorHeat::inner` (write)
-    pub fn insert_service_file_rsw_rhw(&self, service_file: ServiceFile) {
+    pub fn insert_service_config_from_rumor_rsw_rhw(&self, service_config: ServiceConfig) {
+        if let Some(identity) = self.member_identity() {
+            if let Err(e) = service_config.verify(identity) {
+                warn!("Rejecting {}: {}", service_config, e);
+                return;
+            }
+        }
+        self.insert_service_config_rsw_rhw(service_config)
+    }
+
+    /// Insert a service file rumor into the service file store. When members have identities,
+    /// an unsigned rumor is signed as issued by us.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `RumorHeat::inner` (write)
+    pub fn insert_service_file_rsw_rhw(&self, mut service_file: ServiceFile) {
+        if let Some(identity) = self.member_identity() {
+            if service_file.signature.is_none() {
+                if let Err(e) = service_file.sign(identity) {
+                    error!("Could not sign {}: {}", service_file, e);
+                    return;
+                }
+            }
+        }
         let rk = RumorKey::from(&service_file);
         if self.service_file_store.insert_rsw(service_file) {
             self.rumor_heat.lock_rhw().start_hot_rumor(rk);
         }
     }
 
-    /// Insert a departure rumor into the departure store.
+    /// Insert a service file rumor received from another member. When members have identities,
+    /// the rumor must be signed by the trusted member that issued it.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `RumorHeat::inner` (write)
+    pub fn insert_service_file_from_rumor_rsw_rhw(&self, service_file: ServiceFile) {
+        if let Some(identity) = self.member_identity() {
+            if let Err(e) = service_file.verify(identity) {
+                warn!("Rejecting {}: {}", service_file, e);
+                return;
+            }
+        }
+        self.insert_service_file_rsw_rhw(service_file)
+    }
+
+    /// Insert a departure rumor into the departure store. When members have identities, an
+    /// unsigned rumor is signed as issued by us.
     ///
     /// # Locking (see locking.md)
     /// * `RumorStore::list` (write)
     /// * `MemberList::entries` (write)
     /// * `RumorHeat::inner` (write)
-    pub fn insert_departure_rsw_mlw_rhw(&self, departure: Departure) {
+    pub fn insert_departure_rsw_mlw_rhw(&self, mut departure: Departure) {
+        if let Some(identity) = self.member_identity() {
+            if departure.signature.is_none() {
+                if let Err(e) = departure.sign(identity) {
+                    error!("Could not sign {}: {}", departure, e);
+                    return;
+                }
+            }
+        }
         let rk = RumorKey::from(&departure);
         if *self.member_id == departure.member_id {
             self.departed
@@ -791,6 +1001,166 @@ impl Server {
         }
     }
 
+    /// Insert a departure rumor received from another member. When members have identities, the
+    /// rumor must be signed by the trusted member that issued it.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `MemberList::entries` (write)
+    /// * `RumorHeat::inner` (write)
+    pub fn insert_departure_from_rumor_rsw_mlw_rhw(&self, departure: Departure) {
+        if let Some(identity) = self.member_identity() {
+            if let Err(e) = departure.verify(identity) {
+                warn!("Rejecting {}: {}", departure, e);
+                return;
+            }
+        }
+        self.insert_departure_rsw_mlw_rhw(departure)
+    }
+
+    /// Insert a purge rumor into the purge store, dropping every rumor of its service group. A
+    /// purge that has already expired is ignored, so that a member that still holds it, or
+    /// learns of it again, can't purge a service group that has since been reused. So is a purge
+    /// dated more than the TTL ahead of our clock, which would otherwise outlive the TTL.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `RumorHeat::inner` (write)
+    pub fn insert_purge_rsw_rhw(&self, purge: Purge) {
+        if self.purge_expired(&purge) {
+            debug!("Ignoring the expired purge of {}", purge.service_group);
+            return;
+        }
+        if self.purge_from_the_future(&purge) {
+            warn!("Rejecting {}: it is dated more than the rumor TTL ahead of our clock",
+                  purge);
+            return;
+        }
+        let rk = RumorKey::from(&purge);
+        let service_group = purge.service_group.clone();
+        if self.purge_store.insert_rsw(purge) {
+            self.remove_service_group_rsw_rhw(&service_group);
+            self.rumor_heat.lock_rhw().start_hot_rumor(rk);
+        }
+    }
+
+    /// Insert a purge rumor received from another member. When members have identities, the
+    /// rumor must be signed by the trusted member that issued it.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `RumorHeat::inner` (write)
+    pub fn insert_purge_from_rumor_rsw_rhw(&self, purge: Purge) {
+        if let Some(identity) = self.member_identity() {
+            if let Err(e) = purge.verify(identity) {
+                warn!("Rejecting {}: {}", purge, e);
+                return;
+            }
+        }
+        self.insert_purge_rsw_rhw(purge)
+    }
+
+    /// Whether the purge is older than the rumor TTL.
+    pub(crate) fn purge_expired(&self, purge: &Purge) -> bool {
+        self.timing
+            .rumor_ttl_duration()
+            .to_std()
+            .map(|ttl| purge.is_expired(ttl))
+            .unwrap_or(false)
+    }
+
+    /// Whether the purge is dated more than the rumor TTL ahead of our clock.
+    fn purge_from_the_future(&self, purge: &Purge) -> bool {
+        self.timing
+            .rumor_ttl_duration()
+            .to_std()
+            .map(|ttl| purge.is_future(ttl))
+            .unwrap_or(false)
+    }
+
+    /// Whether the service group has been purged, and its rumors are to be turned away.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (read)
+    pub fn is_purged_rsr(&self, service_group: &str) -> bool {
+        self.purge_store.lock_rsr().contains_id(service_group)
+    }
+
+    /// Drop every rumor of the service group. Returns true if there were any.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `RumorHeat::inner` (write)
+    fn remove_service_group_rsw_rhw(&self, service_group: &str) -> bool {
+        let removed = self.service_store.remove_key_rsw(service_group)
+                      | self.service_config_store.remove_key_rsw(service_group)
+                      | self.service_file_store.remove_key_rsw(service_group)
+                      | self.election_store.remove_key_rsw(service_group)
+                      | self.update_store.remove_key_rsw(service_group);
+        if removed {
+            self.rumor_heat
+                .lock_rhw()
+                .forget_service_group(service_group);
+        }
+        removed
+    }
+
+    /// Drop the rumors of members that have been confirmed or departed for longer than the TTL,
+    /// and purges older than the TTL.
+    ///
+    /// A service group's configuration and files don't belong to any one member, and would not
+    /// be gossiped again if they were dropped while its members are only cut off from us. So
+    /// while every member of a service group is gone, its rumors are all kept until every one of
+    /// those members has departed, and then all dropped.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (read)
+    /// * `RumorStore::list` (write)
+    /// * `RumorHeat::inner` (write)
+    pub(crate) fn expire_rumors_mlr_rsw_rhw(&self, ttl: TimeDuration) {
+        let gone = self.member_list.members_gone_longer_than_mlr(ttl);
+        if !gone.is_empty() {
+            let departed: HashSet<&String> =
+                gone.iter()
+                    .filter(|id| {
+                        self.member_list.health_of_by_id_mlr(id) == Some(Health::Departed)
+                    })
+                    .collect();
+            let mut retired = Vec::new();
+            let mut all_gone = HashSet::new();
+            for (service_group, rumors) in self.service_store.lock_rsr().iter() {
+                if rumors.keys().all(|id| gone.contains(id)) {
+                    all_gone.insert(service_group.clone());
+                    if rumors.keys().all(|id| departed.contains(id)) {
+                        retired.push(service_group.clone());
+                    }
+                }
+            }
+
+            let expired = self.service_store.remove_expired_rsw(|service| {
+                                                gone.contains(&service.member_id)
+                                                && !all_gone.contains(service.key())
+                                            });
+            for rk in expired.iter() {
+                self.rumor_heat.lock_rhw().forget(rk);
+            }
+            for service_group in retired {
+                info!("Expiring the rumors of {}; all of its members have departed",
+                      service_group);
+                self.remove_service_group_rsw_rhw(&service_group);
+            }
+        }
+
+        if let Ok(ttl) = ttl.to_std() {
+            for rk in self.purge_store
+                          .remove_expired_rsw(|purge| purge.is_expired(ttl))
+            {
+                debug!("Expired the purge of {}", rk.id);
+                self.rumor_heat.lock_rhw().forget(&rk);
+            }
+        }
+    }
+
     /// Get all the Member ID's who are present in a given service group, and eligible to vote
     /// (alive)
     ///
@@ -859,6 +1229,36 @@ impl Server {
         has_quorum
     }
 
+    /// Check if a given service group has quorum to run an election whose policy requires its
+    /// alive members to span at least `min_zones` zones.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (read)
+    /// * `RumorStore::list` (read)
+    fn check_election_quorum_mlr(&self, key: &str, min_zones: u32) -> bool {
+        if !self.check_quorum_mlr(key) {
+            return false;
+        }
+        if min_zones == 0 {
+            return true;
+        }
+        let zones: HashSet<String> =
+            self.get_electorate_rsr_mlr(key)
+                .iter()
+                .filter_map(|member_id| self.member_list.zone_of_by_id_mlr(member_id))
+                .collect();
+        let has_quorum = zones.len() >= min_zones as usize;
+
+        trace!("check_election_quorum({}): {}/{} zones => {}, zones: {:?}",
+               key,
+               zones.len(),
+               min_zones,
+               has_quorum,
+               zones);
+
+        has_quorum
+    }
+
     /// Start an election for the given service group, declaring this members suitability and the
     /// term for the election.
     ///
@@ -869,15 +1269,20 @@ impl Server {
     /// * `ManagerServices::inner` (read)
     pub fn start_election_rsw_mlr_rhw_msr(&self, service_group: &str, term: u64) {
         let suitability = self.suitability_lookup.suitability_for_msr(&service_group);
-        let has_quorum = self.check_quorum_mlr(service_group);
-        let e = Election::new(self.member_id(),
-                              service_group,
-                              term,
-                              suitability,
-                              has_quorum);
+        let policy = self.suitability_lookup
+                         .election_policy_for_msr(&service_group);
+        let has_quorum = self.check_election_quorum_mlr(service_group, policy.min_zones);
+        let mut e = Election::new(self.member_id(),
+                                  service_group,
+                                  term,
+                                  suitability,
+                                  has_quorum).with_policy(&policy, (*self.zone).as_ref());
         if !has_quorum {
             warn!("start_election check_quorum failed: {:?}", e);
         }
+        if !self.sign_election(&mut e) {
+            return;
+        }
         debug!("start_election: {:?}", e);
         self.rumor_heat
             .lock_rhw()
@@ -894,14 +1299,17 @@ impl Server {
                                              suitability: u64,
                                              term: u64) {
         let has_quorum = self.check_quorum_mlr(service_group);
-        let e = ElectionUpdate::new(self.member_id(),
-                                    service_group,
-                                    term,
-                                    suitability,
-                                    has_quorum);
+        let mut e = ElectionUpdate::new(self.member_id(),
+                                        service_group,
+                                        term,
+                                        suitability,
+                                        has_quorum);
         if !has_quorum {
             warn!("start_election check_quorum failed: {:?}", e);
         }
+        if !self.sign_election(&mut e) {
+            return;
+        }
         debug!("start_update_election: {:?}", e);
         self.rumor_heat
             .lock_rhw()
@@ -921,7 +1329,7 @@ impl Server {
         Self::elections_to_restart_impl(elections,
                                         &self.service_store,
                                         &self.member_id(),
-                                        |k| self.check_quorum_mlr(k),
+                                        |k, min_zones| self.check_election_quorum_mlr(k, min_zones),
                                         &self.member_list,
                                         feature_flags,
                                         &self.data_path)
@@ -930,7 +1338,7 @@ impl Server {
     fn elections_to_restart_impl<T>(elections: &RumorStore<T>,
                                     service_store: &RumorStore<Service>,
                                     myself_member_id: &str,
-                                    check_quorum: impl Fn(&str) -> bool,
+                                    check_quorum: impl Fn(&str, u32) -> bool,
                                     member_list: &MemberList,
                                     feature_flags: FeatureFlag,
                                     data_path: &Option<PathBuf>)
@@ -961,7 +1369,7 @@ impl Server {
                     if election.is_finished() && election.member_id() == myself_member_id {
                         // If we are the leader, and we have lost quorum, we should restart
                         // the election
-                        if !check_quorum(election.key()) {
+                        if !check_quorum(election.key(), election.min_zones()) {
                             warn!("Restarting election with a new term as the leader has lost \
                                    quorum: {:?}",
                                   election);
@@ -1067,7 +1475,7 @@ impl Server {
                 // If we are the member that this election is voting for, then check to see if the
                 // election is over! If it is, mark this election as final before you process it.
                 if self.member_id() == election.member_id {
-                    if self.check_quorum_mlr(election.key()) {
+                    if self.check_election_quorum_mlr(election.key(), election.min_zones) {
                         let electorate = self.get_electorate_rsr_mlr(election.key());
                         let mut num_votes = 0;
                         for vote in election.votes.iter() {
@@ -1116,7 +1524,7 @@ impl Server {
             }
 
             if !election.is_finished() {
-                let has_quorum = self.check_quorum_mlr(election.key());
+                let has_quorum = self.check_election_quorum_mlr(election.key(), election.min_zones);
                 if has_quorum {
                     election.running();
                 } else {
@@ -1125,11 +1533,32 @@ impl Server {
             }
         }
 
+        if election.member_id == self.member_id() && !self.sign_election(&mut election) {
+            return;
+        }
         if self.election_store.insert_rsw(election) {
             self.rumor_heat.lock_rhw().start_hot_rumor(rk);
         }
     }
 
+    /// Insert an election received from another member. When members have identities, the
+    /// election must be signed by its candidate.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `MemberList::entries` (read)
+    /// * `RumorHeat::inner` (write)
+    /// * `ManagerServices::inner` (read)
+    pub fn insert_election_from_rumor_rsw_mlr_rhw_msr(&self, election: Election) {
+        if let Some(identity) = self.member_identity() {
+            if let Err(e) = election.verify(identity) {
+                warn!("Rejecting {}: {}", election, e);
+                return;
+            }
+        }
+        self.insert_election_rsw_mlr_rhw_msr(election)
+    }
+
     /// # Locking (see locking.md)
     /// * `RumorStore::list` (write)
     /// * `MemberList::entries` (read)
@@ -1201,17 +1630,151 @@ impl Server {
             }
         }
 
+        if election.member_id == self.member_id() && !self.sign_election(&mut election) {
+            return;
+        }
         if self.update_store.insert_rsw(election) {
             self.rumor_heat.lock_rhw().start_hot_rumor(rk);
         }
     }
 
-    fn generate_wire(&self, payload: Vec<u8>) -> Result<Vec<u8>> {
-        message::generate_wire(payload, (*self.ring_key).as_ref())
+    /// Insert an update election received from another member. When members have identities,
+    /// the election must be signed by its candidate.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `MemberList::entries` (read)
+    /// * `RumorHeat::inner` (write)
+    pub fn insert_update_election_from_rumor_rsw_mlr_rhw(&self, election: ElectionUpdate) {
+        if let Some(identity) = self.member_identity() {
+            if let Err(e) = election.verify(identity) {
+                warn!("Rejecting {}: {}", election, e);
+                return;
+            }
+        }
+        self.insert_update_election_rsw_mlr_rhw(election)
+    }
+
+    /// Sign an election we are the candidate in, when members have identities. Returns false if
+    /// it could not be signed.
+    fn sign_election(&self, election: &mut Election) -> bool {
+        if let Some(identity) = self.member_identity() {
+            if let Err(e) = election.sign(identity) {
+                error!("Could not sign {}: {}", election, e);
+                return false;
+            }
+        }
+        true
+    }
+
+    /// # Locking (see locking.md)
+    /// * `Server::ring_keys` (read)
+    fn generate_wire_srkr(&self, payload: Vec<u8>) -> Result<Vec<u8>> {
+        message::generate_wire(payload,
+                               self.ring_keys.read().primary(),
+                               self.member_identity())
+    }
+
+    /// Returns the payload, and the member that signed it if members
+    /// have identities.
+    ///
+    /// # Locking (see locking.md)
+    /// * `Server::ring_keys` (read)
+    fn unwrap_wire_srkr(&self, payload: &[u8]) -> Result<(Vec<u8>, Option<String>)> {
+        message::unwrap_wire(payload, &self.ring_keys.read(), self.member_identity())
+    }
+
+    /// Sign all gossip as this member, and only admit members with a
+    /// trusted key. This must be called before the server is started.
+    pub fn set_member_identity(&mut self, identity: MemberIdentity) {
+        assert_eq!(identity.member_id(),
+                   &*self.member_id,
+                   "A server's identity must be for its own member ID");
+        self.myself.lock_smw().set_identity(identity.clone());
+        self.member_identity = Arc::new(Some(identity));
+    }
+
+    pub fn member_identity(&self) -> Option<&MemberIdentity> { (*self.member_identity).as_ref() }
+
+    /// The ring keys this server currently gossips with.
+    ///
+    /// # Locking (see locking.md)
+    /// * `Server::ring_keys` (read)
+    pub fn ring_keys_srkr(&self) -> RingKeys { self.ring_keys.read().clone() }
+
+    /// Accept messages encrypted with an additional ring key.
+    ///
+    /// # Locking (see locking.md)
+    /// * `Server::ring_keys` (write)
+    pub fn accept_ring_key_srkw(&self, ring_key: SymKey) { self.ring_keys.write().accept(ring_key) }
+
+    /// Encrypt messages with an accepted ring key from now on.
+    ///
+    /// # Locking (see locking.md)
+    /// * `Server::ring_keys` (write)
+    pub fn promote_ring_key_srkw(&self, name_with_rev: &str) -> Result<()> {
+        self.ring_keys.write().promote(name_with_rev)
+    }
+
+    /// Stop accepting messages encrypted with a ring key.
+    ///
+    /// # Locking (see locking.md)
+    /// * `Server::ring_keys` (write)
+    pub fn retire_ring_key_srkw(&self, name_with_rev: &str) -> Result<()> {
+        self.ring_keys.write().retire(name_with_rev)
     }
 
-    fn unwrap_wire(&self, payload: &[u8]) -> Result<Vec<u8>> {
-        message::unwrap_wire(payload, (*self.ring_key).as_ref())
+    /// Summarize every rumor this server holds, for inspecting the
+    /// state of the ring.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (read)
+    /// * `RumorHeat::inner` (read)
+    pub fn rumor_summaries_rsr_rhr(&self) -> Vec<RumorSummary> {
+        fn summarize<R: Rumor>(store: &RumorStore<R>,
+                               f: impl Fn(&R) -> RumorSummary)
+                               -> Vec<RumorSummary> {
+            store.lock_rsr().rumors().map(f).collect()
+        }
+        fn summary(key: RumorKey) -> RumorSummary {
+            RumorSummary { key,
+                           incarnation: None,
+                           election: None,
+                           shared: 0,
+                           cooled: 0 }
+        }
+
+        let mut summaries = Vec::new();
+        summaries.extend(summarize(&self.service_store, |s| {
+                             RumorSummary { incarnation: Some(s.incarnation),
+                                            ..summary(RumorKey::from(s)) }
+                         }));
+        summaries.extend(summarize(&self.service_config_store, |s| {
+                             RumorSummary { incarnation: Some(s.incarnation),
+                                            ..summary(RumorKey::from(s)) }
+                         }));
+        summaries.extend(summarize(&self.service_file_store, |s| {
+                             RumorSummary { incarnation: Some(s.incarnation),
+                                            ..summary(RumorKey::from(s)) }
+                         }));
+        summaries.extend(summarize(&self.election_store, |e| {
+                             RumorSummary { election: Some((e.term, e.status)),
+                                            ..summary(RumorKey::from(e)) }
+                         }));
+        summaries.extend(summarize(&self.update_store, |e| {
+                             RumorSummary { election: Some((e.term, e.status)),
+                                            ..summary(RumorKey::from(e)) }
+                         }));
+        summaries.extend(summarize(&self.departure_store, |d| summary(RumorKey::from(d))));
+        summaries.extend(summarize(&self.purge_store, |p| summary(RumorKey::from(p))));
+
+        let rumor_heat = self.rumor_heat.lock_rhr();
+        for summary in summaries.iter_mut() {
+            let (shared, cooled) = rumor_heat.heat_of(&summary.key);
+            summary.shared = shared;
+            summary.cooled = cooled;
+        }
+        summaries
     }
 
     /// # Locking (see locking.md)
@@ -1305,7 +1868,7 @@ impl<'a> Serialize for ServerProxy<'a> {
         let sfsp = RumorStoreProxy::new(&self.0.service_file_store);
         let mlp = MemberListProxy::new(&self.0.member_list);
 
-        let mut strukt = serializer.serialize_struct("butterfly_server", 7)?;
+        let mut strukt = serializer.serialize_struct("butterfly_server", 15)?;
         strukt.serialize_field("member", &self.0.member_list)?;
         strukt.serialize_field("membership", &mlp)?;
         strukt.serialize_field("service", &self.0.service_store)?;
@@ -1320,6 +1883,7 @@ impl<'a> Serialize for ServerProxy<'a> {
         strukt.serialize_field("latest_election_update", &eusp)?;
         strukt.serialize_field("departure", &self.0.departure_store)?;
         strukt.serialize_field("departed_members", &dsp)?;
+        strukt.serialize_field("timing", &self.0.timing)?;
         strukt.end()
     }
 }
@@ -1396,14 +1960,22 @@ mod tests {
 
     fn check_quorum_returns(val: bool) -> impl Fn(&str) -> bool { move |_: &str| val }
 
+    fn check_election_quorum_returns(val: bool) -> impl Fn(&str, u32) -> bool {
+        move |_: &str, _: u32| val
+    }
+
     fn mock_service(member: &Member) -> Service {
-        Service { member_id:     member.id.clone(),
-                  service_group: ServiceGroup::from_str("group.default").unwrap(),
-                  incarnation:   Default::default(),
-                  initialized:   Default::default(),
-                  pkg:           Default::default(),
-                  cfg:           Default::default(),
-                  sys:           Default::default(), }
+        Service { member_id:           member.id.clone(),
+                  service_group:       ServiceGroup::from_str("group.default").unwrap(),
+                  incarnation:         Default::default(),
+                  initialized:         Default::default(),
+                  pkg:                 Default::default(),
+                  cfg:                 Default::default(),
+                  sys:                 Default::default(),
+                  health_check_passed: Default::default(),
+                  ready:               Default::default(),
+                  blocked_releases:    Default::default(),
+                  signature:           Default::default(), }
     }
 
     #[test]
@@ -1431,7 +2003,7 @@ mod tests {
         let to_restart = Server::elections_to_restart_impl(&elections,
                                                            &service_store,
                                                            &myself.id,
-                                                           check_quorum_returns(true),
+                                                           check_election_quorum_returns(true),
                                                            &member_list,
                                                            FeatureFlag::empty(),
                                                            &None);
@@ -1466,7 +2038,7 @@ mod tests {
         let to_restart = Server::elections_to_restart_impl(&elections,
                                                            &service_store,
                                                            &myself.id,
-                                                           check_quorum_returns(true),
+                                                           check_election_quorum_returns(true),
                                                            &member_list,
                                                            FeatureFlag::empty(),
                                                            &None);
@@ -1669,7 +2241,9 @@ mod tests {
             fn suitability_for_msr(&self, _service_group: &str) -> u64 { 0 }
         }
 
-        fn start_server() -> Server {
+        fn start_server() -> Server { start_server_in(None) }
+
+        fn start_server_in(data_path: Option<&Path>) -> Server {
             let swim_port;
             {
                 let mut swim_port_guard = SWIM_PORT.lock().expect("SWIM_PORT poisoned");
@@ -1693,7 +2267,7 @@ mod tests {
                         member,
                         None,
                         None,
-                        None,
+                        data_path,
                         Arc::new(ZeroSuitability)).unwrap()
         }
 
@@ -1747,5 +2321,17 @@ mod tests {
             server.start_rsw_mlw_smw_rhw_msr(&Timing::default())
                   .expect("Server failed to start");
         }
+
+        #[test]
+        fn start_gossips_a_newer_incarnation_than_was_persisted() {
+            let tmpdir = TempDir::new().unwrap();
+            let mut store = IncarnationStore::new(tmpdir.path().join("INCARNATION"));
+            store.store(Incarnation::from(4)).unwrap();
+            let mut server = start_server_in(Some(tmpdir.path()));
+            server.start_rsw_mlw_smw_rhw_msr(&Timing::default())
+                  .expect("Server failed to start");
+            assert_eq!(server.myself().lock_smr().incarnation(), Incarnation::from(5));
+            assert_eq!(store.load().unwrap(), Incarnation::from(5));
+        }
     }
 }
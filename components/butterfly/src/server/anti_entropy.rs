@@ -0,0 +1,252 @@
+//! The anti-entropy thread.
+//!
+//! Hot rumors only spread while they are hot, so a member that misses them, because it is new or
+//! was cut off from the ring, would otherwise not hear of a rumor until it changes again. Every
+//! `Timing::anti_entropy_period_ms`, this thread sends a digest of the rumors we hold, their keys
+//! and versions but not their contents, to one member. That member answers with the rumors we
+//! are missing or behind on, along with a digest of the ones it is missing or behind on, which we
+//! answer with those rumors in turn. Rumors both members already have never cross the wire.
+//!
+//! Membership is left out, as SWIM already carries it on every probe.
+
+use crate::{member::Member,
+            rumor::{Digest,
+                    DigestEntry,
+                    Rumor,
+                    RumorEnvelope,
+                    RumorKey,
+                    RumorKind,
+                    RumorType},
+            server::{push,
+                     timing::Timing,
+                     Server}};
+use habitat_common::liveliness_checker;
+use habitat_core::util::ToI64;
+use prometheus::{IntCounter,
+                 IntCounterVec};
+use std::{collections::HashMap,
+          thread,
+          time::Duration};
+
+lazy_static! {
+    static ref ANTI_ENTROPY_BYTES_SENT: IntCounterVec =
+        register_int_counter_vec!("hab_butterfly_anti_entropy_sent_bytes_total",
+                                  "Bytes sent in digest exchanges, by digests and by the rumors \
+                                   they asked for",
+                                  &["type"]).unwrap();
+    static ref ANTI_ENTROPY_BYTES_SAVED: IntCounter =
+        register_int_counter!("hab_butterfly_anti_entropy_saved_bytes_total",
+                              "Bytes of rumors that digest exchanges found the other member \
+                               already had, and so did not send").unwrap();
+}
+
+/// Gossip to send back to the member a digest came from.
+pub(crate) struct Answer {
+    pub member:   Member,
+    pub payloads: Vec<(RumorType, Vec<u8>)>,
+}
+
+pub fn spawn_thread(name: String, server: Server, timing: Timing) -> std::io::Result<()> {
+    thread::Builder::new().name(name)
+                          .spawn(move || -> ! { run_loop(&server, &timing) })
+                          .map(|_| ())
+}
+
+fn run_loop(server: &Server, timing: &Timing) -> ! {
+    loop {
+        liveliness_checker::mark_thread_alive().and_divergent();
+
+        if server.paused() {
+            thread::sleep(Duration::from_millis(100));
+            continue;
+        }
+
+        let next_exchange = timing.anti_entropy_timeout();
+        if let Some((member, payload)) = start_exchange_rsr_mlr(server) {
+            push::send_payloads(&member, &[(RumorType::Digest, payload)]);
+        }
+
+        let wait_time = (next_exchange - timing.clock.now()).num_milliseconds();
+        if wait_time > 0 {
+            thread::sleep(Duration::from_millis(wait_time as u64));
+        }
+    }
+}
+
+/// Pick a member to exchange digests with, and encode a digest of every rumor we hold for it.
+///
+/// # Locking (see locking.md)
+/// * `RumorStore::list` (read)
+/// * `MemberList::entries` (read)
+pub(crate) fn start_exchange_rsr_mlr(server: &Server) -> Option<(Member, Vec<u8>)> {
+    let member = server.member_list
+                       .check_list_mlr(server.member_id())
+                       .into_iter()
+                       .find(|member| {
+                           !server.is_member_blocked_sblr(&member.id)
+                           && server.member_list.pingable_mlr(member)
+                           && !server.member_list.persistent_and_confirmed_mlr(member)
+                       })?;
+    let digest = Digest { entries:  digest_entries_rsr(server),
+                          complete: true, };
+    let payload = digest_payload(server, digest)?;
+    debug!("Starting a digest exchange with {}", member.id);
+    Some((member, payload))
+}
+
+/// Work out what the member that sent us a digest is missing or behind on, and what we are, and
+/// encode the rumors and the digest to send back to it. Nothing is sent back to a member we don't
+/// know, or if the member has everything we do and we have everything it does.
+///
+/// # Locking (see locking.md)
+/// * `RumorStore::list` (read)
+/// * `MemberList::entries` (read)
+pub(crate) fn answer_digest_rsr_mlr(server: &Server,
+                                    from_id: &str,
+                                    digest: &Digest)
+                                    -> Option<Answer> {
+    let member = server.member_list.get_cloned_mlr(from_id)?;
+    let (theirs_behind, ours_behind) = if digest.complete {
+        compare_rsr(server, digest)
+    } else {
+        // The digest lists the rumors the member wants; send along the ones we have.
+        let wanted = digest.entries
+                           .iter()
+                           .map(DigestEntry::rumor_key)
+                           .filter(|key| version_of_rsr(server, key).is_some())
+                           .collect();
+        (wanted, Vec::new())
+    };
+
+    let mut payloads: Vec<_> =
+        push::rumor_payloads_rsr_mlr(server, &theirs_behind).into_iter()
+                                                            .map(|(key, payload)| {
+                                                                (key.kind, payload)
+                                                            })
+                                                            .collect();
+    ANTI_ENTROPY_BYTES_SENT.with_label_values(&["rumor"])
+                           .inc_by(payloads.iter()
+                                           .map(|(_, payload)| payload.len())
+                                           .sum::<usize>()
+                                           .to_i64());
+    if !ours_behind.is_empty() {
+        let want = Digest { entries:  ours_behind,
+                            complete: false, };
+        if let Some(payload) = digest_payload(server, want) {
+            payloads.push((RumorType::Digest, payload));
+        }
+    }
+    if payloads.is_empty() {
+        return None;
+    }
+    debug!("Answering the digest from {} with {} messages",
+           member.id,
+           payloads.len());
+    Some(Answer { member, payloads })
+}
+
+/// Compare a complete digest with the rumors we hold. Returns the keys of the rumors the sender
+/// is missing or behind on, and the digest entries of the ones we are.
+///
+/// # Locking (see locking.md)
+/// * `RumorStore::list` (read)
+fn compare_rsr(server: &Server, digest: &Digest) -> (Vec<RumorKey>, Vec<DigestEntry>) {
+    let theirs: HashMap<RumorKey, u64> = digest.entries
+                                               .iter()
+                                               .map(|entry| (entry.rumor_key(), entry.version))
+                                               .collect();
+    let mut theirs_behind = Vec::new();
+    let mut saved = 0;
+    for entry in digest_entries_rsr(server) {
+        let key = entry.rumor_key();
+        match theirs.get(&key) {
+            Some(&version) if version == entry.version => saved += encoded_len_rsr(server, &key),
+            Some(&version) if version > entry.version => {}
+            _ => theirs_behind.push(key),
+        }
+    }
+    ANTI_ENTROPY_BYTES_SAVED.inc_by(saved.to_i64());
+
+    let ours_behind = digest.entries
+                            .iter()
+                            .filter(|entry| !server.is_purged_rsr(&entry.key))
+                            .filter_map(|entry| {
+                                let key = entry.rumor_key();
+                                match version_of_rsr(server, &key) {
+                                    Some(version) if version >= entry.version => None,
+                                    version => Some(DigestEntry::new(key, version.unwrap_or(0))),
+                                }
+                            })
+                            .collect();
+    (theirs_behind, ours_behind)
+}
+
+/// The key and version of every rumor we hold, apart from expired purges, which are on their way
+/// out and must not be passed on.
+///
+/// # Locking (see locking.md)
+/// * `RumorStore::list` (read)
+fn digest_entries_rsr(server: &Server) -> Vec<DigestEntry> {
+    let mut entries = Vec::new();
+    entries.extend(server.service_store.lock_rsr().digest_entries());
+    entries.extend(server.service_config_store.lock_rsr().digest_entries());
+    entries.extend(server.service_file_store.lock_rsr().digest_entries());
+    entries.extend(server.election_store.lock_rsr().digest_entries());
+    entries.extend(server.update_store.lock_rsr().digest_entries());
+    entries.extend(server.departure_store.lock_rsr().digest_entries());
+    entries.extend(server.purge_store
+                         .lock_rsr()
+                         .rumors()
+                         .filter(|purge| !server.purge_expired(purge))
+                         .map(|purge| DigestEntry::new(RumorKey::from(purge), purge.version())));
+    entries
+}
+
+/// The version of the rumor we hold for the key, if we hold it.
+///
+/// # Locking (see locking.md)
+/// * `RumorStore::list` (read)
+fn version_of_rsr(server: &Server, key: &RumorKey) -> Option<u64> {
+    match key.kind {
+        RumorType::Service => server.service_store.lock_rsr().version_of(key),
+        RumorType::ServiceConfig => server.service_config_store.lock_rsr().version_of(key),
+        RumorType::ServiceFile => server.service_file_store.lock_rsr().version_of(key),
+        RumorType::Election => server.election_store.lock_rsr().version_of(key),
+        RumorType::ElectionUpdate => server.update_store.lock_rsr().version_of(key),
+        RumorType::Departure => server.departure_store.lock_rsr().version_of(key),
+        RumorType::Purge => server.purge_store.lock_rsr().version_of(key),
+        RumorType::Member | RumorType::Digest | RumorType::Fake | RumorType::Fake2 => None,
+    }
+}
+
+/// How many bytes the rumor we hold for the key takes up, encoded. The length is worked out when
+/// the rumor is stored, so this is only a lookup.
+///
+/// # Locking (see locking.md)
+/// * `RumorStore::list` (read)
+fn encoded_len_rsr(server: &Server, key: &RumorKey) -> usize {
+    match key.kind {
+        RumorType::Service => server.service_store.lock_rsr().encoded_len(key),
+        RumorType::ServiceConfig => server.service_config_store.lock_rsr().encoded_len(key),
+        RumorType::ServiceFile => server.service_file_store.lock_rsr().encoded_len(key),
+        RumorType::Election => server.election_store.lock_rsr().encoded_len(key),
+        RumorType::ElectionUpdate => server.update_store.lock_rsr().encoded_len(key),
+        RumorType::Departure => server.departure_store.lock_rsr().encoded_len(key),
+        RumorType::Purge => server.purge_store.lock_rsr().encoded_len(key),
+        RumorType::Member | RumorType::Digest | RumorType::Fake | RumorType::Fake2 => 0,
+    }
+}
+
+/// Encode a digest for the wire.
+fn digest_payload(server: &Server, digest: Digest) -> Option<Vec<u8>> {
+    let envelope = RumorEnvelope { r#type:  RumorType::Digest,
+                                   from_id: server.member_id().to_string(),
+                                   kind:    RumorKind::Digest(digest), };
+    let payload = envelope.encode()
+                          .and_then(|bytes| server.generate_wire_srkr(bytes))
+                          .map_err(|e| error!("Could not encode a digest: {}", e))
+                          .ok()?;
+    ANTI_ENTROPY_BYTES_SENT.with_label_values(&["digest"])
+                           .inc_by(payload.len().to_i64());
+    Some(payload)
+}
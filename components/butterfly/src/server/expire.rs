@@ -1,16 +1,20 @@
 //! Periodically check membership rumors to automatically "time out"
 //! `Suspect` rumors to `Confirmed`, and `Confirmed` rumors to
-//! `Departed`.
+//! `Departed`. Less often, drop the rumors of members that have been
+//! gone for longer than the rumor TTL.
 
 use crate::{rumor::{RumorKey,
                     RumorType},
             server::{timing::Timing,
                      Server}};
 use habitat_common::liveliness_checker;
-use std::{thread,
+use std::{cmp,
+          thread,
           time::Duration};
 
 const LOOP_DELAY_MS: u64 = 500;
+/// How many loops go by between checks for expired rumors.
+const RUMOR_EXPIRY_LOOPS: u64 = 120;
 
 pub fn spawn_thread(name: String, server: Server, timing: Timing) -> std::io::Result<()> {
     thread::Builder::new().name(name)
@@ -19,30 +23,66 @@ pub fn spawn_thread(name: String, server: Server, timing: Timing) -> std::io::Re
 }
 
 fn run_loop(server: &Server, timing: &Timing) -> ! {
+    let mut loops: u64 = 0;
     loop {
         liveliness_checker::mark_thread_alive().and_divergent();
 
-        let newly_confirmed_members =
-            server.member_list
-                  .members_expired_to_confirmed_mlw(timing.suspicion_timeout_duration());
-
-        for id in newly_confirmed_members {
-            server.rumor_heat
-                  .lock_rhw()
-                  .start_hot_rumor(RumorKey::new(RumorType::Member, &id, ""));
+        expire_members_mlw_rhw(server, timing);
+        if loops % RUMOR_EXPIRY_LOOPS == 0 {
+            expire_rumors_mlr_rsw_rhw(server, timing);
         }
+        loops = loops.wrapping_add(1);
 
-        let newly_departed_members =
-            server.member_list
-                  .members_expired_to_departed_mlw(timing.departure_timeout_duration());
+        thread::sleep(Duration::from_millis(LOOP_DELAY_MS));
+    }
+}
 
-        for id in newly_departed_members {
-            server.rumor_heat.lock_rhw().purge(&id);
-            server.rumor_heat
-                  .lock_rhw()
-                  .start_hot_rumor(RumorKey::new(RumorType::Member, &id, ""));
-        }
+/// Time out `Suspect` members to `Confirmed`, and `Confirmed` members
+/// to `Departed`, starting hot rumors for each.
+///
+/// A suspicion times out sooner the more members have independently
+/// suspected the member, up to `Timing::suspicion_confirmations` of them,
+/// or as many as there are alive members to do so if that is fewer.
+///
+/// # Locking (see locking.md)
+/// * `MemberList::entries` (write)
+/// * `RumorHeat::inner` (write)
+pub(crate) fn expire_members_mlw_rhw(server: &Server, timing: &Timing) {
+    // Every alive member but the first to suspect could confirm it.
+    let expected = cmp::min(timing.suspicion_confirmations,
+                            server.member_list.len_alive_mlr().saturating_sub(1));
+    let newly_confirmed_members =
+        server.member_list.members_expired_to_confirmed_mlw(|suspecters| {
+                              // Members that don't record who suspected count as one
+                              let confirmations = cmp::max(suspecters, 1) - 1;
+                              timing.suspicion_timeout_for(confirmations, expected)
+                          });
 
-        thread::sleep(Duration::from_millis(LOOP_DELAY_MS));
+    for id in newly_confirmed_members {
+        server.rumor_heat
+              .lock_rhw()
+              .start_hot_rumor(RumorKey::new(RumorType::Member, &id, ""));
     }
+
+    let newly_departed_members =
+        server.member_list
+              .members_expired_to_departed_mlw(timing.departure_timeout_duration());
+
+    for id in newly_departed_members {
+        server.rumor_heat.lock_rhw().purge(&id);
+        server.rumor_heat
+              .lock_rhw()
+              .start_hot_rumor(RumorKey::new(RumorType::Member, &id, ""));
+    }
+}
+
+/// Drop the rumors of members that have been `Confirmed` or
+/// `Departed` for longer than the rumor TTL, and purges older than it.
+///
+/// # Locking (see locking.md)
+/// * `MemberList::entries` (read)
+/// * `RumorStore::list` (write)
+/// * `RumorHeat::inner` (write)
+pub(crate) fn expire_rumors_mlr_rsw_rhw(server: &Server, timing: &Timing) {
+    server.expire_rumors_mlr_rsw_rhw(timing.rumor_ttl_duration());
 }
@@ -2,7 +2,8 @@
 //!
 //! This module handles all the inbound SWIM messages.
 
-use super::AckSender;
+use super::{AckSender,
+            SwimSocket};
 use crate::{member::Health,
             server::{outbound,
                      Server},
@@ -56,70 +57,11 @@ pub fn run_loop(server: &Server, socket: &UdpSocket, tx_outbound: &AckSender) ->
 
         match socket.recv_from(&mut recv_buffer[..]) {
             Ok((length, addr)) => {
-                let swim_payload = match server.unwrap_wire(&recv_buffer[0..length]) {
-                    Ok(swim_payload) => swim_payload,
-                    Err(e) => {
-                        // NOTE: In the future, we might want to block people who send us
-                        // garbage all the time.
-                        error!("Error unwrapping protocol message, {}", e);
-                        let label_values = &["unwrap_wire", "failure"];
-                        SWIM_BYTES_RECEIVED.with_label_values(label_values)
-                                           .set(length.to_i64());
-                        SWIM_MESSAGES_RECEIVED.with_label_values(label_values).inc();
-                        continue;
-                    }
-                };
-
-                let bytes_received = swim_payload.len();
-                let msg = match Swim::decode(&swim_payload) {
-                    Ok(msg) => msg,
-                    Err(e) => {
-                        // NOTE: In the future, we might want to block people who send us
-                        // garbage all the time.
-                        error!("Error decoding protocol message, {}", e);
-                        let label_values = &["undecodable", "failure"];
-                        SWIM_BYTES_RECEIVED.with_label_values(label_values)
-                                           .set(bytes_received.to_i64());
-                        SWIM_MESSAGES_RECEIVED.with_label_values(label_values).inc();
-                        continue;
-                    }
-                };
-
-                // Setting a label_values variable here throws errors about moving borrowed
-                // content that I couldn't solve w/o clones. Leaving this for now. I'm sure
-                // there's a better way.
-                SWIM_BYTES_RECEIVED.with_label_values(&[msg.kind.as_str(), "success"])
-                                   .set(bytes_received.to_i64());
-                SWIM_MESSAGES_RECEIVED.with_label_values(&[msg.kind.as_str(), "success"])
-                                      .inc();
-
-                trace!("SWIM Message: {:?}", msg);
-                match msg.kind {
-                    SwimKind::Ping(ping) => {
-                        if server.is_member_blocked_sblr(&ping.from.id) {
-                            debug!("Not processing message from {} - it is blocked",
-                                   ping.from.id);
-                            continue;
-                        }
-                        process_ping_mlw_smw_rhw(server, socket, addr, ping);
-                    }
-                    SwimKind::Ack(ack) => {
-                        if server.is_member_blocked_sblr(&ack.from.id) && ack.forward_to.is_none() {
-                            debug!("Not processing message from {} - it is blocked",
-                                   ack.from.id);
-                            continue;
-                        }
-                        process_ack_mlw_smw_rhw(server, socket, tx_outbound, addr, ack);
-                    }
-                    SwimKind::PingReq(pingreq) => {
-                        if server.is_member_blocked_sblr(&pingreq.from.id) {
-                            debug!("Not processing message from {} - it is blocked",
-                                   pingreq.from.id);
-                            continue;
-                        }
-                        process_pingreq_mlr_smr_rhw(server, socket, addr, pingreq);
-                    }
-                }
+                process_swim_mlw_smw_rhw(server,
+                                         socket,
+                                         tx_outbound,
+                                         addr,
+                                         &recv_buffer[0..length]);
             }
             Err(e) => {
                 // TODO: We can't use magic numbers here because the Supervisor runs on more
@@ -142,6 +84,105 @@ pub fn run_loop(server: &Server, socket: &UdpSocket, tx_outbound: &AckSender) ->
     }
 }
 
+/// Unwrap, decode and handle a single SWIM message received from `addr`.
+///
+/// # Locking (see locking.md)
+/// * `MemberList::entries` (write)
+/// * `Server::member` (write)
+/// * `RumorHeat::inner` (write)
+pub(crate) fn process_swim_mlw_smw_rhw(server: &Server,
+                                       socket: &dyn SwimSocket,
+                                       tx_outbound: &AckSender,
+                                       addr: SocketAddr,
+                                       bytes: &[u8]) {
+    let (swim_payload, signed_by) = match server.unwrap_wire_srkr(bytes) {
+        Ok(unwrapped) => unwrapped,
+        Err(e) => {
+            // NOTE: In the future, we might want to block people who send us
+            // garbage all the time.
+            error!("Error unwrapping protocol message, {}", e);
+            let label_values = &["unwrap_wire", "failure"];
+            SWIM_BYTES_RECEIVED.with_label_values(label_values)
+                               .set(bytes.len().to_i64());
+            SWIM_MESSAGES_RECEIVED.with_label_values(label_values).inc();
+            return;
+        }
+    };
+
+    let bytes_received = swim_payload.len();
+    let msg = match Swim::decode(&swim_payload) {
+        Ok(msg) => msg,
+        Err(e) => {
+            // NOTE: In the future, we might want to block people who send us
+            // garbage all the time.
+            error!("Error decoding protocol message, {}", e);
+            let label_values = &["undecodable", "failure"];
+            SWIM_BYTES_RECEIVED.with_label_values(label_values)
+                               .set(bytes_received.to_i64());
+            SWIM_MESSAGES_RECEIVED.with_label_values(label_values).inc();
+            return;
+        }
+    };
+
+    // Setting a label_values variable here throws errors about moving borrowed
+    // content that I couldn't solve w/o clones. Leaving this for now. I'm sure
+    // there's a better way.
+    SWIM_BYTES_RECEIVED.with_label_values(&[msg.kind.as_str(), "success"])
+                       .set(bytes_received.to_i64());
+    SWIM_MESSAGES_RECEIVED.with_label_values(&[msg.kind.as_str(), "success"])
+                          .inc();
+
+    trace!("SWIM Message: {:?}", msg);
+    match msg.kind {
+        SwimKind::Ping(ping) => {
+            if !signed_by_sender(signed_by.as_ref(), &ping.from.id) {
+                warn!("Not processing message from {} - it is signed by {:?}",
+                      ping.from.id, signed_by);
+                return;
+            }
+            if server.is_member_blocked_sblr(&ping.from.id) {
+                debug!("Not processing message from {} - it is blocked",
+                       ping.from.id);
+                return;
+            }
+            process_ping_mlw_smw_rhw(server, socket, addr, ping);
+        }
+        SwimKind::Ack(ack) => {
+            // Forwarded acks are signed by the member that forwarded them
+            if ack.forward_to.is_none() && !signed_by_sender(signed_by.as_ref(), &ack.from.id) {
+                warn!("Not processing message from {} - it is signed by {:?}",
+                      ack.from.id, signed_by);
+                return;
+            }
+            if server.is_member_blocked_sblr(&ack.from.id) && ack.forward_to.is_none() {
+                debug!("Not processing message from {} - it is blocked",
+                       ack.from.id);
+                return;
+            }
+            process_ack_mlw_smw_rhw(server, socket, tx_outbound, addr, ack);
+        }
+        SwimKind::PingReq(pingreq) => {
+            if !signed_by_sender(signed_by.as_ref(), &pingreq.from.id) {
+                warn!("Not processing message from {} - it is signed by {:?}",
+                      pingreq.from.id, signed_by);
+                return;
+            }
+            if server.is_member_blocked_sblr(&pingreq.from.id) {
+                debug!("Not processing message from {} - it is blocked",
+                       pingreq.from.id);
+                return;
+            }
+            process_pingreq_mlr_smr_rhw(server, socket, addr, pingreq);
+        }
+    }
+}
+
+/// When members have identities, a SWIM message must be signed by the
+/// member it says it is from.
+fn signed_by_sender(signed_by: Option<&String>, from: &str) -> bool {
+    signed_by.map_or(true, |member_id| member_id == from)
+}
+
 /// Process pingreq messages.
 ///
 /// # Locking (see locking.md)
@@ -149,7 +190,7 @@ pub fn run_loop(server: &Server, socket: &UdpSocket, tx_outbound: &AckSender) ->
 /// * `Server::member` (read)
 /// * `RumorHeat::inner` (write)
 fn process_pingreq_mlr_smr_rhw(server: &Server,
-                               socket: &UdpSocket,
+                               socket: &dyn SwimSocket,
                                addr: SocketAddr,
                                mut msg: PingReq) {
     if let Some(target) = server.member_list.get_cloned_mlr(&msg.target.id) {
@@ -177,7 +218,7 @@ fn process_pingreq_mlr_smr_rhw(server: &Server,
 /// * `Server::member` (write)
 /// * `RumorHeat::inner` (write)
 fn process_ack_mlw_smw_rhw(server: &Server,
-                           socket: &UdpSocket,
+                           socket: &dyn SwimSocket,
                            tx_outbound: &AckSender,
                            addr: SocketAddr,
                            mut msg: Ack) {
@@ -209,7 +250,7 @@ fn process_ack_mlw_smw_rhw(server: &Server,
     match tx_outbound.send((addr, msg)) {
         Ok(()) => {
             for membership in memberships {
-                server.insert_member_from_rumor_mlw_smw_rhw(membership.member, membership.health);
+                server.insert_member_from_rumor_mlw_smw_rhw(membership);
             }
         }
         Err(e) => panic!("Outbound thread has died - this shouldn't happen: #{:?}", e),
@@ -220,8 +261,11 @@ fn process_ack_mlw_smw_rhw(server: &Server,
 /// * `MemberList::entries` (write)
 /// * `Server::member` (write)
 /// * `RumorHeat::inner` (write)
-fn process_ping_mlw_smw_rhw(server: &Server, socket: &UdpSocket, addr: SocketAddr, mut msg: Ping) {
-    outbound::ack_mlr_smr_rhw(server, socket, &msg.from, addr, msg.forward_to);
+fn process_ping_mlw_smw_rhw(server: &Server,
+                            socket: &dyn SwimSocket,
+                            addr: SocketAddr,
+                            mut msg: Ping) {
+    let ack_to = msg.from.clone();
     // Populate the member for this sender with its remote address
     msg.from.address = addr.ip().to_string();
     trace!("Ping from {}@{}", msg.from.id, addr);
@@ -230,7 +274,10 @@ fn process_ping_mlw_smw_rhw(server: &Server, socket: &UdpSocket, addr: SocketAdd
     } else {
         server.insert_member_mlw_rhw(msg.from, Health::Alive);
     }
+    // A ping always carries what the sender thinks of us, so if it suspects us, the refutation
+    // has to be in place before we ack for the ack to carry it straight back.
     for membership in msg.membership {
-        server.insert_member_from_rumor_mlw_smw_rhw(membership.member, membership.health);
+        server.insert_member_from_rumor_mlw_smw_rhw(membership);
     }
+    outbound::ack_mlr_smr_rhw(server, socket, &ack_to, addr, msg.forward_to);
 }
@@ -0,0 +1,77 @@
+//! Our own local health, as in Lifeguard.
+//!
+//! A member that is overloaded, or whose network is struggling, misses acks from perfectly
+//! healthy members, and then goes on to suspect them. The local health score counts the evidence
+//! that we are the one in trouble: probes of ours that go unanswered, and suspicions of ourselves
+//! we have had to refute. Every ack to one of our pings is evidence that we are fine, and brings
+//! the score back down. While the score is high, the outbound thread probes more slowly (see
+//! `Timing::with_local_health`), giving others longer to answer before we suspect them.
+
+use habitat_core::util::ToI64;
+use prometheus::IntGauge;
+use std::sync::atomic::{AtomicUsize,
+                        Ordering};
+
+lazy_static! {
+    static ref LOCAL_HEALTH_SCORE: IntGauge =
+        register_int_gauge!("hab_butterfly_swim_local_health_score",
+                            "How much evidence there is that this member, rather than its peers, \
+                             is struggling; 0 is healthy").unwrap();
+}
+
+#[derive(Debug, Default)]
+pub struct LocalHealth {
+    score: AtomicUsize,
+}
+
+impl LocalHealth {
+    /// Something went wrong that suggests we may be the one in trouble. The score never goes
+    /// above `max`.
+    pub fn fault(&self, max: usize) {
+        let score = self.score.load(Ordering::SeqCst);
+        if score < max {
+            self.set(score + 1);
+        }
+    }
+
+    /// One of our probes was answered in time.
+    pub fn success(&self) {
+        let score = self.score.load(Ordering::SeqCst);
+        if score > 0 {
+            self.set(score - 1);
+        }
+    }
+
+    pub fn score(&self) -> usize { self.score.load(Ordering::SeqCst) }
+
+    fn set(&self, score: usize) {
+        self.score.store(score, Ordering::SeqCst);
+        LOCAL_HEALTH_SCORE.set(score.to_i64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalHealth;
+
+    #[test]
+    fn faults_raise_the_score_up_to_the_max() {
+        let health = LocalHealth::default();
+        for _ in 0..5 {
+            health.fault(3);
+        }
+        assert_eq!(health.score(), 3);
+    }
+
+    #[test]
+    fn successes_lower_the_score_down_to_zero() {
+        let health = LocalHealth::default();
+        health.fault(3);
+        health.fault(3);
+        health.success();
+        assert_eq!(health.score(), 1);
+        health.success();
+        health.success();
+        assert_eq!(health.score(), 0);
+    }
+}
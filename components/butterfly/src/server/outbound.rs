@@ -2,7 +2,8 @@
 //!
 //! This module handles the implementation of the swim probe protocol.
 
-use super::AckReceiver;
+use super::{AckReceiver,
+            SwimSocket};
 use crate::{member::{Health,
                      Member},
             rumor::{RumorKey,
@@ -97,7 +98,7 @@ fn run_loop(server: &Server, socket: &UdpSocket, rx_inbound: &AckReceiver, timin
                 } else {
                     server.member_list.with_initial_members_imlr(|member| {
                                           ping_mlr_smr_rhw(&server,
-                                                           &socket,
+                                                           socket,
                                                            &member,
                                                            member.swim_socket_address(),
                                                            None);
@@ -119,12 +120,14 @@ fn run_loop(server: &Server, socket: &UdpSocket, rx_inbound: &AckReceiver, timin
 
         for member in check_list {
             if server.member_list.pingable_mlr(&member) {
+                // The worse our own health, the longer we give the member to answer.
+                let timing = timing.with_local_health(server.local_health_score());
                 // This is the timeout for the next protocol period - if we
                 // complete faster than this, we want to wait in the end
                 // until this timer expires.
                 let next_protocol_period = timing.next_protocol_period();
 
-                probe_mlw_smr_rhw(&server, &socket, &rx_inbound, &timing, member);
+                probe_mlw_smr_rhw(&server, socket, &rx_inbound, &timing, member);
 
                 if SteadyTime::now() <= next_protocol_period {
                     let wait_time = (next_protocol_period - SteadyTime::now()).num_milliseconds();
@@ -160,6 +163,7 @@ fn run_loop(server: &Server, socket: &UdpSocket, rx_inbound: &AckReceiver, timin
 /// PING_RECV_QUEUE_EMPTY_SLEEP_MS, and try again.
 ///
 /// If we don't receive anything at all in the Ping/PingReq loop, we mark the member as Suspect.
+/// A probe that gets an ack brings our local health score down; one that doesn't raises it.
 ///
 /// # Locking (see locking.md)
 /// * `MemberList::entries` (write)
@@ -170,67 +174,142 @@ fn probe_mlw_smr_rhw(server: &Server,
                      rx_inbound: &AckReceiver,
                      timing: &Timing,
                      member: Member) {
-    let pa_timer = SWIM_PROBE_DURATION.with_label_values(&["ping/ack"])
-                                      .start_timer();
-    let mut pr_timer: Option<HistogramTimer> = None;
-    let addr = member.swim_socket_address();
-
-    // Ping the member, and wait for the ack.
-    SWIM_PROBES_SENT.with_label_values(&["ping"]).inc();
-    ping_mlr_smr_rhw(server, socket, &member, addr, None);
-
-    if recv_ack_mlw_rhw(server, rx_inbound, timing, &member, addr, AckFrom::Ping) {
-        SWIM_PROBES_SENT.with_label_values(&["ack"]).inc();
-        pa_timer.observe_duration();
-        return;
+    let mut probe = Probe::start_mlr_smr_rhw(server, socket, timing, member);
+    while !probe.poll_mlw_smr_rhw(server, socket, rx_inbound, timing) {
+        thread::sleep(Duration::from_millis(PING_RECV_QUEUE_EMPTY_SLEEP_MS));
+    }
+}
+
+/// A probe of a single member that is in progress. The outbound thread
+/// polls it until it is over; a simulation polls it alongside the probes
+/// of every other member.
+pub(crate) struct Probe {
+    member:   Member,
+    addr:     SocketAddr,
+    ack_from: AckFrom,
+    timeout:  SteadyTime,
+    pa_timer: Option<HistogramTimer>,
+    pr_timer: Option<HistogramTimer>,
+}
+
+impl Probe {
+    /// Ping the member, and start waiting for the ack.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (read)
+    /// * `Server::member` (read)
+    /// * `RumorHeat::inner` (write)
+    pub(crate) fn start_mlr_smr_rhw(server: &Server,
+                                    socket: &dyn SwimSocket,
+                                    timing: &Timing,
+                                    member: Member)
+                                    -> Probe {
+        let pa_timer = SWIM_PROBE_DURATION.with_label_values(&["ping/ack"])
+                                          .start_timer();
+        let addr = member.swim_socket_address();
+
+        SWIM_PROBES_SENT.with_label_values(&["ping"]).inc();
+        ping_mlr_smr_rhw(server, socket, &member, addr, None);
+
+        Probe { member,
+                addr,
+                ack_from: AckFrom::Ping,
+                timeout: timing.ping_timeout(),
+                pa_timer: Some(pa_timer),
+                pr_timer: None }
+    }
+
+    /// Take the acks the inbound thread has passed on, and if the current
+    /// phase has timed out, move on to the next one. Returns `true` once the
+    /// probe is over.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (write)
+    /// * `Server::member` (read)
+    /// * `RumorHeat::inner` (write)
+    pub(crate) fn poll_mlw_smr_rhw(&mut self,
+                                   server: &Server,
+                                   socket: &dyn SwimSocket,
+                                   rx_inbound: &AckReceiver,
+                                   timing: &Timing)
+                                   -> bool {
+        if recv_ack_mlw_rhw(server, rx_inbound, &self.member) {
+            SWIM_PROBES_SENT.with_label_values(&["ack"]).inc();
+            server.local_health.success();
+            self.finish();
+            return true;
+        }
+
+        if timing.clock.now() <= self.timeout {
+            return false;
+        }
+        warn!("Timed out waiting for Ack from {}@{}",
+              &self.member.id, self.addr);
+
+        match self.ack_from {
+            AckFrom::Ping => {
+                self.pingreq_mlr_smr_rhw(server, socket, timing);
+                self.ack_from = AckFrom::PingReq;
+                self.timeout = timing.pingreq_timeout();
+                false
+            }
+            AckFrom::PingReq => {
+                // We mark as suspect when we fail to get a response from the PingReq. That
+                // moves us into the suspicion phase, where anyone marked as suspect has a
+                // certain number of protocol periods to recover.
+                warn!("Marking {} as Suspect", &self.member.id);
+                server.suspect_member_mlw_rhw(self.member.clone());
+                server.local_health.fault(timing.local_health_max);
+                SWIM_PROBES_SENT.with_label_values(&["pingreq/failure"])
+                                .inc();
+                self.finish();
+                true
+            }
+        }
     }
 
-    let pingreq_message = PingReq { membership: vec![],
-                                    from:       server.myself.lock_smr().to_member(),
-                                    target:     member.clone(), };
-    let swim = populate_membership_rumors_mlr_rhw(server, &member, pingreq_message);
-
-    server.member_list
-          .with_pingreq_targets_mlr(server.member_id(), &member.id, |pingreq_target| {
-              SWIM_PROBES_SENT.with_label_values(&["pingreq"]).inc();
-              pr_timer = Some(SWIM_PROBE_DURATION.with_label_values(&["pingreq/ack"])
-                                                 .start_timer());
-              pingreq(server, socket, pingreq_target, &member, &swim);
-          });
-
-    if recv_ack_mlw_rhw(server, rx_inbound, timing, &member, addr, AckFrom::PingReq) {
-        SWIM_PROBES_SENT.with_label_values(&["ack"]).inc();
-    } else {
-        // We mark as suspect when we fail to get a response from the PingReq. That moves us
-        // into the suspicion phase, where anyone marked as suspect has a certain number of
-        // protocol periods to recover.
-        warn!("Marking {} as Suspect", &member.id);
-        server.insert_member_mlw_rhw(member, Health::Suspect);
-        SWIM_PROBES_SENT.with_label_values(&["pingreq/failure"])
-                        .inc();
+    /// Ask other members to ping the member on our behalf.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (read)
+    /// * `Server::member` (read)
+    /// * `RumorHeat::inner` (write)
+    fn pingreq_mlr_smr_rhw(&mut self, server: &Server, socket: &dyn SwimSocket, timing: &Timing) {
+        let pingreq_message = PingReq { membership: vec![],
+                                        from:       server.myself.lock_smr().to_member(),
+                                        target:     self.member.clone(), };
+        let swim = populate_membership_rumors_mlr_rhw(server, &self.member, pingreq_message);
+
+        let member = &self.member;
+        let pr_timer = &mut self.pr_timer;
+        let targets = timing.pingreq_targets;
+        server.member_list
+              .with_pingreq_targets_mlr(server.member_id(), &member.id, targets, |pingreq_target| {
+                  SWIM_PROBES_SENT.with_label_values(&["pingreq"]).inc();
+                  *pr_timer = Some(SWIM_PROBE_DURATION.with_label_values(&["pingreq/ack"])
+                                                      .start_timer());
+                  pingreq(server, socket, pingreq_target, member, &swim);
+              });
     }
 
-    if let Some(prt) = pr_timer {
-        prt.observe_duration();
+    fn finish(&mut self) {
+        if let AckFrom::Ping = self.ack_from {
+            if let Some(pat) = self.pa_timer.take() {
+                pat.observe_duration();
+            }
+        }
+        if let Some(prt) = self.pr_timer.take() {
+            prt.observe_duration();
+        }
     }
 }
 
-/// Listen for an ack from the `Inbound` thread.
+/// Take the acks the `Inbound` thread has passed on, until we find the one from `member`.
 ///
 /// # Locking (see locking.md)
 /// * `MemberList::entries` (write)
 /// * `RumorHeat::inner` (write)
-fn recv_ack_mlw_rhw(server: &Server,
-                    rx_inbound: &AckReceiver,
-                    timing: &Timing,
-                    member: &Member,
-                    addr: SocketAddr,
-                    ack_from: AckFrom)
-                    -> bool {
-    let timeout = match ack_from {
-        AckFrom::Ping => timing.ping_timeout(),
-        AckFrom::PingReq => timing.pingreq_timeout(),
-    };
+fn recv_ack_mlw_rhw(server: &Server, rx_inbound: &AckReceiver, member: &Member) -> bool {
     loop {
         match rx_inbound.try_recv() {
             Ok((real_addr, mut ack)) => {
@@ -239,31 +318,19 @@ fn recv_ack_mlw_rhw(server: &Server,
                 if ack.forward_to.is_none() {
                     ack.from.address = real_addr.ip().to_string();
                 }
-                if member.id != ack.from.id {
-                    if ack.from.departed {
-                        server.insert_member_mlw_rhw(ack.from, Health::Departed);
-                    } else {
-                        server.insert_member_mlw_rhw(ack.from, Health::Alive);
-                    }
-                    // Keep listening, we want the ack we expected
-                    continue;
+                let expected = member.id == ack.from.id;
+                if ack.from.departed {
+                    server.insert_member_mlw_rhw(ack.from, Health::Departed);
                 } else {
+                    server.insert_member_mlw_rhw(ack.from, Health::Alive);
+                }
+                if expected {
                     // We got the ack we are looking for; return.
-                    if ack.from.departed {
-                        server.insert_member_mlw_rhw(ack.from, Health::Departed);
-                    } else {
-                        server.insert_member_mlw_rhw(ack.from, Health::Alive);
-                    }
                     return true;
                 }
+                // Keep listening, we want the ack we expected
             }
-            Err(mpsc::TryRecvError::Empty) => {
-                if SteadyTime::now() > timeout {
-                    warn!("Timed out waiting for Ack from {}@{}", &member.id, addr);
-                    return false;
-                }
-                thread::sleep(Duration::from_millis(PING_RECV_QUEUE_EMPTY_SLEEP_MS));
-            }
+            Err(mpsc::TryRecvError::Empty) => return false,
             Err(mpsc::TryRecvError::Disconnected) => {
                 panic!("Outbound thread has disconnected! This is fatal.");
             }
@@ -323,7 +390,7 @@ pub fn populate_membership_rumors_mlr_rhw(server: &Server,
 /// `target` is alive despite not being directly reachable from `server`. In other words,
 /// `pingreq_target` is the proxy and `target` is the final destination.
 fn pingreq(server: &Server, // TODO: eliminate this arg
-           socket: &UdpSocket,
+           socket: &dyn SwimSocket,
            pingreq_target: &Member,
            target: &Member,
            swim: &Swim) {
@@ -335,7 +402,7 @@ fn pingreq(server: &Server, // TODO: eliminate this arg
             return;
         }
     };
-    let payload = match server.generate_wire(bytes) {
+    let payload = match server.generate_wire_srkr(bytes) {
         Ok(payload) => payload,
         Err(e) => {
             error!("Generating protocol message failed: {}", e);
@@ -372,7 +439,7 @@ fn pingreq(server: &Server, // TODO: eliminate this arg
 /// * `Server::member` (read)
 /// * `RumorHeat::inner` (write)
 pub fn ping_mlr_smr_rhw(server: &Server,
-                        socket: &UdpSocket,
+                        socket: &dyn SwimSocket,
                         target: &Member,
                         addr: SocketAddr,
                         forward_to: Option<&Member>) {
@@ -388,7 +455,7 @@ pub fn ping_mlr_smr_rhw(server: &Server,
             return;
         }
     };
-    let payload = match server.generate_wire(bytes) {
+    let payload = match server.generate_wire_srkr(bytes) {
         Ok(payload) => payload,
         Err(e) => {
             error!("Generating protocol message failed: {}", e);
@@ -412,7 +479,7 @@ pub fn ping_mlr_smr_rhw(server: &Server,
 }
 
 pub fn ping(server: &Server,
-            socket: &UdpSocket,
+            socket: &dyn SwimSocket,
             addr: SocketAddr,
             forward_to: Option<&Member>,
             swim: &Swim) {
@@ -423,7 +490,7 @@ pub fn ping(server: &Server,
             return;
         }
     };
-    let payload = match server.generate_wire(bytes) {
+    let payload = match server.generate_wire_srkr(bytes) {
         Ok(payload) => payload,
         Err(e) => {
             error!("Generating protocol message failed: {}", e);
@@ -447,7 +514,7 @@ pub fn ping(server: &Server,
 }
 
 /// Forward an ack on.
-pub fn forward_ack(server: &Server, socket: &UdpSocket, addr: SocketAddr, msg: Ack) {
+pub fn forward_ack(server: &Server, socket: &dyn SwimSocket, addr: SocketAddr, msg: Ack) {
     let member_id = msg.from.id.clone();
     let swim: Swim = msg.into();
     let bytes = match swim.encode() {
@@ -457,7 +524,7 @@ pub fn forward_ack(server: &Server, socket: &UdpSocket, addr: SocketAddr, msg: A
             return;
         }
     };
-    let payload = match server.generate_wire(bytes) {
+    let payload = match server.generate_wire_srkr(bytes) {
         Ok(payload) => payload,
         Err(e) => {
             error!("Generating protocol message failed: {}", e);
@@ -477,7 +544,7 @@ pub fn forward_ack(server: &Server, socket: &UdpSocket, addr: SocketAddr, msg: A
 /// * `Server::member` (read)
 /// * `RumorHeat::inner` (write)
 pub fn ack_mlr_smr_rhw(server: &Server,
-                       socket: &UdpSocket,
+                       socket: &dyn SwimSocket,
                        target: &Member,
                        addr: SocketAddr,
                        forward_to: Option<Member>) {
@@ -493,7 +560,7 @@ pub fn ack_mlr_smr_rhw(server: &Server,
             return;
         }
     };
-    let payload = match server.generate_wire(bytes) {
+    let payload = match server.generate_wire_srkr(bytes) {
         Ok(payload) => payload,
         Err(e) => {
             error!("Generating protocol message failed: {}", e);
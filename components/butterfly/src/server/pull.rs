@@ -2,15 +2,22 @@
 //!
 //! This module handles pulling all the pushed rumors from every member off a ZMQ socket.
 
-use crate::{rumor::{RumorEnvelope,
+use crate::{rumor::{Rumor,
+                    RumorEnvelope,
                     RumorKind},
-            server::Server,
+            server::{anti_entropy::{self,
+                                    Answer},
+                     push,
+                     Server},
             ZMQ_CONTEXT};
 use habitat_common::liveliness_checker;
 use habitat_core::util::ToI64;
 use prometheus::{IntCounterVec,
                  IntGaugeVec};
-use std::{thread,
+use std::{sync::mpsc::{self,
+                     Receiver,
+                     TrySendError},
+          thread,
           time::Duration};
 use zmq;
 
@@ -25,6 +32,10 @@ lazy_static! {
                                 &["type", "mode", "blocked"]).unwrap();
 }
 
+/// How many answers to digests may wait to be sent before we stop answering. A member whose
+/// digest goes unanswered will send another soon enough.
+const ANSWER_QUEUE_SIZE: usize = 64;
+
 pub fn spawn_thread(name: String, server: Server) -> std::io::Result<()> {
     thread::Builder::new().name(name)
                           .spawn(move || -> ! { run_loop(&server) })
@@ -45,6 +56,12 @@ fn run_loop(server: &Server) -> ! {
           .expect("Failure to set the ZMQ Pull socket receive timeout");
     socket.bind(&format!("tcp://{}", server.gossip_addr()))
           .expect("Failure to bind the ZMQ Pull socket to the port");
+    // Answers are sent from a thread of their own, so connecting to the member doesn't hold up
+    // the rumors still to come.
+    let (answer_tx, answer_rx) = mpsc::sync_channel(ANSWER_QUEUE_SIZE);
+    thread::Builder::new().name(String::from("digest-answer"))
+                          .spawn(move || send_answers(&answer_rx))
+                          .expect("Failure to spawn the digest answer thread");
     'recv: loop {
         if let Ok(-1) = socket.get_rcvtimeo() {
             trace!("Skipping thread liveliness checks due to infinite recv timeout");
@@ -69,69 +86,134 @@ fn run_loop(server: &Server) -> ! {
             }
         };
 
-        let payload = match server.unwrap_wire(&msg) {
-            Ok(payload) => payload,
-            Err(e) => {
-                // NOTE: In the future, we might want to block people who send us
-                // garbage all the time.
-                error!("Error parsing protocol message: {:?}", e);
-                let label_values = &["unwrap_wire", "failure", "unknown"];
-                GOSSIP_BYTES_RECEIVED.with_label_values(label_values)
-                                     .set(msg.len().to_i64());
-                GOSSIP_MESSAGES_RECEIVED.with_label_values(label_values)
-                                        .inc();
-                continue;
+        if let Some(answer) = process_rumor_rsw_mlw_smw_rhw_msr(server, &msg) {
+            match answer_tx.try_send(answer) {
+                Ok(()) => {}
+                Err(TrySendError::Full(answer)) => {
+                    warn!("Too many digests waiting to be answered; not answering {}",
+                          answer.member.id)
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    panic!("The digest answer thread has stopped")
+                }
             }
-        };
+        }
+    }
+}
 
-        let proto = match RumorEnvelope::decode(&payload) {
-            Ok(proto) => proto,
-            Err(e) => {
-                error!("Error parsing protocol message: {:?}", e);
-                let label_values = &["undecodable", "failure", "unknown"];
-                GOSSIP_BYTES_RECEIVED.with_label_values(label_values)
-                                     .set(payload.len().to_i64());
-                GOSSIP_MESSAGES_RECEIVED.with_label_values(label_values)
-                                        .inc();
-                continue 'recv;
-            }
-        };
+fn send_answers(answers: &Receiver<Answer>) {
+    for answer in answers {
+        push::send_payloads(&answer.member, &answer.payloads);
+    }
+}
+
+/// Unwrap, decode and apply a single gossip message. Returns what to send back, if the message
+/// was a digest.
+///
+/// # Locking (see locking.md)
+/// * `RumorStore::list` (write)
+/// * `MemberList::entries` (write)
+/// * `Server::member` (write)
+/// * `RumorHeat::inner` (write)
+/// * `ManagerServices::inner` (read)
+pub(crate) fn process_rumor_rsw_mlw_smw_rhw_msr(server: &Server, msg: &[u8]) -> Option<Answer> {
+    // Rumors are relayed, so the member that signed the message need not be the one a rumor is
+    // from or about. Rumors carry signatures of their own for that, checked as they are inserted.
+    let payload = match server.unwrap_wire_srkr(msg) {
+        Ok((payload, _)) => payload,
+        Err(e) => {
+            // NOTE: In the future, we might want to block people who send us
+            // garbage all the time.
+            error!("Error parsing protocol message: {:?}", e);
+            let label_values = &["unwrap_wire", "failure", "unknown"];
+            GOSSIP_BYTES_RECEIVED.with_label_values(label_values)
+                                 .set(msg.len().to_i64());
+            GOSSIP_MESSAGES_RECEIVED.with_label_values(label_values)
+                                    .inc();
+            return None;
+        }
+    };
+
+    let proto = match RumorEnvelope::decode(&payload) {
+        Ok(proto) => proto,
+        Err(e) => {
+            error!("Error parsing protocol message: {:?}", e);
+            let label_values = &["undecodable", "failure", "unknown"];
+            GOSSIP_BYTES_RECEIVED.with_label_values(label_values)
+                                 .set(payload.len().to_i64());
+            GOSSIP_MESSAGES_RECEIVED.with_label_values(label_values)
+                                    .inc();
+            return None;
+        }
+    };
+
+    let blocked = server.is_member_blocked_sblr(&proto.from_id);
+    let blocked_label = if blocked { "true" } else { "false" };
+    let label_values = &[&proto.r#type.to_string(), "success", blocked_label];
 
-        let blocked = server.is_member_blocked_sblr(&proto.from_id);
-        let blocked_label = if blocked { "true" } else { "false" };
-        let label_values = &[&proto.r#type.to_string(), "success", blocked_label];
+    GOSSIP_MESSAGES_RECEIVED.with_label_values(label_values)
+                            .inc();
+    GOSSIP_BYTES_RECEIVED.with_label_values(label_values)
+                         .set(payload.len().to_i64());
 
-        GOSSIP_MESSAGES_RECEIVED.with_label_values(label_values)
-                                .inc();
-        GOSSIP_BYTES_RECEIVED.with_label_values(label_values)
-                             .set(payload.len().to_i64());
+    if blocked {
+        warn!("Not processing message from {} - it is blocked",
+              proto.from_id);
+        return None;
+    }
 
-        if blocked {
-            warn!("Not processing message from {} - it is blocked",
-                  proto.from_id);
-            continue 'recv;
+    if let Some(service_group) = purgeable_service_group(&proto.kind) {
+        if server.is_purged_rsr(service_group) {
+            debug!("Not processing {} rumor for purged service group {}",
+                   proto.r#type,
+                   service_group);
+            return None;
         }
+    }
 
-        match proto.kind {
-            RumorKind::Membership(membership) => {
-                server.insert_member_from_rumor_mlw_smw_rhw(membership.member, membership.health);
-            }
-            RumorKind::Service(service) => server.insert_service_rsw_mlw_rhw(*service),
-            RumorKind::ServiceConfig(service_config) => {
-                server.insert_service_config_rsw_rhw(service_config);
-            }
-            RumorKind::ServiceFile(service_file) => {
-                server.insert_service_file_rsw_rhw(service_file);
-            }
-            RumorKind::Election(election) => {
-                server.insert_election_rsw_mlr_rhw_msr(election);
-            }
-            RumorKind::ElectionUpdate(election) => {
-                server.insert_update_election_rsw_mlr_rhw(election);
-            }
-            RumorKind::Departure(departure) => {
-                server.insert_departure_rsw_mlw_rhw(departure);
-            }
+    match proto.kind {
+        RumorKind::Membership(membership) => {
+            server.insert_member_from_rumor_mlw_smw_rhw(membership);
+        }
+        RumorKind::Service(service) => {
+            server.insert_service_from_rumor_rsw_mlw_rhw(*service);
+        }
+        RumorKind::ServiceConfig(service_config) => {
+            server.insert_service_config_from_rumor_rsw_rhw(service_config);
         }
+        RumorKind::ServiceFile(service_file) => {
+            server.insert_service_file_from_rumor_rsw_rhw(service_file);
+        }
+        RumorKind::Election(election) => {
+            server.insert_election_from_rumor_rsw_mlr_rhw_msr(election);
+        }
+        RumorKind::ElectionUpdate(election) => {
+            server.insert_update_election_from_rumor_rsw_mlr_rhw(election);
+        }
+        RumorKind::Departure(departure) => {
+            server.insert_departure_from_rumor_rsw_mlw_rhw(departure);
+        }
+        RumorKind::Purge(purge) => {
+            server.insert_purge_from_rumor_rsw_rhw(purge);
+        }
+        RumorKind::Digest(digest) => {
+            return anti_entropy::answer_digest_rsr_mlr(server, &proto.from_id, &digest);
+        }
+    }
+    None
+}
+
+/// The service group of a rumor that purges drop, if it is one.
+fn purgeable_service_group(kind: &RumorKind) -> Option<&str> {
+    match kind {
+        RumorKind::Service(service) => Some(service.key()),
+        RumorKind::ServiceConfig(service_config) => Some(service_config.key()),
+        RumorKind::ServiceFile(service_file) => Some(service_file.key()),
+        RumorKind::Election(election) => Some(election.key()),
+        RumorKind::ElectionUpdate(election) => Some(election.key()),
+        RumorKind::Departure(_)
+        | RumorKind::Digest(_)
+        | RumorKind::Membership(_)
+        | RumorKind::Purge(_) => None,
     }
 }
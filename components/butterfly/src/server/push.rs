@@ -1,10 +1,9 @@
 //! The push thread.
 //!
-//! This is the thread for distributing rumors to members. It distributes to `FANOUT` members, no
-//! more often than `Timing::GOSSIP_PERIOD_DEFAULT_MS`.
+//! This is the thread for distributing rumors to members. It distributes to
+//! `Timing::gossip_fanout` members, no more often than `Timing::gossip_period_ms`.
 
-use crate::{member::{Member,
-                     Membership},
+use crate::{member::Member,
             rumor::{RumorEnvelope,
                     RumorKey,
                     RumorKind,
@@ -21,8 +20,6 @@ use std::{thread,
 use time::SteadyTime;
 use zmq;
 
-const FANOUT: usize = 5;
-
 lazy_static! {
     static ref GOSSIP_MESSAGES_SENT: IntCounterVec =
         register_int_counter_vec!("hab_butterfly_gossip_messages_sent_total",
@@ -41,8 +38,8 @@ pub fn spawn_thread(name: String, server: Server, timing: Timing) -> std::io::Re
 }
 
 /// Executes the Push thread. Gets a list of members to talk to that are not Confirmed; then
-/// proceeds to process the list in `FANOUT` sized chunks. If we finish sending the messages to
-/// all FANOUT targets faster than `Timing::GOSSIP_PERIOD_DEFAULT_MS`, we will block until we
+/// proceeds to process the list in `Timing::gossip_fanout` sized chunks. If we finish sending the
+/// messages to all fanout targets faster than `Timing::gossip_period_ms`, we will block until we
 /// exceed that time.
 fn run_loop(server: &Server, timing: &Timing) -> ! {
     loop {
@@ -59,12 +56,12 @@ fn run_loop(server: &Server, timing: &Timing) -> ! {
         let long_wait = timing.gossip_timeout();
 
         'fanout: loop {
-            let mut thread_list = Vec::with_capacity(FANOUT);
+            let mut thread_list = Vec::with_capacity(timing.gossip_fanout);
             if check_list.is_empty() {
                 break 'fanout;
             }
-            let drain_length = if check_list.len() >= FANOUT {
-                FANOUT
+            let drain_length = if check_list.len() >= timing.gossip_fanout {
+                timing.gossip_fanout
             } else {
                 check_list.len()
             };
@@ -123,21 +120,32 @@ fn run_loop(server: &Server, timing: &Timing) -> ! {
     }
 }
 
-/// Send the list of rumors to a given member. This method creates an outbound socket and then
-/// closes the connection as soon as we are done sending rumors. ZeroMQ may choose to keep the
-/// connection and socket open for 1 second longer - so it is possible, but unlikely, that this
-/// method can lose messages.
+/// Send the list of rumors to a given member, and cool them off for it.
 ///
 /// # Locking (see locking.md)
 /// * `RumorStore::list` (read)
 /// * `MemberList::entries` (read)
 /// * `RumorHeat::inner` (write)
-// If we ever need to modify this function, it would be an excellent opportunity to
-// simplify the redundant aspects and remove this allow(clippy::cognitive_complexity),
-// but changing it in the absence of other necessity seems like too much risk for the
-// expected reward.
-#[allow(clippy::cognitive_complexity)]
 fn send_rumors_rsr_mlr_rhw(server: &Server, member: &Member, rumors: &[RumorKey]) {
+    let payloads: Vec<_> = rumor_payloads_rsr_mlr(server, rumors).into_iter()
+                                                                 .map(|(rumor_key, payload)| {
+                                                                     (rumor_key.kind, payload)
+                                                                 })
+                                                                 .collect();
+    if !send_payloads(member, &payloads) {
+        return;
+    }
+
+    server.rumor_heat
+          .lock_rhw()
+          .cool_rumors(&member.id, &rumors);
+}
+
+/// Send encoded gossip to a given member. This method creates an outbound socket and then closes
+/// the connection as soon as we are done sending. ZeroMQ may choose to keep the connection and
+/// socket open for 1 second longer - so it is possible, but unlikely, that this method can lose
+/// messages. Returns false if we could not connect to the member at all.
+pub(crate) fn send_payloads(member: &Member, payloads: &[(RumorType, Vec<u8>)]) -> bool {
     let socket = (**ZMQ_CONTEXT).as_mut()
                                 .socket(zmq::PUSH)
                                 .expect("Failure to create the ZMQ push socket");
@@ -159,9 +167,41 @@ fn send_rumors_rsr_mlr_rhw(server: &Server, member: &Member, rumors: &[RumorKey]
             let label_values = &["socket_connect", "failure"];
             GOSSIP_MESSAGES_SENT.with_label_values(label_values).inc();
             GOSSIP_BYTES_SENT.with_label_values(label_values).set(0);
-            return;
+            return false;
+        }
+    }
+    for (kind, payload) in payloads {
+        match socket.send(payload.as_slice(), 0) {
+            Ok(()) => {
+                GOSSIP_MESSAGES_SENT.with_label_values(&[&kind.to_string(), "success"])
+                                    .inc();
+                GOSSIP_BYTES_SENT.with_label_values(&[&kind.to_string(), "success"])
+                                 .set(payload.len().to_i64());
+                debug!("Sent {} gossip to {:?}", kind, member);
+            }
+            Err(e) => {
+                warn!("Could not send {} gossip to {:?} @ {:?}; ZMQ said: {:?}",
+                      kind, member.id, to_addr, e)
+            }
         }
     }
+    true
+}
+
+/// Encode the rumors for the wire, leaving out any that can't be.
+///
+/// # Locking (see locking.md)
+/// * `RumorStore::list` (read)
+/// * `MemberList::entries` (read)
+// If we ever need to modify this function, it would be an excellent opportunity to
+// simplify the redundant aspects and remove this allow(clippy::cognitive_complexity),
+// but changing it in the absence of other necessity seems like too much risk for the
+// expected reward.
+#[allow(clippy::cognitive_complexity)]
+pub(crate) fn rumor_payloads_rsr_mlr<'a>(server: &Server,
+                                         rumors: &'a [RumorKey])
+                                         -> Vec<(&'a RumorKey, Vec<u8>)> {
+    let mut payloads = Vec::with_capacity(rumors.len());
     'rumorlist: for rumor_key in rumors.iter() {
         let rumor_as_bytes = match rumor_key.kind {
             RumorType::Member => {
@@ -247,6 +287,20 @@ fn send_rumors_rsr_mlr_rhw(server: &Server, member: &Member, rumors: &[RumorKey]
                     }
                 }
             }
+            RumorType::Purge => {
+                match server.purge_store.lock_rsr().encode_rumor_for(&rumor_key) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        error!("Could not write our own rumor to bytes; abandoning sending \
+                                rumor: {:?}",
+                               e);
+                        let label_values = &["purge_rumor_encode", "failure"];
+                        GOSSIP_MESSAGES_SENT.with_label_values(label_values).inc();
+                        GOSSIP_BYTES_SENT.with_label_values(label_values).set(0);
+                        continue 'rumorlist;
+                    }
+                }
+            }
             RumorType::Election => {
                 match server.election_store
                             .lock_rsr()
@@ -278,13 +332,17 @@ fn send_rumors_rsr_mlr_rhw(server: &Server, member: &Member, rumors: &[RumorKey]
                     }
                 }
             }
+            RumorType::Digest => {
+                debug!("Digests are not kept as rumors; not sending {:?}", rumor_key);
+                continue 'rumorlist;
+            }
             RumorType::Fake | RumorType::Fake2 => {
                 debug!("You have fake rumors; how odd!");
                 continue 'rumorlist;
             }
         };
         let rumor_len = rumor_as_bytes.len().to_i64();
-        let payload = match server.generate_wire(rumor_as_bytes) {
+        let payload = match server.generate_wire_srkr(rumor_as_bytes) {
             Ok(payload) => payload,
             Err(e) => {
                 error!("Generating protobuf failed: {}", e);
@@ -295,24 +353,9 @@ fn send_rumors_rsr_mlr_rhw(server: &Server, member: &Member, rumors: &[RumorKey]
                 continue 'rumorlist;
             }
         };
-        match socket.send(&payload, 0) {
-            Ok(()) => {
-                GOSSIP_MESSAGES_SENT.with_label_values(&[&rumor_key.kind.to_string(), "success"])
-                                    .inc();
-                GOSSIP_BYTES_SENT.with_label_values(&[&rumor_key.kind.to_string(), "success"])
-                                 .set(payload.len().to_i64());
-                debug!("Sent rumor {:?} to {:?}", rumor_key, member);
-            }
-            Err(e) => {
-                warn!("Could not send rumor to {:?} @ {:?}; ZMQ said: {:?}",
-                      member.id, to_addr, e)
-            }
-        }
+        payloads.push((rumor_key, payload));
     }
-
-    server.rumor_heat
-          .lock_rhw()
-          .cool_rumors(&member.id, &rumors);
+    payloads
 }
 
 /// Given a rumorkey, creates a protobuf rumor for sharing.
@@ -320,11 +363,7 @@ fn send_rumors_rsr_mlr_rhw(server: &Server, member: &Member, rumors: &[RumorKey]
 /// # Locking (see locking.md)
 /// * `MemberList::entries` (read)
 fn create_member_rumor_mlr(server: &Server, rumor_key: &RumorKey) -> Option<RumorEnvelope> {
-    let member = server.member_list.get_cloned_mlr(&rumor_key.to_string())?;
-    let payload = Membership { member,
-                               health: server.member_list
-                                             .health_of_by_id_mlr(&rumor_key.to_string())
-                                             .unwrap() };
+    let payload = server.member_list.membership_for_mlr(&rumor_key.to_string())?;
     let rumor = RumorEnvelope { r#type:  RumorType::Member,
                                 from_id: server.member_id().to_string(),
                                 kind:    RumorKind::Membership(payload), };
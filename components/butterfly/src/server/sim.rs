@@ -0,0 +1,656 @@
+//! A deterministic, in-memory simulation of a ring.
+//!
+//! Every member of a `SimNet` is a real `Server`, but none of them bind sockets or start
+//! threads. Instead, the simulation steps each member's probes, gossip, digest exchanges and
+//! expiry in turn, and carries their SWIM and gossip messages over an in-memory network which
+//! can drop, delay, duplicate or partition them. Time comes from a simulated `Clock`, and every
+//! random choice is drawn from the seed the simulation was created with, so a run can be
+//! repeated exactly, and protocol periods go by as fast as the members can be stepped.
+//!
+//! This makes it possible to watch suspicion, confirmation, elections and departures play out
+//! across hundreds of members in a few seconds.
+
+use super::{anti_entropy,
+            expire,
+            inbound,
+            outbound::Probe,
+            pull,
+            push,
+            timing::{Clock,
+                     Timing},
+            AckReceiver,
+            AckSender,
+            Server,
+            Suitability,
+            SwimSocket};
+use crate::{member::{Health,
+                     Member,
+                     MemberList,
+                     ZONE_TAG},
+            rumor::election::ElectionPolicy};
+use rand::{rngs::StdRng,
+           Rng,
+           SeedableRng};
+use std::{cell::RefCell,
+          cmp::{self,
+                Reverse},
+          collections::{BinaryHeap,
+                        HashMap,
+                        HashSet},
+          io,
+          net::{IpAddr,
+                Ipv4Addr,
+                SocketAddr},
+          ops::{Index,
+                Range},
+          sync::{mpsc,
+                 Arc,
+                 Mutex}};
+use time::{Duration as TimeDuration,
+           SteadyTime};
+
+/// How far the clock moves on each step of the simulation.
+const STEP_MS: i64 = 50;
+
+/// Simulated members all listen on this port, at an address of their own.
+const SIM_PORT: u16 = 9638;
+
+/// What the network does to the messages it carries. Rates are chances between 0.0 and 1.0.
+#[derive(Clone, Debug, Default)]
+pub struct Faults {
+    /// How likely a message is to be lost
+    pub drop_rate: f64,
+    /// How likely a message is to be delivered twice
+    pub duplicate_rate: f64,
+    /// The least and the most milliseconds a message takes to arrive
+    pub delay_ms: (i64, i64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Channel {
+    Swim,
+    Gossip,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct InFlight {
+    deliver_at_ms: i64,
+    seq:           u64,
+    from:          usize,
+    to:            usize,
+    channel:       Channel,
+    payload:       Vec<u8>,
+}
+
+/// Collects what a member sends over SWIM while it is stepped, for the network to carry
+/// afterwards.
+#[derive(Debug, Default)]
+struct SimSocket(RefCell<Vec<(SocketAddr, Vec<u8>)>>);
+
+impl SwimSocket for SimSocket {
+    fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+        self.0.borrow_mut().push((addr, buf.to_vec()));
+        Ok(buf.len())
+    }
+}
+
+/// Every simulated member has the same suitability and election policy.
+#[derive(Debug, Default)]
+struct SimSuitability(Mutex<ElectionPolicy>);
+
+impl Suitability for SimSuitability {
+    fn suitability_for_msr(&self, _service_group: &str) -> u64 { 0 }
+
+    fn election_policy_for_msr(&self, _service_group: &str) -> ElectionPolicy {
+        self.0
+            .lock()
+            .expect("Election policy lock poisoned")
+            .clone()
+    }
+}
+
+/// A member of the simulation, along with the state its outbound and push threads would keep.
+struct SimMember {
+    server:         Server,
+    tx_outbound:    AckSender,
+    rx_inbound:     AckReceiver,
+    running:        bool,
+    probe:          Option<Probe>,
+    probe_list:     Vec<Member>,
+    next_probe_at:  SteadyTime,
+    gossip_list:    Vec<Member>,
+    next_gossip_at: SteadyTime,
+    next_digest_at: SteadyTime,
+}
+
+impl SimMember {
+    /// Carry on with the current probe, or start the next one once a protocol period has gone
+    /// by, just as the outbound thread does, slowed down by the member's local health.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (write)
+    /// * `Server::member` (read)
+    /// * `RumorHeat::inner` (write)
+    fn probe_mlw_smr_rhw(&mut self, socket: &SimSocket, timing: &Timing) {
+        let timing = &timing.with_local_health(self.server.local_health_score());
+        if let Some(mut probe) = self.probe.take() {
+            if !probe.poll_mlw_smr_rhw(&self.server, socket, &self.rx_inbound, timing) {
+                self.probe = Some(probe);
+                return;
+            }
+        }
+        if timing.clock.now() < self.next_probe_at {
+            return;
+        }
+        if self.probe_list.is_empty() {
+            self.server.update_swim_round();
+            self.probe_list = self.server
+                                  .member_list
+                                  .check_list_mlr(self.server.member_id());
+        }
+        while let Some(member) = self.probe_list.pop() {
+            if self.server.member_list.pingable_mlr(&member) {
+                self.probe = Some(Probe::start_mlr_smr_rhw(&self.server, socket, timing, member));
+                self.next_probe_at = timing.next_protocol_period();
+                return;
+            }
+        }
+        // Nobody to probe this round, so wait a protocol period before the next one
+        self.next_probe_at = timing.next_protocol_period();
+    }
+
+    /// Once a gossip period has gone by, encode the hot rumors for the next `gossip_fanout`
+    /// members, just as the push thread does. Returns the payloads and where to send them.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (read)
+    /// * `MemberList::entries` (read)
+    /// * `RumorHeat::inner` (write)
+    fn gossip_rsr_mlr_rhw(&mut self, timing: &Timing) -> Vec<(SocketAddr, Vec<u8>)> {
+        let mut sent = Vec::new();
+        if timing.clock.now() < self.next_gossip_at {
+            return sent;
+        }
+        self.next_gossip_at = timing.gossip_timeout();
+        if self.gossip_list.is_empty() {
+            self.server.update_gossip_round();
+            self.gossip_list = self.server
+                                   .member_list
+                                   .check_list_mlr(self.server.member_id());
+        }
+        let fanout = cmp::min(timing.gossip_fanout, self.gossip_list.len());
+        for member in self.gossip_list.drain(0..fanout) {
+            if self.server.is_member_blocked_sblr(&member.id)
+               || !self.server.member_list.pingable_mlr(&member)
+               || self.server
+                      .member_list
+                      .persistent_and_confirmed_mlr(&member)
+            {
+                continue;
+            }
+            let rumors = self.server
+                             .rumor_heat
+                             .lock_rhr()
+                             .currently_hot_rumors(&member.id);
+            if rumors.is_empty() {
+                continue;
+            }
+            let addr = gossip_addr(&member);
+            for (_, payload) in push::rumor_payloads_rsr_mlr(&self.server, &rumors) {
+                sent.push((addr, payload));
+            }
+            self.server
+                .rumor_heat
+                .lock_rhw()
+                .cool_rumors(&member.id, &rumors);
+        }
+        sent
+    }
+
+    /// Once an anti-entropy period has gone by, encode a digest for another member, just as the
+    /// anti-entropy thread does. Returns the payload and where to send it.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (read)
+    /// * `MemberList::entries` (read)
+    fn digest_rsr_mlr(&mut self, timing: &Timing) -> Option<(SocketAddr, Vec<u8>)> {
+        if timing.clock.now() < self.next_digest_at {
+            return None;
+        }
+        self.next_digest_at = timing.anti_entropy_timeout();
+        let (member, payload) = anti_entropy::start_exchange_rsr_mlr(&self.server)?;
+        Some((gossip_addr(&member), payload))
+    }
+}
+
+fn gossip_addr(member: &Member) -> SocketAddr {
+    SocketAddr::new(member.swim_socket_address().ip(), member.gossip_port)
+}
+
+/// A simulated ring. Index it to get at each member's `Server`.
+pub struct SimNet {
+    members:     Vec<SimMember>,
+    addrs:       HashMap<SocketAddr, usize>,
+    suitability: Arc<SimSuitability>,
+    timing:      Timing,
+    rng:         StdRng,
+    faults:      Faults,
+    blocked:     HashSet<(usize, usize)>,
+    in_flight:   BinaryHeap<Reverse<InFlight>>,
+    now_ms:      i64,
+    sent:        u64,
+}
+
+impl Index<usize> for SimNet {
+    type Output = Server;
+
+    fn index(&self, index: usize) -> &Server { &self.members[index].server }
+}
+
+impl SimNet {
+    /// Create `count` members with the default `Timing`. They don't know about each other until
+    /// they are connected.
+    pub fn new(count: usize, seed: u64) -> SimNet {
+        SimNet::with_timing(count, seed, Timing::default())
+    }
+
+    /// Create `count` members with the given `Timing`; its clock is replaced by the
+    /// simulation's.
+    pub fn with_timing(count: usize, seed: u64, timing: Timing) -> SimNet {
+        SimNet::build(count, seed, timing, &[])
+    }
+
+    /// Create `count` members with the default `Timing`, tagged with the given zones in turn.
+    pub fn with_zones(count: usize, seed: u64, zones: &[&str]) -> SimNet {
+        SimNet::build(count, seed, Timing::default(), zones)
+    }
+
+    fn build(count: usize, seed: u64, timing: Timing, zones: &[&str]) -> SimNet {
+        let clock = Clock::simulated();
+        let timing = timing.with_clock(clock.clone());
+        let mut rng = StdRng::seed_from_u64(seed);
+        let now = clock.now();
+        let suitability = Arc::new(SimSuitability::default());
+
+        let mut members = Vec::with_capacity(count);
+        let mut addrs = HashMap::with_capacity(count);
+        for n in 0..count {
+            let ip = Ipv4Addr::new(10, (n >> 16) as u8, (n >> 8) as u8, n as u8);
+            let addr = SocketAddr::new(IpAddr::V4(ip), SIM_PORT);
+            let mut member = Member { id: format!("{:032x}", rng.gen::<u128>()),
+                                      address: ip.to_string(),
+                                      ..Member::default() };
+            if !zones.is_empty() {
+                member.tags
+                      .insert(ZONE_TAG.to_string(), zones[n % zones.len()].to_string());
+            }
+            let mut server = Server::new(addr,
+                                         addr,
+                                         member,
+                                         None,
+                                         Some(n.to_string()),
+                                         None,
+                                         suitability.clone()).expect("Cannot create a simulated \
+                                                                      server");
+            server.member_list = Arc::new(MemberList::simulated(clock.clone(), rng.gen()));
+            let (tx_outbound, rx_inbound) = mpsc::channel();
+            members.push(SimMember { server,
+                                     tx_outbound,
+                                     rx_inbound,
+                                     running: true,
+                                     probe: None,
+                                     probe_list: Vec::new(),
+                                     next_probe_at: now,
+                                     gossip_list: Vec::new(),
+                                     next_gossip_at: now,
+                                     next_digest_at: now });
+            addrs.insert(addr, n);
+        }
+
+        SimNet { members,
+                 addrs,
+                 suitability,
+                 timing,
+                 rng,
+                 faults: Faults::default(),
+                 blocked: HashSet::new(),
+                 in_flight: BinaryHeap::new(),
+                 now_ms: 0,
+                 sent: 0 }
+    }
+
+    pub fn len(&self) -> usize { self.members.len() }
+
+    pub fn is_empty(&self) -> bool { self.members.is_empty() }
+
+    /// Load every member's services with this election policy.
+    pub fn set_election_policy(&self, policy: ElectionPolicy) {
+        *self.suitability
+             .0
+             .lock()
+             .expect("Election policy lock poisoned") = policy;
+    }
+
+    pub fn timing(&self) -> &Timing { &self.timing }
+
+    /// How much simulated time has gone by.
+    pub fn elapsed(&self) -> TimeDuration { TimeDuration::milliseconds(self.now_ms) }
+
+    /// How many messages the network has carried, counting duplicates.
+    pub fn messages_sent(&self) -> u64 { self.sent }
+
+    pub fn set_faults(&mut self, faults: Faults) { self.faults = faults; }
+
+    /// Tell `from` about `to`, the way a Supervisor is told about its peers.
+    ///
+    /// # Locking (see locking.md)
+    /// * `Server::member` (read)
+    /// * `MemberList::entries` (write)
+    /// * `RumorHeat::inner` (write)
+    pub fn connect_smr_mlw_rhw(&mut self, from: usize, to: usize) {
+        let member = self[to].myself().lock_smr().to_member();
+        self[from].insert_member_mlw_rhw(member, Health::Alive);
+    }
+
+    /// Have every member know every other as alive, as if the ring had long since settled and
+    /// there were no membership rumors left to gossip.
+    ///
+    /// # Locking (see locking.md)
+    /// * `Server::member` (read)
+    /// * `MemberList::entries` (write)
+    pub fn mesh_smr_mlw(&mut self) {
+        let all: Vec<Member> = self.members
+                                   .iter()
+                                   .map(|m| m.server.myself().lock_smr().to_member())
+                                   .collect();
+        for sim_member in self.members.iter() {
+            for member in all.iter() {
+                if member.id != sim_member.server.member_id() {
+                    sim_member.server
+                              .member_list
+                              .insert_mlw(member.clone(), Health::Alive);
+                }
+            }
+        }
+    }
+
+    /// Drop everything `from` sends to `to`.
+    pub fn block(&mut self, from: usize, to: usize) { self.blocked.insert((from, to)); }
+
+    pub fn unblock(&mut self, from: usize, to: usize) { self.blocked.remove(&(from, to)); }
+
+    /// Drop everything sent between the two sides.
+    pub fn partition(&mut self, left: Range<usize>, right: Range<usize>) {
+        for l in left {
+            for r in right.clone() {
+                self.block(l, r);
+                self.block(r, l);
+            }
+        }
+    }
+
+    pub fn unpartition(&mut self, left: Range<usize>, right: Range<usize>) {
+        for l in left {
+            for r in right.clone() {
+                self.unblock(l, r);
+                self.unblock(r, l);
+            }
+        }
+    }
+
+    /// Stop a member dead: it neither sends nor receives anything from now on.
+    pub fn stop(&mut self, n: usize) { self.members[n].running = false; }
+
+    /// Have a member leave the ring gracefully, then stop it.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (write)
+    /// * `Server::member` (write)
+    /// * `RumorHeat::inner` (write)
+    pub fn depart_mlw_smw_rhw(&mut self, n: usize) {
+        let socket = SimSocket::default();
+        self[n].announce_departure_mlw_smw_rhw(&socket);
+        self.send_swim(n, socket);
+        self.stop(n);
+    }
+
+    /// What `from` thinks of the health of `to`, if it knows of it at all.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (read)
+    pub fn health_of_mlr(&self, from: usize, to: usize) -> Option<Health> {
+        self[from].member_list
+                  .health_of_by_id_mlr(self[to].member_id())
+    }
+
+    /// Whether every running member other than `to` sees it with the given health.
+    ///
+    /// # Locking (see locking.md)
+    /// * `MemberList::entries` (read)
+    pub fn all_see_mlr(&self, to: usize, health: Health) -> bool {
+        (0..self.len()).filter(|&from| from != to && self.members[from].running)
+                       .all(|from| self.health_of_mlr(from, to) == Some(health))
+    }
+
+    /// Move the clock on by one step, deliver the messages that are due, and then let every
+    /// running member do its work.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `MemberList::entries` (write)
+    /// * `Server::member` (write)
+    /// * `RumorHeat::inner` (write)
+    /// * `ManagerServices::inner` (read)
+    pub fn step_rsw_mlw_smw_rhw_msr(&mut self) {
+        self.timing
+            .clock
+            .advance(TimeDuration::milliseconds(STEP_MS));
+        self.now_ms += STEP_MS;
+        self.deliver_rsw_mlw_smw_rhw_msr();
+        for n in 0..self.members.len() {
+            self.step_member_rsw_mlw_smr_rhw(n);
+        }
+    }
+
+    /// Step the simulation until `duration` has gone by.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `MemberList::entries` (write)
+    /// * `Server::member` (write)
+    /// * `RumorHeat::inner` (write)
+    /// * `ManagerServices::inner` (read)
+    pub fn run_for_rsw_mlw_smw_rhw_msr(&mut self, duration: TimeDuration) {
+        let until = self.now_ms + duration.num_milliseconds();
+        while self.now_ms < until {
+            self.step_rsw_mlw_smw_rhw_msr();
+        }
+    }
+
+    /// Step the simulation until `done` is true, for no longer than `limit`. Returns whether
+    /// `done` became true.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `MemberList::entries` (write)
+    /// * `Server::member` (write)
+    /// * `RumorHeat::inner` (write)
+    /// * `ManagerServices::inner` (read)
+    pub fn run_until_rsw_mlw_smw_rhw_msr(&mut self,
+                                         limit: TimeDuration,
+                                         mut done: impl FnMut(&SimNet) -> bool)
+                                         -> bool {
+        let until = self.now_ms + limit.num_milliseconds();
+        while !done(self) {
+            if self.now_ms >= until {
+                return false;
+            }
+            self.step_rsw_mlw_smw_rhw_msr();
+        }
+        true
+    }
+
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `MemberList::entries` (write)
+    /// * `Server::member` (write)
+    /// * `RumorHeat::inner` (write)
+    /// * `ManagerServices::inner` (read)
+    fn deliver_rsw_mlw_smw_rhw_msr(&mut self) {
+        let now_ms = self.now_ms;
+        while self.in_flight
+                  .peek()
+                  .map_or(false, |msg| (msg.0).deliver_at_ms <= now_ms)
+        {
+            let msg = self.in_flight
+                          .pop()
+                          .expect("Lost a message we just peeked at")
+                          .0;
+            let to = &self.members[msg.to];
+            if !to.running {
+                continue;
+            }
+            match msg.channel {
+                Channel::Swim => {
+                    let socket = SimSocket::default();
+                    inbound::process_swim_mlw_smw_rhw(&to.server,
+                                                      &socket,
+                                                      &to.tx_outbound,
+                                                      self.members[msg.from].server.swim_addr,
+                                                      &msg.payload);
+                    self.send_swim(msg.to, socket);
+                }
+                Channel::Gossip => {
+                    let answer = pull::process_rumor_rsw_mlw_smw_rhw_msr(&to.server,
+                                                                          &msg.payload);
+                    if let Some(answer) = answer {
+                        let addr = gossip_addr(&answer.member);
+                        for (_, payload) in answer.payloads {
+                            self.send_to(msg.to, addr, Channel::Gossip, payload);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `MemberList::entries` (write)
+    /// * `Server::member` (read)
+    /// * `RumorHeat::inner` (write)
+    fn step_member_rsw_mlw_smr_rhw(&mut self, n: usize) {
+        let socket = SimSocket::default();
+        let timing = &self.timing;
+        let member = &mut self.members[n];
+        if !member.running {
+            return;
+        }
+        member.probe_mlw_smr_rhw(&socket, timing);
+        let gossip = member.gossip_rsr_mlr_rhw(timing);
+        let digest = member.digest_rsr_mlr(timing);
+        expire::expire_members_mlw_rhw(&member.server, timing);
+        expire::expire_rumors_mlr_rsw_rhw(&member.server, timing);
+
+        self.send_swim(n, socket);
+        for (addr, payload) in gossip.into_iter().chain(digest) {
+            self.send_to(n, addr, Channel::Gossip, payload);
+        }
+    }
+
+    fn send_swim(&mut self, from: usize, socket: SimSocket) {
+        for (addr, payload) in socket.0.into_inner() {
+            self.send_to(from, addr, Channel::Swim, payload);
+        }
+    }
+
+    /// Put a message on the network, unless it is blocked or dropped.
+    fn send_to(&mut self, from: usize, addr: SocketAddr, channel: Channel, payload: Vec<u8>) {
+        let to = match self.addrs.get(&addr) {
+            Some(&to) => to,
+            None => {
+                trace!("No simulated member at {}; dropping {:?} message",
+                       addr,
+                       channel);
+                return;
+            }
+        };
+        if self.blocked.contains(&(from, to)) || self.rng.gen_bool(self.faults.drop_rate) {
+            return;
+        }
+        let copies = if self.rng.gen_bool(self.faults.duplicate_rate) {
+            2
+        } else {
+            1
+        };
+        for _ in 0..copies {
+            let (min_ms, max_ms) = self.faults.delay_ms;
+            let delay_ms = if max_ms > min_ms {
+                self.rng.gen_range(min_ms, max_ms + 1)
+            } else {
+                min_ms
+            };
+            self.sent += 1;
+            self.in_flight
+                .push(Reverse(InFlight { deliver_at_ms: self.now_ms + delay_ms,
+                                         seq: self.sent,
+                                         from,
+                                         to,
+                                         channel,
+                                         payload: payload.clone() }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health_matrix(net: &SimNet) -> Vec<Option<Health>> {
+        let mut healths = Vec::new();
+        for from in 0..net.len() {
+            for to in 0..net.len() {
+                if from != to {
+                    healths.push(net.health_of_mlr(from, to));
+                }
+            }
+        }
+        healths
+    }
+
+    fn lossy_run(seed: u64) -> (Vec<Option<Health>>, u64) {
+        let mut net = SimNet::new(20, seed);
+        net.mesh_smr_mlw();
+        net.set_faults(Faults { drop_rate:      0.2,
+                                duplicate_rate: 0.1,
+                                delay_ms:       (0, 400), });
+        net.stop(3);
+        net.run_for_rsw_mlw_smw_rhw_msr(TimeDuration::seconds(30));
+        (health_matrix(&net), net.messages_sent())
+    }
+
+    #[test]
+    fn same_seed_same_run() {
+        assert_eq!(lossy_run(7), lossy_run(7));
+    }
+
+    #[test]
+    fn clock_only_moves_when_stepped() {
+        let mut net = SimNet::new(2, 1);
+        let started_at = net.timing().clock.now();
+        net.run_for_rsw_mlw_smw_rhw_msr(TimeDuration::seconds(10));
+        assert_eq!(net.timing().clock.now() - started_at,
+                   TimeDuration::seconds(10));
+        assert_eq!(net.elapsed(), TimeDuration::seconds(10));
+    }
+
+    #[test]
+    fn blocked_member_is_suspected() {
+        let mut net = SimNet::new(2, 1);
+        net.mesh_smr_mlw();
+        net.block(1, 0);
+        assert!(net.run_until_rsw_mlw_smw_rhw_msr(TimeDuration::seconds(30), |net| {
+                       net.health_of_mlr(0, 1) == Some(Health::Suspect)
+                   }));
+    }
+}
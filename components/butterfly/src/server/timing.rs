@@ -1,3 +1,10 @@
+use crate::error::{Error,
+                   Result};
+use habitat_core::util::ToI64;
+use std::{cmp,
+          sync::{atomic::{AtomicI64,
+                          Ordering},
+                 Arc}};
 use time::{Duration as TimeDuration,
            SteadyTime};
 
@@ -5,31 +12,105 @@ use time::{Duration as TimeDuration,
 const PING_TIMING_DEFAULT_MS: i64 = 1000;
 /// How long to wait for an Ack after we PingReq - should be at least 2x the PING_TIMING_DEFAULT_MS
 const PINGREQ_TIMING_DEFAULT_MS: i64 = 2100;
-/// How many protocol periods before a suspect member is marked as confirmed.
+/// How many protocol periods before a suspect member is marked as confirmed, at the soonest.
 const SUSPICION_TIMEOUT_DEFAULT_PROTOCOL_PERIODS: i64 = 3;
+/// How many times longer than that a suspect member has when no other member suspects it too
+const SUSPICION_MAX_MULTIPLIER_DEFAULT: i64 = 6;
+/// How many other members suspecting a member bring its suspicion timeout down to the soonest
+const SUSPICION_CONFIRMATIONS_DEFAULT: usize = 3;
+/// How high our local health score goes; our probes are slowed down by up to one more than this
+/// many times over
+const LOCAL_HEALTH_MAX_DEFAULT: usize = 8;
 /// How long is the gossip period
 const GOSSIP_PERIOD_DEFAULT_MS: i64 = 1000;
+/// How many members we ask to ping a member that didn't answer our own ping
+const PINGREQ_TARGETS_DEFAULT: usize = 5;
+/// How many members we send hot rumors to at a time
+const GOSSIP_FANOUT_DEFAULT: usize = 5;
+/// The shortest ping timeout or period that makes sense
+const MIN_PERIOD_MS: i64 = 10;
 /// How long before we set a confirmed member to a departed member, removing them from quorums
 ///   just for your own sanity - this is 3 days.
 const DEPARTURE_TIMEOUT_DEFAULT_MS: i64 = 259_200_000;
+/// How often a member exchanges a digest of its rumors with another member
+const ANTI_ENTROPY_PERIOD_DEFAULT_MS: i64 = 10_000;
+/// How long the rumors of a confirmed or departed member are kept, and how long a purged service
+/// group stays purged - this is 1 day.
+const RUMOR_TTL_DEFAULT_MS: i64 = 86_400_000;
+
+/// Where the protocol threads get the current time from. Unless it is
+/// simulated, this is the system's steady clock.
+#[derive(Debug, Clone, Default)]
+pub struct Clock(Option<Arc<SimulatedClock>>);
+
+#[derive(Debug)]
+struct SimulatedClock {
+    started_at: SteadyTime,
+    elapsed_ms: AtomicI64,
+}
+
+impl Clock {
+    /// A clock that stands still until it is advanced, so that a simulation
+    /// can let protocol periods go by without waiting for them.
+    pub fn simulated() -> Clock {
+        Clock(Some(Arc::new(SimulatedClock { started_at: SteadyTime::now(),
+                                             elapsed_ms: AtomicI64::new(0), })))
+    }
+
+    pub fn now(&self) -> SteadyTime {
+        match self.0 {
+            Some(ref clock) => {
+                clock.started_at
+                + TimeDuration::milliseconds(clock.elapsed_ms.load(Ordering::SeqCst))
+            }
+            None => SteadyTime::now(),
+        }
+    }
+
+    /// Move a simulated clock forward. The system clock can't be moved, so
+    /// this does nothing to it.
+    pub fn advance(&self, duration: TimeDuration) {
+        if let Some(ref clock) = self.0 {
+            clock.elapsed_ms
+                 .fetch_add(duration.num_milliseconds(), Ordering::SeqCst);
+        }
+    }
+}
 
 /// The timing of the outbound threads.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Timing {
     pub ping_ms: i64,
     pub pingreq_ms: i64,
+    pub pingreq_targets: usize,
     pub gossip_period_ms: i64,
+    pub gossip_fanout: usize,
+    pub anti_entropy_period_ms: i64,
     pub suspicion_timeout_protocol_periods: i64,
+    pub suspicion_max_multiplier: i64,
+    pub suspicion_confirmations: usize,
+    pub local_health_max: usize,
     pub departure_timeout_ms: i64,
+    pub rumor_ttl_ms: i64,
+    #[serde(skip)]
+    pub clock: Clock,
 }
 
 impl Default for Timing {
     fn default() -> Timing {
         Timing { ping_ms: PING_TIMING_DEFAULT_MS,
                  pingreq_ms: PINGREQ_TIMING_DEFAULT_MS,
+                 pingreq_targets: PINGREQ_TARGETS_DEFAULT,
                  gossip_period_ms: GOSSIP_PERIOD_DEFAULT_MS,
+                 gossip_fanout: GOSSIP_FANOUT_DEFAULT,
+                 anti_entropy_period_ms: ANTI_ENTROPY_PERIOD_DEFAULT_MS,
                  suspicion_timeout_protocol_periods: SUSPICION_TIMEOUT_DEFAULT_PROTOCOL_PERIODS,
-                 departure_timeout_ms: DEPARTURE_TIMEOUT_DEFAULT_MS, }
+                 suspicion_max_multiplier: SUSPICION_MAX_MULTIPLIER_DEFAULT,
+                 suspicion_confirmations: SUSPICION_CONFIRMATIONS_DEFAULT,
+                 local_health_max: LOCAL_HEALTH_MAX_DEFAULT,
+                 departure_timeout_ms: DEPARTURE_TIMEOUT_DEFAULT_MS,
+                 rumor_ttl_ms: RUMOR_TTL_DEFAULT_MS,
+                 clock: Clock::default(), }
     }
 }
 
@@ -43,14 +124,90 @@ impl Timing {
                -> Timing {
         Timing { ping_ms,
                  pingreq_ms,
+                 pingreq_targets: PINGREQ_TARGETS_DEFAULT,
                  gossip_period_ms,
+                 gossip_fanout: GOSSIP_FANOUT_DEFAULT,
+                 anti_entropy_period_ms: ANTI_ENTROPY_PERIOD_DEFAULT_MS,
                  suspicion_timeout_protocol_periods,
-                 departure_timeout_ms }
+                 suspicion_max_multiplier: SUSPICION_MAX_MULTIPLIER_DEFAULT,
+                 suspicion_confirmations: SUSPICION_CONFIRMATIONS_DEFAULT,
+                 local_health_max: LOCAL_HEALTH_MAX_DEFAULT,
+                 departure_timeout_ms,
+                 rumor_ttl_ms: RUMOR_TTL_DEFAULT_MS,
+                 clock: Clock::default() }
+    }
+
+    /// Check that the timing makes sense: timeouts and periods aren't too short, a PingReq gets
+    /// at least twice as long as a Ping, and members are confirmed before they depart.
+    ///
+    /// # Errors
+    ///
+    /// * Returns `Error::InvalidTiming` describing the first value that doesn't make sense
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |msg: String| Err(Error::InvalidTiming(msg));
+        for &(name, value) in &[("ping", self.ping_ms),
+                                ("gossip period", self.gossip_period_ms),
+                                ("anti-entropy period", self.anti_entropy_period_ms),
+                                ("rumor TTL", self.rumor_ttl_ms)]
+        {
+            if value < MIN_PERIOD_MS {
+                return invalid(format!("the {} must be at least {}ms, not {}ms",
+                                       name, MIN_PERIOD_MS, value));
+            }
+        }
+        if self.pingreq_ms < 2 * self.ping_ms {
+            return invalid(format!("the pingreq timeout ({}ms) must be at least twice the \
+                                    ping timeout ({}ms)",
+                                   self.pingreq_ms, self.ping_ms));
+        }
+        if self.suspicion_timeout_protocol_periods < 1 {
+            return invalid(format!("the suspicion timeout must be at least 1 protocol period, \
+                                    not {}",
+                                   self.suspicion_timeout_protocol_periods));
+        }
+        if self.suspicion_max_multiplier < 1 {
+            return invalid(format!("the suspicion max multiplier must be at least 1, not {}",
+                                   self.suspicion_max_multiplier));
+        }
+        if self.departure_timeout_duration() < self.suspicion_timeout_duration() {
+            return invalid(format!("the departure timeout ({}ms) must be at least the \
+                                    suspicion timeout ({}ms)",
+                                   self.departure_timeout_ms,
+                                   self.suspicion_timeout_duration().num_milliseconds()));
+        }
+        if self.pingreq_targets < 1 {
+            return invalid("there must be at least 1 pingreq target".to_string());
+        }
+        if self.gossip_fanout < 1 {
+            return invalid("the gossip fanout must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// The timing to probe with while our local health score is `score`: every timeout and
+    /// period of a probe is stretched to `score + 1` times its length, so that a member that is
+    /// itself struggling to keep up gives others longer to answer before it suspects them.
+    pub fn with_local_health(&self, score: usize) -> Timing {
+        let multiplier = score.to_i64() + 1;
+        Timing { ping_ms: self.ping_ms * multiplier,
+                 pingreq_ms: self.pingreq_ms * multiplier,
+                 ..self.clone() }
+    }
+
+    /// Use `clock` rather than the system clock.
+    pub fn with_clock(mut self, clock: Clock) -> Timing {
+        self.clock = clock;
+        self
     }
 
     /// When should this gossip period expire
     pub fn gossip_timeout(&self) -> SteadyTime {
-        SteadyTime::now() + TimeDuration::milliseconds(self.gossip_period_ms)
+        self.clock.now() + TimeDuration::milliseconds(self.gossip_period_ms)
+    }
+
+    /// When should the next digest exchange start
+    pub fn anti_entropy_timeout(&self) -> SteadyTime {
+        self.clock.now() + TimeDuration::milliseconds(self.anti_entropy_period_ms)
     }
 
     /// How long is a protocol period, in millis.
@@ -58,26 +215,106 @@ impl Timing {
 
     /// When should this ping record time out?
     pub fn ping_timeout(&self) -> SteadyTime {
-        SteadyTime::now() + TimeDuration::milliseconds(self.ping_ms)
+        self.clock.now() + TimeDuration::milliseconds(self.ping_ms)
     }
 
     /// When should this pingreq timeout?
     pub fn pingreq_timeout(&self) -> SteadyTime {
-        SteadyTime::now() + TimeDuration::milliseconds(self.pingreq_ms)
+        self.clock.now() + TimeDuration::milliseconds(self.pingreq_ms)
     }
 
     /// How long before the next scheduled protocol period
     pub fn next_protocol_period(&self) -> SteadyTime {
-        SteadyTime::now() + TimeDuration::milliseconds(self.ping_ms + self.pingreq_ms)
+        self.clock.now() + TimeDuration::milliseconds(self.ping_ms + self.pingreq_ms)
     }
 
-    /// How long before this suspect entry times out
+    /// How long before this suspect entry times out, at the soonest
     pub fn suspicion_timeout_duration(&self) -> TimeDuration {
         TimeDuration::milliseconds(self.protocol_period_ms()
                                    * self.suspicion_timeout_protocol_periods)
     }
 
+    /// How long before a suspect entry that `confirmations` members beyond the first have
+    /// suspected times out, when `expected` confirmations would be enough to be sure.
+    ///
+    /// A suspicion starts out with `suspicion_max_multiplier` times the suspicion timeout, and
+    /// each independent confirmation brings it down, logarithmically, towards the suspicion
+    /// timeout itself. A member that is only suspected by one member that is struggling has
+    /// plenty of time to refute the suspicion; one that many members fail to reach is confirmed
+    /// as soon as before.
+    pub fn suspicion_timeout_for(&self, confirmations: usize, expected: usize) -> TimeDuration {
+        let min = self.suspicion_timeout_duration().num_milliseconds();
+        if expected == 0 {
+            return TimeDuration::milliseconds(min);
+        }
+        let max = min * self.suspicion_max_multiplier;
+        let fraction = ((confirmations as f64) + 1.0).ln() / ((expected as f64) + 1.0).ln();
+        let timeout = max - (fraction * (max - min) as f64).floor() as i64;
+        TimeDuration::milliseconds(cmp::max(timeout, min))
+    }
+
     pub fn departure_timeout_duration(&self) -> TimeDuration {
         TimeDuration::milliseconds(self.departure_timeout_ms)
     }
+
+    /// How long before the rumors of a confirmed or departed member, or a purge, expire
+    pub fn rumor_ttl_duration(&self) -> TimeDuration {
+        TimeDuration::milliseconds(self.rumor_ttl_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Timing;
+
+    #[test]
+    fn default_timing_is_valid() { assert!(Timing::default().validate().is_ok()); }
+
+    #[test]
+    fn pingreq_must_be_twice_the_ping() {
+        let timing = Timing { ping_ms: 1000,
+                              pingreq_ms: 1500,
+                              ..Timing::default() };
+        assert!(timing.validate().is_err());
+    }
+
+    #[test]
+    fn departure_must_not_come_before_confirmation() {
+        let timing = Timing { departure_timeout_ms: 1000,
+                              ..Timing::default() };
+        assert!(timing.validate().is_err());
+    }
+
+    #[test]
+    fn suspicion_timeout_shrinks_with_confirmations() {
+        let timing = Timing::default();
+        let min = timing.suspicion_timeout_duration();
+        let max = min * timing.suspicion_max_multiplier as i32;
+        assert_eq!(timing.suspicion_timeout_for(0, 3), max);
+        assert!(timing.suspicion_timeout_for(1, 3) < max);
+        assert!(timing.suspicion_timeout_for(2, 3) < timing.suspicion_timeout_for(1, 3));
+        assert_eq!(timing.suspicion_timeout_for(3, 3), min);
+        assert_eq!(timing.suspicion_timeout_for(10, 3), min);
+        assert_eq!(timing.suspicion_timeout_for(0, 0), min);
+    }
+
+    #[test]
+    fn local_health_stretches_probes() {
+        let timing = Timing::default();
+        assert_eq!(timing.with_local_health(0).ping_ms, timing.ping_ms);
+        let degraded = timing.with_local_health(2);
+        assert_eq!(degraded.ping_ms, timing.ping_ms * 3);
+        assert_eq!(degraded.protocol_period_ms(), timing.protocol_period_ms() * 3);
+        assert_eq!(degraded.suspicion_max_multiplier, timing.suspicion_max_multiplier);
+    }
+
+    #[test]
+    fn fanout_and_pingreq_targets_must_be_positive() {
+        let timing = Timing { gossip_fanout: 0,
+                              ..Timing::default() };
+        assert!(timing.validate().is_err());
+        let timing = Timing { pingreq_targets: 0,
+                              ..Timing::default() };
+        assert!(timing.validate().is_err());
+    }
 }
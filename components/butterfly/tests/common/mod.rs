@@ -1,4 +1,5 @@
 use habitat_butterfly::{error::Error,
+                        identity::MemberIdentity,
                         member::{Health,
                                  Member},
                         rumor::{departure::Departure,
@@ -12,7 +13,8 @@ use habitat_butterfly::{error::Error,
                         server::{timing::Timing,
                                  Server,
                                  Suitability}};
-use habitat_core::{crypto::keys::sym_key::SymKey,
+use habitat_core::{crypto::{keys::sym_key::SymKey,
+                            SigKeyPair},
                    package::{Identifiable,
                              PackageIdent},
                    service::ServiceGroup};
@@ -43,6 +45,18 @@ impl Suitability for NSuitability {
 /// * `Server::member` (write)
 /// * `RumorHeat::inner` (write)
 pub fn start_server_smw_rhw(name: &str, ring_key: Option<SymKey>, suitability: u64) -> Server {
+    start_member_server_smw_rhw(name, Member::default(), ring_key, None, suitability)
+}
+
+/// # Locking (see locking.md)
+/// * `Server::member` (write)
+/// * `RumorHeat::inner` (write)
+pub fn start_member_server_smw_rhw(name: &str,
+                                   mut member: Member,
+                                   ring_key: Option<SymKey>,
+                                   identity: Option<MemberIdentity>,
+                                   suitability: u64)
+                                   -> Server {
     let swim_port;
     let gossip_port;
     {
@@ -54,7 +68,6 @@ pub fn start_server_smw_rhw(name: &str, ring_key: Option<SymKey>, suitability: u
     }
     let listen_swim = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), swim_port);
     let listen_gossip = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), gossip_port);
-    let mut member = Member::default();
     member.swim_port = swim_port;
     member.gossip_port = gossip_port;
     let mut server = Server::new(listen_swim,
@@ -64,6 +77,9 @@ pub fn start_server_smw_rhw(name: &str, ring_key: Option<SymKey>, suitability: u
                                  Some(String::from(name)),
                                  None,
                                  Arc::new(NSuitability(suitability))).unwrap();
+    if let Some(identity) = identity {
+        server.set_member_identity(identity);
+    }
     server.start_rsw_mlw_smw_rhw_msr(&Timing::default())
           .expect("Cannot start server");
     server
@@ -131,6 +147,37 @@ impl SwimNet {
         SwimNet { members }
     }
 
+    /// Start `trusted` members that only admit each other, followed by
+    /// `untrusted` members that nobody else admits.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorHeat::inner` (write)
+    pub fn new_member_identity_rhw(trusted: usize, untrusted: usize) -> SwimNet {
+        let members = (0..trusted + untrusted).map(|_| Member::default())
+                                              .collect::<Vec<_>>();
+        let keys = members.iter()
+                          .map(|member| {
+                              SigKeyPair::generate_pair_for_member(&member.id).expect("Failed to \
+                                                                                     generate a \
+                                                                                     member key")
+                          })
+                          .collect::<Vec<_>>();
+        let trusted_keys = keys[..trusted].to_vec();
+        let members = members.into_iter()
+                             .zip(keys)
+                             .enumerate()
+                             .map(|(x, (member, key))| {
+                                 let identity = MemberIdentity::new(key, trusted_keys.clone());
+                                 start_member_server_smw_rhw(&format!("{}", x),
+                                                             member,
+                                                             None,
+                                                             Some(identity),
+                                                             0)
+                             })
+                             .collect();
+        SwimNet { members }
+    }
+
     /// # Locking (see locking.md)
     /// * `Server::member` (read)
     pub fn connect_smr(&mut self, from_entry: usize, to_entry: usize) {
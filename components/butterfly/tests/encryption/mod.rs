@@ -1,6 +1,11 @@
 use crate::btest;
-use habitat_butterfly::member::Health;
-use habitat_core::crypto::keys::sym_key::SymKey;
+use habitat_butterfly::{client::Client,
+                        member::Health,
+                        rumor::{ConstIdRumor as _,
+                                Election,
+                                ServiceConfig}};
+use habitat_core::{crypto::keys::sym_key::SymKey,
+                   service::ServiceGroup};
 
 #[test]
 fn symmetric_encryption_of_wire_payloads() {
@@ -16,3 +21,92 @@ fn symmetric_encryption_of_wire_payloads() {
                   .service_group("beast.prod")
                   .contains_id(net[0].member_id()));
 }
+
+#[test]
+fn ring_key_rotation_keeps_members_gossiping() {
+    let old = SymKey::generate_pair_for_ring("wolverine").expect("Failed to generate an in \
+                                                                  memory symkey");
+    let new =
+        SymKey::generate_pair_for_ring("storm").expect("Failed to generate an in memory symkey");
+    let mut net = btest::SwimNet::new_ring_encryption_rhw(2, &old);
+    net.connect_smr(0, 1);
+    assert_wait_for_health_of_mlr!(net, [0..2, 0..2], Health::Alive);
+
+    net[0].accept_ring_key_srkw(new.clone());
+    net[1].accept_ring_key_srkw(new.clone());
+    net[0].promote_ring_key_srkw(&new.name_with_rev())
+          .expect("new ring key is accepted");
+    net.add_service(0, "core/beast/1.2.3/20161208121212");
+    net.wait_for_gossip_rounds(2);
+    assert!(net[1].service_store
+                  .lock_rsr()
+                  .service_group("beast.prod")
+                  .contains_id(net[0].member_id()));
+
+    net[1].promote_ring_key_srkw(&new.name_with_rev())
+          .expect("new ring key is accepted");
+    net[0].retire_ring_key_srkw(&old.name_with_rev())
+          .expect("old ring key is no longer primary");
+    net[1].retire_ring_key_srkw(&old.name_with_rev())
+          .expect("old ring key is no longer primary");
+    net.add_service(1, "core/logan/1.2.3/20161208121212");
+    net.wait_for_gossip_rounds(2);
+    assert!(net[0].service_store
+                  .lock_rsr()
+                  .service_group("logan.prod")
+                  .contains_id(net[1].member_id()));
+    assert_wait_for_health_of_mlr!(net, [0..2, 0..2], Health::Alive);
+}
+
+#[test]
+fn members_without_a_trusted_member_key_are_not_admitted() {
+    let mut net = btest::SwimNet::new_member_identity_rhw(2, 1);
+    net.mesh_mlw_smr();
+    assert_wait_for_health_of_mlr!(net, [0..2, 0..2], Health::Alive);
+    net.add_service(0, "core/beast/1.2.3/20161208121212");
+    net.add_service(2, "core/logan/1.2.3/20161208121212");
+    net.wait_for_gossip_rounds(2);
+    assert!(net[1].service_store
+                  .lock_rsr()
+                  .service_group("beast.prod")
+                  .contains_id(net[0].member_id()));
+    assert!(!net[0].service_store
+                   .lock_rsr()
+                   .service_group("logan.prod")
+                   .contains_id(net[2].member_id()));
+    assert_wait_for_health_of_mlr!(net, 0, 2, Health::Confirmed);
+}
+
+#[test]
+fn rumors_must_be_signed_by_the_member_they_are_from() {
+    let mut net = btest::SwimNet::new_member_identity_rhw(3, 0);
+    net.mesh_mlw_smr();
+    assert_wait_for_health_of_mlr!(net, [0..3, 0..3], Health::Alive);
+
+    // Member 1 is trusted, but can't speak for member 0
+    let mut client = Client::new(&net[2].gossip_addr().to_string(),
+                                 None,
+                                 net[1].member_identity().cloned()).expect("Cannot create \
+                                                                            Butterfly Client");
+    let mut forged = Election::new(net[0].member_id(), "witcher.prod", 0, 100, true);
+    forged.finish();
+    client.send(&forged).expect("Cannot send the election");
+
+    // What member 1 issues itself is signed, and relayed on
+    client.send_service_config(ServiceGroup::new(None, "witcher", "prod", None).unwrap(),
+                               1,
+                               b"port = 8080",
+                               false)
+          .expect("Cannot send the service configuration");
+    net.wait_for_gossip_rounds(2);
+    assert!(net[0].service_config_store
+                  .lock_rsr()
+                  .service_group("witcher.prod")
+                  .contains_id(ServiceConfig::const_id()));
+    for m in 0..3 {
+        assert!(!net[m].election_store
+                       .lock_rsr()
+                       .service_group("witcher.prod")
+                       .contains_id(Election::const_id()));
+    }
+}
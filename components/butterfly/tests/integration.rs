@@ -2,6 +2,7 @@
 mod common;
 mod encryption;
 mod rumor;
+mod sim;
 
 use common as btest;
 use habitat_butterfly::{self,
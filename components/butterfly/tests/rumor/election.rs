@@ -2,7 +2,8 @@ use crate::btest;
 use habitat_butterfly::{member::Health,
                         rumor::{election::ElectionStatus,
                                 ConstIdRumor as _,
-                                Election}};
+                                Election,
+                                RumorType}};
 use habitat_common::FeatureFlag;
 
 #[test]
@@ -21,6 +22,29 @@ fn three_members_run_election() {
     assert_wait_for_equal_election!(net, [0..3, 0..3], "witcher.prod");
 }
 
+#[test]
+fn rumor_summaries_report_election_terms() {
+    let mut net = btest::SwimNet::new_rhw(2);
+    net.mesh_mlw_smr();
+    net.add_service(0, "core/witcher/1.2.3/20161208121212");
+    net.add_service(1, "core/witcher/1.2.3/20161208121212");
+    net.add_election(0, "witcher");
+    assert_wait_for_election_status!(net, [0..2], "witcher.prod", ElectionStatus::Finished);
+
+    for server in net.iter() {
+        let summaries = server.rumor_summaries_rsr_rhr();
+        let election = summaries.iter()
+                                .find(|s| s.key.kind == RumorType::Election)
+                                .expect("No election rumor");
+        assert_eq!(election.key.key, "witcher.prod");
+        assert_eq!(election.election, Some((0, ElectionStatus::Finished)));
+        assert_eq!(summaries.iter()
+                            .filter(|s| s.key.kind == RumorType::Service)
+                            .count(),
+                   2);
+    }
+}
+
 #[test]
 fn three_members_run_election_from_one_starting_rumor() {
     let mut net = btest::SwimNet::new_rhw(3);
@@ -0,0 +1,305 @@
+use habitat_butterfly::{member::{Health,
+                                 ZONE_TAG},
+                        rumor::{election::{ElectionPolicy,
+                                           ElectionStatus},
+                                purge::Purge,
+                                service::{Service,
+                                          SysInfo},
+                                service_config::ServiceConfig,
+                                ConstIdRumor as _,
+                                Election},
+                        server::{sim::{Faults,
+                                       SimNet},
+                                 timing::Timing}};
+use habitat_common::FeatureFlag;
+use habitat_core::{package::PackageIdent,
+                   service::ServiceGroup};
+use std::str::FromStr;
+use time::Duration;
+
+fn add_service(net: &SimNet, member: usize) {
+    let ident = PackageIdent::from_str("core/witcher/1.2.3/20161208121212").unwrap();
+    let sg = ServiceGroup::new(None, "witcher", "prod", None).unwrap();
+    let s = Service::new(net[member].member_id().to_string(),
+                         &ident,
+                         sg,
+                         SysInfo::default(),
+                         None);
+    net[member].insert_service_rsw_mlw_rhw(s);
+}
+
+fn election_of(net: &SimNet, member: usize) -> Option<(String, u64, ElectionStatus)> {
+    net[member].election_store
+               .lock_rsr()
+               .service_group("witcher.prod")
+               .map_rumor(Election::const_id(), |e| {
+                   (e.member_id.clone(), e.term, e.status)
+               })
+}
+
+fn all_finished(net: &SimNet, members: &[usize], term: u64) -> bool {
+    let first = election_of(net, members[0]);
+    match first {
+        Some((_, t, ElectionStatus::Finished)) if t == term => {
+            members.iter().all(|&m| election_of(net, m) == first)
+        }
+        _ => false,
+    }
+}
+
+#[test]
+fn two_hundred_members_confirm_a_stopped_member() {
+    let mut net = SimNet::new(200, 1);
+    net.mesh_smr_mlw();
+    net.stop(0);
+    assert!(net.run_until_rsw_mlw_smw_rhw_msr(Duration::minutes(2), |net| {
+                   net.all_see_mlr(0, Health::Confirmed)
+               }));
+}
+
+#[test]
+fn lossy_ring_confirms_a_stopped_member() {
+    let mut net = SimNet::new(100, 2);
+    net.mesh_smr_mlw();
+    net.set_faults(Faults { drop_rate:      0.1,
+                            duplicate_rate: 0.05,
+                            delay_ms:       (10, 300), });
+    net.stop(0);
+    assert!(net.run_until_rsw_mlw_smw_rhw_msr(Duration::minutes(3), |net| {
+                   net.all_see_mlr(0, Health::Confirmed)
+               }));
+}
+
+#[test]
+fn partitioned_members_are_confirmed_on_the_other_side() {
+    let mut net = SimNet::new(100, 3);
+    net.mesh_smr_mlw();
+    net.partition(0..10, 10..100);
+    assert!(net.run_until_rsw_mlw_smw_rhw_msr(Duration::minutes(3), |net| {
+                   (0..10).all(|l| {
+                              (10..100).all(|r| net.health_of_mlr(r, l) == Some(Health::Confirmed))
+                          })
+               }));
+}
+
+#[test]
+fn member_that_hears_nothing_does_not_get_healthy_members_confirmed() {
+    let mut net = SimNet::new(20, 11);
+    net.mesh_smr_mlw();
+    // Member 0 can still send, but everything sent to it is lost, so it
+    // suspects everyone it probes.
+    for r in 1..20 {
+        net.block(r, 0);
+    }
+    net.run_for_rsw_mlw_smw_rhw_msr(Duration::minutes(2));
+    assert!((1..20).all(|l| {
+                       (1..20).all(|r| net.health_of_mlr(l, r) != Some(Health::Confirmed))
+                   }));
+    assert!(net[0].local_health_score() > 0);
+}
+
+#[test]
+fn two_hundred_members_learn_of_a_departure() {
+    let mut net = SimNet::new(200, 4);
+    net.mesh_smr_mlw();
+    net.depart_mlw_smw_rhw(0);
+    assert!(net.run_until_rsw_mlw_smw_rhw_msr(Duration::minutes(2), |net| {
+                   net.all_see_mlr(0, Health::Departed)
+               }));
+}
+
+#[test]
+fn election_is_rerun_when_the_leader_dies() {
+    let group = [0, 1, 2, 3, 4];
+    let mut net = SimNet::new(100, 5);
+    net.mesh_smr_mlw();
+    for &m in group.iter() {
+        add_service(&net, m);
+    }
+    net.run_for_rsw_mlw_smw_rhw_msr(Duration::seconds(30));
+
+    net[0].start_election_rsw_mlr_rhw_msr("witcher.prod", 0);
+    assert!(net.run_until_rsw_mlw_smw_rhw_msr(Duration::minutes(1), |net| {
+                   all_finished(net, &group, 0)
+               }));
+
+    let (leader_id, ..) = election_of(&net, 0).unwrap();
+    let leader = group.iter()
+                      .cloned()
+                      .find(|&m| net[m].member_id() == leader_id)
+                      .unwrap();
+    let survivors: Vec<usize> = group.iter().cloned().filter(|&m| m != leader).collect();
+    net.stop(leader);
+    assert!(net.run_until_rsw_mlw_smw_rhw_msr(Duration::minutes(2), |net| {
+                   survivors.iter()
+                            .all(|&m| net.health_of_mlr(m, leader) == Some(Health::Confirmed))
+               }));
+
+    for &m in survivors.iter() {
+        net[m].restart_elections_rsw_mlr_rhw_msr(FeatureFlag::empty());
+    }
+    assert!(net.run_until_rsw_mlw_smw_rhw_msr(Duration::minutes(1), |net| {
+                   all_finished(net, &survivors, 1)
+               }));
+    assert_ne!(election_of(&net, survivors[0]).unwrap().0, leader_id);
+}
+
+#[test]
+fn election_prefers_a_leader_in_the_preferred_zone() {
+    let group = [0, 1, 2, 3, 4, 5];
+    let mut net = SimNet::with_zones(30, 6, &["a", "b", "c"]);
+    net.set_election_policy(ElectionPolicy { preferred_zone: Some("c".to_string()),
+                                             min_zones:      3, });
+    net.mesh_smr_mlw();
+    for &m in group.iter() {
+        add_service(&net, m);
+    }
+    net.run_for_rsw_mlw_smw_rhw_msr(Duration::seconds(30));
+
+    net[0].start_election_rsw_mlr_rhw_msr("witcher.prod", 0);
+    assert!(net.run_until_rsw_mlw_smw_rhw_msr(Duration::minutes(1), |net| {
+                   all_finished(net, &group, 0)
+               }));
+
+    let (leader_id, ..) = election_of(&net, 0).unwrap();
+    let leader = group.iter()
+                      .cloned()
+                      .find(|&m| net[m].member_id() == leader_id)
+                      .unwrap();
+    let zone = net[leader].myself()
+                          .lock_smr()
+                          .to_member()
+                          .tags
+                          .remove(ZONE_TAG);
+    assert_eq!(zone, Some("c".to_string()));
+}
+
+#[test]
+fn election_lacks_quorum_when_members_span_too_few_zones() {
+    // Members 0, 1, 3 and 4 are in zones "a" and "b" only
+    let group = [0, 1, 3, 4];
+    let mut net = SimNet::with_zones(30, 7, &["a", "b", "c"]);
+    net.set_election_policy(ElectionPolicy { preferred_zone: None,
+                                             min_zones:      3, });
+    net.mesh_smr_mlw();
+    for &m in group.iter() {
+        add_service(&net, m);
+    }
+    net.run_for_rsw_mlw_smw_rhw_msr(Duration::seconds(30));
+
+    net[0].start_election_rsw_mlr_rhw_msr("witcher.prod", 0);
+    net.run_for_rsw_mlw_smw_rhw_msr(Duration::minutes(1));
+    for &m in group.iter() {
+        let (_, _, status) = election_of(&net, m).unwrap();
+        assert_eq!(status, ElectionStatus::NoQuorum);
+    }
+}
+
+fn has_service_group(net: &SimNet, member: usize) -> bool {
+    net[member].service_store
+               .lock_rsr()
+               .service_group("witcher.prod")
+               .rumors()
+               .next()
+               .is_some()
+}
+
+#[test]
+fn rumors_of_a_departed_member_expire_after_the_ttl() {
+    let timing = Timing { rumor_ttl_ms: 60_000,
+                          departure_timeout_ms: 60_000,
+                          ..Timing::default() };
+    let mut net = SimNet::with_timing(20, 8, timing);
+    net.mesh_smr_mlw();
+    add_service(&net, 0);
+    net.run_for_rsw_mlw_smw_rhw_msr(Duration::seconds(30));
+    assert!((1..20).all(|m| has_service_group(&net, m)));
+
+    net.stop(0);
+    assert!(net.run_until_rsw_mlw_smw_rhw_msr(Duration::minutes(10), |net| {
+                   (1..20).all(|m| !has_service_group(net, m))
+               }));
+}
+
+#[test]
+fn rumors_of_a_service_group_whose_members_are_only_confirmed_are_kept() {
+    let timing = Timing { rumor_ttl_ms: 60_000,
+                          ..Timing::default() };
+    let mut net = SimNet::with_timing(20, 11, timing);
+    net.mesh_smr_mlw();
+    add_service(&net, 0);
+    let sg = ServiceGroup::new(None, "witcher", "prod", None).unwrap();
+    net[0].insert_service_config_rsw_rhw(ServiceConfig::new(net[0].member_id(),
+                                                            sg,
+                                                            b"port = 8080".to_vec()));
+    net.run_for_rsw_mlw_smw_rhw_msr(Duration::seconds(30));
+
+    // As if cut off from the rest of the ring for much longer than the TTL
+    net.stop(0);
+    net.run_for_rsw_mlw_smw_rhw_msr(Duration::minutes(10));
+    assert!((1..20).all(|m| has_service_group(&net, m) && has_service_config(&net, m)));
+}
+
+#[test]
+fn purge_drops_a_service_group_ring_wide() {
+    let mut net = SimNet::new(20, 9);
+    net.mesh_smr_mlw();
+    for m in 0..3 {
+        add_service(&net, m);
+    }
+    net.run_for_rsw_mlw_smw_rhw_msr(Duration::seconds(30));
+    assert!((0..20).all(|m| has_service_group(&net, m)));
+
+    // The members running the service stop before the group is purged
+    for m in 0..3 {
+        net.stop(m);
+    }
+    net[10].insert_purge_rsw_rhw(Purge::new("witcher.prod"));
+    assert!(net.run_until_rsw_mlw_smw_rhw_msr(Duration::minutes(1), |net| {
+                   (3..20).all(|m| {
+                              !has_service_group(net, m) && net[m].is_purged_rsr("witcher.prod")
+                          })
+               }));
+}
+
+#[test]
+fn expired_purges_are_ignored() {
+    let mut net = SimNet::new(20, 12);
+    net.mesh_smr_mlw();
+    add_service(&net, 0);
+    net.run_for_rsw_mlw_smw_rhw_msr(Duration::seconds(30));
+
+    net[10].insert_purge_rsw_rhw(Purge { purged_at: 0,
+                                         ..Purge::new("witcher.prod") });
+    net.run_for_rsw_mlw_smw_rhw_msr(Duration::seconds(30));
+    assert!((0..20).all(|m| has_service_group(&net, m) && !net[m].is_purged_rsr("witcher.prod")));
+}
+
+fn has_service_config(net: &SimNet, member: usize) -> bool {
+    net[member].service_config_store
+               .lock_rsr()
+               .service_group("witcher.prod")
+               .rumors()
+               .next()
+               .is_some()
+}
+
+#[test]
+fn member_that_lost_its_rumors_catches_up_through_digests() {
+    let mut net = SimNet::new(20, 10);
+    net.mesh_smr_mlw();
+    let sg = ServiceGroup::new(None, "witcher", "prod", None).unwrap();
+    net[0].insert_service_config_rsw_rhw(ServiceConfig::new(net[0].member_id(),
+                                                            sg,
+                                                            b"port = 8080".to_vec()));
+    net.run_for_rsw_mlw_smw_rhw_msr(Duration::seconds(30));
+    assert!((0..20).all(|m| has_service_config(&net, m)));
+
+    // The rumor has long since cooled off, so only a digest exchange can bring it back
+    net[19].service_config_store
+           .remove_rsw("witcher.prod", ServiceConfig::const_id());
+    assert!(!has_service_config(&net, 19));
+    assert!(net.run_until_rsw_mlw_smw_rhw_msr(Duration::minutes(1), |net| {
+                   has_service_config(net, 19)
+               }));
+}
@@ -24,6 +24,8 @@ pub enum Error {
     BadGlyphStyle(String),
     CantUploadGossipToml,
     ChannelNotFound,
+    /// Occurs when a package's configuration schema can't be read or isn't a schema
+    ConfigSchema(PathBuf, String),
     CryptoKeyError(String),
     DownloadFailed(String),
     EditorEnv(env::VarError),
@@ -34,8 +36,13 @@ pub enum Error {
     GossipFileRelativePath(String),
     HabitatCore(hcore::Error),
     InstallHookFailed(PackageIdent),
+    /// Occurs when configuration doesn't match the package's configuration schema
+    InvalidConfig(String, Vec<String>),
+    InvalidEventStreamSink(String),
     InvalidEventStreamToken(String),
     InvalidInstallHookMode(String),
+    /// Occurs when a secret a configuration value refers to can't be read
+    InvalidSecret(String, String),
     /// Occurs when making lower level IO calls.
     IO(io::Error),
     /// Errors when joining paths :)
@@ -53,6 +60,9 @@ pub enum Error {
     /// When an error occurs serializing rendering context
     RenderContextSerialization(serde_json::Error),
     RootRequired,
+    /// Occurs when none of a service's secret providers have a secret a configuration value
+    /// refers to
+    SecretNotFound(String),
     StatusFileCorrupt(PathBuf),
     StrFromUtf8Error(str::Utf8Error),
     StringFromUtf8Error(string::FromUtf8Error),
@@ -91,6 +101,9 @@ impl fmt::Display for Error {
                 "Can't upload gossip.toml, it's a reserved file name".to_string()
             }
             Error::ChannelNotFound => "Channel not found".to_string(),
+            Error::ConfigSchema(ref path, ref e) => {
+                format!("Invalid configuration schema at {}: {}", path.display(), e)
+            }
             Error::CryptoKeyError(ref s) => format!("Missing or invalid key: {}", s),
             Error::DownloadFailed(ref msg) => msg.to_string(),
             Error::EditorEnv(ref e) => format!("Missing EDITOR environment variable: {}", e),
@@ -108,12 +121,26 @@ impl fmt::Display for Error {
             Error::InstallHookFailed(ref ident) => {
                 format!("Install hook exited unsuccessfully: {}", ident)
             }
+            Error::InvalidConfig(ref source, ref errors) => {
+                format!("{} does not match the configuration schema:\n{}",
+                        source,
+                        errors.iter()
+                              .map(|e| format!("  {}", e))
+                              .collect::<Vec<_>>()
+                              .join("\n"))
+            }
+            Error::InvalidEventStreamSink(ref s) => {
+                format!("Invalid event stream sink provided: '{}'", s)
+            }
             Error::InvalidEventStreamToken(ref s) => {
                 format!("Invalid event stream token provided: '{}'", s)
             }
             Error::InvalidInstallHookMode(ref e) => {
                 format!("Invalid InstallHookMode conversion from {}", e)
             }
+            Error::InvalidSecret(ref name, ref e) => {
+                format!("Unable to read secret {}: {}", name, e)
+            }
             Error::IO(ref err) => format!("{}", err),
             Error::JoinPathsError(ref err) => format!("{}", err),
             Error::NamedPipeTimeoutOnStart(ref group, ref hook, ref err) => {
@@ -142,6 +169,10 @@ impl fmt::Display for Error {
             Error::RootRequired => {
                 "Root or administrator permissions required to complete operation".to_string()
             }
+            Error::SecretNotFound(ref name) => {
+                format!("Secret {} was not found by any of the service's secret providers",
+                        name)
+            }
             Error::StatusFileCorrupt(ref path) => {
                 format!("Unable to decode contents of INSTALL_STATUS file, {}",
                         path.display())
@@ -3,6 +3,8 @@ mod context;
 pub mod helpers;
 pub mod hooks;
 pub mod package;
+pub mod schema;
+pub mod secrets;
 pub mod test_helpers;
 
 pub use self::context::RenderContext;
@@ -62,6 +64,7 @@ impl TemplateRenderer {
     pub fn new() -> Self {
         let mut handlebars = Handlebars::new();
         handlebars.register_helper("eachAlive", Box::new(helpers::EACH_ALIVE));
+        handlebars.register_helper("eachAliveWithTag", Box::new(helpers::EACH_ALIVE_WITH_TAG));
         handlebars.register_helper("pkgPathFor", Box::new(helpers::PKG_PATH_FOR));
         handlebars.register_helper("strConcat", Box::new(helpers::STR_CONCAT));
         handlebars.register_helper("strJoin", Box::new(helpers::STR_JOIN));
@@ -71,6 +74,24 @@ impl TemplateRenderer {
         handlebars.register_helper("toJson", Box::new(helpers::TO_JSON));
         handlebars.register_helper("toToml", Box::new(helpers::TO_TOML));
         handlebars.register_helper("toYaml", Box::new(helpers::TO_YAML));
+        handlebars.register_helper("add", Box::new(helpers::ADD));
+        handlebars.register_helper("subtract", Box::new(helpers::SUBTRACT));
+        handlebars.register_helper("multiply", Box::new(helpers::MULTIPLY));
+        handlebars.register_helper("divide", Box::new(helpers::DIVIDE));
+        handlebars.register_helper("modulo", Box::new(helpers::MODULO));
+        handlebars.register_helper("coalesce", Box::new(helpers::COALESCE));
+        handlebars.register_helper("default", Box::new(helpers::COALESCE));
+        handlebars.register_helper("base64Encode", Box::new(helpers::BASE64_ENCODE));
+        handlebars.register_helper("base64Decode", Box::new(helpers::BASE64_DECODE));
+        handlebars.register_helper("sha256", Box::new(helpers::SHA256));
+        handlebars.register_helper("regexMatch", Box::new(helpers::REGEX_MATCH));
+        handlebars.register_helper("regexReplace", Box::new(helpers::REGEX_REPLACE));
+        handlebars.register_helper("strSplit", Box::new(helpers::STR_SPLIT));
+        handlebars.register_helper("strTrim", Box::new(helpers::STR_TRIM));
+        handlebars.register_helper("eachAliveSortedBy", Box::new(helpers::EACH_ALIVE_SORTED_BY));
+        handlebars.register_helper("eachAliveWhere", Box::new(helpers::EACH_ALIVE_WHERE));
+        handlebars.register_helper("readFile", Box::new(helpers::READ_FILE));
+        handlebars.register_helper("cidrContains", Box::new(helpers::CIDR_CONTAINS));
 
         handlebars.register_escape_fn(never_escape);
         TemplateRenderer(handlebars)
@@ -398,6 +419,54 @@ test: something"#
         assert_eq!(each_alive_render, each_if_render);
     }
 
+    #[test]
+    fn each_alive_helper_skips_members_that_are_not_ready() {
+        let content = "{{~#eachAlive members as |m|}}{{m.id}} {{/eachAlive}}";
+        let mut renderer = TemplateRenderer::new();
+        renderer.register_template_string("t", content).unwrap();
+
+        let json = r#"{"members": [{"id": "ready", "alive": true, "ready": true},
+                                   {"id": "not-ready", "alive": true, "ready": false},
+                                   {"id": "dead", "alive": false, "ready": true},
+                                   {"id": "unreported", "alive": true}]}"#;
+        let data: serde_json::Value = serde_json::from_str(json).unwrap();
+        let rendered = renderer.render("t", &data).unwrap();
+        assert_eq!(rendered, "ready unreported ");
+    }
+
+    #[test]
+    fn each_alive_with_tag_helper_only_renders_matching_members() {
+        let content = "{{~#eachAliveWithTag members \"zone\" me.tags.zone as |m|}}{{m.id}} \
+                       {{/eachAliveWithTag}}";
+        let mut renderer = TemplateRenderer::new();
+        renderer.register_template_string("t", content).unwrap();
+
+        let json = r#"{"me": {"tags": {"zone": "a"}},
+                       "members": [{"id": "same", "alive": true, "tags": {"zone": "a"}},
+                                   {"id": "other", "alive": true, "tags": {"zone": "b"}},
+                                   {"id": "untagged", "alive": true},
+                                   {"id": "dead", "alive": false, "tags": {"zone": "a"}}]}"#;
+        let data: serde_json::Value = serde_json::from_str(json).unwrap();
+        let rendered = renderer.render("t", &data).unwrap();
+        assert_eq!(rendered, "same ");
+    }
+
+    #[test]
+    fn each_alive_with_tag_helper_renders_else_when_nothing_matches() {
+        let content = "{{~#eachAliveWithTag members \"zone\" me.tags.zone as |m|}}{{m.id}} \
+                       {{else}}{{#eachAlive members as |m|}}{{m.id}} \
+                       {{/eachAlive}}{{/eachAliveWithTag}}";
+        let mut renderer = TemplateRenderer::new();
+        renderer.register_template_string("t", content).unwrap();
+
+        let json = r#"{"me": {"tags": {}},
+                       "members": [{"id": "one", "alive": true, "tags": {"zone": "a"}},
+                                   {"id": "two", "alive": true}]}"#;
+        let data: serde_json::Value = serde_json::from_str(json).unwrap();
+        let rendered = renderer.render("t", &data).unwrap();
+        assert_eq!(rendered, "one two ");
+    }
+
     #[test]
     fn render_package_install() {
         let root = TempDir::new().expect("create temp dir").into_path();
@@ -7,6 +7,9 @@ use crate::{error::{Error,
                          USER_CONFIG_FILE}},
             outputln,
             templating::{package::Pkg,
+                         schema::ConfigSchema,
+                         secrets::{self,
+                                   SecretProvider},
                          TemplateRenderer}};
 use serde::{Serialize,
             Serializer};
@@ -14,7 +17,10 @@ use serde_json;
 use serde_transcode;
 use std::{self,
           borrow::Cow,
+          collections::{BTreeMap,
+                        BTreeSet},
           env,
+          fmt,
           fs::File,
           io::prelude::*,
           path::{Path,
@@ -82,6 +88,54 @@ impl PackageConfigPaths for Pkg {
     fn deprecated_user_config_dir(&self) -> PathBuf { self.svc_path.clone() }
 }
 
+/// A layer of configuration. Each layer takes precedence over the ones before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CfgLayer {
+    /// The package's `default.toml`
+    Default,
+    /// The `HAB_<PACKAGE>` environment variable of the Supervisor
+    Environment,
+    /// The service's `user.toml`
+    User,
+    /// Configuration applied to the service group
+    Gossip,
+}
+
+impl fmt::Display for CfgLayer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            CfgLayer::Default => "default",
+            CfgLayer::Environment => "environment",
+            CfgLayer::User => "user",
+            CfgLayer::Gossip => "gossip",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A key of the effective configuration, and where its value came from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CfgKey {
+    /// The dotted path of the key, e.g. `upstream.servers`
+    pub key:        String,
+    pub value:      toml::Value,
+    /// The layer the value came from
+    pub layer:      CfgLayer,
+    /// The values lower layers give the key, highest layer first
+    pub overridden: Vec<(CfgLayer, toml::Value)>,
+}
+
+/// A key of the effective configuration whose value would change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CfgKeyChange {
+    /// The dotted path of the key, e.g. `upstream.servers`
+    pub key:     String,
+    /// The current value, or `None` if the key would be added
+    pub current: Option<toml::Value>,
+    /// The pending value, or `None` if the key would be removed
+    pub pending: Option<toml::Value>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Cfg {
     /// Default level configuration loaded by a Package's `default.toml`
@@ -96,6 +150,8 @@ pub struct Cfg {
     pub user_config_path: UserConfigPath,
     /// Last known incarnation number of the census group's service config
     pub gossip_incarnation: u64,
+    /// The schema of the configuration, if the package ships one next to its `default.toml`
+    pub schema: Option<ConfigSchema>,
     /// The path to an optional dev-time configuration directory that
     /// is being used.
     override_config_dir: Option<PathBuf>,
@@ -106,12 +162,12 @@ impl Cfg {
         where P: PackageConfigPaths
     {
         let override_config_dir = config_from.and_then(|c| Some(c.clone()));
-        let default = {
+        let (default, schema) = {
             let pkg_root = match override_config_dir {
                 Some(ref path) => Cow::Borrowed(path),
                 None => Cow::Owned(package.default_config_dir()),
             };
-            Self::load_default(pkg_root.as_ref())?
+            (Self::load_default(pkg_root.as_ref())?, ConfigSchema::load(pkg_root.as_ref())?)
         };
         let user_config_path = Self::determine_user_config_path(package);
         let user = Self::load_user(user_config_path.get_path())?;
@@ -121,6 +177,7 @@ impl Cfg {
                   gossip: None,
                   environment,
                   gossip_incarnation: 0,
+                  schema,
                   user_config_path,
                   override_config_dir })
     }
@@ -146,16 +203,144 @@ impl Cfg {
 
     /// A structured interface which describes configuration keys which are configurable and their
     /// optional default values.
-    pub fn interface(&self) -> Option<&toml::value::Table> {
-        // JW TODO: For now let's use `default.toml` as it is for the interface. In the future,
-        // we will need to be able to describe more than just the key value relationship that
-        // `default.toml` provides. We will need to be able to describe things like:
-        //
-        // * Keys which have no default
-        // * Key with a default value
-        // * Keys which only accept certain values
-        // * Allowed types for a key
-        self.default.as_ref()
+    ///
+    /// Keys without defaults, the types of keys and the values they accept are described by the
+    /// package's `schema`, if it ships one.
+    pub fn interface(&self) -> Option<&toml::value::Table> { self.default.as_ref() }
+
+    /// Checks the configuration the layers currently merge into against the package's schema.
+    ///
+    /// Returns `None` if valid, or if the package has no schema, and `Some` containing a list of
+    /// errors if invalid.
+    pub fn validate_schema(&self) -> Option<Vec<String>> {
+        self.schema_errors(self.user.as_ref(), self.gossip.as_ref())
+    }
+
+    /// Checks the configuration that applying `gossip` to the service group would result in
+    /// against the package's schema.
+    ///
+    /// Returns `None` if valid, or if the package has no schema, and `Some` containing a list of
+    /// errors if invalid.
+    pub fn validate_gossip(&self, gossip: &toml::value::Table) -> Option<Vec<String>> {
+        self.schema_errors(self.user.as_ref(), Some(gossip))
+    }
+
+    fn schema_errors(&self,
+                     user: Option<&toml::value::Table>,
+                     gossip: Option<&toml::value::Table>)
+                     -> Option<Vec<String>> {
+        self.schema
+            .as_ref()
+            .and_then(|schema| schema.validate(&self.merged_with(user, gossip)))
+    }
+
+    /// Merges the layers, in order of precedence, with `user` and `gossip` in place of the user
+    /// and gossip layers. Secret references are redacted.
+    fn merged_with(&self,
+                   user: Option<&toml::value::Table>,
+                   gossip: Option<&toml::value::Table>)
+                   -> toml::value::Table {
+        secrets::redact(&self.merge_layers(user, gossip))
+    }
+
+    fn merge_layers(&self,
+                    user: Option<&toml::value::Table>,
+                    gossip: Option<&toml::value::Table>)
+                    -> toml::value::Table {
+        let mut table = toml::value::Table::new();
+        for (layer, values) in self.layers_with(user, gossip).iter() {
+            if let Some(values) = values {
+                if let Err(err) = toml_merge(&mut table, values) {
+                    outputln!("Error merging {}-cfg into config, {}", layer, err);
+                }
+            }
+        }
+        table
+    }
+
+    /// The layers, in order of precedence, with `user` and `gossip` in place of the user and
+    /// gossip layers.
+    fn layers_with<'a>(&'a self,
+                       user: Option<&'a toml::value::Table>,
+                       gossip: Option<&'a toml::value::Table>)
+                       -> [(CfgLayer, Option<&'a toml::value::Table>); 4] {
+        [(CfgLayer::Default, self.default.as_ref()),
+         (CfgLayer::Environment, self.environment.as_ref()),
+         (CfgLayer::User, user),
+         (CfgLayer::Gossip, gossip)]
+    }
+
+    /// Lists every key of the effective configuration, with the layer its value came from and
+    /// the values of lower layers it overrides. Secret references are redacted.
+    pub fn explain(&self) -> Vec<CfgKey> {
+        let layers: Vec<(CfgLayer, toml::value::Table)> =
+            self.layers_with(self.user.as_ref(), self.gossip.as_ref())
+                .iter()
+                .filter_map(|(layer, values)| values.map(|v| (*layer, secrets::redact(v))))
+                .collect();
+        let merged = self.merged_with(self.user.as_ref(), self.gossip.as_ref());
+        let mut keys = Vec::new();
+        for (path, value) in leaves(&merged) {
+            let mut found = Vec::new();
+            for (layer, values) in layers.iter().rev() {
+                if let Some(v) = value_at(values, &path) {
+                    found.push((*layer, v.clone()));
+                }
+            }
+            // The highest layer with a value for the key is the one that won
+            let layer = if found.is_empty() {
+                CfgLayer::Default
+            } else {
+                found.remove(0).0
+            };
+            keys.push(CfgKey { key: path.join("."),
+                               value,
+                               layer,
+                               overridden: found });
+        }
+        keys
+    }
+
+    /// Lists the keys of the effective configuration that applying `gossip` to the service group
+    /// would change. Secret references are redacted, so a change to the value of a secret is
+    /// not a change here.
+    pub fn diff_gossip(&self, gossip: &toml::value::Table) -> Vec<CfgKeyChange> {
+        let current = self.merged_with(self.user.as_ref(), self.gossip.as_ref());
+        let pending = self.merged_with(self.user.as_ref(), Some(gossip));
+        let current: BTreeMap<_, _> = leaves(&current).into_iter().collect();
+        let pending: BTreeMap<_, _> = leaves(&pending).into_iter().collect();
+        let paths: BTreeSet<&Vec<String>> = current.keys().chain(pending.keys()).collect();
+        paths.into_iter()
+             .filter_map(|path| {
+                 let (current, pending) = (current.get(path), pending.get(path));
+                 if current == pending {
+                     None
+                 } else {
+                     Some(CfgKeyChange { key:     path.join("."),
+                                         current: current.cloned(),
+                                         pending: pending.cloned(), })
+                 }
+             })
+             .collect()
+    }
+
+    /// Returns a copy of the configuration with every secret reference replaced by the secret,
+    /// for rendering templates. The copy must not be exposed anywhere else.
+    ///
+    /// Only references that survive the merge are resolved, so the copy holds the merged
+    /// configuration in its default layer, and its other layers are empty.
+    ///
+    /// # Errors
+    ///
+    /// * Returns `Error::SecretNotFound` if `provider` doesn't have a secret that is referred to
+    pub fn with_secrets_resolved(&self, provider: &dyn SecretProvider) -> Result<Cfg> {
+        let merged = self.merge_layers(self.user.as_ref(), self.gossip.as_ref());
+        let mut cfg = self.clone();
+        cfg.default = Some(secrets::resolve(&merged, provider)?);
+        cfg.environment = None;
+        cfg.user = None;
+        cfg.gossip = None;
+        Ok(cfg)
     }
 
     /// Updates the default layer of the configuration when a service
@@ -171,13 +356,14 @@ impl Cfg {
     pub fn update_defaults_from_package<P>(&mut self, package: &P) -> Result<bool>
         where P: PackageConfigPaths
     {
-        let incoming_defaults = {
+        let (incoming_defaults, incoming_schema) = {
             let pkg_root = match self.override_config_dir {
                 Some(ref path) => Cow::Borrowed(path),
                 None => Cow::Owned(package.default_config_dir()),
             };
-            Self::load_default(pkg_root.as_ref())?
+            (Self::load_default(pkg_root.as_ref())?, ConfigSchema::load(pkg_root.as_ref())?)
         };
+        self.schema = incoming_schema;
 
         if incoming_defaults != self.default {
             self.default = incoming_defaults;
@@ -291,9 +477,14 @@ impl Cfg {
         Self::load_toml_file(path, USER_CONFIG_FILE)
     }
 
-    /// Reloads the user configuration file.
+    /// Reloads the user configuration file. A file that doesn't match the package's schema is
+    /// rejected, and the configuration is left as it was.
     pub fn reload_user(&mut self) -> Result<()> {
         let user = Self::load_user(self.user_config_path.get_path())?;
+        if let Some(errors) = self.schema_errors(user.as_ref(), self.gossip.as_ref()) {
+            let path = self.user_config_path.get_path().join(USER_CONFIG_FILE);
+            return Err(Error::InvalidConfig(path.display().to_string(), errors));
+        }
         self.user = user;
         Ok(())
     }
@@ -362,28 +553,7 @@ impl Serialize for Cfg {
     fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
         where S: Serializer
     {
-        let mut table = toml::value::Table::new();
-        if let Some(ref default_cfg) = self.default {
-            if let Err(err) = toml_merge(&mut table, default_cfg) {
-                outputln!("Error merging default-cfg into config, {}", err);
-            }
-        }
-        if let Some(ref env_cfg) = self.environment {
-            if let Err(err) = toml_merge(&mut table, env_cfg) {
-                outputln!("Error merging environment-cfg into config, {}", err);
-            }
-        }
-        if let Some(ref user_cfg) = self.user {
-            if let Err(err) = toml_merge(&mut table, user_cfg) {
-                outputln!("Error merging user-cfg into config, {}", err);
-            }
-        }
-        if let Some(ref gossip_cfg) = self.gossip {
-            if let Err(err) = toml_merge(&mut table, gossip_cfg) {
-                outputln!("Error merging gossip-cfg into config, {}", err);
-            }
-        }
-
+        let table = self.merged_with(self.user.as_ref(), self.gossip.as_ref());
         toml::ser::tables_last(&table, serializer)
     }
 }
@@ -471,6 +641,79 @@ impl CfgRenderer {
         }
         Ok(changed)
     }
+
+    /// Lists the templates whose rendered content differs between the `current` and `pending`
+    /// contexts. Nothing is written.
+    pub fn changed_by<T>(&self, current: &T, pending: &T) -> Result<Vec<String>>
+        where T: Serialize
+    {
+        let mut changed = Vec::new();
+        for template in self.0.get_templates().keys() {
+            if self.0.render(&template, current)? != self.0.render(&template, pending)? {
+                changed.push(template.clone());
+            }
+        }
+        changed.sort();
+        Ok(changed)
+    }
+}
+
+/// Formats a value as inline TOML, e.g. `"info"`, `[1, 2]` or `{ port = 80 }`.
+pub fn inline_toml(value: &toml::Value) -> String {
+    match value {
+        toml::Value::Table(table) if table.is_empty() => "{}".to_string(),
+        toml::Value::Table(table) => {
+            let pairs: Vec<String> =
+                table.iter()
+                     .map(|(key, value)| format!("{} = {}", inline_key(key), inline_toml(value)))
+                     .collect();
+            format!("{{ {} }}", pairs.join(", "))
+        }
+        toml::Value::Array(array) => {
+            let values: Vec<String> = array.iter().map(inline_toml).collect();
+            format!("[{}]", values.join(", "))
+        }
+        _ => value.to_string(),
+    }
+}
+
+fn inline_key(key: &str) -> String {
+    if !key.is_empty()
+       && key.chars()
+             .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        toml::Value::String(key.to_string()).to_string()
+    }
+}
+
+/// The paths and values of the leaves of `table`: values that aren't tables, and empty tables.
+fn leaves(table: &toml::value::Table) -> Vec<(Vec<String>, toml::Value)> {
+    fn collect(table: &toml::value::Table,
+               path: &mut Vec<String>,
+               leaves: &mut Vec<(Vec<String>, toml::Value)>) {
+        for (key, value) in table.iter() {
+            path.push(key.clone());
+            match value.as_table() {
+                Some(table) if !table.is_empty() => collect(table, path, leaves),
+                _ => leaves.push((path.clone(), value.clone())),
+            }
+            path.pop();
+        }
+    }
+    let mut leaves = Vec::new();
+    collect(table, &mut Vec::new(), &mut leaves);
+    leaves
+}
+
+fn value_at<'a>(table: &'a toml::value::Table, path: &[String]) -> Option<&'a toml::Value> {
+    let (key, parents) = path.split_last()?;
+    let mut table = table;
+    for parent in parents {
+        table = table.get(parent)?.as_table()?;
+    }
+    table.get(key)
 }
 
 // Recursively merges the `other` TOML table into `me`
@@ -607,6 +850,7 @@ mod test {
                         package::{PackageIdent,
                                   PackageInstall}},
                 templating::{context::RenderContext,
+                             schema::CONFIG_SCHEMA_FILE,
                              test_helpers::*}};
     use std::{env,
               fs::{self,
@@ -932,6 +1176,101 @@ mod test {
         assert_eq!(cfg.user, Some(toml_from_str(toml)));
     }
 
+    const PORT_SCHEMA: &str = r#"{
+        "type": "object",
+        "properties": { "port": { "type": "integer", "minimum": 1 } }
+    }"#;
+
+    #[test]
+    fn reload_user_rejects_config_that_does_not_match_the_schema() {
+        let cfg_data = CfgTestData::new();
+        write_toml(&cfg_data.pkg.default_config_dir().join("default.toml"), "port = 80");
+        fs::write(cfg_data.pkg.default_config_dir().join(CONFIG_SCHEMA_FILE),
+                  PORT_SCHEMA).expect("write schema");
+        write_toml(&cfg_data.rucp, "port = 8080");
+        let mut cfg = Cfg::new(&cfg_data.pkg, None).expect("create config");
+        assert!(cfg.schema.is_some());
+        assert_eq!(cfg.validate_schema(), None);
+
+        write_toml(&cfg_data.rucp, "port = \"eighty\"");
+        match cfg.reload_user() {
+            Err(Error::InvalidConfig(_, errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert!(errors[0].starts_with("port: "), "{:?}", errors);
+            }
+            other => panic!("Expected an invalid config error, got {:?}", other),
+        }
+        assert_eq!(cfg.user, Some(toml_from_str("port = 8080")));
+    }
+
+    #[test]
+    fn validate_gossip_checks_the_merged_config() {
+        let cfg_data = CfgTestData::new();
+        write_toml(&cfg_data.pkg.default_config_dir().join("default.toml"), "port = 80");
+        fs::write(cfg_data.pkg.default_config_dir().join(CONFIG_SCHEMA_FILE),
+                  PORT_SCHEMA).expect("write schema");
+        let cfg = Cfg::new(&cfg_data.pkg, None).expect("create config");
+
+        assert_eq!(cfg.validate_gossip(&toml_from_str("port = 443")), None);
+        assert!(cfg.validate_gossip(&toml_from_str("port = 0")).is_some());
+    }
+
+    #[test]
+    fn explain_names_the_layer_each_value_came_from() {
+        let concrete_path = TempDir::new().expect("create temp dir");
+        let pkg = TestPkg::new(&concrete_path);
+        let mut cfg = Cfg::new(&pkg, None).expect("Could not create config");
+        cfg.default = Some(toml_from_str("port = 80\nlog_level = \"info\"\n[tls]\nenabled = \
+                                          false\n"));
+        cfg.user = Some(toml_from_str("port = 8080\n[tls]\nenabled = true\n"));
+        cfg.set_gossip(3, toml_from_str("port = 9090"));
+
+        let keys = cfg.explain();
+        assert_eq!(keys,
+                   vec![CfgKey { key:        "log_level".to_string(),
+                                 value:      toml::Value::from("info"),
+                                 layer:      CfgLayer::Default,
+                                 overridden: vec![], },
+                        CfgKey { key:        "port".to_string(),
+                                 value:      toml::Value::from(9090),
+                                 layer:      CfgLayer::Gossip,
+                                 overridden: vec![(CfgLayer::User, toml::Value::from(8080)),
+                                                  (CfgLayer::Default, toml::Value::from(80)),], },
+                        CfgKey { key:        "tls.enabled".to_string(),
+                                 value:      toml::Value::from(true),
+                                 layer:      CfgLayer::User,
+                                 overridden: vec![(CfgLayer::Default,
+                                                   toml::Value::from(false))], },]);
+    }
+
+    #[test]
+    fn diff_gossip_lists_changed_added_and_removed_keys() {
+        let concrete_path = TempDir::new().expect("create temp dir");
+        let pkg = TestPkg::new(&concrete_path);
+        let mut cfg = Cfg::new(&pkg, None).expect("Could not create config");
+        cfg.default = Some(toml_from_str("port = 80\nlog_level = \"info\"\n"));
+        cfg.set_gossip(1, toml_from_str("port = 8080\nworkers = 4\n"));
+
+        let changes = cfg.diff_gossip(&toml_from_str("port = 9090\nlog_level = \"info\"\n\
+                                                      [tls]\nenabled = true\n"));
+        assert_eq!(changes,
+                   vec![CfgKeyChange { key:     "port".to_string(),
+                                       current: Some(toml::Value::from(8080)),
+                                       pending: Some(toml::Value::from(9090)), },
+                        CfgKeyChange { key:     "tls.enabled".to_string(),
+                                       current: None,
+                                       pending: Some(toml::Value::from(true)), },
+                        CfgKeyChange { key:     "workers".to_string(),
+                                       current: Some(toml::Value::from(4)),
+                                       pending: None, },]);
+    }
+
+    #[test]
+    fn inline_toml_formats_nested_values_on_one_line() {
+        let cfg = toml_from_str("a = { b = [1, 2], \"c d\" = \"e\" }");
+        assert_eq!(inline_toml(&cfg["a"]), "{ b = [1, 2], \"c d\" = \"e\" }");
+    }
+
     #[test]
     fn serialize_config() {
         let concrete_path = TempDir::new().expect("create temp dir");
@@ -944,6 +1283,27 @@ mod test {
         assert_eq!(default_toml, toml::to_string(&cfg).unwrap());
     }
 
+    #[test]
+    fn secret_references_are_only_resolved_in_a_resolved_copy() {
+        let concrete_path = TempDir::new().expect("create temp dir");
+        let pkg = TestPkg::new(&concrete_path);
+        let mut cfg = Cfg::new(&pkg, None).expect("Could not create config");
+        cfg.default = Some(toml_from_str("[db]\npassword = \"\"\n"));
+        cfg.user = Some(toml_from_str("[db]\npassword = { secret = \"cfg-test/password\" }\n"));
+        env::set_var("HAB_SECRET_CFG_TEST_PASSWORD", "hunter2");
+
+        let resolved = cfg.with_secrets_resolved(&secrets::EnvSecretProvider)
+                          .expect("resolve secrets");
+        env::remove_var("HAB_SECRET_CFG_TEST_PASSWORD");
+
+        assert_eq!(toml::to_string(&cfg).unwrap(),
+                   "[db]\npassword = \"<secret cfg-test/password>\"\n");
+        assert_eq!(toml::to_string(&resolved).unwrap(),
+                   "[db]\npassword = \"hunter2\"\n");
+        assert!(cfg.with_secrets_resolved(&secrets::EnvSecretProvider)
+                   .is_err());
+    }
+
     // env_key: the name of the environment variable the config should
     //     be read from
     // package_name: the name of the package that would read
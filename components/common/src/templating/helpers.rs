@@ -1,27 +1,65 @@
+mod base64_decode;
+mod base64_encode;
+mod cidr_contains;
+mod coalesce;
 mod each_alive;
+mod each_alive_sorted_by;
+mod each_alive_where;
+mod each_alive_with_tag;
+mod math;
 mod pkg_path_for;
+mod read_file;
+mod regex_match;
+mod regex_replace;
+mod sha256;
 mod str_concat;
 mod str_join;
 mod str_replace;
+mod str_split;
+mod str_trim;
 mod to_json;
 mod to_lowercase;
 mod to_toml;
 mod to_uppercase;
 mod to_yaml;
 
-pub use self::{each_alive::EACH_ALIVE,
+pub use self::{base64_decode::BASE64_DECODE,
+               base64_encode::BASE64_ENCODE,
+               cidr_contains::CIDR_CONTAINS,
+               coalesce::COALESCE,
+               each_alive::EACH_ALIVE,
+               each_alive_sorted_by::EACH_ALIVE_SORTED_BY,
+               each_alive_where::EACH_ALIVE_WHERE,
+               each_alive_with_tag::EACH_ALIVE_WITH_TAG,
+               math::{ADD,
+                      DIVIDE,
+                      MODULO,
+                      MULTIPLY,
+                      SUBTRACT},
                pkg_path_for::PKG_PATH_FOR,
+               read_file::READ_FILE,
+               regex_match::REGEX_MATCH,
+               regex_replace::REGEX_REPLACE,
+               sha256::SHA256,
                str_concat::STR_CONCAT,
                str_join::STR_JOIN,
                str_replace::STR_REPLACE,
+               str_split::STR_SPLIT,
+               str_trim::STR_TRIM,
                to_json::TO_JSON,
                to_lowercase::TO_LOWERCASE,
                to_toml::TO_TOML,
                to_uppercase::TO_UPPERCASE,
                to_yaml::TO_YAML};
+use super::RenderResult;
+use handlebars::{Handlebars,
+                 Helper,
+                 RenderContext,
+                 Renderable};
 use serde::Serialize;
 use serde_json::{self,
                  Value as Json};
+use std::collections::BTreeMap;
 
 // Taken from `handlebars::context::JsonTruthy`. The trait is marked public but it's in a private
 // module. It's super useful so let's pull it into here.
@@ -48,3 +86,76 @@ fn to_json<T>(src: &T) -> Json
 {
     serde_json::to_value(src).unwrap_or(Json::Null)
 }
+
+/// The text a helper writes for a value: strings as they are, nothing for a missing value, and
+/// anything else as JSON.
+fn to_text(value: &Json) -> String {
+    match *value {
+        Json::String(ref s) => s.clone(),
+        Json::Null => String::new(),
+        _ => value.to_string(),
+    }
+}
+
+/// The value at a dotted path within `value`, e.g. `sys.hostname` of a census member.
+fn value_at<'a>(value: &'a Json, path: &str) -> Option<&'a Json> {
+    path.split('.').try_fold(value, |value, key| value.get(key))
+}
+
+/// Renders the block of a conditional helper, or its `{{else}}` block, when it is used as a
+/// block helper, and writes `true` or `false` otherwise.
+fn render_condition(h: &Helper<'_>,
+                    r: &Handlebars,
+                    rc: &mut RenderContext<'_>,
+                    condition: bool)
+                    -> RenderResult<()> {
+    if h.template().is_none() && h.inverse().is_none() {
+        rc.writer.write_all(condition.to_string().as_bytes())?;
+        return Ok(());
+    }
+    let template = if condition { h.template() } else { h.inverse() };
+    if let Some(template) = template {
+        template.render(r, rc)?;
+    }
+    Ok(())
+}
+
+/// Renders the block of an iterating helper for each of `items` as `eachAlive` does, with
+/// `@first`, `@last` and `@index` set and the item bound to the block parameter. Without items,
+/// the `{{else}}` block is rendered instead.
+fn render_each(h: &Helper<'_>,
+               r: &Handlebars,
+               rc: &mut RenderContext<'_>,
+               items: &[Json])
+               -> RenderResult<()> {
+    if items.is_empty() {
+        if let Some(else_template) = h.inverse() {
+            else_template.render(r, rc)?;
+        }
+        return Ok(());
+    }
+    if let Some(template) = h.template() {
+        rc.promote_local_vars();
+        let len = items.len();
+        for (i, item) in items.iter().enumerate() {
+            let mut local_rc = rc.derive();
+            local_rc.set_local_var("@first".to_string(), to_json(&(i == 0usize)));
+            local_rc.set_local_var("@last".to_string(), to_json(&(i == len - 1)));
+            local_rc.set_local_var("@index".to_string(), to_json(&i));
+
+            if let Some(block_param) = h.block_param() {
+                let mut map = BTreeMap::new();
+                map.insert(block_param.to_string(), item.clone());
+                local_rc.push_block_context(&map)?;
+            }
+
+            template.render(r, &mut local_rc)?;
+
+            if h.block_param().is_some() {
+                local_rc.pop_block_context();
+            }
+        }
+        rc.demote_local_vars();
+    }
+    Ok(())
+}
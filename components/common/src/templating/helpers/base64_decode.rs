@@ -0,0 +1,56 @@
+use base64;
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+pub struct Base64DecodeHelper;
+
+impl HelperDef for Base64DecodeHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected a string \
+                                                                              parameter for \
+                                                                              \"base64Decode\"")
+                                                        })?;
+        let decoded = base64::decode(param.trim())
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| String::from_utf8(bytes).map_err(|e| e.to_string()))
+            .map_err(|e| RenderError::new(format!("Can't decode base64 for \"base64Decode\": {}",
+                                                  e)))?;
+        rc.writer.write_all(decoded.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static BASE64_DECODE: Base64DecodeHelper = Base64DecodeHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_base64_decode_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("base64Decode", Box::new(BASE64_DECODE));
+        assert_eq!("user:hunter2",
+                   handlebars.template_render("{{base64Decode \"dXNlcjpodW50ZXIy\"}}", &json!({}))
+                             .unwrap());
+    }
+
+    #[test]
+    fn test_base64_decode_helper_errors_on_invalid_input() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("base64Decode", Box::new(BASE64_DECODE));
+        assert!(handlebars.template_render("{{base64Decode \"not base64!\"}}", &json!({}))
+                          .is_err());
+        // Valid base64, but not UTF-8
+        assert!(handlebars.template_render("{{base64Decode \"/w==\"}}", &json!({}))
+                          .is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use super::{super::RenderResult,
+            render_condition};
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use std::net::IpAddr;
+
+/// Parses a network such as `10.0.0.0/8` into its address and prefix length. An address without
+/// a prefix length is a network of that one address.
+fn parse_cidr(cidr: &str) -> Result<(IpAddr, u32), String> {
+    let mut parts = cidr.splitn(2, '/');
+    let addr: IpAddr = parts.next()
+                            .unwrap_or_default()
+                            .trim()
+                            .parse()
+                            .map_err(|_| format!("invalid network {}", cidr))?;
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    let prefix = match parts.next() {
+        Some(prefix) => {
+            prefix.trim()
+                  .parse()
+                  .ok()
+                  .filter(|prefix| *prefix <= max)
+                  .ok_or_else(|| format!("invalid prefix length in {}", cidr))?
+        }
+        None => max,
+    };
+    Ok((addr, prefix))
+}
+
+/// Whether the network `(network, prefix)` contains `addr`. Addresses of a different family are
+/// never contained.
+fn contains(network: IpAddr, prefix: u32, addr: IpAddr) -> bool {
+    match (network, addr) {
+        (IpAddr::V4(network), IpAddr::V4(addr)) => {
+            let mask = u32::max_value().checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(network) & mask == u32::from(addr) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(addr)) => {
+            let mask = u128::max_value().checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(network) & mask == u128::from(addr) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Whether an address is within a network, e.g. `{{cidrContains "10.0.0.0/8" sys.ip}}`. Used as
+/// a block helper, it renders its block if the address is in the network and its `{{else}}`
+/// block otherwise.
+#[derive(Clone, Copy)]
+pub struct CidrContainsHelper;
+
+impl HelperDef for CidrContainsHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param = |i| {
+            h.param(i)
+             .and_then(|v| v.value().as_str())
+             .ok_or_else(|| {
+                 RenderError::new("Expected a network and an address for \"cidrContains\"")
+             })
+        };
+        let (network, prefix) = parse_cidr(param(0)?).map_err(RenderError::new)?;
+        let addr = param(1)?;
+        let addr: IpAddr =
+            addr.trim()
+                .parse()
+                .map_err(|_| RenderError::new(format!("Invalid address {}", addr)))?;
+        render_condition(h, r, rc, contains(network, prefix, addr))
+    }
+}
+
+pub static CIDR_CONTAINS: CidrContainsHelper = CidrContainsHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ipv4_networks() {
+        let json = json!({"ip": "10.1.2.3"});
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("cidrContains", Box::new(CIDR_CONTAINS));
+        assert_eq!("true",
+                   handlebars.template_render("{{cidrContains \"10.0.0.0/8\" ip}}", &json)
+                             .unwrap());
+        assert_eq!("true",
+                   handlebars.template_render("{{cidrContains \"10.1.2.0/24\" ip}}", &json)
+                             .unwrap());
+        assert_eq!("false",
+                   handlebars.template_render("{{cidrContains \"192.168.0.0/16\" ip}}", &json)
+                             .unwrap());
+        assert_eq!("true",
+                   handlebars.template_render("{{cidrContains \"0.0.0.0/0\" ip}}", &json)
+                             .unwrap());
+        assert_eq!("true",
+                   handlebars.template_render("{{cidrContains \"10.1.2.3\" ip}}", &json)
+                             .unwrap());
+        assert_eq!("false",
+                   handlebars.template_render("{{cidrContains \"10.1.2.4/32\" ip}}", &json)
+                             .unwrap());
+    }
+
+    #[test]
+    fn test_ipv6_networks() {
+        let json = json!({"ip": "10.1.2.3", "ip6": "fd00::1"});
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("cidrContains", Box::new(CIDR_CONTAINS));
+        assert_eq!("true",
+                   handlebars.template_render("{{cidrContains \"fd00::/8\" ip6}}", &json)
+                             .unwrap());
+        assert_eq!("false",
+                   handlebars.template_render("{{cidrContains \"fe80::/10\" ip6}}", &json)
+                             .unwrap());
+        assert_eq!("true",
+                   handlebars.template_render("{{cidrContains \"::/0\" ip6}}", &json)
+                             .unwrap());
+        assert_eq!("false",
+                   handlebars.template_render("{{cidrContains \"fd00::/8\" ip}}", &json)
+                             .unwrap());
+    }
+
+    #[test]
+    fn test_block_form() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("cidrContains", Box::new(CIDR_CONTAINS));
+        let template = "{{#cidrContains \"10.0.0.0/8\" ip}}internal{{else}}external\
+                        {{/cidrContains}}";
+        assert_eq!("internal",
+                   handlebars.template_render(template, &json!({"ip": "10.1.2.3"}))
+                             .unwrap());
+    }
+
+    #[test]
+    fn test_invalid_networks() {
+        let json = json!({"ip": "10.1.2.3"});
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("cidrContains", Box::new(CIDR_CONTAINS));
+        assert!(handlebars.template_render("{{cidrContains \"10.0.0.0/33\" ip}}", &json)
+                          .is_err());
+        assert!(handlebars.template_render("{{cidrContains \"10.0.0/8\" ip}}", &json)
+                          .is_err());
+        assert!(handlebars.template_render("{{cidrContains \"10.0.0.0/8\" \"host\"}}", &json)
+                          .is_err());
+    }
+}
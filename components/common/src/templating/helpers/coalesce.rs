@@ -0,0 +1,86 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+
+use super::{super::RenderResult,
+            to_text};
+
+/// Writes the first of its parameters that is set, e.g.
+/// `{{coalesce cfg.listen_addr sys.ip "0.0.0.0"}}`. A parameter is unset if it is missing, null
+/// or an empty string. Also registered as `default`, for `{{default cfg.port 8080}}`.
+#[derive(Clone, Copy)]
+pub struct CoalesceHelper;
+
+impl HelperDef for CoalesceHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        if h.params().is_empty() {
+            return Err(RenderError::new(format!("Expected at least 1 parameter for \"{}\"",
+                                                h.name())));
+        }
+        let value = h.params()
+                     .iter()
+                     .map(handlebars::ContextJson::value)
+                     .find(|v| {
+                         match **v {
+                             Json::Null => false,
+                             Json::String(ref s) => !s.is_empty(),
+                             _ => true,
+                         }
+                     });
+        if let Some(value) = value {
+            rc.writer.write_all(to_text(value).into_bytes().as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+pub static COALESCE: CoalesceHelper = CoalesceHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_coalesce_helper() {
+        let json = json!({
+            "cfg": {
+                "empty": "",
+                "host": "db.example"
+            }
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("coalesce", Box::new(COALESCE));
+        assert_eq!("db.example",
+                   handlebars.template_render("{{coalesce cfg.missing cfg.empty cfg.host \
+                                               \"localhost\"}}",
+                                              &json)
+                             .unwrap());
+        assert_eq!("",
+                   handlebars.template_render("{{coalesce cfg.missing cfg.empty}}", &json)
+                             .unwrap());
+    }
+
+    #[test]
+    fn test_default_helper() {
+        let json = json!({
+            "cfg": {
+                "empty": "",
+                "port": 5432
+            }
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("default", Box::new(COALESCE));
+        assert_eq!("5432",
+                   handlebars.template_render("{{default cfg.port 8080}}", &json)
+                             .unwrap());
+        assert_eq!("8080",
+                   handlebars.template_render("{{default cfg.missing 8080}}", &json)
+                             .unwrap());
+        assert_eq!("none",
+                   handlebars.template_render("{{default cfg.empty \"none\"}}", &json)
+                             .unwrap());
+    }
+}
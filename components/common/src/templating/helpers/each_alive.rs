@@ -7,9 +7,19 @@ use handlebars::{Handlebars,
                  RenderContext,
                  RenderError,
                  Renderable};
-use serde_json::Value as Json;
+use serde_json::{Map,
+                 Value as Json};
 use std::collections::BTreeMap;
 
+/// Whether a census member should be rendered: it must be alive and,
+/// unless it comes from a Supervisor that predates readiness checks,
+/// ready for its consumers.
+pub(super) fn is_alive_and_ready(member: &Map<String, Json>) -> bool {
+    let flag =
+        |key: &str, default: bool| member.get(key).and_then(Json::as_bool).unwrap_or(default);
+    flag("alive", false) && flag("ready", true)
+}
+
 #[derive(Clone, Copy)]
 pub struct EachAliveHelper;
 
@@ -24,15 +34,9 @@ impl HelperDef for EachAliveHelper {
             let rendered = match (value.value().is_truthy(), value.value()) {
                 (true, &Json::Array(ref list)) => {
                     let alive_members: Vec<Json> = list.iter()
-                                                       .filter_map(|m| {
-                                                           m.as_object().and_then(|m| {
-                                if m.contains_key("alive") && m["alive"].as_bool().unwrap() {
-                                    Some(to_json(&m))
-                                } else {
-                                    None
-                                }
-                            })
-                                                       })
+                                                       .filter_map(Json::as_object)
+                                                       .filter(|m| is_alive_and_ready(m))
+                                                       .map(to_json)
                                                        .collect();
                     let len = alive_members.len();
                     for (i, alive_member) in alive_members.iter().enumerate() {
@@ -57,7 +61,7 @@ impl HelperDef for EachAliveHelper {
                 }
                 (true, &Json::Object(ref obj)) => {
                     let mut first: bool = true;
-                    if !obj.contains_key("alive") || !obj["alive"].as_bool().unwrap() {
+                    if !is_alive_and_ready(obj) {
                         return Ok(());
                     }
                     for k in obj.keys() {
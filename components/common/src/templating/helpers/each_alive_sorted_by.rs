@@ -0,0 +1,108 @@
+use super::{super::RenderResult,
+            each_alive::is_alive_and_ready,
+            render_each,
+            to_text,
+            value_at};
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+use std::cmp::Ordering;
+
+/// Orders values of a field: numbers by value, anything else by its text, and members without
+/// the field last.
+fn compare(a: Option<&Json>, b: Option<&Json>) -> Ordering {
+    match (a, b) {
+        (Some(Json::Number(a)), Some(Json::Number(b))) => {
+            a.as_f64()
+             .partial_cmp(&b.as_f64())
+             .unwrap_or(Ordering::Equal)
+        }
+        (Some(a), Some(b)) => to_text(a).cmp(&to_text(b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Like `eachAlive`, but renders members in the order of a field, given as a dotted path, e.g.
+/// `{{#eachAliveSortedBy bind.db.members "sys.hostname" as |member|}}`. This keeps rendered
+/// files stable as the census changes.
+#[derive(Clone, Copy)]
+pub struct EachAliveSortedByHelper;
+
+impl HelperDef for EachAliveSortedByHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let list =
+            h.param(0)
+             .map(|v| v.value())
+             .ok_or_else(|| RenderError::new("Param not found for helper \"eachAliveSortedBy\""))?;
+        let field =
+            h.param(1)
+             .and_then(|v| v.value().as_str())
+             .ok_or_else(|| RenderError::new("Expected a field for \"eachAliveSortedBy\""))?;
+
+        let mut members: Vec<Json> = match *list {
+            Json::Array(ref list) => {
+                list.iter()
+                    .filter(|m| m.as_object().map_or(false, is_alive_and_ready))
+                    .cloned()
+                    .collect()
+            }
+            Json::Null => Vec::new(),
+            _ => {
+                return Err(RenderError::new("Param for \"eachAliveSortedBy\" must be a list \
+                                             of members"));
+            }
+        };
+        members.sort_by(|a, b| compare(value_at(a, field), value_at(b, field)));
+        render_each(h, r, rc, &members)
+    }
+}
+
+pub static EACH_ALIVE_SORTED_BY: EachAliveSortedByHelper = EachAliveSortedByHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sorts_alive_members_by_text() {
+        let json = json!({
+            "members": [
+                {"alive": true, "sys": {"hostname": "db-c"}},
+                {"alive": false, "sys": {"hostname": "db-a"}},
+                {"alive": true, "sys": {"hostname": "db-b"}},
+                {"alive": true, "sys": {"hostname": "db-d"}}
+            ]
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("eachAliveSortedBy", Box::new(EACH_ALIVE_SORTED_BY));
+        let template = "{{#eachAliveSortedBy members \"sys.hostname\" as |m|}}{{m.sys.hostname}} \
+                        {{/eachAliveSortedBy}}";
+        assert_eq!("db-b db-c db-d ",
+                   handlebars.template_render(template, &json).unwrap());
+    }
+
+    #[test]
+    fn test_sorts_numbers_by_value_and_missing_fields_last() {
+        let json = json!({
+            "members": [
+                {"alive": true, "sys": {"hostname": "db-c"}, "cfg": {"weight": 10}},
+                {"alive": true, "sys": {"hostname": "db-b"}, "cfg": {"weight": 9}},
+                {"alive": true, "sys": {"hostname": "db-d"}}
+            ]
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("eachAliveSortedBy", Box::new(EACH_ALIVE_SORTED_BY));
+        let template = "{{#eachAliveSortedBy members \"cfg.weight\" as |m|}}{{m.sys.hostname}} \
+                        {{/eachAliveSortedBy}}";
+        assert_eq!("db-b db-c db-d ",
+                   handlebars.template_render(template, &json).unwrap());
+        let template = "{{#eachAliveSortedBy members \"cfg.weight\" as |m|}}{{#if @first}}\
+                        {{m.sys.hostname}}{{/if}}{{/eachAliveSortedBy}}";
+        assert_eq!("db-b", handlebars.template_render(template, &json).unwrap());
+    }
+}
@@ -0,0 +1,114 @@
+use super::{super::RenderResult,
+            each_alive::is_alive_and_ready,
+            render_each,
+            to_text,
+            value_at};
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+
+/// Like `eachAlive`, but only renders members whose field, given as a dotted path, has the given
+/// value, e.g. `{{#eachAliveWhere bind.db.members "cfg.role" "replica" as |member|}}`. Values are
+/// compared as text, so `"5432"` matches `5432`. If no member matches, the `{{else}}` block is
+/// rendered instead.
+#[derive(Clone, Copy)]
+pub struct EachAliveWhereHelper;
+
+impl HelperDef for EachAliveWhereHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let list =
+            h.param(0)
+             .map(|v| v.value())
+             .ok_or_else(|| RenderError::new("Param not found for helper \"eachAliveWhere\""))?;
+        let field =
+            h.param(1)
+             .and_then(|v| v.value().as_str())
+             .ok_or_else(|| RenderError::new("Expected a field for \"eachAliveWhere\""))?;
+        // A missing value matches nothing, as in `eachAliveWithTag`.
+        let value = h.param(2)
+                     .map(|v| v.value())
+                     .filter(|v| !v.is_null())
+                     .map(to_text);
+
+        let members: Vec<Json> = match *list {
+            Json::Array(ref list) => {
+                list.iter()
+                    .filter(|m| m.as_object().map_or(false, is_alive_and_ready))
+                    .filter(|m| {
+                        value.as_ref().map_or(false, |value| {
+                                          value_at(m, field).map(to_text).as_ref() == Some(value)
+                                      })
+                    })
+                    .cloned()
+                    .collect()
+            }
+            Json::Null => Vec::new(),
+            _ => {
+                return Err(RenderError::new("Param for \"eachAliveWhere\" must be a list of \
+                                             members"));
+            }
+        };
+        render_each(h, r, rc, &members)
+    }
+}
+
+pub static EACH_ALIVE_WHERE: EachAliveWhereHelper = EachAliveWhereHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_filters_alive_members_by_field() {
+        let json = json!({
+            "members": [
+                {"alive": true, "sys": {"hostname": "db-a"}, "cfg": {"role": "primary"}},
+                {"alive": true, "sys": {"hostname": "db-b"}, "cfg": {"role": "replica"}},
+                {"alive": false, "sys": {"hostname": "db-c"}, "cfg": {"role": "replica"}},
+                {"alive": true, "sys": {"hostname": "db-d"}, "cfg": {"role": "replica"}}
+            ]
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("eachAliveWhere", Box::new(EACH_ALIVE_WHERE));
+        let template = "{{#eachAliveWhere members \"cfg.role\" \"replica\" as |m|}}\
+                        {{m.sys.hostname}} {{/eachAliveWhere}}";
+        assert_eq!("db-b db-d ",
+                   handlebars.template_render(template, &json).unwrap());
+    }
+
+    #[test]
+    fn test_compares_values_as_text() {
+        let json = json!({
+            "members": [
+                {"alive": true, "sys": {"hostname": "db-b"}, "cfg": {"port": 5432}},
+                {"alive": false, "sys": {"hostname": "db-c"}, "cfg": {"port": 5433}},
+                {"alive": true, "sys": {"hostname": "db-d"}, "cfg": {"port": 5433}}
+            ]
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("eachAliveWhere", Box::new(EACH_ALIVE_WHERE));
+        let template = "{{#eachAliveWhere members \"cfg.port\" \"5433\" as |m|}}\
+                        {{m.sys.hostname}} {{/eachAliveWhere}}";
+        assert_eq!("db-d ", handlebars.template_render(template, &json).unwrap());
+    }
+
+    #[test]
+    fn test_renders_else_block_without_matches() {
+        let json = json!({
+            "members": [
+                {"alive": true, "sys": {"hostname": "db-a"}, "cfg": {"role": "primary"}}
+            ]
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("eachAliveWhere", Box::new(EACH_ALIVE_WHERE));
+        let template = "{{#eachAliveWhere members \"cfg.role\" \"witness\" as |m|}}\
+                        {{m.sys.hostname}}{{else}}none{{/eachAliveWhere}}";
+        assert_eq!("none", handlebars.template_render(template, &json).unwrap());
+        let template = "{{#eachAliveWhere members \"cfg.role\" missing as |m|}}\
+                        {{m.sys.hostname}}{{else}}none{{/eachAliveWhere}}";
+        assert_eq!("none", handlebars.template_render(template, &json).unwrap());
+    }
+}
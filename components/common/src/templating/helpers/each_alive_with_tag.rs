@@ -0,0 +1,94 @@
+use super::{super::RenderResult,
+            each_alive::is_alive_and_ready,
+            to_json};
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError,
+                 Renderable};
+use serde_json::{Map,
+                 Value as Json};
+use std::collections::BTreeMap;
+
+/// Whether a census member's Supervisor was started with `--tag key=value`.
+fn has_tag(member: &Map<String, Json>, key: &str, value: &str) -> bool {
+    member.get("tags")
+          .and_then(|tags| tags.get(key))
+          .and_then(Json::as_str)
+          .map_or(false, |v| v == value)
+}
+
+/// Like `eachAlive`, but only renders members carrying the given tag,
+/// e.g. `{{#eachAliveWithTag bind.db.members "zone" svc.me.tags.zone as |m|}}`.
+///
+/// If no member matches, the `{{else}}` block is rendered instead, so
+/// templates can prefer same-zone peers and fall back to the rest.
+#[derive(Clone, Copy)]
+pub struct EachAliveWithTagHelper;
+
+impl HelperDef for EachAliveWithTagHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let list =
+            h.param(0)
+             .map(|v| v.value())
+             .ok_or_else(|| RenderError::new("Param not found for helper \"eachAliveWithTag\""))?;
+        let key =
+            h.param(1)
+             .and_then(|v| v.value().as_str())
+             .ok_or_else(|| RenderError::new("Expected a tag name for \"eachAliveWithTag\""))?;
+        // A missing value (e.g. `svc.me.tags.zone` on an untagged
+        // Supervisor) matches nothing, rather than being an error.
+        let value = h.param(2).and_then(|v| v.value().as_str());
+
+        let members: Vec<Json> = match *list {
+            Json::Array(ref list) => {
+                list.iter()
+                    .filter_map(Json::as_object)
+                    .filter(|m| is_alive_and_ready(m))
+                    .filter(|m| value.map_or(false, |value| has_tag(m, key, value)))
+                    .map(to_json)
+                    .collect()
+            }
+            Json::Null => Vec::new(),
+            _ => {
+                return Err(RenderError::new("Param for \"eachAliveWithTag\" must be \
+                                             a list of members"));
+            }
+        };
+
+        if members.is_empty() {
+            if let Some(else_template) = h.inverse() {
+                else_template.render(r, rc)?;
+            }
+            return Ok(());
+        }
+
+        if let Some(template) = h.template() {
+            rc.promote_local_vars();
+            let len = members.len();
+            for (i, member) in members.iter().enumerate() {
+                let mut local_rc = rc.derive();
+                local_rc.set_local_var("@first".to_string(), to_json(&(i == 0usize)));
+                local_rc.set_local_var("@last".to_string(), to_json(&(i == len - 1)));
+                local_rc.set_local_var("@index".to_string(), to_json(&i));
+
+                if let Some(block_param) = h.block_param() {
+                    let mut map = BTreeMap::new();
+                    map.insert(block_param.to_string(), to_json(member));
+                    local_rc.push_block_context(&map)?;
+                }
+
+                template.render(r, &mut local_rc)?;
+
+                if h.block_param().is_some() {
+                    local_rc.pop_block_context();
+                }
+            }
+            rc.demote_local_vars();
+        }
+        Ok(())
+    }
+}
+
+pub static EACH_ALIVE_WITH_TAG: EachAliveWithTagHelper = EachAliveWithTagHelper;
@@ -0,0 +1,216 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+use std::fmt;
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Number {
+    Integer(i64),
+    Float(f64),
+}
+
+impl Number {
+    fn from_json(value: &Json) -> Option<Self> {
+        match *value {
+            Json::Number(ref n) => {
+                n.as_i64()
+                 .map(Number::Integer)
+                 .or_else(|| n.as_f64().map(Number::Float))
+            }
+            // Subexpressions, e.g. `(add cfg.port 1)`, are rendered to strings
+            Json::String(ref s) => {
+                let s = s.trim();
+                s.parse()
+                 .map(Number::Integer)
+                 .ok()
+                 .or_else(|| s.parse().map(Number::Float).ok())
+            }
+            _ => None,
+        }
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Integer(i) => i as f64,
+            Number::Float(f) => f,
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Number::Integer(i) => write!(f, "{}", i),
+            Number::Float(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum MathOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+/// Arithmetic on two numbers, e.g. `{{add cfg.port 1}}`. Integers stay integers, unless a
+/// division has a remainder.
+#[derive(Clone, Copy)]
+pub struct MathHelper(MathOp);
+
+impl MathHelper {
+    fn name(self) -> &'static str {
+        match self.0 {
+            MathOp::Add => "add",
+            MathOp::Subtract => "subtract",
+            MathOp::Multiply => "multiply",
+            MathOp::Divide => "divide",
+            MathOp::Modulo => "modulo",
+        }
+    }
+
+    fn apply(self, a: Number, b: Number) -> Result<Number, String> {
+        let divisor_is_zero = b.as_f64() == 0.0;
+        let result = match (self.0, a, b) {
+            (MathOp::Divide, ..) | (MathOp::Modulo, ..) if divisor_is_zero => {
+                return Err("division by zero".to_string());
+            }
+            (MathOp::Add, Number::Integer(a), Number::Integer(b)) => {
+                a.checked_add(b).map(Number::Integer)
+            }
+            (MathOp::Subtract, Number::Integer(a), Number::Integer(b)) => {
+                a.checked_sub(b).map(Number::Integer)
+            }
+            (MathOp::Multiply, Number::Integer(a), Number::Integer(b)) => {
+                a.checked_mul(b).map(Number::Integer)
+            }
+            (MathOp::Divide, Number::Integer(a), Number::Integer(b))
+                if a.checked_rem(b) == Some(0) =>
+            {
+                a.checked_div(b).map(Number::Integer)
+            }
+            (MathOp::Modulo, Number::Integer(a), Number::Integer(b)) => {
+                a.checked_rem(b).map(Number::Integer)
+            }
+            (op, a, b) => {
+                let (a, b) = (a.as_f64(), b.as_f64());
+                Some(Number::Float(match op {
+                                       MathOp::Add => a + b,
+                                       MathOp::Subtract => a - b,
+                                       MathOp::Multiply => a * b,
+                                       MathOp::Divide => a / b,
+                                       MathOp::Modulo => a % b,
+                                   }))
+            }
+        };
+        result.ok_or_else(|| "integer overflow".to_string())
+    }
+}
+
+impl HelperDef for MathHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let operand = |i| {
+            h.param(i)
+             .and_then(|v| Number::from_json(v.value()))
+             .ok_or_else(|| {
+                 RenderError::new(format!("Expected 2 number parameters for \"{}\"", self.name()))
+             })
+        };
+        let (a, b) = (operand(0)?, operand(1)?);
+        let result = self.apply(a, b).map_err(|e| {
+                                         RenderError::new(format!("Can't {} {} and {}: {}",
+                                                                  self.name(),
+                                                                  a,
+                                                                  b,
+                                                                  e))
+                                     })?;
+        rc.writer.write_all(result.to_string().into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static ADD: MathHelper = MathHelper(MathOp::Add);
+pub static SUBTRACT: MathHelper = MathHelper(MathOp::Subtract);
+pub static MULTIPLY: MathHelper = MathHelper(MathOp::Multiply);
+pub static DIVIDE: MathHelper = MathHelper(MathOp::Divide);
+pub static MODULO: MathHelper = MathHelper(MathOp::Modulo);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_integer_arithmetic() {
+        let json = json!({"port": 8080});
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("add", Box::new(ADD));
+        handlebars.register_helper("subtract", Box::new(SUBTRACT));
+        handlebars.register_helper("multiply", Box::new(MULTIPLY));
+        handlebars.register_helper("divide", Box::new(DIVIDE));
+        handlebars.register_helper("modulo", Box::new(MODULO));
+        assert_eq!("8081",
+                   handlebars.template_render("{{add port 1}}", &json)
+                             .unwrap());
+        assert_eq!("8000",
+                   handlebars.template_render("{{subtract port 80}}", &json)
+                             .unwrap());
+        assert_eq!("12",
+                   handlebars.template_render("{{multiply 3 4}}", &json)
+                             .unwrap());
+        assert_eq!("3",
+                   handlebars.template_render("{{divide 12 4}}", &json)
+                             .unwrap());
+        assert_eq!("1",
+                   handlebars.template_render("{{modulo 7 3}}", &json)
+                             .unwrap());
+    }
+
+    #[test]
+    fn test_float_arithmetic() {
+        let json = json!({"ratio": 0.5});
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("multiply", Box::new(MULTIPLY));
+        handlebars.register_helper("divide", Box::new(DIVIDE));
+        assert_eq!("2.5",
+                   handlebars.template_render("{{divide 5 2}}", &json)
+                             .unwrap());
+        assert_eq!("1.5",
+                   handlebars.template_render("{{multiply ratio 3}}", &json)
+                             .unwrap());
+    }
+
+    #[test]
+    fn test_numeric_strings_are_numbers() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("add", Box::new(ADD));
+        assert_eq!("5",
+                   handlebars.template_render("{{add \"2\" 3}}", &json!({}))
+                             .unwrap());
+    }
+
+    #[test]
+    fn test_math_helper_errors() {
+        let json = json!({});
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("add", Box::new(ADD));
+        handlebars.register_helper("divide", Box::new(DIVIDE));
+        handlebars.register_helper("modulo", Box::new(MODULO));
+        assert!(handlebars.template_render("{{divide 1 0}}", &json)
+                          .is_err());
+        assert!(handlebars.template_render("{{modulo 1 0}}", &json)
+                          .is_err());
+        assert!(handlebars.template_render("{{add 9223372036854775807 1}}", &json)
+                          .is_err());
+        assert!(handlebars.template_render("{{add \"one\" 1}}", &json)
+                          .is_err());
+        assert!(handlebars.template_render("{{add 1}}", &json)
+                          .is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use super::super::RenderResult;
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use std::{fs,
+          path::{Component,
+                 Path,
+                 PathBuf}};
+
+/// Writes the content of a file shipped in the package, given relative to the package's
+/// installed path, e.g. `{{readFile "share/mime.types"}}`. Files outside of the package can't be
+/// read.
+#[derive(Clone, Copy)]
+pub struct ReadFileHelper;
+
+impl HelperDef for ReadFileHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let file = h.param(0)
+                    .and_then(|v| v.value().as_str())
+                    .ok_or_else(|| RenderError::new("Expected a file for \"readFile\""))?;
+        let file = Path::new(file);
+        if file.components().any(|c| {
+                                match c {
+                                    Component::Normal(_) => false,
+                                    _ => true,
+                                }
+                            })
+        {
+            return Err(RenderError::new(format!("Can't read {} with \"readFile\": the path \
+                                                 must be relative to the package and within it",
+                                                file.display())));
+        }
+        let pkg_path = rc.context()
+                         .data()
+                         .get("pkg")
+                         .and_then(|pkg| pkg.get("path"))
+                         .and_then(|path| path.as_str())
+                         .map(PathBuf::from)
+                         .ok_or_else(|| RenderError::new("No package path for \"readFile\""))?;
+        let path = pkg_path.join(file);
+        let content = fs::read_to_string(&path).map_err(|e| {
+                                                   RenderError::new(format!("Can't read {}: {}",
+                                                                            path.display(),
+                                                                            e))
+                                               })?;
+        rc.writer.write_all(content.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static READ_FILE: ReadFileHelper = ReadFileHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reads_files_in_the_package() {
+        let dir = TempDir::new().expect("Could not create tempdir");
+        fs::create_dir(dir.path().join("share")).unwrap();
+        fs::write(dir.path().join("share").join("motd"), "hello").unwrap();
+        let json = json!({"pkg": {"path": dir.path()}});
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("readFile", Box::new(READ_FILE));
+        assert_eq!("hello",
+                   handlebars.template_render("{{readFile \"share/motd\"}}", &json)
+                             .unwrap());
+    }
+
+    #[test]
+    fn test_rejects_files_outside_of_the_package() {
+        let dir = TempDir::new().expect("Could not create tempdir");
+        let json = json!({"pkg": {"path": dir.path()}});
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("readFile", Box::new(READ_FILE));
+        assert!(handlebars.template_render("{{readFile \"../secret\"}}", &json)
+                          .is_err());
+        assert!(handlebars.template_render("{{readFile \"/etc/passwd\"}}", &json)
+                          .is_err());
+        assert!(handlebars.template_render("{{readFile \"share/missing\"}}", &json)
+                          .is_err());
+    }
+}
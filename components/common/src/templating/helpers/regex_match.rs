@@ -0,0 +1,75 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use regex::Regex;
+
+use super::{super::RenderResult,
+            render_condition,
+            to_text};
+
+/// Whether a value matches a regular expression. As a block helper, renders its block if it
+/// does, and its `{{else}}` block if it doesn't, e.g.
+/// `{{#regexMatch pkg.version "^1[.]"}}...{{else}}...{{/regexMatch}}`. Otherwise writes `true`
+/// or `false`.
+#[derive(Clone, Copy)]
+pub struct RegexMatchHelper;
+
+impl HelperDef for RegexMatchHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let value = h.param(0)
+                     .map(|v| to_text(v.value()))
+                     .ok_or_else(|| RenderError::new("Expected 2 parameters for \"regexMatch\""))?;
+        let pattern =
+            h.param(1)
+             .and_then(|v| v.value().as_str())
+             .ok_or_else(|| RenderError::new("Expected 2 parameters for \"regexMatch\""))?;
+        let regex = Regex::new(pattern).map_err(|e| {
+                                           RenderError::new(format!("Invalid regular expression \
+                                                                     for \"regexMatch\": {}",
+                                                                    e))
+                                       })?;
+        render_condition(h, r, rc, regex.is_match(&value))
+    }
+}
+
+pub static REGEX_MATCH: RegexMatchHelper = RegexMatchHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_regex_match_helper() {
+        let json = json!({"version": "1.15.2"});
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("regexMatch", Box::new(REGEX_MATCH));
+        assert_eq!("true",
+                   handlebars.template_render("{{regexMatch version \"^1[.]\"}}", &json)
+                             .unwrap());
+        assert_eq!("false",
+                   handlebars.template_render("{{regexMatch version \"^2[.]\"}}", &json)
+                             .unwrap());
+    }
+
+    #[test]
+    fn test_regex_match_block_helper() {
+        let json = json!({"version": "1.15.2"});
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("regexMatch", Box::new(REGEX_MATCH));
+        let template = "{{#regexMatch version \"^1[.]1[0-9]\"}}new{{else}}old{{/regexMatch}}";
+        assert_eq!("new", handlebars.template_render(template, &json).unwrap());
+        let template = "{{#regexMatch version \"^0[.]\"}}new{{else}}old{{/regexMatch}}";
+        assert_eq!("old", handlebars.template_render(template, &json).unwrap());
+    }
+
+    #[test]
+    fn test_regex_match_helper_errors_on_invalid_regex() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("regexMatch", Box::new(REGEX_MATCH));
+        assert!(handlebars.template_render("{{regexMatch version \"(\"}}",
+                                           &json!({"version": "1.15.2"}))
+                          .is_err());
+    }
+}
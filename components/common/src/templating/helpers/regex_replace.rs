@@ -0,0 +1,63 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use regex::Regex;
+
+use super::{super::RenderResult,
+            to_text};
+
+/// Replaces every match of a regular expression in a value. The replacement can refer to
+/// capture groups, e.g. `{{regexReplace sys.hostname "^([^.]+)[.].*$" "$1"}}`.
+#[derive(Clone, Copy)]
+pub struct RegexReplaceHelper;
+
+impl HelperDef for RegexReplaceHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let value =
+            h.param(0)
+             .map(|v| to_text(v.value()))
+             .ok_or_else(|| RenderError::new("Expected 3 parameters for \"regexReplace\""))?;
+        let pattern =
+            h.param(1)
+             .and_then(|v| v.value().as_str())
+             .ok_or_else(|| RenderError::new("Expected 3 parameters for \"regexReplace\""))?;
+        let replacement =
+            h.param(2)
+             .map(|v| to_text(v.value()))
+             .ok_or_else(|| RenderError::new("Expected 3 parameters for \"regexReplace\""))?;
+        let regex = Regex::new(pattern).map_err(|e| {
+                                           RenderError::new(format!("Invalid regular expression \
+                                                                     for \"regexReplace\": {}",
+                                                                    e))
+                                       })?;
+        rc.writer.write_all(regex.replace_all(&value, replacement.as_str())
+                                 .into_owned()
+                                 .into_bytes()
+                                 .as_ref())?;
+        Ok(())
+    }
+}
+
+pub static REGEX_REPLACE: RegexReplaceHelper = RegexReplaceHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_regex_replace_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("regexReplace", Box::new(REGEX_REPLACE));
+        let json = json!({"hostname": "db-1.prod.example.com"});
+        assert_eq!("db-1",
+                   handlebars.template_render("{{regexReplace hostname \"^([^.]+)[.].*$\" \
+                                               \"$1\"}}",
+                                              &json)
+                             .unwrap());
+        assert_eq!("db_1_prod_example_com",
+                   handlebars.template_render("{{regexReplace hostname \"[.-]\" \"_\"}}", &json)
+                             .unwrap());
+    }
+}
@@ -0,0 +1,44 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use sha2::{Digest,
+           Sha256};
+
+use super::{super::RenderResult,
+            to_text};
+
+/// Writes the SHA-256 digest of a value as hex, e.g. to restart a process when a file it reads
+/// changes: `# {{sha256 cfg.tls.cert}}`.
+#[derive(Clone, Copy)]
+pub struct Sha256Helper;
+
+impl HelperDef for Sha256Helper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param = h.param(0)
+                     .map(|v| to_text(v.value()))
+                     .ok_or_else(|| RenderError::new("Expected 1 parameter for \"sha256\""))?;
+        let digest: String = Sha256::digest(param.as_bytes()).iter()
+                                                              .map(|b| format!("{:02x}", b))
+                                                              .collect();
+        rc.writer.write_all(digest.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static SHA256: Sha256Helper = Sha256Helper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sha256_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("sha256", Box::new(SHA256));
+        assert_eq!("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+                   handlebars.template_render("{{sha256 \"hello\"}}", &json!({}))
+                             .unwrap());
+    }
+}
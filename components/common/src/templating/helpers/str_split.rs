@@ -0,0 +1,94 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+
+use super::{super::RenderResult,
+            render_each,
+            to_text};
+
+/// Splits a value on a separator. Given an index, writes that part, e.g.
+/// `{{strSplit cfg.listen ":" 1}}`. As a block helper, renders its block for each part, like
+/// `eachAlive`: `{{#strSplit cfg.peers "," as |peer|}}server {{peer}}{{/strSplit}}`.
+#[derive(Clone, Copy)]
+pub struct StrSplitHelper;
+
+impl HelperDef for StrSplitHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let value = h.param(0)
+                     .map(|v| to_text(v.value()))
+                     .ok_or_else(|| RenderError::new("Expected 2 parameters for \"strSplit\""))?;
+        let separator =
+            h.param(1)
+             .and_then(|v| v.value().as_str())
+             .ok_or_else(|| RenderError::new("Expected 2 parameters for \"strSplit\""))?;
+        let parts: Vec<&str> = if value.is_empty() {
+            Vec::new()
+        } else {
+            value.split(separator).collect()
+        };
+
+        if h.template().is_some() {
+            let parts: Vec<Json> = parts.into_iter().map(Json::from).collect();
+            return render_each(h, r, rc, &parts);
+        }
+
+        let index =
+            h.param(2)
+             .and_then(|v| v.value().as_u64())
+             .ok_or_else(|| RenderError::new("Expected an index for \"strSplit\", or a block"))?;
+        if let Some(part) = parts.get(index as usize) {
+            rc.writer.write_all(part.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+pub static STR_SPLIT: StrSplitHelper = StrSplitHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_helper_with_index() {
+        let json = json!({
+            "listen": "0.0.0.0:8080"
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("strSplit", Box::new(STR_SPLIT));
+        assert_eq!("8080",
+                   handlebars.template_render("{{strSplit listen \":\" 1}}", &json)
+                             .unwrap());
+        assert_eq!("",
+                   handlebars.template_render("{{strSplit listen \":\" 2}}", &json)
+                             .unwrap());
+    }
+
+    #[test]
+    fn test_split_block_helper() {
+        let json = json!({
+            "peers": "a.example,b.example,c.example",
+            "empty": ""
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("strSplit", Box::new(STR_SPLIT));
+        let template = "{{#strSplit peers \",\" as |peer|}}{{peer}}{{#unless @last}};{{/unless}}\
+                        {{/strSplit}}";
+        assert_eq!("a.example;b.example;c.example",
+                   handlebars.template_render(template, &json).unwrap());
+        let template = "{{#strSplit empty \",\" as |peer|}}{{peer}}{{else}}none{{/strSplit}}";
+        assert_eq!("none", handlebars.template_render(template, &json).unwrap());
+    }
+
+    #[test]
+    fn test_split_helper_errors_without_index_or_block() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("strSplit", Box::new(STR_SPLIT));
+        assert!(handlebars.template_render("{{strSplit listen \":\"}}",
+                                           &json!({"listen": "0.0.0.0:8080"}))
+                          .is_err());
+    }
+}
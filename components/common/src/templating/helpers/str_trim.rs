@@ -0,0 +1,53 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::{super::RenderResult,
+            to_text};
+
+/// Trims whitespace from both ends of a value, or, given a second parameter, any of its
+/// characters, e.g. `{{strTrim cfg.path "/"}}`.
+#[derive(Clone, Copy)]
+pub struct StrTrimHelper;
+
+impl HelperDef for StrTrimHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let value = h.param(0)
+                     .map(|v| to_text(v.value()))
+                     .ok_or_else(|| RenderError::new("Expected a parameter for \"strTrim\""))?;
+        let trimmed = match h.param(1).and_then(|v| v.value().as_str()) {
+            Some(chars) => value.trim_matches(|c: char| chars.contains(c)),
+            None => value.trim(),
+        };
+        rc.writer.write_all(trimmed.as_bytes())?;
+        Ok(())
+    }
+}
+
+pub static STR_TRIM: StrTrimHelper = StrTrimHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_trim_helper() {
+        let json = json!({
+            "name": "  redis \n",
+            "path": "/var/lib/"
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("strTrim", Box::new(STR_TRIM));
+        assert_eq!("redis",
+                   handlebars.template_render("{{strTrim name}}", &json)
+                             .unwrap());
+        assert_eq!("var/lib",
+                   handlebars.template_render("{{strTrim path \"/\"}}", &json)
+                             .unwrap());
+        assert_eq!("",
+                   handlebars.template_render("{{strTrim missing}}", &json)
+                             .unwrap());
+    }
+}
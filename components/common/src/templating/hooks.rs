@@ -144,6 +144,15 @@ pub trait Hook: fmt::Debug + Sized + Send {
         }
     }
 
+    /// Whether the hook's content differs between the `current` and `pending` contexts.
+    fn changed_by<T>(&self, current: &T, pending: &T) -> Result<bool>
+        where T: Serialize
+    {
+        let current = self.renderer().render(Self::file_name(), current)?;
+        let pending = self.renderer().render(Self::file_name(), pending)?;
+        Ok(current != pending)
+    }
+
     #[cfg(not(windows))]
     fn set_permissions<T: AsRef<Path>>(path: T) -> habitat_core::error::Result<()> {
         use habitat_core::util::posix_perm;
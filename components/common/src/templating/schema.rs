@@ -0,0 +1,204 @@
+//! Typed configuration schemas for packages.
+//!
+//! A package can ship a [JSON Schema](https://json-schema.org) next to its `default.toml`, in
+//! `default.schema.json`, describing the types, allowed values, ranges and required keys of its
+//! configuration, including nested tables. The configuration the layers merge into is checked
+//! against it when a service is loaded, when its `user.toml` is reloaded, when configuration is
+//! applied to its service group and when a template is rendered with `hab plan render`.
+
+use crate::error::{Error,
+                   Result};
+use serde_json::{self,
+                 Value as Json};
+use std::{fs,
+          io,
+          path::Path,
+          result};
+use toml;
+use valico::json_schema;
+
+/// The name of the schema file, in the same directory as `default.toml`.
+pub const CONFIG_SCHEMA_FILE: &str = "default.schema.json";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigSchema(Json);
+
+impl ConfigSchema {
+    /// Load the schema from `dir`, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// * Returns `Error::ConfigSchema` if the schema can't be read, isn't JSON, or isn't a schema
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Option<Self>> {
+        let path = dir.as_ref().join(CONFIG_SCHEMA_FILE);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::ConfigSchema(path, err.to_string())),
+        };
+        Self::parse(&content).map(Some)
+                             .map_err(|err| Error::ConfigSchema(path, err))
+    }
+
+    /// Parse a schema, checking that it compiles.
+    pub fn parse(content: &str) -> result::Result<Self, String> {
+        let schema: Json = serde_json::from_str(content).map_err(|err| err.to_string())?;
+        let mut scope = json_schema::scope::Scope::new();
+        // NOTE: using `false` instead of `true` allows the `$comment` keyword, and keys of our
+        // own, in schemas.
+        scope.compile_and_return(schema.clone(), false)
+             .map_err(|err| format!("{:?}", err))?;
+        Ok(ConfigSchema(schema))
+    }
+
+    /// Checks `cfg` against the schema.
+    ///
+    /// Returns `None` if valid and `Some` containing a list of errors, each starting with the
+    /// path of the offending key, if invalid.
+    pub fn validate(&self, cfg: &toml::value::Table) -> Option<Vec<String>> {
+        match serde_json::to_value(cfg) {
+            Ok(cfg) => self.validate_json(&cfg),
+            Err(err) => Some(vec![format!("Unable to check configuration, {}", err)]),
+        }
+    }
+
+    /// Checks configuration that has already been converted to JSON against the schema.
+    pub fn validate_json(&self, cfg: &Json) -> Option<Vec<String>> {
+        let mut scope = json_schema::scope::Scope::new();
+        let schema = scope.compile_and_return(self.0.clone(), false)
+                          .expect("Schema was compiled when it was parsed");
+        let state = schema.validate(cfg);
+        if state.is_valid() {
+            return None;
+        }
+        let errors = state.errors
+                          .iter()
+                          .map(|err| {
+                              format!("{}: {}",
+                                      key_path(err.get_path()),
+                                      err.get_detail().unwrap_or_else(|| err.get_title()))
+                          })
+                          .collect();
+        Some(errors)
+    }
+}
+
+/// Turn the JSON pointer of an error into the dotted key path of the configuration, e.g.
+/// `/upstream/servers/0` into `upstream.servers[0]`.
+fn key_path(pointer: &str) -> String {
+    let mut path = String::new();
+    for segment in pointer.split('/').skip(1) {
+        let segment = segment.replace("~1", "/").replace("~0", "~");
+        if segment.parse::<usize>().is_ok() {
+            path.push_str(&format!("[{}]", segment));
+        } else {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(&segment);
+        }
+    }
+    if path.is_empty() {
+        "(top level)".to_string()
+    } else {
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SCHEMA: &str = r#"{
+        "type": "object",
+        "required": ["port"],
+        "properties": {
+            "port": { "type": "integer", "minimum": 1, "maximum": 65535 },
+            "log_level": { "enum": ["debug", "info", "warn"] },
+            "upstream": {
+                "type": "object",
+                "properties": {
+                    "servers": { "type": "array", "items": { "type": "string" } }
+                }
+            }
+        }
+    }"#;
+
+    fn toml_from_str(content: &str) -> toml::value::Table {
+        toml::from_str(content).unwrap_or_else(|_| panic!("Content should parse as TOML: {}",
+                                                          content))
+    }
+
+    fn errors_for(cfg: &str) -> Vec<String> {
+        ConfigSchema::parse(SCHEMA).unwrap()
+                                   .validate(&toml_from_str(cfg))
+                                   .unwrap_or_default()
+    }
+
+    #[test]
+    fn valid_config_has_no_errors() {
+        assert!(errors_for("port = 80\nlog_level = \"info\"\n[upstream]\nservers = [\"a\"]\n")
+                .is_empty());
+    }
+
+    #[test]
+    fn wrong_type_is_an_error_at_the_key() {
+        let errors = errors_for("port = \"eighty\"");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("port: "), "{:?}", errors);
+    }
+
+    #[test]
+    fn value_out_of_range_is_an_error() {
+        let errors = errors_for("port = 70000");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("port: "), "{:?}", errors);
+    }
+
+    #[test]
+    fn value_not_in_enum_is_an_error() {
+        let errors = errors_for("port = 80\nlog_level = \"loud\"");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("log_level: "), "{:?}", errors);
+    }
+
+    #[test]
+    fn missing_required_key_is_an_error() {
+        let errors = errors_for("log_level = \"info\"");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("port: "), "{:?}", errors);
+    }
+
+    #[test]
+    fn nested_errors_have_the_full_key_path() {
+        let errors = errors_for("port = 80\n[upstream]\nservers = [\"a\", 2]\n");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("upstream.servers[1]: "), "{:?}", errors);
+    }
+
+    #[test]
+    fn load_without_a_schema_is_none() {
+        let tmp = TempDir::new().expect("create temp dir");
+        assert_eq!(ConfigSchema::load(tmp.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn load_reads_the_schema() {
+        let tmp = TempDir::new().expect("create temp dir");
+        fs::write(tmp.path().join(CONFIG_SCHEMA_FILE), SCHEMA).unwrap();
+        assert_eq!(ConfigSchema::load(tmp.path()).unwrap(),
+                   Some(ConfigSchema::parse(SCHEMA).unwrap()));
+    }
+
+    #[test]
+    fn load_rejects_a_schema_that_is_not_json() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let path = tmp.path().join(CONFIG_SCHEMA_FILE);
+        fs::write(&path, "port = 80").unwrap();
+        match ConfigSchema::load(tmp.path()) {
+            Err(Error::ConfigSchema(p, _)) => assert_eq!(p, path),
+            other => panic!("Expected a schema error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,338 @@
+//! Secret references in service configuration.
+//!
+//! Rather than holding a secret, a configuration value can refer to one by name:
+//!
+//! ```toml
+//! [db]
+//! password = { secret = "db/password" }
+//! ```
+//!
+//! References are only resolved when templates are rendered, by asking each `SecretProvider` in
+//! turn for the named secret. Everywhere else, such as the HTTP gateway, exported binds and
+//! schema checks, the configuration only holds the reference, which is shown by name (see
+//! `redact`), so the value of a secret never leaves the rendered files of the service it was
+//! meant for.
+
+use crate::{error::{Error,
+                    Result},
+            hcore::crypto::{keys::{box_key_pair::WrappedSealedBox,
+                                   parse_name_with_rev},
+                            BoxKeyPair}};
+use std::{env,
+          fs,
+          io,
+          path::{Path,
+                 PathBuf}};
+use toml;
+
+/// The key of a table that refers to a secret.
+pub const SECRET_KEY: &str = "secret";
+/// The prefix of the environment variables `EnvSecretProvider` reads secrets from.
+pub const SECRET_ENV_VAR_PREFIX: &str = "HAB_SECRET_";
+
+/// A source of secrets.
+pub trait SecretProvider {
+    /// Returns the value of the secret called `name`, or `None` if this provider doesn't have it.
+    fn secret(&self, name: &str) -> Result<Option<String>>;
+}
+
+/// Reads secrets from the environment of the Supervisor. The secret `db/password` is read from
+/// `HAB_SECRET_DB_PASSWORD`.
+#[derive(Debug, Default)]
+pub struct EnvSecretProvider;
+
+impl EnvSecretProvider {
+    pub fn var_name(name: &str) -> String {
+        let name: String = name.chars()
+                               .map(|c| {
+                                   if c.is_ascii_alphanumeric() {
+                                       c.to_ascii_uppercase()
+                                   } else {
+                                       '_'
+                                   }
+                               })
+                               .collect();
+        format!("{}{}", SECRET_ENV_VAR_PREFIX, name)
+    }
+}
+
+impl SecretProvider for EnvSecretProvider {
+    fn secret(&self, name: &str) -> Result<Option<String>> {
+        Ok(env::var(Self::var_name(name)).ok())
+    }
+}
+
+/// Reads secrets from a directory of files encrypted for a service key, such as those written by
+/// `hab svc secret set`. The secret `db/password` is read from `db/password` in the directory.
+#[derive(Debug)]
+pub struct FileSecretProvider {
+    dir:            PathBuf,
+    service_key:    String,
+    cache_key_path: PathBuf,
+}
+
+impl FileSecretProvider {
+    /// `service_key` is the name of the service key, e.g. `redis.default@acme`, that secrets
+    /// must have been encrypted for.
+    pub fn new<P, Q>(dir: P, service_key: &str, cache_key_path: Q) -> Self
+        where P: Into<PathBuf>,
+              Q: Into<PathBuf>
+    {
+        FileSecretProvider { dir:            dir.into(),
+                             service_key:    service_key.to_string(),
+                             cache_key_path: cache_key_path.into(), }
+    }
+
+    /// The path of the file holding the secret called `name`.
+    pub fn path(dir: &Path, name: &str) -> Result<PathBuf> {
+        if !is_valid_name(name) {
+            return Err(Error::InvalidSecret(name.to_string(),
+                                            "names are made of letters, digits, '_', '-' and \
+                                             '.', separated by '/'"
+                                                                   .to_string()));
+        }
+        Ok(name.split('/').fold(dir.to_path_buf(), |path, part| path.join(part)))
+    }
+
+    fn decrypt(&self, name: &str, content: &[u8]) -> Result<String> {
+        let invalid = |e: String| Error::InvalidSecret(name.to_string(), e);
+        let payload = WrappedSealedBox::from_bytes(content).map_err(|e| invalid(e.to_string()))?;
+        let receiver = BoxKeyPair::secret_metadata(&payload).map_err(|e| invalid(e.to_string()))?
+                                                            .receiver
+                                                            .map(parse_name_with_rev);
+        match receiver {
+            Some(Ok((ref key, _))) if *key == self.service_key => {}
+            _ => return Err(invalid(format!("not encrypted for {}", self.service_key))),
+        }
+        let bytes = BoxKeyPair::decrypt_with_path(&payload, &self.cache_key_path)
+            .map_err(|e| invalid(e.to_string()))?;
+        String::from_utf8(bytes).map_err(|e| invalid(e.to_string()))
+    }
+}
+
+impl SecretProvider for FileSecretProvider {
+    fn secret(&self, name: &str) -> Result<Option<String>> {
+        let path = Self::path(&self.dir, name)?;
+        match fs::read(&path) {
+            Ok(content) => self.decrypt(name, &content).map(Some),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::InvalidSecret(name.to_string(), e.to_string())),
+        }
+    }
+}
+
+/// Asks each provider in turn, returning the first secret found.
+#[derive(Default)]
+pub struct SecretProviders(Vec<Box<dyn SecretProvider>>);
+
+impl SecretProviders {
+    pub fn push<T>(&mut self, provider: T)
+        where T: SecretProvider + 'static
+    {
+        self.0.push(Box::new(provider));
+    }
+}
+
+impl SecretProvider for SecretProviders {
+    fn secret(&self, name: &str) -> Result<Option<String>> {
+        for provider in self.0.iter() {
+            if let Some(secret) = provider.secret(name)? {
+                return Ok(Some(secret));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Returns the name of the secret `value` refers to, if it is a reference.
+pub fn secret_ref(value: &toml::Value) -> Option<&str> {
+    match value.as_table() {
+        Some(table) if table.len() == 1 => table.get(SECRET_KEY).and_then(toml::Value::as_str),
+        _ => None,
+    }
+}
+
+/// How a reference to the secret called `name` is shown wherever it isn't resolved.
+pub fn redacted(name: &str) -> String { format!("<secret {}>", name) }
+
+/// Returns `table` with every secret reference replaced by the value of its secret.
+///
+/// # Errors
+///
+/// * Returns `Error::SecretNotFound` if none of the providers have a secret that is referred to
+pub fn resolve(table: &toml::value::Table,
+               provider: &dyn SecretProvider)
+               -> Result<toml::value::Table> {
+    let mut resolved = toml::value::Table::new();
+    for (key, value) in table.iter() {
+        resolved.insert(key.clone(), resolve_value(value, provider)?);
+    }
+    Ok(resolved)
+}
+
+/// Returns `table` with every secret reference replaced by `redacted`.
+pub fn redact(table: &toml::value::Table) -> toml::value::Table {
+    table.iter()
+         .map(|(key, value)| (key.clone(), redact_value(value)))
+         .collect()
+}
+
+fn resolve_value(value: &toml::Value, provider: &dyn SecretProvider) -> Result<toml::Value> {
+    if let Some(name) = secret_ref(value) {
+        return match provider.secret(name)? {
+            Some(secret) => Ok(toml::Value::String(secret)),
+            None => Err(Error::SecretNotFound(name.to_string())),
+        };
+    }
+    match value {
+        toml::Value::Table(table) => resolve(table, provider).map(toml::Value::Table),
+        toml::Value::Array(array) => {
+            array.iter()
+                 .map(|v| resolve_value(v, provider))
+                 .collect::<Result<_>>()
+                 .map(toml::Value::Array)
+        }
+        _ => Ok(value.clone()),
+    }
+}
+
+fn redact_value(value: &toml::Value) -> toml::Value {
+    if let Some(name) = secret_ref(value) {
+        return toml::Value::String(redacted(name));
+    }
+    match value {
+        toml::Value::Table(table) => toml::Value::Table(redact(table)),
+        toml::Value::Array(array) => toml::Value::Array(array.iter().map(redact_value).collect()),
+        _ => value.clone(),
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    name.split('/').all(|part| {
+                       !part.is_empty()
+                       && part != "."
+                       && part != ".."
+                       && part.chars().all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c))
+                   })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    struct MapSecretProvider(HashMap<&'static str, &'static str>);
+
+    impl SecretProvider for MapSecretProvider {
+        fn secret(&self, name: &str) -> Result<Option<String>> {
+            Ok(self.0.get(name).map(ToString::to_string))
+        }
+    }
+
+    fn provider() -> MapSecretProvider {
+        let mut secrets = HashMap::new();
+        secrets.insert("db/password", "hunter2");
+        MapSecretProvider(secrets)
+    }
+
+    fn toml_from_str(content: &str) -> toml::value::Table {
+        toml::from_str(content).unwrap_or_else(|_| panic!("Content should parse as TOML: {}",
+                                                          content))
+    }
+
+    #[test]
+    fn only_a_table_with_a_single_secret_key_is_a_reference() {
+        let cfg = toml_from_str("a = { secret = \"db/password\" }\nb = { secret = \"x\", c = 1 \
+                                 }\nc = { secret = 1 }\nd = \"secret\"\n");
+        assert_eq!(secret_ref(&cfg["a"]), Some("db/password"));
+        assert_eq!(secret_ref(&cfg["b"]), None);
+        assert_eq!(secret_ref(&cfg["c"]), None);
+        assert_eq!(secret_ref(&cfg["d"]), None);
+    }
+
+    #[test]
+    fn resolve_replaces_nested_references() {
+        let cfg = toml_from_str("port = 5432\n[db]\npassword = { secret = \"db/password\" \
+                                 }\nreplicas = [{ password = { secret = \"db/password\" } }]\n");
+        let resolved = resolve(&cfg, &provider()).unwrap();
+        assert_eq!(resolved, toml_from_str("port = 5432\n[db]\npassword = \"hunter2\"\nreplicas \
+                                            = [{ password = \"hunter2\" }]\n"));
+    }
+
+    #[test]
+    fn resolve_fails_when_a_secret_is_missing() {
+        let cfg = toml_from_str("password = { secret = \"db/other\" }");
+        match resolve(&cfg, &provider()) {
+            Err(Error::SecretNotFound(name)) => assert_eq!(name, "db/other"),
+            other => panic!("Expected a missing secret, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn redact_replaces_references_with_their_name() {
+        let cfg = toml_from_str("[db]\npassword = { secret = \"db/password\" }\n");
+        assert_eq!(redact(&cfg),
+                   toml_from_str("[db]\npassword = \"<secret db/password>\"\n"));
+    }
+
+    #[test]
+    fn env_var_names_are_upper_case_with_underscores() {
+        assert_eq!(EnvSecretProvider::var_name("db/password-1.2"),
+                   "HAB_SECRET_DB_PASSWORD_1_2");
+    }
+
+    #[test]
+    fn env_provider_reads_the_environment() {
+        env::set_var("HAB_SECRET_ENV_PROVIDER_TEST", "shh");
+        assert_eq!(EnvSecretProvider.secret("env/provider-test").unwrap(),
+                   Some("shh".to_string()));
+        assert_eq!(EnvSecretProvider.secret("env/provider-missing").unwrap(), None);
+    }
+
+    #[test]
+    fn names_cannot_leave_the_directory() {
+        let dir = Path::new("/secrets");
+        assert_eq!(FileSecretProvider::path(dir, "db/password").unwrap(),
+                   dir.join("db").join("password"));
+        for name in &["", "/db", "db//password", "../password", "db/./password", "db password"] {
+            assert!(FileSecretProvider::path(dir, name).is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn file_provider_decrypts_secrets_for_the_service_key() {
+        let cache = TempDir::new().expect("create temp dir");
+        let secrets = TempDir::new().expect("create temp dir");
+        let user = BoxKeyPair::generate_pair_for_user("wecoyote").unwrap();
+        let service = BoxKeyPair::generate_pair_for_service("acme", "tnt.default").unwrap();
+        let other = BoxKeyPair::generate_pair_for_service("acme", "other.default").unwrap();
+        for pair in &[&user, &service, &other] {
+            pair.to_pair_files(cache.path()).unwrap();
+        }
+        fs::create_dir(secrets.path().join("db")).unwrap();
+        fs::write(secrets.path().join("db").join("password"),
+                  user.encrypt(b"hunter2", Some(&service)).unwrap().as_bytes()).unwrap();
+        fs::write(secrets.path().join("stolen"),
+                  user.encrypt(b"hunter2", Some(&other)).unwrap().as_bytes()).unwrap();
+
+        let provider = FileSecretProvider::new(secrets.path(), "tnt.default@acme", cache.path());
+        assert_eq!(provider.secret("db/password").unwrap(),
+                   Some("hunter2".to_string()));
+        assert_eq!(provider.secret("db/missing").unwrap(), None);
+        assert!(provider.secret("stolen").is_err());
+    }
+
+    #[test]
+    fn providers_are_asked_in_order() {
+        let mut providers = SecretProviders::default();
+        providers.push(EnvSecretProvider);
+        providers.push(provider());
+        env::set_var("HAB_SECRET_PROVIDERS_TEST", "from env");
+        assert_eq!(providers.secret("providers/test").unwrap(),
+                   Some("from env".to_string()));
+        assert_eq!(providers.secret("db/password").unwrap(),
+                   Some("hunter2".to_string()));
+        assert_eq!(providers.secret("nowhere").unwrap(), None);
+    }
+}
@@ -200,6 +200,124 @@ impl Into<Option<Duration>> for EventStreamConnectMethod {
     }
 }
 
+/// The backend to which the Supervisor publishes its events.
+///
+/// The meaning of the event stream URL depends on the sink: a NATS
+/// Streaming server address, the path of a JSON-lines file, the path
+/// of a Unix domain socket, or the URL of an HTTP webhook.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventStreamSink {
+    Nats,
+    File,
+    UnixSocket,
+    Webhook,
+}
+
+impl EventStreamSink {
+    /// The name of the Clap argument.
+    pub const ARG_NAME: &'static str = "EVENT_STREAM_SINK";
+    /// The environment variable to set this value.
+    pub const ENVVAR: &'static str = "HAB_EVENT_STREAM_SINK";
+    /// The values accepted on the command line.
+    pub const VALUES: &'static [&'static str] = &["nats", "file", "unix", "webhook"];
+}
+
+impl Default for EventStreamSink {
+    fn default() -> Self { EventStreamSink::Nats }
+}
+
+impl FromStr for EventStreamSink {
+    type Err = Error;
+
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
+        match s {
+            "nats" => Ok(EventStreamSink::Nats),
+            "file" => Ok(EventStreamSink::File),
+            "unix" => Ok(EventStreamSink::UnixSocket),
+            "webhook" => Ok(EventStreamSink::Webhook),
+            _ => Err(Error::InvalidEventStreamSink(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for EventStreamSink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            EventStreamSink::Nats => "nats",
+            EventStreamSink::File => "file",
+            EventStreamSink::UnixSocket => "unix",
+            EventStreamSink::Webhook => "webhook",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl<'a> From<&'a ArgMatches<'a>> for EventStreamSink {
+    /// Create an instance of `EventStreamSink` from validated user
+    /// input. If no sink was given, events go to NATS.
+    fn from(m: &ArgMatches) -> Self {
+        m.value_of(Self::ARG_NAME)
+         .map(|value| {
+             value.parse()
+                  .expect("EVENT_STREAM_SINK should be validated")
+         })
+         .unwrap_or_default()
+    }
+}
+
+/// Bounds on the on-disk spool that holds events until they have been
+/// delivered to the event sink.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EventStreamSpoolLimits {
+    /// The most space the spool may take up; the oldest events are
+    /// discarded to make room for new ones.
+    pub max_bytes: u64,
+    /// How long an event may wait in the spool before it is discarded
+    /// rather than delivered.
+    pub max_age: Duration,
+}
+
+impl EventStreamSpoolLimits {
+    const DEFAULT_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+    const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+    /// The name of the Clap argument for the maximum age, in seconds.
+    pub const MAX_AGE_ARG_NAME: &'static str = "EVENT_STREAM_SPOOL_MAX_AGE";
+    /// The environment variable to set the maximum age.
+    pub const MAX_AGE_ENVVAR: &'static str = "HAB_EVENT_STREAM_SPOOL_MAX_AGE";
+    /// The name of the Clap argument for the maximum size.
+    pub const MAX_BYTES_ARG_NAME: &'static str = "EVENT_STREAM_SPOOL_MAX_BYTES";
+    /// The environment variable to set the maximum size.
+    pub const MAX_BYTES_ENVVAR: &'static str = "HAB_EVENT_STREAM_SPOOL_MAX_BYTES";
+}
+
+impl Default for EventStreamSpoolLimits {
+    fn default() -> Self {
+        EventStreamSpoolLimits { max_bytes: Self::DEFAULT_MAX_BYTES,
+                                 max_age:   Duration::from_secs(Self::DEFAULT_MAX_AGE_SECS), }
+    }
+}
+
+impl<'a> From<&'a ArgMatches<'a>> for EventStreamSpoolLimits {
+    /// Create an instance of `EventStreamSpoolLimits` from validated
+    /// user input, using the defaults for anything not given.
+    fn from(m: &ArgMatches) -> Self {
+        let default = Self::default();
+        let max_bytes = m.value_of(Self::MAX_BYTES_ARG_NAME)
+                         .map(|value| {
+                             value.parse()
+                                  .expect("EVENT_STREAM_SPOOL_MAX_BYTES should be validated")
+                         })
+                         .unwrap_or(default.max_bytes);
+        let max_age = m.value_of(Self::MAX_AGE_ARG_NAME)
+                       .map(|value| {
+                           Duration::from_secs(value.parse().expect("EVENT_STREAM_SPOOL_MAX_AGE \
+                                                                     should be validated"))
+                       })
+                       .unwrap_or(default.max_age);
+        EventStreamSpoolLimits { max_bytes, max_age }
+    }
+}
+
 #[derive(Clone)]
 pub struct EventStreamServerCertificate(Certificate);
 
@@ -347,6 +465,21 @@ mod tests {
         fn cannot_parse_from_empty_string() { assert!("".parse::<AutomateAuthToken>().is_err()) }
     }
 
+    mod event_stream_sink {
+        use super::*;
+
+        #[test]
+        fn round_trips_through_strings() {
+            for value in EventStreamSink::VALUES {
+                let sink = value.parse::<EventStreamSink>().unwrap();
+                assert_eq!(sink.to_string(), *value);
+            }
+        }
+
+        #[test]
+        fn rejects_unknown_sinks() { assert!("kafka".parse::<EventStreamSink>().is_err()) }
+    }
+
     mod gossip_listen_addr {
         use super::*;
         #[test]
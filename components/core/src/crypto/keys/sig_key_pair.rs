@@ -34,6 +34,30 @@ impl SigKeyPair {
         Ok(Self::new(name.to_string(), revision, Some(pk), Some(sk)))
     }
 
+    /// Generate a key pair that identifies a Supervisor to the other
+    /// members of its gossip ring.
+    pub fn generate_pair_for_member(member_id: &str) -> Result<Self> {
+        Self::generate_pair_for_origin(member_id)
+    }
+
+    /// Sign `data`, returning the signature on its own.
+    pub fn sign_detached(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(sign::sign_detached(data, self.secret()?).0.to_vec())
+    }
+
+    /// Check that `signature` is a signature of `data` by this key pair.
+    pub fn verify_detached(&self, data: &[u8], signature: &[u8]) -> Result<()> {
+        let signature = sign::Signature::from_slice(signature).ok_or_else(|| {
+                            Error::CryptoError(format!("Malformed signature for {}",
+                                                       self.name_with_rev()))
+                        })?;
+        if sign::verify_detached(&signature, data, self.public()?) {
+            Ok(())
+        } else {
+            Err(Error::CryptoError(format!("Signature does not match {}", self.name_with_rev())))
+        }
+    }
+
     /// Return a Vec of origin keys with a given name.
     /// The newest key is listed first in the Vec.
     pub fn get_pairs_for<P: AsRef<Path> + ?Sized>(name: &str,
@@ -366,6 +390,18 @@ mod test {
                      .exists());
     }
 
+    #[test]
+    fn detached_signatures_verify_only_with_the_signing_pair() {
+        let pair = SigKeyPair::generate_pair_for_member("wolverine").unwrap();
+        let other = SigKeyPair::generate_pair_for_member("cyclops").unwrap();
+        let signature = pair.sign_detached(b"hello").unwrap();
+
+        assert!(pair.verify_detached(b"hello", &signature).is_ok());
+        assert!(pair.verify_detached(b"goodbye", &signature).is_err());
+        assert!(other.verify_detached(b"hello", &signature).is_err());
+        assert!(pair.verify_detached(b"hello", b"garbage").is_err());
+    }
+
     #[test]
     fn get_pairs_for() {
         let cache = Builder::new().prefix("key_cache").tempdir().unwrap();
@@ -239,6 +239,11 @@ pub fn svc_files_path<T: AsRef<Path>>(service_name: T) -> PathBuf {
     svc_path(service_name).join("files")
 }
 
+/// Returns the path to a given service's encrypted secrets.
+pub fn svc_secrets_path<T: AsRef<Path>>(service_name: T) -> PathBuf {
+    svc_path(service_name).join("secrets")
+}
+
 /// Returns the path to a given service's hooks.
 pub fn svc_hooks_path<T: AsRef<Path>>(service_name: T) -> PathBuf {
     svc_path(service_name).join("hooks")
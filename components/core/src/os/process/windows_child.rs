@@ -41,7 +41,8 @@ use winapi::{shared::{minwindef::{BOOL,
                                  ERROR_INVALID_PARAMETER,
                                  ERROR_IO_PENDING,
                                  ERROR_LOGON_TYPE_NOT_GRANTED,
-                                 ERROR_PRIVILEGE_NOT_HELD}},
+                                 ERROR_PRIVILEGE_NOT_HELD,
+                                 WAIT_TIMEOUT}},
              um::{fileapi::{self,
                             CREATE_ALWAYS,
                             CREATE_NEW,
@@ -300,6 +301,25 @@ impl Child {
             Ok(ExitStatus(status))
         }
     }
+
+    /// The exit status of the child if it has exited, without waiting for it.
+    pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+        unsafe {
+            match synchapi::WaitForSingleObject(self.handle.raw(), 0) {
+                WAIT_OBJECT_0 => {}
+                WAIT_TIMEOUT => return Ok(None),
+                _ => {
+                    return Err(Error::WaitForSingleObjectFailed(format!(
+                        "Failed calling WaitForSingleObjectFailed: {}",
+                        io::Error::last_os_error()
+                    )));
+                }
+            }
+            let mut status = 0;
+            cvt(processthreadsapi::GetExitCodeProcess(self.handle.raw(), &mut status))?;
+            Ok(Some(ExitStatus(status)))
+        }
+    }
 }
 
 pub trait AsInner<Inner: ?Sized> {
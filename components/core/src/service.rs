@@ -31,6 +31,11 @@ pub enum BindingMode {
     /// applications that cannot cope with the absence of a service
     /// dependency at startup should bind with this mode.
     Strict,
+    /// Like `Strict`, but a service also waits until every service it
+    /// binds to has at least one member that has passed a health
+    /// check. This orders the startup of services that depend on one
+    /// another, without resorting to polling in an `init` hook.
+    Ordered,
 }
 
 impl Default for BindingMode {
@@ -50,6 +55,7 @@ impl fmt::Display for BindingMode {
         let value = match *self {
             BindingMode::Relaxed => "relaxed",
             BindingMode::Strict => "strict",
+            BindingMode::Ordered => "ordered",
         };
         write!(f, "{}", value)
     }
@@ -62,6 +68,7 @@ impl FromStr for BindingMode {
         match value.to_lowercase().as_ref() {
             "relaxed" => Ok(BindingMode::Relaxed),
             "strict" => Ok(BindingMode::Strict),
+            "ordered" => Ok(BindingMode::Ordered),
             _ => Err(Error::BadBindingMode(value.to_string())),
         }
     }
@@ -723,6 +730,19 @@ mod test {
                    HealthCheckInterval::from(30));
     }
 
+    #[test]
+    fn binding_mode_round_trips_through_strings() {
+        for mode in &[BindingMode::Relaxed,
+                      BindingMode::Strict,
+                      BindingMode::Ordered]
+        {
+            assert_eq!(BindingMode::from_str(&mode.to_string()).unwrap(), *mode);
+        }
+        assert_eq!(BindingMode::from_str("ordered").unwrap(),
+                   BindingMode::Ordered);
+        assert!(BindingMode::from_str("eventually").is_err());
+    }
+
     #[test]
     fn health_check_interval_must_be_positive() {
         assert!(HealthCheckInterval::from_str("-123").is_err());
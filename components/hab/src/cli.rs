@@ -14,6 +14,7 @@ use habitat_common::{cli::{file_into_idents,
                              EventStreamConnectMethod,
                              EventStreamMetadata,
                              EventStreamServerCertificate,
+                             EventStreamSink,
                              GossipListenAddr,
                              HttpListenAddr,
                              ListenCtlAddr},
@@ -80,11 +81,24 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
             (aliases: &["co", "con", "conf", "confi"])
             (@setting ArgRequiredElseHelp)
             (subcommand: sub_config_apply().aliases(&["ap", "app", "appl"]))
+            (@subcommand history =>
+                (about: "Lists the versions of a Service Group's configuration a Supervisor \
+                    running one of its services has applied")
+                (aliases: &["hi", "his", "hist", "histo", "histor"])
+                (@arg SERVICE_GROUP: +required {valid_service_group}
+                    "Target service group service.group[@organization] (ex: redis.default or foo.default@bazcorp)")
+                (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+                    "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+            )
+            (subcommand: sub_config_rollback().aliases(&["ro", "rol", "roll", "rollb"]))
             (@subcommand show =>
                 (about: "Displays the default configuration options for a service")
                 (aliases: &["sh", "sho"])
                 (@arg PKG_IDENT: +required +takes_value {valid_ident}
                     "A package identifier (ex: core/redis, core/busybox-static/1.42.2)")
+                (@arg EXPLAIN: --explain
+                    "Show each key of the effective configuration of the running service, the \
+                    layer its value came from and the values it overrides")
                 (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
                     "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
             )
@@ -733,6 +747,64 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
                         Default value is hab/cache/keys if root and .hab/cache/keys under the home \
                         directory otherwise."))
                 )
+                (@subcommand accept =>
+                    (about: "Makes a running Supervisor accept gossip encrypted with an \
+                    additional ring key from its key cache")
+                    (@arg RING: +required +takes_value "Ring key name, for its latest revision, \
+                        or ring key name with revision")
+                    (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+                        "Address to a remote Supervisor's Control Gateway \
+                        [default: 127.0.0.1:9632]")
+                )
+                (@subcommand promote =>
+                    (about: "Makes a running Supervisor encrypt gossip with an accepted ring key")
+                    (@arg RING: +required +takes_value "Ring key name with revision")
+                    (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+                        "Address to a remote Supervisor's Control Gateway \
+                        [default: 127.0.0.1:9632]")
+                )
+                (@subcommand retire =>
+                    (about: "Makes a running Supervisor stop accepting gossip encrypted with a \
+                    ring key")
+                    (@arg RING: +required +takes_value "Ring key name with revision")
+                    (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+                        "Address to a remote Supervisor's Control Gateway \
+                        [default: 127.0.0.1:9632]")
+                )
+                (@subcommand list =>
+                    (about: "Lists the ring keys a running Supervisor gossips with")
+                    (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+                        "Address to a remote Supervisor's Control Gateway \
+                        [default: 127.0.0.1:9632]")
+                )
+            )
+            (@subcommand status =>
+                (about: "Shows the members of a ring and their health, as seen by one or more \
+                running Supervisors")
+                (aliases: &["s", "st", "sta", "stat", "statu"])
+                (@arg REMOTE_SUP: --("remote-sup") -r +takes_value +multiple
+                    "Address to a remote Supervisor's Control Gateway; give several to compare \
+                    their views of the ring [default: 127.0.0.1:9632]")
+            )
+            (@subcommand rumors =>
+                (about: "Lists the rumors held by one or more running Supervisors")
+                (@arg REMOTE_SUP: --("remote-sup") -r +takes_value +multiple
+                    "Address to a remote Supervisor's Control Gateway; give several to compare \
+                    the rumors they hold [default: 127.0.0.1:9632]")
+            )
+            (@subcommand rumor =>
+                (about: "Commands relating to the rumors of a ring")
+                (@setting ArgRequiredElseHelp)
+                (@subcommand purge =>
+                    (about: "Drops every rumor of a retired service group, ring-wide. Rumors of \
+                    the service group are turned away until the purge expires with the rumor TTL")
+                    (aliases: &["p", "pu", "pur", "purg"])
+                    (@arg SERVICE_GROUP: +required +takes_value {valid_service_group}
+                        "Target service group service.group[@organization] (ex: redis.default or foo.default@bazcorp)")
+                    (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+                        "Address to a remote Supervisor's Control Gateway \
+                        [default: 127.0.0.1:9632]")
+                )
             )
         )
         (subcommand: sup_commands(feature_flags))
@@ -755,6 +827,26 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
                         directory otherwise."))
                 )
             )
+            (@subcommand secret =>
+                (about: "Commands relating to the secrets of Habitat services")
+                (aliases: &["sec", "secr", "secre"])
+                (@setting ArgRequiredElseHelp)
+                (@subcommand set =>
+                    (about: "Encrypts a secret for a service group's key, for its services on \
+                        this machine to refer to in their configuration")
+                    (@arg SERVICE_GROUP: +required +takes_value {valid_service_group}
+                        "Target service group service.group@organization (ex: foo.default@bazcorp)")
+                    (@arg NAME: +required +takes_value
+                        "The name configuration refers to the secret by (ex: db/password)")
+                    (@arg FILE: {file_exists_or_stdin}
+                        "Path to local file on disk holding the secret (ex: /tmp/password, \
+                        default: <stdin>)")
+                    (@arg USER: -u --user +takes_value "Name of a user key to use for encryption")
+                    (arg: arg_cache_key_path("Path to search for encryption keys. \
+                        Default value is hab/cache/keys if root and .hab/cache/keys under the home \
+                        directory otherwise."))
+                )
+            )
             (subcommand: sub_svc_load().aliases(&["l", "lo", "loa"]))
             (subcommand: sub_svc_start().aliases(&["star"]))
             (subcommand: sub_svc_status().aliases(&["stat", "statu"]))
@@ -995,6 +1087,25 @@ fn sub_config_apply() -> App<'static, 'static> {
     (@arg FILE: {file_exists_or_stdin}
         "Path to local file on disk (ex: /tmp/config.toml, default: <stdin>)")
     (@arg USER: -u --user +takes_value "Name of a user key to use for encryption")
+    (@arg DRY_RUN: --("dry-run")
+        "Show the configuration keys, templates and hooks of the service the configuration \
+        would change, without applying it")
+    (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+        "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+    (arg: arg_cache_key_path("Path to search for encryption keys. \
+        Default value is hab/cache/keys if root and .hab/cache/keys under the home \
+        directory otherwise."))
+    )
+}
+
+fn sub_config_rollback() -> App<'static, 'static> {
+    clap_app!(@subcommand rollback =>
+    (about: "Applies an earlier version of a Service Group's configuration again, as a new version")
+    (@arg SERVICE_GROUP: +required {valid_service_group}
+        "Target service group service.group[@organization] (ex: redis.default or foo.default@bazcorp)")
+    (@arg INCARNATION: +required {valid_numeric::<u64>}
+        "The version number of the configuration to apply again, as listed by 'hab config history'")
+    (@arg USER: -u --user +takes_value "Name of a user key to use for encryption")
     (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
         "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
     (arg: arg_cache_key_path("Path to search for encryption keys. \
@@ -1075,6 +1186,37 @@ pub fn sub_sup_run(feature_flags: FeatureFlag) -> App<'static, 'static> {
              foo-20181113185935 \
 
                   GCrBOW6CCN75LMl0j2V5QqQ6nNzWm6and9hkKBSUFPI=')")
+                            (@arg MEMBER_KEYS: --("member-keys") +takes_value {dir_exists}
+                             "Sign gossip with this Supervisor's member key, and only admit members whose \
+                              member keys are in this directory. The member key is generated in the \
+                              directory if it doesn't exist.")
+                            (@arg RUMOR_TTL: --("rumor-ttl") +takes_value {valid_numeric::<u64>}
+                             "Drop the rumors of members that have been confirmed dead or departed for \
+                              longer than this many seconds, and forget rumor purges older than this. \
+                              [default: 86400]")
+                            (@arg GOSSIP_PING_MS: --("gossip-ping-ms") +takes_value {valid_numeric::<i64>}
+                             "How long to wait for an ack after pinging a member, in milliseconds. \
+                              [default: 1000]")
+                            (@arg GOSSIP_PINGREQ_MS: --("gossip-pingreq-ms") +takes_value {valid_numeric::<i64>}
+                             "How long to wait for an ack after asking other members to ping a member \
+                              that didn't answer, in milliseconds. Must be at least twice the ping \
+                              timeout. [default: 2100]")
+                            (@arg GOSSIP_PINGREQ_TARGETS: --("gossip-pingreq-targets") +takes_value {valid_numeric::<usize>}
+                             "How many members to ask to ping a member that didn't answer. [default: 5]")
+                            (@arg GOSSIP_SUSPICION_PERIODS: --("gossip-suspicion-periods") +takes_value {valid_numeric::<i64>}
+                             "How many protocol periods a suspect member has to refute before it is \
+                              confirmed dead. [default: 3]")
+                            (@arg GOSSIP_PERIOD_MS: --("gossip-period-ms") +takes_value {valid_numeric::<i64>}
+                             "How often to send hot rumors, in milliseconds. [default: 1000]")
+                            (@arg GOSSIP_FANOUT: --("gossip-fanout") +takes_value {valid_numeric::<usize>}
+                             "How many members to send hot rumors to at a time. [default: 5]")
+                            (@arg GOSSIP_DEPARTURE_TIMEOUT_MS: --("gossip-departure-timeout-ms") +takes_value {valid_numeric::<i64>}
+                             "How long a member stays confirmed dead before it is marked departed, in \
+                              milliseconds. [default: 259200000]")
+                            (@arg TAG: --tag +takes_value +multiple number_of_values(1) {valid_tag}
+                             "Attach a KEY=VALUE tag to this Supervisor. Tags are gossiped \
+                              with the member and available to templates \
+                              (ex: --tag zone=us-east-1a --tag rack=12)")
                             (@arg CHANNEL: --channel +takes_value default_value[stable]
                              "Receive Supervisor updates from the specified release channel")
                             (@arg BLDR_URL: -u --url +takes_value {valid_url}
@@ -1110,12 +1252,14 @@ pub fn sub_sup_run(feature_flags: FeatureFlag) -> App<'static, 'static> {
                             (@arg TOPOLOGY: --topology -t +takes_value possible_value[standalone leader]
                              "Service topology; [default: none]")
                             (@arg STRATEGY: --strategy -s +takes_value {valid_update_strategy}
-                             "The update strategy; [default: none] [values: none, at-once, rolling]")
+                             "The update strategy; [default: none] [values: none, at-once, rolling, batched]")
                             (@arg BIND: --bind +takes_value +multiple
                              "One or more service groups to bind to a configuration")
                             (@arg BINDING_MODE: --("binding-mode") +takes_value {valid_binding_mode}
                              "Governs how the presence or absence of binds affects service startup. `strict` blocks \
-                              startup until all binds are present. [default: strict] [values: relaxed, strict]")
+                              startup until all binds are present. `ordered` also waits until each bound \
+                              service has passed a health check. [default: strict] \
+                              [values: relaxed, strict, ordered]")
                             (@arg VERBOSE: -v "Verbose output; shows file and line/column numbers")
                             (@arg NO_COLOR: --("no-color") "Turn ANSI color off")
                             (@arg JSON: --("json-logging") "Use structured JSON logging for the Supervisor. \
@@ -1134,7 +1278,11 @@ pub fn sub_sup_run(feature_flags: FeatureFlag) -> App<'static, 'static> {
     } else {
         sub
     };
-    add_shutdown_timeout_option(sub)
+    add_election_options(add_update_batch_options(add_rollback_window_option(
+        add_readiness_options(add_health_check_probe_options(add_restart_policy_options(
+            add_shutdown_timeout_option(sub),
+        ))),
+    )))
 }
 
 pub fn sub_sup_sh() -> App<'static, 'static> {
@@ -1184,6 +1332,67 @@ pub fn parse_optional_arg<T: FromStr>(name: &str, m: &ArgMatches) -> Option<T>
     m.value_of(name).map(|s| s.parse().expect("Valid argument"))
 }
 
+/// The restart policy given by the options added by
+/// `add_restart_policy_options`, or `None` if none of them were given.
+pub fn parse_restart_policy(m: &ArgMatches) -> Option<habitat_sup_protocol::types::RestartPolicy> {
+    use habitat_sup_protocol::types::{RestartCondition,
+                                      RestartPolicy};
+
+    let condition = parse_optional_arg::<RestartCondition>("RESTART_POLICY", m);
+    let policy = RestartPolicy { condition:      condition.map(|c| c as i32),
+                                 max_retries:    parse_optional_arg("RESTART_MAX_RETRIES", m),
+                                 window:         parse_optional_arg("RESTART_WINDOW", m),
+                                 backoff:        parse_optional_arg("RESTART_BACKOFF", m),
+                                 backoff_max:    parse_optional_arg("RESTART_BACKOFF_MAX", m),
+                                 backoff_jitter: parse_optional_arg("RESTART_JITTER", m), };
+    if policy == RestartPolicy::default() {
+        None
+    } else {
+        Some(policy)
+    }
+}
+
+/// The health check probe given by the options added by
+/// `add_health_check_probe_options`, or `None` if none was given.
+pub fn parse_health_check_probe(m: &ArgMatches)
+                                -> Option<habitat_sup_protocol::types::HealthCheckProbe> {
+    parse_probe("HEALTH_CHECK", m)
+}
+
+/// The readiness probe given by the options added by
+/// `add_readiness_options`, or `None` if none was given.
+pub fn parse_readiness_probe(m: &ArgMatches)
+                             -> Option<habitat_sup_protocol::types::HealthCheckProbe> {
+    parse_probe("READINESS_CHECK", m)
+}
+
+fn parse_probe(prefix: &str,
+               m: &ArgMatches)
+               -> Option<habitat_sup_protocol::types::HealthCheckProbe> {
+    use habitat_sup_protocol::types::{HealthCheckProbe,
+                                      HealthCheckProbeKind};
+
+    let arg = |suffix: &str| format!("{}_{}", prefix, suffix);
+    let (kind, target) = if let Some(url) = m.value_of(arg("HTTP")) {
+        (HealthCheckProbeKind::Http, Some(url))
+    } else if let Some(address) = m.value_of(arg("TCP")) {
+        (HealthCheckProbeKind::Tcp, Some(address))
+    } else if m.is_present(arg("EXEC")) {
+        (HealthCheckProbeKind::Exec, None)
+    } else {
+        return None;
+    };
+    let command = m.value_of(arg("EXEC"))
+                   .map(|c| c.split_whitespace().map(str::to_string).collect())
+                   .unwrap_or_default();
+    Some(HealthCheckProbe { kind: Some(kind as i32),
+                            target: target.map(str::to_string),
+                            expected_status: parse_optional_arg(&arg("STATUS"), m),
+                            expected_body: m.value_of(arg("BODY")).map(str::to_string),
+                            command,
+                            timeout: parse_optional_arg(&arg("TIMEOUT"), m) })
+}
+
 fn sub_svc_stop() -> App<'static, 'static> {
     let sub = clap_app!(@subcommand stop =>
         (about: "Stop a running Habitat service.")
@@ -1217,12 +1426,14 @@ fn sub_svc_load() -> App<'static, 'static> {
         (@arg TOPOLOGY: --topology -t +takes_value possible_value[standalone leader]
             "Service topology; [default: none]")
         (@arg STRATEGY: --strategy -s +takes_value {valid_update_strategy}
-            "The update strategy; [default: none] [values: none, at-once, rolling]")
+            "The update strategy; [default: none] [values: none, at-once, rolling, batched]")
         (@arg BIND: --bind +takes_value +multiple
             "One or more service groups to bind to a configuration")
         (@arg BINDING_MODE: --("binding-mode") +takes_value {valid_binding_mode}
              "Governs how the presence or absence of binds affects service startup. `strict` blocks \
-              startup until all binds are present. [default: strict] [values: relaxed, strict]")
+              startup until all binds are present. `ordered` also waits until each bound \
+              service has passed a health check. [default: strict] \
+              [values: relaxed, strict, ordered]")
         (@arg FORCE: --force -f "Load or reload an already loaded service. If the service \
             was previously loaded and running this operation will also restart the service")
         (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
@@ -1237,7 +1448,11 @@ fn sub_svc_load() -> App<'static, 'static> {
                                                 .help("Password of the service user"));
     }
 
-    add_shutdown_timeout_option(sub)
+    add_election_options(add_update_batch_options(add_rollback_window_option(
+        add_readiness_options(add_health_check_probe_options(add_restart_policy_options(
+            add_shutdown_timeout_option(sub),
+        ))),
+    )))
 }
 
 fn sub_svc_unload() -> App<'static, 'static> {
@@ -1295,11 +1510,49 @@ fn add_event_stream_options(app: App<'static, 'static>) -> App<'static, 'static>
                                                    .validator(valid_numeric::<u64>))
        .arg(Arg::with_name("EVENT_STREAM_URL").help("The event stream connection string \
                                                      (host:port) used by this Supervisor to send \
-                                                     events to a messaging server.")
+                                                     events to a messaging server. For the \
+                                                     `file` and `unix` sinks this is a path, \
+                                                     and for the `webhook` sink an HTTP(S) URL.")
                                               .long("event-stream-url")
                                               .required(true)
                                               .takes_value(true)
                                               .validator(non_empty))
+       .arg(Arg::with_name(EventStreamSink::ARG_NAME).help("Where this Supervisor sends its \
+                                                            events: a NATS Streaming server, \
+                                                            a JSON-lines file, a Unix domain \
+                                                            socket, or an HTTP webhook. \
+                                                            [default: nats]")
+                                                     .long("event-stream-sink")
+                                                     .required(false)
+                                                     .takes_value(true)
+                                                     .env(EventStreamSink::ENVVAR)
+                                                     .possible_values(EventStreamSink::VALUES))
+       .arg(Arg::with_name(EventStreamSpoolLimits::MAX_BYTES_ARG_NAME).help("The maximum \
+                                                                             size in bytes of \
+                                                                             the on-disk spool \
+                                                                             of events waiting \
+                                                                             to be delivered. \
+                                                                             The oldest events \
+                                                                             are discarded \
+                                                                             first. [default: \
+                                                                             67108864]")
+                                              .long("event-stream-spool-max-bytes")
+                                              .required(false)
+                                              .takes_value(true)
+                                              .env(EventStreamSpoolLimits::MAX_BYTES_ENVVAR)
+                                              .validator(valid_numeric::<u64>))
+       .arg(Arg::with_name(EventStreamSpoolLimits::MAX_AGE_ARG_NAME).help("How long in \
+                                                                           seconds an event may \
+                                                                           wait in the on-disk \
+                                                                           spool before it is \
+                                                                           discarded rather than \
+                                                                           delivered. [default: \
+                                                                           86400]")
+                                              .long("event-stream-spool-max-age")
+                                              .required(false)
+                                              .takes_value(true)
+                                              .env(EventStreamSpoolLimits::MAX_AGE_ENVVAR)
+                                              .validator(valid_numeric::<u64>))
        .arg(Arg::with_name("EVENT_STREAM_SITE").help("The name of the site where this Supervisor \
                                                       is running. It is used for event stream \
                                                       purposes.")
@@ -1310,7 +1563,9 @@ fn add_event_stream_options(app: App<'static, 'static>) -> App<'static, 'static>
                                                               streaming events to an messaging \
                                                               server.")
                                                        .long("event-stream-token")
-                                                       .required(true)
+                                                       .required_unless(EventStreamSink::ARG_NAME)
+                                                       .required_if(EventStreamSink::ARG_NAME,
+                                                                    "nats")
                                                        .takes_value(true)
                                                        .validator(AutomateAuthToken::validate)
                                                        .env(AutomateAuthToken::ENVVAR))
@@ -1359,6 +1614,18 @@ fn valid_service_group(val: String) -> result::Result<(), String> {
     ServiceGroup::validate(&val).map_err(|e| e.to_string())
 }
 
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_tag(val: String) -> result::Result<(), String> {
+    match val.find('=') {
+        Some(idx) if idx > 0 => Ok(()),
+        _ => {
+            Err(format!("Tag: '{}' is not valid, must be of the form \
+                         KEY=VALUE",
+                        val))
+        }
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
 fn dir_exists(val: String) -> result::Result<(), String> {
     if Path::new(&val).is_dir() {
@@ -1443,6 +1710,26 @@ fn valid_update_strategy(val: String) -> result::Result<(), String> {
     }
 }
 
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_update_batch_size(val: String) -> result::Result<(), String> {
+    match habitat_sup_protocol::types::UpdateBatchSize::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(_) => {
+            Err(format!("Update batch size: '{}' is not a positive count \
+                         or a percentage",
+                        &val))
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_restart_condition(val: String) -> result::Result<(), String> {
+    match habitat_sup_protocol::types::RestartCondition::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("Restart policy: '{}' is not valid", &val)),
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
 fn valid_ident(val: String) -> result::Result<(), String> {
     match PackageIdent::from_str(&val) {
@@ -1532,6 +1819,171 @@ fn add_shutdown_timeout_option(app: App<'static, 'static>) -> App<'static, 'stat
                                               .takes_value(true))
 }
 
+/// Adds configuration options for when, and how quickly, a service's process is restarted
+/// after it exits.
+fn add_restart_policy_options(app: App<'static, 'static>) -> App<'static, 'static> {
+    app.arg(Arg::with_name("RESTART_POLICY").help("When to restart the service's process after \
+                                                   it exits [default: always] [values: always, \
+                                                   on-failure, never]")
+                                            .long("restart-policy")
+                                            .validator(valid_restart_condition)
+                                            .takes_value(true))
+       .arg(Arg::with_name("RESTART_MAX_RETRIES").help("The number of restarts allowed within \
+                                                        the restart window before the service is \
+                                                        considered to be crash-looping; 0 allows \
+                                                        unlimited restarts [default: 0]")
+                                                 .long("restart-max-retries")
+                                                 .validator(valid_numeric::<u32>)
+                                                 .takes_value(true))
+       .arg(Arg::with_name("RESTART_WINDOW").help("The period (seconds) over which restarts are \
+                                                   counted [default: 600]")
+                                            .long("restart-window")
+                                            .validator(valid_numeric::<u64>)
+                                            .takes_value(true))
+       .arg(Arg::with_name("RESTART_BACKOFF").help("The delay (seconds) before the first \
+                                                    restart, doubling with each restart within \
+                                                    the window [default: 0]")
+                                             .long("restart-backoff")
+                                             .validator(valid_numeric::<u64>)
+                                             .takes_value(true))
+       .arg(Arg::with_name("RESTART_BACKOFF_MAX").help("The longest delay (seconds) before a \
+                                                        restart [default: 60]")
+                                                 .long("restart-backoff-max")
+                                                 .validator(valid_numeric::<u64>)
+                                                 .takes_value(true))
+       .arg(Arg::with_name("RESTART_JITTER").help("The percentage of each restart delay that is \
+                                                   randomized [default: 0]")
+                                            .long("restart-jitter")
+                                            .validator(valid_numeric::<u32>)
+                                            .takes_value(true))
+}
+
+/// Options for a declarative health check, run in place of the
+/// service's `health_check` hook.
+fn add_health_check_probe_options(app: App<'static, 'static>) -> App<'static, 'static> {
+    app.arg(Arg::with_name("HEALTH_CHECK_HTTP").help("Check the service's health with an HTTP \
+                                                      GET request to this URL, in place of its \
+                                                      health_check hook")
+                                               .long("health-check-http")
+                                               .conflicts_with_all(&["HEALTH_CHECK_TCP",
+                                                                     "HEALTH_CHECK_EXEC"])
+                                               .takes_value(true))
+       .arg(Arg::with_name("HEALTH_CHECK_STATUS").help("The response status the HTTP health \
+                                                        check expects [default: 200]")
+                                                 .long("health-check-status")
+                                                 .requires("HEALTH_CHECK_HTTP")
+                                                 .validator(valid_numeric::<u16>)
+                                                 .takes_value(true))
+       .arg(Arg::with_name("HEALTH_CHECK_BODY").help("A regular expression that the body of the \
+                                                      HTTP health check's response must match")
+                                               .long("health-check-body")
+                                               .requires("HEALTH_CHECK_HTTP")
+                                               .takes_value(true))
+       .arg(Arg::with_name("HEALTH_CHECK_TCP").help("Check the service's health by opening a TCP \
+                                                     connection to this host:port, in place of \
+                                                     its health_check hook")
+                                              .long("health-check-tcp")
+                                              .conflicts_with("HEALTH_CHECK_EXEC")
+                                              .takes_value(true))
+       .arg(Arg::with_name("HEALTH_CHECK_EXEC").help("Check the service's health by running this \
+                                                      command, whose exit code is treated like \
+                                                      that of a health_check hook, and which \
+                                                      runs as the service's user with its \
+                                                      package's environment like one. The \
+                                                      command is split on whitespace")
+                                               .long("health-check-exec")
+                                               .takes_value(true))
+       .arg(Arg::with_name("HEALTH_CHECK_TIMEOUT").help("How long (seconds) to wait for the \
+                                                         health check to complete [default: 5]")
+                                                  .long("health-check-timeout")
+                                                  .validator(valid_numeric::<u64>)
+                                                  .takes_value(true))
+}
+
+/// Options for a readiness check, which decides whether the service
+/// is advertised to the services bound to it, and for restarting the
+/// service when its health check keeps failing.
+fn add_readiness_options(app: App<'static, 'static>) -> App<'static, 'static> {
+    app.arg(Arg::with_name("READINESS_CHECK_HTTP").help("Decide whether the service is ready for \
+                                                         its consumers with an HTTP GET request \
+                                                         to this URL. If no readiness check is \
+                                                         given, the health check decides")
+                                                  .long("readiness-check-http")
+                                                  .conflicts_with_all(&["READINESS_CHECK_TCP",
+                                                                        "READINESS_CHECK_EXEC"])
+                                                  .takes_value(true))
+       .arg(Arg::with_name("READINESS_CHECK_TCP").help("Decide whether the service is ready for \
+                                                        its consumers by opening a TCP \
+                                                        connection to this host:port")
+                                                 .long("readiness-check-tcp")
+                                                 .conflicts_with("READINESS_CHECK_EXEC")
+                                                 .takes_value(true))
+       .arg(Arg::with_name("READINESS_CHECK_EXEC").help("Decide whether the service is ready for \
+                                                         its consumers by running this command, \
+                                                         which is split on whitespace")
+                                                  .long("readiness-check-exec")
+                                                  .takes_value(true))
+       .arg(Arg::with_name("READINESS_CHECK_TIMEOUT").help("How long (seconds) to wait for the \
+                                                            readiness check to complete \
+                                                            [default: 5]")
+                                                     .long("readiness-check-timeout")
+                                                     .validator(valid_numeric::<u64>)
+                                                     .takes_value(true))
+       .arg(Arg::with_name("LIVENESS_THRESHOLD").help("Restart the service after its health \
+                                                       check has been critical this many times \
+                                                       in a row; 0 never restarts it [default: 0]")
+                                                .long("liveness-threshold")
+                                                .validator(valid_numeric::<u32>)
+                                                .takes_value(true))
+}
+
+fn add_rollback_window_option(app: App<'static, 'static>) -> App<'static, 'static> {
+    app.arg(Arg::with_name("ROLLBACK_WINDOW").help("Roll back to the previous release if an \
+                                                    updated service's health check is critical \
+                                                    three times in a row or it crash-loops within \
+                                                    this many seconds, or its health check is \
+                                                    still critical after them; 0 never rolls \
+                                                    back [default: 0]")
+                                             .long("rollback-window")
+                                             .validator(valid_numeric::<u64>)
+                                             .takes_value(true))
+}
+
+fn add_update_batch_options(app: App<'static, 'static>) -> App<'static, 'static> {
+    app.arg(Arg::with_name("UPDATE_BATCH_SIZE").help("How many members the batched update \
+                                                      strategy updates at a time, as a count or \
+                                                      a percentage of the service group, e.g. 3 \
+                                                      or 25% [default: 1]")
+                                               .long("update-batch-size")
+                                               .validator(valid_update_batch_size)
+                                               .takes_value(true))
+       .arg(Arg::with_name("UPDATE_CANARY_SOAK").help("Under the batched update strategy, update \
+                                                       one canary member first, which must stay \
+                                                       healthy for this many seconds before the \
+                                                       rest of the service group updates; 0 \
+                                                       skips the canary [default: 0]")
+                                                .long("update-canary-soak")
+                                                .validator(valid_numeric::<u64>)
+                                                .takes_value(true))
+}
+
+fn add_election_options(app: App<'static, 'static>) -> App<'static, 'static> {
+    app.arg(Arg::with_name("ELECTION_PREFERRED_ZONE").help("Under the leader topology, prefer a \
+                                                            leader whose Supervisor is tagged \
+                                                            with this zone (hab sup run --tag \
+                                                            zone=ZONE)")
+                                                     .long("election-preferred-zone")
+                                                     .validator(non_empty)
+                                                     .takes_value(true))
+       .arg(Arg::with_name("ELECTION_MIN_ZONES").help("Under the leader topology, only elect a \
+                                                       leader while the alive members of the \
+                                                       service group span at least this many \
+                                                       zones; 0 ignores zones [default: 0]")
+                                                .long("election-min-zones")
+                                                .validator(valid_numeric::<u32>)
+                                                .takes_value(true))
+}
+
 ////////////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
@@ -1571,8 +2023,186 @@ mod tests {
         }
     }
 
+    mod restart_policy {
+        use super::*;
+        use habitat_sup_protocol::types::{RestartCondition,
+                                          RestartPolicy};
+
+        #[test]
+        fn load_without_restart_options_has_no_policy() {
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load", "core/redis"])
+                                        .unwrap();
+            assert_eq!(parse_restart_policy(&matches), None);
+        }
+
+        #[test]
+        fn load_with_restart_options() {
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load",
+                                                                    "core/redis",
+                                                                    "--restart-policy",
+                                                                    "on-failure",
+                                                                    "--restart-max-retries",
+                                                                    "3",
+                                                                    "--restart-backoff",
+                                                                    "5"])
+                                        .unwrap();
+            assert_eq!(parse_restart_policy(&matches),
+                       Some(RestartPolicy { condition: Some(RestartCondition::OnFailure
+                                                            as i32),
+                                            max_retries: Some(3),
+                                            backoff: Some(5),
+                                            ..Default::default() }));
+        }
+
+        #[test]
+        fn restart_policy_must_be_known() {
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load",
+                                                                    "core/redis",
+                                                                    "--restart-policy",
+                                                                    "sometimes"]);
+            assert_eq!(matches.unwrap_err().kind, clap::ErrorKind::ValueValidation);
+        }
+    }
+
+    mod health_check_probe {
+        use super::*;
+        use habitat_sup_protocol::types::{HealthCheckProbe,
+                                          HealthCheckProbeKind};
+
+        #[test]
+        fn load_without_probe_options_has_no_probe() {
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load", "core/redis"])
+                                        .unwrap();
+            assert_eq!(parse_health_check_probe(&matches), None);
+        }
+
+        #[test]
+        fn load_with_http_probe() {
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load",
+                                                                    "core/nginx",
+                                                                    "--health-check-http",
+                                                                    "http://localhost/status",
+                                                                    "--health-check-status",
+                                                                    "204"])
+                                        .unwrap();
+            assert_eq!(parse_health_check_probe(&matches),
+                       Some(HealthCheckProbe { kind: Some(HealthCheckProbeKind::Http as i32),
+                                               target:
+                                                   Some("http://localhost/status".to_string()),
+                                               expected_status: Some(204),
+                                               ..Default::default() }));
+        }
+
+        #[test]
+        fn load_with_exec_probe() {
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load",
+                                                                    "core/postgresql",
+                                                                    "--health-check-exec",
+                                                                    "pg_isready -h localhost"])
+                                        .unwrap();
+            let probe = parse_health_check_probe(&matches).unwrap();
+            assert_eq!(probe.kind, Some(HealthCheckProbeKind::Exec as i32));
+            assert_eq!(probe.command, vec!["pg_isready", "-h", "localhost"]);
+        }
+
+        #[test]
+        fn only_one_probe_may_be_given() {
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load",
+                                                                    "core/redis",
+                                                                    "--health-check-tcp",
+                                                                    "localhost:6379",
+                                                                    "--health-check-exec",
+                                                                    "redis-cli ping"]);
+            assert_eq!(matches.unwrap_err().kind, clap::ErrorKind::ArgumentConflict);
+        }
+
+        #[test]
+        fn status_requires_an_http_probe() {
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load",
+                                                                    "core/redis",
+                                                                    "--health-check-tcp",
+                                                                    "localhost:6379",
+                                                                    "--health-check-status",
+                                                                    "200"]);
+            assert!(matches.is_err());
+        }
+    }
+
+    mod readiness {
+        use super::*;
+        use habitat_sup_protocol::types::HealthCheckProbeKind;
+
+        #[test]
+        fn load_without_readiness_options_has_no_probe() {
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load", "core/redis"])
+                                        .unwrap();
+            assert_eq!(parse_readiness_probe(&matches), None);
+            assert_eq!(parse_optional_arg::<u32>("LIVENESS_THRESHOLD", &matches),
+                       None);
+        }
+
+        #[test]
+        fn readiness_and_health_checks_are_independent() {
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load",
+                                                                    "core/redis",
+                                                                    "--health-check-tcp",
+                                                                    "localhost:6379",
+                                                                    "--readiness-check-exec",
+                                                                    "redis-cli ping",
+                                                                    "--liveness-threshold",
+                                                                    "3"])
+                                        .unwrap();
+            let health_check = parse_health_check_probe(&matches).unwrap();
+            assert_eq!(health_check.kind, Some(HealthCheckProbeKind::Tcp as i32));
+            let readiness = parse_readiness_probe(&matches).unwrap();
+            assert_eq!(readiness.kind, Some(HealthCheckProbeKind::Exec as i32));
+            assert_eq!(readiness.command, vec!["redis-cli", "ping"]);
+            assert_eq!(parse_optional_arg::<u32>("LIVENESS_THRESHOLD", &matches),
+                       Some(3));
+        }
+
+        #[test]
+        fn rollback_window_must_be_numeric() {
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load",
+                                                                    "core/redis",
+                                                                    "--rollback-window",
+                                                                    "300"])
+                                        .unwrap();
+            assert_eq!(parse_optional_arg::<u64>("ROLLBACK_WINDOW", &matches),
+                       Some(300));
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load",
+                                                                    "core/redis",
+                                                                    "--rollback-window",
+                                                                    "soon"]);
+            assert!(matches.is_err());
+        }
+
+        #[test]
+        fn update_batch_size_is_a_count_or_a_percentage() {
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load",
+                                                                    "core/redis",
+                                                                    "--strategy",
+                                                                    "batched",
+                                                                    "--update-batch-size",
+                                                                    "25%",
+                                                                    "--update-canary-soak",
+                                                                    "600"])
+                                        .unwrap();
+            assert_eq!(parse_optional_arg::<String>("UPDATE_BATCH_SIZE", &matches),
+                       Some("25%".to_string()));
+            assert_eq!(parse_optional_arg::<u64>("UPDATE_CANARY_SOAK", &matches),
+                       Some(600));
+            let matches = sub_svc_load().get_matches_from_safe(vec!["load",
+                                                                    "core/redis",
+                                                                    "--update-batch-size",
+                                                                    "half"]);
+            assert!(matches.is_err());
+        }
+    }
+
     mod event_stream_feature {
         use super::*;
+        use std::time::Duration;
 
         fn event_stream_enabled() -> FeatureFlag {
             let mut f = FeatureFlag::empty();
@@ -1930,5 +2560,95 @@ mod tests {
             let error = matches.unwrap_err();
             assert_eq!(error.kind, clap::ErrorKind::ValueValidation);
         }
+
+        #[test]
+        fn non_nats_sinks_do_not_require_a_token() {
+            let matches = sub_sup_run(event_stream_enabled()).get_matches_from_safe(vec![
+                "run",
+                "--event-stream-application",
+                "MY_APP",
+                "--event-stream-environment",
+                "MY_ENV",
+                "--event-stream-sink",
+                "file",
+                "--event-stream-url",
+                "/hab/sup/default/events.jsonl",
+            ]);
+            assert!(matches.is_ok());
+        }
+
+        #[test]
+        fn nats_sink_requires_a_token() {
+            let matches = sub_sup_run(event_stream_enabled()).get_matches_from_safe(vec![
+                "run",
+                "--event-stream-application",
+                "MY_APP",
+                "--event-stream-environment",
+                "MY_ENV",
+                "--event-stream-sink",
+                "nats",
+                "--event-stream-url",
+                "127.0.0.1:4222",
+            ]);
+            assert!(matches.is_err());
+            assert_eq!(matches.unwrap_err().kind,
+                       clap::ErrorKind::MissingRequiredArgument);
+        }
+
+        #[test]
+        fn sink_option_must_be_a_known_sink() {
+            let matches = sub_sup_run(event_stream_enabled()).get_matches_from_safe(vec![
+                "run",
+                "--event-stream-application",
+                "MY_APP",
+                "--event-stream-environment",
+                "MY_ENV",
+                "--event-stream-sink",
+                "kafka",
+                "--event-stream-url",
+                "127.0.0.1:4222",
+            ]);
+            assert!(matches.is_err());
+            assert_eq!(matches.unwrap_err().kind, clap::ErrorKind::InvalidValue);
+        }
+
+        #[test]
+        fn spool_limits_must_be_numeric() {
+            let matches = sub_sup_run(event_stream_enabled()).get_matches_from_safe(vec![
+                "run",
+                "--event-stream-application",
+                "MY_APP",
+                "--event-stream-environment",
+                "MY_ENV",
+                "--event-stream-token",
+                "MY_TOKEN",
+                "--event-stream-url",
+                "127.0.0.1:4222",
+                "--event-stream-spool-max-bytes",
+                "lots",
+            ]);
+            assert!(matches.is_err());
+            assert_eq!(matches.unwrap_err().kind, clap::ErrorKind::ValueValidation);
+
+            let matches = sub_sup_run(event_stream_enabled()).get_matches_from_safe(vec![
+                "run",
+                "--event-stream-application",
+                "MY_APP",
+                "--event-stream-environment",
+                "MY_ENV",
+                "--event-stream-token",
+                "MY_TOKEN",
+                "--event-stream-url",
+                "127.0.0.1:4222",
+                "--event-stream-spool-max-bytes",
+                "1048576",
+                "--event-stream-spool-max-age",
+                "3600",
+            ])
+                                                             .unwrap();
+            let limits = EventStreamSpoolLimits::from(&matches);
+            assert_eq!(limits.max_bytes, 1_048_576);
+            assert_eq!(limits.max_age, Duration::from_secs(3600));
+        }
     }
 }
@@ -8,7 +8,10 @@ use std::{fs::{create_dir_all,
           path::Path};
 use toml::Value;
 
-use crate::{common::{templating::TemplateRenderer,
+use crate::{common::{self,
+                     templating::{schema::{ConfigSchema,
+                                           CONFIG_SCHEMA_FILE},
+                                  TemplateRenderer},
                      ui::{Status,
                           UIWriter,
                           UI}},
@@ -68,6 +71,19 @@ pub fn start(ui: &mut UI,
     // merge default into data struct
     merge(&mut data, toml_to_json(&user_toml)?);
 
+    // check the configuration against the schema next to default.toml, if there is one
+    let schema_dir = default_toml_path.parent().unwrap_or_else(|| Path::new(""));
+    if let Some(schema) = ConfigSchema::load(schema_dir)? {
+        if !quiet {
+            ui.begin(format!("Validating against {}",
+                             schema_dir.join(CONFIG_SCHEMA_FILE).display()))?;
+        }
+        if let Some(errors) = schema.validate_json(&data["cfg"]) {
+            return Err(common::Error::InvalidConfig("default.toml and user.toml".to_string(),
+                                                    errors).into());
+        }
+    }
+
     // read mock data if provided
     let mock_data = match mock_data_path {
         Some(path) => {
@@ -0,0 +1,37 @@
+use std::{fs,
+          path::Path};
+
+use crate::{common::{templating::secrets::FileSecretProvider,
+                     ui::{Status,
+                          UIWriter,
+                          UI}},
+            hcore::{crypto::BoxKeyPair,
+                    fs::{atomic_write,
+                         svc_secrets_path},
+                    service::ServiceGroup}};
+
+use crate::error::Result;
+
+pub fn start(ui: &mut UI,
+             service_group: &ServiceGroup,
+             name: &str,
+             secret: &[u8],
+             user: &str,
+             cache: &Path)
+             -> Result<()> {
+    let path = FileSecretProvider::path(&svc_secrets_path(service_group.service()), name)?;
+    ui.begin(format!("Setting secret {} for {}", name, service_group))?;
+    let user_pair = BoxKeyPair::get_latest_pair_for(user, cache)?;
+    let service_pair = BoxKeyPair::get_latest_pair_for(service_group, cache)?;
+    ui.status(Status::Encrypting,
+              format!("secret as {} for {}",
+                      user_pair.name_with_rev(),
+                      service_pair.name_with_rev()))?;
+    let payload = user_pair.encrypt(secret, Some(&service_pair))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    atomic_write(&path, payload.as_bytes())?;
+    ui.end(format!("Secret {} is stored in {}.", name, path.display()))?;
+    Ok(())
+}
@@ -7,13 +7,18 @@ extern crate lazy_static;
 #[macro_use]
 extern crate log;
 
+use chrono::{TimeZone,
+             Utc};
 use clap::{ArgMatches,
            Shell};
 use env_logger;
 use futures::prelude::*;
 use glob::glob_with;
 use hab::{cli::{self,
-                parse_optional_arg},
+                parse_health_check_probe,
+                parse_optional_arg,
+                parse_readiness_probe,
+                parse_restart_policy},
           command::{self,
                     pkg::list::ListingType},
           config::{self,
@@ -71,8 +76,11 @@ use habitat_sup_protocol::{self as sup_proto,
                            net::ErrCode,
                            types::*};
 use pbr;
-use std::{env,
+use std::{collections::{BTreeMap,
+                        BTreeSet},
+          env,
           ffi::OsString,
+          fmt,
           fs::File,
           io::{self,
                prelude::*,
@@ -172,6 +180,8 @@ fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
         ("config", Some(m)) => {
             match m.subcommand() {
                 ("apply", Some(m)) => sub_svc_set(m)?,
+                ("history", Some(m)) => sub_config_history(m)?,
+                ("rollback", Some(m)) => sub_config_rollback(m)?,
                 ("show", Some(m)) => sub_svc_config(m)?,
                 _ => unreachable!(),
             }
@@ -278,6 +288,18 @@ fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
                         ("export", Some(sc)) => sub_ring_key_export(sc)?,
                         ("import", Some(sc)) => sub_ring_key_import(ui, sc)?,
                         ("generate", Some(sc)) => sub_ring_key_generate(ui, sc)?,
+                        ("accept", Some(sc)) => sub_ring_key_accept(sc)?,
+                        ("promote", Some(sc)) => sub_ring_key_promote(sc)?,
+                        ("retire", Some(sc)) => sub_ring_key_retire(sc)?,
+                        ("list", Some(sc)) => sub_ring_key_list(sc)?,
+                        _ => unreachable!(),
+                    }
+                }
+                ("status", Some(sc)) => sub_ring_status(sc)?,
+                ("rumors", Some(sc)) => sub_ring_rumors(sc)?,
+                ("rumor", Some(m)) => {
+                    match m.subcommand() {
+                        ("purge", Some(sc)) => sub_ring_rumor_purge(sc)?,
                         _ => unreachable!(),
                     }
                 }
@@ -292,6 +314,12 @@ fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
                         _ => unreachable!(),
                     }
                 }
+                ("secret", Some(m)) => {
+                    match m.subcommand() {
+                        ("set", Some(sc)) => sub_svc_secret_set(ui, sc)?,
+                        _ => unreachable!(),
+                    }
+                }
                 ("load", Some(m)) => sub_svc_load(m)?,
                 ("unload", Some(m)) => sub_svc_unload(m)?,
                 ("start", Some(m)) => sub_svc_start(m)?,
@@ -1036,9 +1064,67 @@ fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
         process::exit(1);
     }
     validate.cfg = Some(buf.clone());
-    let cache = cache_key_path_from_matches(&m);
+    let mut invalid = false;
+    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+                                                         conn.call(validate.clone())
+                .for_each(|reply| match reply.message_id() {
+                    "NetOk" => Ok(()),
+                    "NetErr" => {
+                        let m = reply
+                            .parse::<sup_proto::net::NetErr>()
+                            .map_err(SrvClientError::Decode)?;
+                        match ErrCode::from_i32(m.code) {
+                            Some(ErrCode::InvalidPayload) => {
+                                ui.warn(m)?;
+                                invalid = true;
+                                Ok(())
+                            }
+                            _ => Err(SrvClientError::from(m)),
+                        }
+                    }
+                    _ => Err(SrvClientError::from(io::Error::from(
+                        io::ErrorKind::UnexpectedEof,
+                    ))),
+                })
+                                                     })
+                                                     .wait()?;
+    if invalid {
+        ui.fatal("Configuration does not match the configuration schema of the service.")?;
+        process::exit(1);
+    }
+    let version = value_t!(m, "VERSION_NUMBER", u64).unwrap();
+    if m.is_present("DRY_RUN") {
+        let explain = sup_proto::ctl::SvcCfgExplain { service_group: validate.service_group,
+                                                      format: validate.format,
+                                                      cfg: validate.cfg,
+                                                      ..Default::default() };
+        return print_svc_cfg_dry_run(&mut ui,
+                                     &svc_cfg_explain(&listen_ctl_addr, &secret_key, explain)?,
+                                     &service_group,
+                                     version);
+    }
+    svc_set_cfg(&mut ui,
+                m,
+                &listen_ctl_addr,
+                &secret_key,
+                service_group,
+                version,
+                &buf)
+}
+
+/// Encrypts configuration for the service group if it has an organization and a user key is
+/// given, and applies it to the service group as version `version`.
+fn svc_set_cfg(ui: &mut UI,
+               m: &ArgMatches<'_>,
+               listen_ctl_addr: &ListenCtlAddr,
+               secret_key: &str,
+               service_group: ServiceGroup,
+               version: u64,
+               buf: &[u8])
+               -> Result<()> {
+    let cache = cache_key_path_from_matches(m);
     let mut set = sup_proto::ctl::SvcSetCfg::default();
-    match (service_group.org(), user_param_or_env(&m)) {
+    match (service_group.org(), user_param_or_env(m)) {
         (Some(_org), Some(username)) => {
             let user_pair = BoxKeyPair::get_latest_pair_for(username, &cache)?;
             let service_pair = BoxKeyPair::get_latest_pair_for(&service_group, &cache)?;
@@ -1046,13 +1132,21 @@ fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
                       format!("TOML as {} for {}",
                               user_pair.name_with_rev(),
                               service_pair.name_with_rev()))?;
-            set.cfg = Some(user_pair.encrypt(&buf, Some(&service_pair))?.into_bytes());
+            set.cfg = Some(user_pair.encrypt(buf, Some(&service_pair))?.into_bytes());
             set.is_encrypted = Some(true);
         }
         _ => set.cfg = Some(buf.to_vec()),
     }
     set.service_group = Some(service_group.into());
-    set.version = Some(value_t!(m, "VERSION_NUMBER", u64).unwrap());
+    set.version = Some(version);
+    svc_send_cfg(ui, listen_ctl_addr, secret_key, set)
+}
+
+fn svc_send_cfg(ui: &mut UI,
+                listen_ctl_addr: &ListenCtlAddr,
+                secret_key: &str,
+                set: sup_proto::ctl::SvcSetCfg)
+                -> Result<()> {
     ui.begin(format!("Setting new configuration version {} for {}",
                      set.version
                         .as_ref()
@@ -1063,33 +1157,11 @@ fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
                         .map(ToString::to_string)
                         .unwrap_or_else(|| "UNKNOWN".to_string()),))?;
     ui.status(Status::Creating, "service configuration")?;
-    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
-                                                         conn.call(validate)
-                .for_each(|reply| match reply.message_id() {
-                    "NetOk" => Ok(()),
-                    "NetErr" => {
-                        let m = reply
-                            .parse::<sup_proto::net::NetErr>()
-                            .map_err(SrvClientError::Decode)?;
-                        match ErrCode::from_i32(m.code) {
-                            Some(ErrCode::InvalidPayload) => {
-                                ui.warn(m)?;
-                                Ok(())
-                            }
-                            _ => Err(SrvClientError::from(m)),
-                        }
-                    }
-                    _ => Err(SrvClientError::from(io::Error::from(
-                        io::ErrorKind::UnexpectedEof,
-                    ))),
-                })
-                                                     })
-                                                     .wait()?;
     ui.status(Status::Applying, format!("via peer {}", listen_ctl_addr))?;
     // JW: We should not need to make two connections here. I need a way to return the
     // SrvClient from a for_each iterator so we can chain upon a successful stream but I don't
     // know if it's possible with this version of futures.
-    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+    SrvClient::connect(listen_ctl_addr, secret_key).and_then(|conn| {
                                                          conn.call(set).for_each(|reply| {
                           match reply.message_id() {
                 "NetOk" => Ok(()),
@@ -1115,6 +1187,13 @@ fn sub_svc_config(m: &ArgMatches<'_>) -> Result<()> {
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
     let secret_key = ctl_secret_key(&cfg)?;
+    if m.is_present("EXPLAIN") {
+        let explain = sup_proto::ctl::SvcCfgExplain { ident: Some(ident.into()),
+                                                      ..Default::default() };
+        return print_svc_cfg_explanation(&svc_cfg_explain(&listen_ctl_addr,
+                                                          &secret_key,
+                                                          explain)?);
+    }
     let mut msg = sup_proto::ctl::SvcGetDefaultCfg::default();
     msg.ident = Some(ident.into());
     SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
@@ -1143,6 +1222,201 @@ fn sub_svc_config(m: &ArgMatches<'_>) -> Result<()> {
     Ok(())
 }
 
+fn sub_config_history(m: &ArgMatches<'_>) -> Result<()> {
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let service_group = ServiceGroup::from_str(m.value_of("SERVICE_GROUP").unwrap())?;
+    let msg = sup_proto::ctl::SvcCfgHistory { service_group: Some(service_group.into()),
+                                              incarnation:   None, };
+    let history = svc_cfg_history(&listen_ctl_addr, &secret_key, msg)?;
+    let current = history.incarnation.unwrap_or_default();
+    let mut out = TabWriter::new(io::stdout());
+    writeln!(out, "INCARNATION\tAPPLIED\tAUTHOR\tHASH")?;
+    for version in &history.versions {
+        let incarnation = version.incarnation.unwrap_or_default();
+        writeln!(out,
+                 "{}{}\t{}\t{}\t{}",
+                 incarnation,
+                 if incarnation == current {
+                     " (current)"
+                 } else {
+                     ""
+                 },
+                 version.applied_at
+                        .map(|secs| Utc.timestamp(secs, 0).to_rfc3339())
+                        .unwrap_or_default(),
+                 version.author.as_ref().map_or("(unencrypted)", String::as_str),
+                 version.hash.as_ref().map_or("", String::as_str))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn sub_config_rollback(m: &ArgMatches<'_>) -> Result<()> {
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let service_group = ServiceGroup::from_str(m.value_of("SERVICE_GROUP").unwrap())?;
+    let incarnation = value_t!(m, "INCARNATION", u64).unwrap();
+    let mut ui = ui();
+    let msg = sup_proto::ctl::SvcCfgHistory { service_group: Some(service_group.clone().into()),
+                                              incarnation:   Some(incarnation), };
+    let history = svc_cfg_history(&listen_ctl_addr, &secret_key, msg)?;
+    let mut rolled_back = match history.versions.into_iter().next() {
+        Some(rolled_back) if rolled_back.cfg.is_some() => rolled_back,
+        _ => {
+            ui.fatal(format!("Configuration version {} of {} is not in its history.",
+                             incarnation, service_group))?;
+            process::exit(1);
+        }
+    };
+    let buf = rolled_back.cfg.take().unwrap_or_default();
+    // The Supervisor may not have seen the newest version yet, if it was applied elsewhere in
+    // the ring moments ago. It is then ignored, and the rollback has to be run again.
+    let version = history.incarnation.unwrap_or_default() + 1;
+    ui.para(&format!("Applying configuration version {} of {} again, as version {}.",
+                     incarnation, service_group, version))?;
+    if rolled_back.encrypted.unwrap_or(false) {
+        // Gossiped again as it was encrypted, so it stays encrypted by its author.
+        let set = sup_proto::ctl::SvcSetCfg { service_group: Some(service_group.into()),
+                                              cfg:           Some(buf),
+                                              version:       Some(version),
+                                              is_encrypted:  Some(true), };
+        return svc_send_cfg(&mut ui, &listen_ctl_addr, &secret_key, set);
+    }
+    if let Some(author) = rolled_back.author {
+        // A version recorded before encrypted versions were kept encrypted. It is only applied
+        // again encrypted.
+        if service_group.org().is_none() || user_param_or_env(m).is_none() {
+            ui.fatal(format!("Configuration version {} of {} was encrypted by {}, and can only \
+                              be applied again encrypted. Specify an organization in the \
+                              service group and a user key with --user.",
+                             incarnation, service_group, author))?;
+            process::exit(1);
+        }
+    }
+    svc_set_cfg(&mut ui,
+                m,
+                &listen_ctl_addr,
+                &secret_key,
+                service_group,
+                version,
+                &buf)
+}
+
+fn svc_cfg_history(listen_ctl_addr: &ListenCtlAddr,
+                   secret_key: &str,
+                   msg: sup_proto::ctl::SvcCfgHistory)
+                   -> Result<sup_proto::ctl::SvcCfgVersions> {
+    let replies = SrvClient::connect(listen_ctl_addr, secret_key).and_then(|conn| {
+                                                                     conn.call(msg).collect()
+                                                                 })
+                                                                 .wait()?;
+    for reply in replies {
+        match reply.message_id() {
+            "SvcCfgVersions" => {
+                return Ok(reply.parse::<sup_proto::ctl::SvcCfgVersions>()
+                               .map_err(SrvClientError::Decode)?);
+            }
+            "NetErr" => {
+                let err = reply.parse::<sup_proto::net::NetErr>()
+                               .map_err(SrvClientError::Decode)?;
+                return Err(SrvClientError::from(err).into());
+            }
+            _ => warn!("Unexpected status message, {:?}", reply),
+        }
+    }
+    Err(SrvClientError::from(io::Error::from(io::ErrorKind::UnexpectedEof)).into())
+}
+
+fn svc_cfg_explain(listen_ctl_addr: &ListenCtlAddr,
+                   secret_key: &str,
+                   msg: sup_proto::ctl::SvcCfgExplain)
+                   -> Result<sup_proto::ctl::SvcCfgExplanation> {
+    let replies = SrvClient::connect(listen_ctl_addr, secret_key).and_then(|conn| {
+                                                                     conn.call(msg).collect()
+                                                                 })
+                                                                 .wait()?;
+    for reply in replies {
+        match reply.message_id() {
+            "SvcCfgExplanation" => {
+                return Ok(reply.parse::<sup_proto::ctl::SvcCfgExplanation>()
+                               .map_err(SrvClientError::Decode)?);
+            }
+            "NetErr" => {
+                let err = reply.parse::<sup_proto::net::NetErr>()
+                               .map_err(SrvClientError::Decode)?;
+                return Err(SrvClientError::from(err).into());
+            }
+            _ => warn!("Unexpected status message, {:?}", reply),
+        }
+    }
+    Err(SrvClientError::from(io::Error::from(io::ErrorKind::UnexpectedEof)).into())
+}
+
+fn print_svc_cfg_explanation(explanation: &sup_proto::ctl::SvcCfgExplanation) -> Result<()> {
+    let mut out = TabWriter::new(io::stdout());
+    writeln!(out, "KEY\tVALUE\tLAYER\tINCARNATION\tOVERRIDES")?;
+    for key in &explanation.keys {
+        let overridden = key.overridden
+                            .iter()
+                            .map(|o| {
+                                format!("{} = {}",
+                                        o.layer.as_ref().map_or("", String::as_str),
+                                        o.value.as_ref().map_or("", String::as_str))
+                            })
+                            .collect::<Vec<_>>();
+        writeln!(out,
+                 "{}\t{}\t{}\t{}\t{}",
+                 key.key.as_ref().map_or("", String::as_str),
+                 key.value.as_ref().map_or("", String::as_str),
+                 key.layer.as_ref().map_or("", String::as_str),
+                 key.incarnation.map(|i| i.to_string()).unwrap_or_default(),
+                 overridden.join(", "))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn print_svc_cfg_dry_run(ui: &mut UI,
+                         explanation: &sup_proto::ctl::SvcCfgExplanation,
+                         service_group: &ServiceGroup,
+                         version: u64)
+                         -> Result<()> {
+    let incarnation = explanation.incarnation.unwrap_or_default();
+    ui.begin(format!("Checking configuration version {} for {}", version, service_group))?;
+    if version <= incarnation {
+        ui.warn(format!("Version {} is not newer than the current version {} of the \
+                         configuration of {}, and would be ignored.",
+                        version, incarnation, service_group))?;
+    }
+    if explanation.changes.is_empty() {
+        ui.para("No configuration keys would change.")?;
+    } else {
+        let mut out = TabWriter::new(io::stdout());
+        writeln!(out, "KEY\tCURRENT\tPENDING")?;
+        for change in &explanation.changes {
+            writeln!(out,
+                     "{}\t{}\t{}",
+                     change.key.as_ref().map_or("", String::as_str),
+                     change.current.as_ref().map_or("(unset)", String::as_str),
+                     change.pending.as_ref().map_or("(unset)", String::as_str))?;
+        }
+        out.flush()?;
+    }
+    if !explanation.templates.is_empty() {
+        ui.para(&format!("Templates that would be re-rendered: {}",
+                         explanation.templates.join(", ")))?;
+    }
+    if !explanation.hooks.is_empty() {
+        ui.para(&format!("Hooks that would be re-rendered: {}",
+                         explanation.hooks.join(", ")))?;
+    }
+    ui.end("Dry run, configuration not applied")?;
+    Ok(())
+}
+
 fn sub_svc_load(m: &ArgMatches<'_>) -> Result<()> {
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
@@ -1399,6 +1673,285 @@ fn sub_ring_key_import(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     command::ring::key::import::start(ui, content.trim(), &cache_key_path)
 }
 
+fn sub_ring_key_accept(m: &ArgMatches<'_>) -> Result<()> {
+    let mut msg = sup_proto::ctl::RingKeyAccept::default();
+    msg.name = Some(m.value_of("RING").unwrap().to_string()); // Required via clap
+    call_ring_key_ctl(m, msg)
+}
+
+fn sub_ring_key_promote(m: &ArgMatches<'_>) -> Result<()> {
+    let mut msg = sup_proto::ctl::RingKeyPromote::default();
+    msg.name_with_rev = Some(m.value_of("RING").unwrap().to_string()); // Required via clap
+    call_ring_key_ctl(m, msg)
+}
+
+fn sub_ring_key_retire(m: &ArgMatches<'_>) -> Result<()> {
+    let mut msg = sup_proto::ctl::RingKeyRetire::default();
+    msg.name_with_rev = Some(m.value_of("RING").unwrap().to_string()); // Required via clap
+    call_ring_key_ctl(m, msg)
+}
+
+fn call_ring_key_ctl<T>(m: &ArgMatches<'_>, msg: T) -> Result<()>
+    where T: Into<SrvMessage> + fmt::Debug
+{
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+                                                         conn.call(msg)
+                                                             .for_each(|m| handle_ctl_reply(&m))
+                                                     })
+                                                     .wait()?;
+    Ok(())
+}
+
+fn sub_ring_key_list(m: &ArgMatches<'_>) -> Result<()> {
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let msg = sup_proto::ctl::RingKeyList::default();
+    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+                                                         conn.call(msg).for_each(|reply| {
+                                                                           print_ring_keys(&reply)
+                                                                       })
+                                                     })
+                                                     .wait()?;
+    Ok(())
+}
+
+fn print_ring_keys(reply: &SrvMessage) -> result::Result<(), SrvClientError> {
+    let status = match reply.message_id() {
+        "RingKeyStatus" => {
+            reply.parse::<sup_proto::ctl::RingKeyStatus>()
+                 .map_err(SrvClientError::Decode)?
+        }
+        "NetErr" => {
+            let err = reply.parse::<sup_proto::net::NetErr>()
+                           .map_err(SrvClientError::Decode)?;
+            return Err(SrvClientError::from(err));
+        }
+        _ => {
+            warn!("Unexpected status message, {:?}", reply);
+            return Ok(());
+        }
+    };
+    let mut out = TabWriter::new(io::stdout());
+    writeln!(out, "RING KEY\tUSE")?;
+    match status.primary {
+        Some(ref primary) => writeln!(out, "{}\tprimary", primary)?,
+        None => writeln!(out, "(none)\tprimary, gossip is not encrypted")?,
+    }
+    for accepted in &status.accepted {
+        writeln!(out, "{}\taccepted", accepted)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn sub_ring_status(m: &ArgMatches<'_>) -> Result<()> {
+    let mut views = Vec::new();
+    for (listen_ctl_addr, replies) in call_each_sup(m, &sup_proto::ctl::RingStatus::default())? {
+        let members = replies.iter()
+                             .map(SrvMessage::parse::<sup_proto::ctl::RingMember>)
+                             .collect::<result::Result<Vec<_>, _>>()
+                             .map_err(SrvClientError::Decode)?;
+        views.push((listen_ctl_addr, members));
+    }
+    let mut out = TabWriter::new(io::stdout());
+    if let [(_, ref members)] = views[..] {
+        writeln!(out, "MEMBER\tADDRESS\tHEALTH\tINCARNATION\tPERSISTENT")?;
+        for member in members {
+            writeln!(out,
+                     "{}{}\t{}:{}/{}\t{}\t{}\t{}",
+                     member.member_id.as_ref().map_or("", String::as_str),
+                     if member.myself == Some(true) {
+                         " (self)"
+                     } else {
+                         ""
+                     },
+                     member.address.as_ref().map_or("", String::as_str),
+                     member.swim_port.unwrap_or_default(),
+                     member.gossip_port.unwrap_or_default(),
+                     member.health.as_ref().map_or("", String::as_str),
+                     member.incarnation.unwrap_or_default(),
+                     member.persistent.unwrap_or_default())?;
+        }
+        out.flush()?;
+        return Ok(());
+    }
+    let views = views.into_iter()
+                     .map(|(listen_ctl_addr, members)| {
+                         let view: BTreeMap<_, _> =
+                             members.into_iter()
+                                    .map(|member| {
+                                        (member.member_id.unwrap_or_default(),
+                                         format!("{} ({})",
+                                                 member.health.unwrap_or_default(),
+                                                 member.incarnation.unwrap_or_default()))
+                                    })
+                                    .collect();
+                         (listen_ctl_addr, view)
+                     })
+                     .collect::<Vec<_>>();
+    let differ = print_ring_views(&mut out, "MEMBER", &views)?;
+    out.flush()?;
+    if differ > 0 {
+        println!("{} member(s) are seen differently; the ring may be partitioned, or still \
+                  converging",
+                 differ);
+    }
+    Ok(())
+}
+
+fn sub_ring_rumor_purge(m: &ArgMatches<'_>) -> Result<()> {
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let service_group = ServiceGroup::from_str(m.value_of("SERVICE_GROUP").unwrap())?;
+    let mut ui = ui();
+    let mut msg = sup_proto::ctl::RingRumorPurge::default();
+    msg.service_group = Some(service_group.clone().into());
+    SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+        ui.begin(format!("Purging the rumors of {}", service_group))
+          .unwrap();
+        ui.status(Status::Applying, format!("via peer {}", listen_ctl_addr))
+          .unwrap();
+        conn.call(msg).for_each(|reply| handle_ctl_reply(&reply))
+    })
+    .wait()?;
+    ui.end("Purge recorded.")?;
+    Ok(())
+}
+
+fn sub_ring_rumors(m: &ArgMatches<'_>) -> Result<()> {
+    let mut views = Vec::new();
+    for (listen_ctl_addr, replies) in call_each_sup(m, &sup_proto::ctl::RingRumors::default())? {
+        let rumors = replies.iter()
+                            .map(SrvMessage::parse::<sup_proto::ctl::RingRumor>)
+                            .collect::<result::Result<Vec<_>, _>>()
+                            .map_err(SrvClientError::Decode)?;
+        views.push((listen_ctl_addr, rumors));
+    }
+    let mut out = TabWriter::new(io::stdout());
+    if let [(_, ref rumors)] = views[..] {
+        writeln!(out, "KIND\tKEY\tID\tVERSION\tSHARED\tCOOLED")?;
+        for rumor in rumors {
+            writeln!(out,
+                     "{}\t{}\t{}\t{}\t{}\t{}",
+                     rumor.kind.as_ref().map_or("", String::as_str),
+                     rumor.key.as_ref().map_or("", String::as_str),
+                     rumor.id.as_ref().map_or("", String::as_str),
+                     rumor_version(rumor),
+                     rumor.shared.unwrap_or_default(),
+                     rumor.cooled.unwrap_or_default())?;
+        }
+        out.flush()?;
+        return Ok(());
+    }
+    let views = views.into_iter()
+                     .map(|(listen_ctl_addr, rumors)| {
+                         let view: BTreeMap<_, _> =
+                             rumors.iter()
+                                   .map(|rumor| {
+                                       (format!("{}\t{}\t{}",
+                                                rumor.kind.as_ref().map_or("", String::as_str),
+                                                rumor.key.as_ref().map_or("", String::as_str),
+                                                rumor.id.as_ref().map_or("", String::as_str)),
+                                        rumor_version(rumor))
+                                   })
+                                   .collect();
+                         (listen_ctl_addr, view)
+                     })
+                     .collect::<Vec<_>>();
+    let differ = print_ring_views(&mut out, "KIND\tKEY\tID", &views)?;
+    out.flush()?;
+    if differ > 0 {
+        println!("{} rumor(s) are held differently; they may never converge if this persists",
+                 differ);
+    }
+    Ok(())
+}
+
+fn rumor_version(rumor: &sup_proto::ctl::RingRumor) -> String {
+    match (rumor.incarnation, rumor.term, rumor.election_status.as_ref()) {
+        (Some(incarnation), ..) => format!("incarnation {}", incarnation),
+        (None, Some(term), Some(status)) => format!("term {}, {}", term, status),
+        (None, Some(term), None) => format!("term {}", term),
+        _ => "present".to_string(),
+    }
+}
+
+/// Call every Supervisor given with `--remote-sup`, in turn, returning
+/// each one's replies.
+fn call_each_sup<T>(m: &ArgMatches<'_>, msg: &T) -> Result<Vec<(ListenCtlAddr, Vec<SrvMessage>)>>
+    where T: Into<SrvMessage> + Clone + fmt::Debug
+{
+    let cfg = config::load()?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let listen_ctl_addrs = match m.values_of("REMOTE_SUP") {
+        Some(remote_sups) => {
+            remote_sups.map(resolve_listen_ctl_addr)
+                       .collect::<Result<_>>()?
+        }
+        None => vec![ListenCtlAddr::default()],
+    };
+    let mut views = Vec::new();
+    for listen_ctl_addr in listen_ctl_addrs {
+        let replies = SrvClient::connect(&listen_ctl_addr, &secret_key).and_then(|conn| {
+                                                                           conn.call(msg.clone())
+                                                                               .collect()
+                                                                       })
+                                                                       .wait()?;
+        let mut view = Vec::new();
+        for reply in replies {
+            match reply.message_id() {
+                "NetOk" => (),
+                "NetErr" => {
+                    let err = reply.parse::<sup_proto::net::NetErr>()
+                                   .map_err(SrvClientError::Decode)?;
+                    return Err(SrvClientError::from(err).into());
+                }
+                _ => view.push(reply),
+            }
+        }
+        views.push((listen_ctl_addr, view));
+    }
+    Ok(views)
+}
+
+/// Print a row for everything any Supervisor knows of, with a column
+/// for each Supervisor's view of it, and flag the rows on which the
+/// Supervisors don't agree. Returns how many rows that is.
+fn print_ring_views<W: Write>(out: &mut W,
+                              header: &str,
+                              views: &[(ListenCtlAddr, BTreeMap<String, String>)])
+                              -> Result<usize> {
+    write!(out, "{}", header)?;
+    for (listen_ctl_addr, _) in views {
+        write!(out, "\t{}", listen_ctl_addr.as_ref())?;
+    }
+    writeln!(out, "\tVIEWS")?;
+    let keys = views.iter()
+                    .flat_map(|(_, view)| view.keys())
+                    .collect::<BTreeSet<_>>();
+    let mut differ = 0;
+    for key in keys {
+        let cells = views.iter()
+                         .map(|(_, view)| view.get(key).map_or("-", String::as_str))
+                         .collect::<Vec<_>>();
+        let agree = cells.iter().all(|cell| *cell == cells[0]);
+        if !agree {
+            differ += 1;
+        }
+        writeln!(out,
+                 "{}\t{}\t{}",
+                 key,
+                 cells.join("\t"),
+                 if agree { "agree" } else { "DIFFER" })?;
+    }
+    Ok(differ)
+}
+
 fn sub_service_key_generate(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     let org = org_param_or_env(&m)?;
     let service_group = ServiceGroup::from_str(m.value_of("SERVICE_GROUP").unwrap())?;
@@ -1408,6 +1961,39 @@ fn sub_service_key_generate(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     command::service::key::generate::start(ui, &org, &service_group, &cache_key_path)
 }
 
+fn sub_svc_secret_set(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
+    let service_group = ServiceGroup::from_str(m.value_of("SERVICE_GROUP").unwrap())?;
+    let name = m.value_of("NAME").unwrap(); // Required via clap
+    let cache_key_path = cache_key_path_from_matches(&m);
+    let user = match (service_group.org(), user_param_or_env(&m)) {
+        (Some(_org), Some(user)) => user,
+        (None, _) => {
+            ui.fatal(format!("Secrets are encrypted for a service key, but {} has no \
+                              organization.",
+                             service_group))?;
+            process::exit(1);
+        }
+        (_, None) => {
+            ui.fatal("A user key is required to encrypt secrets, set one with --user or \
+                      HAB_USER.")?;
+            process::exit(1);
+        }
+    };
+    let mut secret = Vec::new();
+    match m.value_of("FILE") {
+        Some("-") | None => io::stdin().read_to_end(&mut secret)?,
+        Some(f) => File::open(f)?.read_to_end(&mut secret)?,
+    };
+    init();
+
+    command::service::secret::set::start(ui,
+                                         &service_group,
+                                         name,
+                                         &secret,
+                                         &user,
+                                         &cache_key_path)
+}
+
 fn sub_user_key_generate(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     let user = m.value_of("USER").unwrap(); // Required via clap
     let cache_key_path = cache_key_path_from_matches(&m);
@@ -1995,6 +2581,15 @@ fn svc_load_from_input(m: &ArgMatches) -> Result<sup_proto::ctl::SvcLoad> {
     msg.update_strategy = get_strategy_from_input(m).map(|v| v as i32);
     msg.shutdown_timeout =
         parse_optional_arg::<ShutdownTimeout>("SHUTDOWN_TIMEOUT", m).map(u32::from);
+    msg.restart_policy = parse_restart_policy(m);
+    msg.health_check_probe = parse_health_check_probe(m);
+    msg.readiness_probe = parse_readiness_probe(m);
+    msg.liveness_threshold = parse_optional_arg("LIVENESS_THRESHOLD", m);
+    msg.rollback_window = parse_optional_arg("ROLLBACK_WINDOW", m);
+    msg.update_batch_size = parse_optional_arg("UPDATE_BATCH_SIZE", m);
+    msg.update_canary_soak = parse_optional_arg("UPDATE_CANARY_SOAK", m);
+    msg.election_preferred_zone = parse_optional_arg("ELECTION_PREFERRED_ZONE", m);
+    msg.election_min_zones = parse_optional_arg("ELECTION_MIN_ZONES", m);
     Ok(msg)
 }
 
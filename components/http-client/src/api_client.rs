@@ -65,8 +65,6 @@ impl ApiClient {
                   -> Result<Self>
         where T: IntoUrl
     {
-        let endpoint = endpoint.into_url().map_err(Error::ReqwestError)?;
-
         let timeout_in_secs = match env::var("HAB_CLIENT_SOCKET_TIMEOUT") {
             Ok(t) => {
                 match t.parse::<u64>() {
@@ -76,7 +74,30 @@ impl ApiClient {
             }
             Err(_) => CLIENT_SOCKET_RW_TIMEOUT_SEC,
         };
-        debug!("Client socket timeout: {} secs", timeout_in_secs);
+        Self::with_timeout(endpoint,
+                           product,
+                           version,
+                           fs_root_path,
+                           Duration::from_secs(timeout_in_secs))
+    }
+
+    /// Creates and returns a new `ApiClient` instance whose requests
+    /// fail if they take longer than `timeout`, rather than the
+    /// default socket timeout.
+    ///
+    /// # Errors
+    ///
+    /// * See `ApiClient::new`
+    pub fn with_timeout<T>(endpoint: T,
+                           product: &str,
+                           version: &str,
+                           fs_root_path: Option<&Path>,
+                           timeout: Duration)
+                           -> Result<Self>
+        where T: IntoUrl
+    {
+        let endpoint = endpoint.into_url().map_err(Error::ReqwestError)?;
+        debug!("Client socket timeout: {} secs", timeout.as_secs());
 
         let skip_cert_verify = env::var("HAB_SSL_CERT_VERIFY_NONE").is_ok();
         debug!("Skip cert verification: {}", skip_cert_verify);
@@ -99,7 +120,7 @@ impl ApiClient {
 
         let mut client = reqwest::Client::builder().proxy(proxy_for(&endpoint)?)
                                                    .default_headers(headers)
-                                                   .timeout(Duration::from_secs(timeout_in_secs))
+                                                   .timeout(timeout)
                                                    .danger_accept_invalid_certs(skip_cert_verify);
 
         client =
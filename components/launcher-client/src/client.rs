@@ -1,7 +1,8 @@
 use crate::error::{Error,
                    Result};
 use habitat_common::types::UserInfo;
-use habitat_core::os::process::Pid;
+use habitat_core::{env,
+                   os::process::Pid};
 use habitat_launcher_protocol::{self as protocol,
                                 Error as ProtocolError};
 use ipc_channel::ipc::{IpcOneShotServer,
@@ -17,6 +18,9 @@ type IpcServer = IpcOneShotServer<Vec<u8>>;
 pub struct LauncherCli {
     tx: IpcSender<Vec<u8>>,
     rx: IpcReceiver<Vec<u8>>,
+    // Whether the Launcher answers `ExitStatus` requests. Older Launchers never reply to a
+    // request they don't recognize, so it must not be sent to them.
+    reports_exit_status: bool,
     // We persist the pipe identifier so we can delete the file on drop.
     // This is not necessary on Windows because named pipes are removed
     // upon releasing the last handle to the pipe. The ipc-channel crate
@@ -46,8 +50,10 @@ impl LauncherCli {
         Self::send(&tx, &cmd)?;
         let (rx, raw) = ipc_srv.accept().map_err(|_| Error::AcceptConn)?;
         Self::read::<protocol::NetOk>(&raw)?;
+        let reports_exit_status = env::var(protocol::LAUNCHER_EXIT_STATUS_ENV).is_ok();
         Ok(LauncherCli { tx,
                          rx,
+                         reports_exit_status,
                          #[cfg(not(windows))]
                          pipe: pipe_to_sup })
     }
@@ -155,6 +161,18 @@ impl LauncherCli {
         Ok(reply.pid as Pid)
     }
 
+    /// Collect the exit status of a process which has exited. Returns `None` if the Launcher is
+    /// too old to report exit statuses.
+    pub fn exit_status(&self, pid: Pid) -> Result<Option<protocol::ExitStatusOk>> {
+        if !self.reports_exit_status {
+            return Ok(None);
+        }
+        let msg = protocol::ExitStatus { pid: pid.into() };
+        Self::send(&self.tx, &msg)?;
+        let reply = Self::recv::<protocol::ExitStatusOk>(&self.rx)?;
+        Ok(Some(reply))
+    }
+
     pub fn terminate(&self, pid: Pid) -> Result<i32> {
         let msg = protocol::Terminate { pid: pid.into() };
         Self::send(&self.tx, &msg)?;
@@ -16,6 +16,9 @@ pub const LAUNCHER_PID_ENV: &str = "HAB_LAUNCHER_PID";
 // Set to instruct the Supervisor to clean the Launcher's process LOCK on startup. This is useful
 // when restarting a Supervisor which terminated normally.
 pub const LAUNCHER_LOCK_CLEAN_ENV: &str = "HAB_LAUNCHER_LOCK_CLEAN";
+// Set by Launchers which answer `ExitStatus` requests. Older Launchers never reply to requests
+// they don't recognize, so the Supervisor must not send one unless this is set.
+pub const LAUNCHER_EXIT_STATUS_ENV: &str = "HAB_LAUNCHER_EXIT_STATUS";
 /// Process exit code from Supervisor which indicates to Launcher that the Supervisor
 /// ran to completion with a successful result. The Launcher should not attempt to restart
 /// the Supervisor and should exit immediately with a successful exit code.
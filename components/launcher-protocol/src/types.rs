@@ -237,6 +237,51 @@ impl From<TerminateOk> for generated::TerminateOk {
     }
 }
 
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExitStatus {
+    pub pid: i64,
+}
+
+impl LauncherMessage for ExitStatus {
+    type Generated = generated::ExitStatus;
+
+    const MESSAGE_ID: &'static str = "ExitStatus";
+
+    fn from_proto(proto: generated::ExitStatus) -> Result<Self> {
+        Ok(ExitStatus { pid: proto.pid.ok_or(Error::ProtocolMismatch("pid"))?, })
+    }
+}
+
+impl From<ExitStatus> for generated::ExitStatus {
+    fn from(value: ExitStatus) -> Self { generated::ExitStatus { pid: Some(value.pid), } }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExitStatusOk {
+    /// `None` if the process was killed by a signal.
+    pub exit_code: Option<i32>,
+}
+
+impl ExitStatusOk {
+    pub fn success(&self) -> bool { self.exit_code == Some(0) }
+}
+
+impl LauncherMessage for ExitStatusOk {
+    type Generated = generated::ExitStatusOk;
+
+    const MESSAGE_ID: &'static str = "ExitStatusOk";
+
+    fn from_proto(proto: generated::ExitStatusOk) -> Result<Self> {
+        Ok(ExitStatusOk { exit_code: proto.exit_code, })
+    }
+}
+
+impl From<ExitStatusOk> for generated::ExitStatusOk {
+    fn from(value: ExitStatusOk) -> Self {
+        generated::ExitStatusOk { exit_code: value.exit_code, }
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Envelope {
     pub message_id: String,
@@ -30,7 +30,8 @@ use ipc_channel::ipc::{IpcOneShotServer,
 use libc;
 use semver::{Version,
              VersionReq};
-use std::{collections::HashMap,
+use std::{collections::{HashMap,
+                        VecDeque},
           fs,
           io::Write,
           path::PathBuf,
@@ -57,6 +58,9 @@ const SUP_VERSION_CHECK_DISABLE: &str = "HAB_LAUNCH_NO_SUP_VERSION_CHECK";
 // changes to the launcher that depend on supervisor behavior that hasn't
 // always existed such as https://github.com/habitat-sh/habitat/issues/5380
 const SUP_VERSION_REQ: &str = ">= 0.56";
+/// The number of exit statuses kept for the Supervisor to collect. Older statuses are dropped
+/// first; the Supervisor asks for each one shortly after the process exits.
+const EXITED_SERVICES_MAX: usize = 64;
 
 type Receiver = IpcReceiver<Vec<u8>>;
 type Sender = IpcSender<Vec<u8>>;
@@ -368,19 +372,36 @@ impl Server {
 }
 
 #[derive(Debug, Default)]
-pub struct ServiceTable(HashMap<u32, Service>);
+pub struct ServiceTable {
+    services: HashMap<u32, Service>,
+    /// The pids and exit codes of reaped services, oldest first. The code is `None` if the
+    /// process was killed by a signal.
+    exited:   VecDeque<(u32, Option<i32>)>,
+}
 
 impl ServiceTable {
-    pub fn get(&self, pid: u32) -> Option<&Service> { self.0.get(&pid) }
+    pub fn get(&self, pid: u32) -> Option<&Service> { self.services.get(&pid) }
+
+    pub fn get_mut(&mut self, pid: u32) -> Option<&mut Service> { self.services.get_mut(&pid) }
 
-    pub fn get_mut(&mut self, pid: u32) -> Option<&mut Service> { self.0.get_mut(&pid) }
+    pub fn insert(&mut self, service: Service) {
+        // A reused pid must not report the exit status of an earlier process.
+        self.exited.retain(|&(pid, _)| pid != service.id());
+        self.services.insert(service.id(), service);
+    }
 
-    pub fn insert(&mut self, service: Service) { self.0.insert(service.id(), service); }
+    pub fn remove(&mut self, pid: u32) -> Option<Service> { self.services.remove(&pid) }
 
-    pub fn remove(&mut self, pid: u32) -> Option<Service> { self.0.remove(&pid) }
+    /// Take the exit status of a reaped service. Returns `None` if no service with this pid has
+    /// been reaped, and `Some(None)` if it was killed by a signal.
+    pub fn exit_status(&mut self, pid: u32) -> Option<Option<i32>> {
+        self.reap_services();
+        let index = self.exited.iter().position(|&(exited, _)| exited == pid)?;
+        self.exited.remove(index).map(|(_, code)| code)
+    }
 
     fn kill_all(&mut self) {
-        for service in self.0.values_mut() {
+        for service in self.services.values_mut() {
             outputln!(preamble service.name(), "Stopping...");
             let shutdown_method = service.kill();
             outputln!(preamble service.name(), "Shutdown OK: {}", shutdown_method);
@@ -388,25 +409,29 @@ impl ServiceTable {
     }
 
     fn reap_services(&mut self) {
-        let mut dead: Vec<u32> = vec![];
-        for service in self.0.values_mut() {
+        let mut dead: Vec<(u32, Option<i32>)> = vec![];
+        for service in self.services.values_mut() {
             match service.try_wait() {
                 Ok(None) => (),
-                Ok(Some(code)) => {
+                Ok(Some(status)) => {
                     outputln!("Child for service '{}' with PID {} exited with code {}",
                               service.name(),
                               service.id(),
-                              code);
-                    dead.push(service.id());
+                              status);
+                    dead.push((service.id(), status.code()));
                 }
                 Err(err) => {
                     warn!("Error waiting for child, {}, {}", service.id(), err);
-                    dead.push(service.id());
+                    dead.push((service.id(), None));
                 }
             }
         }
-        for pid in dead {
-            self.0.remove(&pid);
+        for (pid, code) in dead {
+            self.services.remove(&pid);
+            if self.exited.len() == EXITED_SERVICES_MAX {
+                self.exited.pop_front();
+            }
+            self.exited.push_back((pid, code));
         }
     }
 }
@@ -458,6 +483,7 @@ fn dispatch(tx: &Sender, bytes: &[u8], services: &mut ServiceTable) {
         }
     };
     let func = match msg.message_id() {
+        "ExitStatus" => handlers::ExitStatusHandler::run,
         "Restart" => handlers::RestartHandler::run,
         "Spawn" => handlers::SpawnHandler::run,
         "Terminate" => handlers::TerminateHandler::run,
@@ -589,6 +615,7 @@ fn spawn_supervisor(pipe: &str, args: &[String], clean: bool) -> Result<Child> {
                        .env(protocol::LAUNCHER_PIPE_ENV, pipe)
                        .env(protocol::LAUNCHER_PID_ENV,
                             process::current_pid().to_string())
+                       .env(protocol::LAUNCHER_EXIT_STATUS_ENV, "true")
                        .args(args)
                        .spawn()
                        .map_err(Error::SupSpawn)?;
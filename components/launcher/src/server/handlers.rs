@@ -1,8 +1,10 @@
+mod exit_status;
 mod restart;
 mod spawn;
 mod terminate;
 
-pub use self::{restart::*,
+pub use self::{exit_status::*,
+               restart::*,
                spawn::*,
                terminate::*};
 
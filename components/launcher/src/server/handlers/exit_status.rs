@@ -0,0 +1,26 @@
+use crate::protocol;
+
+use super::{HandleResult,
+            Handler};
+use crate::server::ServiceTable;
+
+pub struct ExitStatusHandler;
+impl Handler for ExitStatusHandler {
+    type Message = protocol::ExitStatus;
+    type Reply = protocol::ExitStatusOk;
+
+    fn handle(msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        match services.exit_status(msg.pid as u32) {
+            Some(exit_code) => {
+                let mut reply = protocol::ExitStatusOk::default();
+                reply.exit_code = exit_code;
+                Ok(reply)
+            }
+            None => {
+                let mut reply = protocol::NetErr::default();
+                reply.code = protocol::ErrCode::NoPid;
+                Err(reply)
+            }
+        }
+    }
+}
@@ -1197,7 +1197,8 @@ function Invoke-DefaultInstall {
 }
 
 # Copy the `./config` directory, relative to the Plan, to `$pkg_prefix\config`.
-# Do the same with `default.toml`. Delegates most of the implementation to the
+# Do the same with `default.toml`, and with `default.schema.json`, the schema its
+# configuration is checked against. Delegates most of the implementation to the
 # `Invoke-DefaultBuildConfig` function.
 function Invoke-BuildConfig {
     Invoke-DefaultBuildConfig
@@ -1221,6 +1222,10 @@ function Invoke-DefaultBuildConfig {
         Write-BuildLine "Writing default.toml"
         Copy-Item "$PLAN_CONTEXT/default.toml" $pkg_prefix
     }
+    if (Test-Path "$PLAN_CONTEXT/default.schema.json") {
+        Write-BuildLine "Writing default.schema.json"
+        Copy-Item "$PLAN_CONTEXT/default.schema.json" $pkg_prefix
+    }
 }
 
 # Write out the `$pkg_prefix\run` file. If a file named `hooks\run`
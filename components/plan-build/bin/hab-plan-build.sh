@@ -1964,7 +1964,8 @@ _build_metadata() {
 }
 
 # Copy the `./config` directory, relative to the Plan, to `$pkg_prefix/config`.
-# Do the same with `default.toml`. Delegates most of the implementation to the
+# Do the same with `default.toml`, and with `default.schema.json`, the schema its
+# configuration is checked against. Delegates most of the implementation to the
 # `do_default_build_config()` function.
 do_build_config() {
   do_default_build_config
@@ -1983,6 +1984,9 @@ do_default_build_config() {
   if [[ -f "$PLAN_CONTEXT/default.toml" ]]; then
     cp "$PLAN_CONTEXT/default.toml" "$pkg_prefix"
   fi
+  if [[ -f "$PLAN_CONTEXT/default.schema.json" ]]; then
+    cp "$PLAN_CONTEXT/default.schema.json" "$pkg_prefix"
+  fi
   return 0
 }
 
@@ -12,6 +12,36 @@ impl message::MessageStatic for ServiceBindList {
 impl message::MessageStatic for SupDepart {
     const MESSAGE_ID: &'static str = "SupDepart";
 }
+impl message::MessageStatic for RingKeyAccept {
+    const MESSAGE_ID: &'static str = "RingKeyAccept";
+}
+impl message::MessageStatic for RingKeyPromote {
+    const MESSAGE_ID: &'static str = "RingKeyPromote";
+}
+impl message::MessageStatic for RingKeyRetire {
+    const MESSAGE_ID: &'static str = "RingKeyRetire";
+}
+impl message::MessageStatic for RingKeyList {
+    const MESSAGE_ID: &'static str = "RingKeyList";
+}
+impl message::MessageStatic for RingKeyStatus {
+    const MESSAGE_ID: &'static str = "RingKeyStatus";
+}
+impl message::MessageStatic for RingStatus {
+    const MESSAGE_ID: &'static str = "RingStatus";
+}
+impl message::MessageStatic for RingMember {
+    const MESSAGE_ID: &'static str = "RingMember";
+}
+impl message::MessageStatic for RingRumors {
+    const MESSAGE_ID: &'static str = "RingRumors";
+}
+impl message::MessageStatic for RingRumor {
+    const MESSAGE_ID: &'static str = "RingRumor";
+}
+impl message::MessageStatic for RingRumorPurge {
+    const MESSAGE_ID: &'static str = "RingRumorPurge";
+}
 impl message::MessageStatic for SvcFilePut {
     const MESSAGE_ID: &'static str = "SvcFilePut";
 }
@@ -21,6 +51,30 @@ impl message::MessageStatic for SvcGetDefaultCfg {
 impl message::MessageStatic for SvcValidateCfg {
     const MESSAGE_ID: &'static str = "SvcValidateCfg";
 }
+impl message::MessageStatic for SvcCfgExplain {
+    const MESSAGE_ID: &'static str = "SvcCfgExplain";
+}
+impl message::MessageStatic for CfgOverriddenValue {
+    const MESSAGE_ID: &'static str = "CfgOverriddenValue";
+}
+impl message::MessageStatic for CfgKey {
+    const MESSAGE_ID: &'static str = "CfgKey";
+}
+impl message::MessageStatic for CfgKeyChange {
+    const MESSAGE_ID: &'static str = "CfgKeyChange";
+}
+impl message::MessageStatic for SvcCfgExplanation {
+    const MESSAGE_ID: &'static str = "SvcCfgExplanation";
+}
+impl message::MessageStatic for SvcCfgHistory {
+    const MESSAGE_ID: &'static str = "SvcCfgHistory";
+}
+impl message::MessageStatic for SvcCfgVersion {
+    const MESSAGE_ID: &'static str = "SvcCfgVersion";
+}
+impl message::MessageStatic for SvcCfgVersions {
+    const MESSAGE_ID: &'static str = "SvcCfgVersions";
+}
 impl message::MessageStatic for SvcSetCfg {
     const MESSAGE_ID: &'static str = "SvcSetCfg";
 }
@@ -38,6 +38,119 @@ pub struct SupDepart {
     #[prost(string, optional, tag="1")]
     pub member_id: ::std::option::Option<std::string::String>,
 }
+/// Request to accept gossip encrypted with an additional ring key. The key is loaded from the
+/// Supervisor's own key cache so that no secret is sent over the wire.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RingKeyAccept {
+    /// A ring key name, for its latest revision, or a ring key name with revision.
+    #[prost(string, optional, tag="1")]
+    pub name: ::std::option::Option<std::string::String>,
+}
+/// Request to encrypt gossip with an accepted ring key.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RingKeyPromote {
+    #[prost(string, optional, tag="1")]
+    pub name_with_rev: ::std::option::Option<std::string::String>,
+}
+/// Request to stop accepting gossip encrypted with a ring key.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RingKeyRetire {
+    #[prost(string, optional, tag="1")]
+    pub name_with_rev: ::std::option::Option<std::string::String>,
+}
+/// Request to retrieve the ring keys a Supervisor gossips with.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RingKeyList {}
+/// The ring keys a Supervisor gossips with.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RingKeyStatus {
+    /// The key gossip is encrypted with, if the ring is encrypted.
+    #[prost(string, optional, tag="1")]
+    pub primary: ::std::option::Option<std::string::String>,
+    /// Keys gossip is also accepted with.
+    #[prost(string, repeated, tag="2")]
+    pub accepted: ::std::vec::Vec<std::string::String>,
+}
+/// Request to retrieve a Supervisor's view of the members of its ring.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RingStatus {}
+/// A member of the ring, as seen by the Supervisor that replied.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RingMember {
+    #[prost(string, optional, tag="1")]
+    pub member_id: ::std::option::Option<std::string::String>,
+    #[prost(string, optional, tag="2")]
+    pub address: ::std::option::Option<std::string::String>,
+    #[prost(uint32, optional, tag="3")]
+    pub swim_port: ::std::option::Option<u32>,
+    #[prost(uint32, optional, tag="4")]
+    pub gossip_port: ::std::option::Option<u32>,
+    /// One of "alive", "suspect", "confirmed" or "departed".
+    #[prost(string, optional, tag="5")]
+    pub health: ::std::option::Option<std::string::String>,
+    #[prost(uint64, optional, tag="6")]
+    pub incarnation: ::std::option::Option<u64>,
+    #[prost(bool, optional, tag="7")]
+    pub persistent: ::std::option::Option<bool>,
+    /// Whether this member is the Supervisor that replied.
+    #[prost(bool, optional, tag="8")]
+    pub myself: ::std::option::Option<bool>,
+}
+/// Request to retrieve the rumors a Supervisor holds.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RingRumors {}
+/// A rumor, as held by the Supervisor that replied.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RingRumor {
+    /// The kind of rumor, e.g. "service" or "election".
+    #[prost(string, optional, tag="1")]
+    pub kind: ::std::option::Option<std::string::String>,
+    #[prost(string, optional, tag="2")]
+    pub id: ::std::option::Option<std::string::String>,
+    #[prost(string, optional, tag="3")]
+    pub key: ::std::option::Option<std::string::String>,
+    /// The incarnation of service, service config and service file rumors.
+    #[prost(uint64, optional, tag="4")]
+    pub incarnation: ::std::option::Option<u64>,
+    /// The term of election rumors.
+    #[prost(uint64, optional, tag="5")]
+    pub term: ::std::option::Option<u64>,
+    /// The status of election rumors, one of "running", "no-quorum" or "finished".
+    #[prost(string, optional, tag="6")]
+    pub election_status: ::std::option::Option<std::string::String>,
+    /// How many times the Supervisor has shared the rumor.
+    #[prost(uint64, optional, tag="7")]
+    pub shared: ::std::option::Option<u64>,
+    /// How many members the rumor has cooled off for.
+    #[prost(uint64, optional, tag="8")]
+    pub cooled: ::std::option::Option<u64>,
+}
+/// Request to drop every rumor of a service group, ring-wide.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RingRumorPurge {
+    #[prost(message, optional, tag="1")]
+    pub service_group: ::std::option::Option<super::types::ServiceGroup>,
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -77,6 +190,143 @@ pub struct SvcValidateCfg {
     #[prost(bytes, optional, tag="3")]
     pub cfg: ::std::option::Option<std::vec::Vec<u8>>,
 }
+/// Request for the effective configuration of a running service, and where each of its values
+/// came from. With `cfg`, also what applying that configuration to the service group would change.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcCfgExplain {
+    /// Package identifier of a running service, as in `SvcGetDefaultCfg`.
+    #[prost(message, optional, tag="1")]
+    pub ident: ::std::option::Option<super::types::PackageIdent>,
+    /// Service group of a running service, instead of `ident`.
+    #[prost(message, optional, tag="2")]
+    pub service_group: ::std::option::Option<super::types::ServiceGroup>,
+    /// Structured and self-describing string format contained in the configuration string.
+    #[prost(enumeration="super::types::service_cfg::Format", optional, tag="3", default="Toml")]
+    pub format: ::std::option::Option<i32>,
+    /// Unencrypted configuration a pending `hab config apply` would set.
+    #[prost(bytes, optional, tag="4")]
+    pub cfg: ::std::option::Option<std::vec::Vec<u8>>,
+}
+/// A value a lower layer gives a configuration key, overridden by a higher layer.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CfgOverriddenValue {
+    #[prost(string, optional, tag="1")]
+    pub layer: ::std::option::Option<std::string::String>,
+    /// The value, as inline TOML.
+    #[prost(string, optional, tag="2")]
+    pub value: ::std::option::Option<std::string::String>,
+}
+/// A key of the effective configuration of a service, and where its value came from.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CfgKey {
+    /// The dotted path of the key.
+    #[prost(string, optional, tag="1")]
+    pub key: ::std::option::Option<std::string::String>,
+    /// The value, as inline TOML.
+    #[prost(string, optional, tag="2")]
+    pub value: ::std::option::Option<std::string::String>,
+    /// The layer the value came from, one of "default", "environment", "user" or "gossip".
+    #[prost(string, optional, tag="3")]
+    pub layer: ::std::option::Option<std::string::String>,
+    /// The incarnation of the service group's configuration, if the value came from it.
+    #[prost(uint64, optional, tag="4")]
+    pub incarnation: ::std::option::Option<u64>,
+    /// The values lower layers give the key, highest layer first.
+    #[prost(message, repeated, tag="5")]
+    pub overridden: ::std::vec::Vec<CfgOverriddenValue>,
+}
+/// A key of the effective configuration of a service that applying configuration would change.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CfgKeyChange {
+    #[prost(string, optional, tag="1")]
+    pub key: ::std::option::Option<std::string::String>,
+    /// The current value, as inline TOML, unless the key would be added.
+    #[prost(string, optional, tag="2")]
+    pub current: ::std::option::Option<std::string::String>,
+    /// The pending value, as inline TOML, unless the key would be removed.
+    #[prost(string, optional, tag="3")]
+    pub pending: ::std::option::Option<std::string::String>,
+}
+/// Reply to `SvcCfgExplain`.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcCfgExplanation {
+    #[prost(message, optional, tag="1")]
+    pub service_group: ::std::option::Option<super::types::ServiceGroup>,
+    /// The incarnation of the service group's configuration.
+    #[prost(uint64, optional, tag="2")]
+    pub incarnation: ::std::option::Option<u64>,
+    #[prost(message, repeated, tag="3")]
+    pub keys: ::std::vec::Vec<CfgKey>,
+    /// The keys whose values applying the configuration of the request would change.
+    #[prost(message, repeated, tag="4")]
+    pub changes: ::std::vec::Vec<CfgKeyChange>,
+    /// The configuration templates whose rendered content applying it would change.
+    #[prost(string, repeated, tag="5")]
+    pub templates: ::std::vec::Vec<std::string::String>,
+    /// The hooks whose rendered content applying it would change.
+    #[prost(string, repeated, tag="6")]
+    pub hooks: ::std::vec::Vec<std::string::String>,
+}
+/// Request for the versions of a service group's configuration a Supervisor running one of its
+/// services has applied.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcCfgHistory {
+    #[prost(message, optional, tag="1")]
+    pub service_group: ::std::option::Option<super::types::ServiceGroup>,
+    /// Only the version with this incarnation, including its configuration.
+    #[prost(uint64, optional, tag="2")]
+    pub incarnation: ::std::option::Option<u64>,
+}
+/// A version of a service group's configuration applied by a Supervisor.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcCfgVersion {
+    #[prost(uint64, optional, tag="1")]
+    pub incarnation: ::std::option::Option<u64>,
+    /// The name with revision of the user key the configuration was encrypted by, if it was.
+    #[prost(string, optional, tag="2")]
+    pub author: ::std::option::Option<std::string::String>,
+    /// When the Supervisor applied the configuration, in seconds since the Unix epoch.
+    #[prost(int64, optional, tag="3")]
+    pub applied_at: ::std::option::Option<i64>,
+    /// The hash of the configuration.
+    #[prost(string, optional, tag="4")]
+    pub hash: ::std::option::Option<std::string::String>,
+    /// The configuration when a single version was requested, as TOML, or as the payload it was
+    /// gossiped as if it was encrypted.
+    #[prost(bytes, optional, tag="5")]
+    pub cfg: ::std::option::Option<std::vec::Vec<u8>>,
+    /// If `cfg` is encrypted with the service group's key.
+    #[prost(bool, optional, tag="6")]
+    pub encrypted: ::std::option::Option<bool>,
+}
+/// Reply to `SvcCfgHistory`.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcCfgVersions {
+    #[prost(message, optional, tag="1")]
+    pub service_group: ::std::option::Option<super::types::ServiceGroup>,
+    /// The incarnation of the service group's configuration.
+    #[prost(uint64, optional, tag="2")]
+    pub incarnation: ::std::option::Option<u64>,
+    /// Newest first.
+    #[prost(message, repeated, tag="3")]
+    pub versions: ::std::vec::Vec<SvcCfgVersion>,
+}
 /// Request to set a running service's configuration to the given values.
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -151,6 +401,42 @@ pub struct SvcLoad {
     /// The shutdown timeout for the service.
     #[prost(uint32, optional, tag="16")]
     pub shutdown_timeout: ::std::option::Option<u32>,
+    /// When, and how quickly, to restart the service's process after it exits.
+    #[prost(message, optional, tag="17")]
+    pub restart_policy: ::std::option::Option<super::types::RestartPolicy>,
+    /// A declarative health check to run in place of the service's
+    /// `health_check` hook.
+    #[prost(message, optional, tag="18")]
+    pub health_check_probe: ::std::option::Option<super::types::HealthCheckProbe>,
+    /// A check that decides whether the service is ready to be used by
+    /// the services bound to it. If not given, the health check decides.
+    #[prost(message, optional, tag="19")]
+    pub readiness_probe: ::std::option::Option<super::types::HealthCheckProbe>,
+    /// Restart the service after its health check has been critical this
+    /// many times in a row. Zero disables these restarts.
+    #[prost(uint32, optional, tag="20")]
+    pub liveness_threshold: ::std::option::Option<u32>,
+    /// Roll back to the previous release if an updated service fails
+    /// within this many seconds of starting. Zero disables rollbacks.
+    #[prost(uint64, optional, tag="21")]
+    pub rollback_window: ::std::option::Option<u64>,
+    /// How many members the `batched` update strategy updates at a time,
+    /// as a count ("3") or a percentage of the group ("25%").
+    #[prost(string, optional, tag="22")]
+    pub update_batch_size: ::std::option::Option<std::string::String>,
+    /// Under the `batched` update strategy, update a single canary member
+    /// first, which must stay healthy for this many seconds before the rest
+    /// of the group updates. Zero skips the canary phase.
+    #[prost(uint64, optional, tag="23")]
+    pub update_canary_soak: ::std::option::Option<u64>,
+    /// Under the `leader` topology, prefer a leader whose Supervisor is
+    /// tagged with this zone (`hab sup run --tag zone=...`).
+    #[prost(string, optional, tag="24")]
+    pub election_preferred_zone: ::std::option::Option<std::string::String>,
+    /// Under the `leader` topology, only elect a leader while the alive
+    /// members of the service group span at least this many zones.
+    #[prost(uint32, optional, tag="25")]
+    pub election_min_zones: ::std::option::Option<u32>,
 }
 /// Request to unload a loaded service.
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -24,3 +24,9 @@ impl message::MessageStatic for ServiceStatus {
 impl message::MessageStatic for HealthCheckInterval {
     const MESSAGE_ID: &'static str = "HealthCheckInterval";
 }
+impl message::MessageStatic for RestartPolicy {
+    const MESSAGE_ID: &'static str = "RestartPolicy";
+}
+impl message::MessageStatic for HealthCheckProbe {
+    const MESSAGE_ID: &'static str = "HealthCheckProbe";
+}
@@ -94,6 +94,55 @@ pub struct HealthCheckInterval {
     #[prost(uint64, required, tag="1")]
     pub seconds: u64,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RestartPolicy {
+    #[prost(enumeration="RestartCondition", optional, tag="1")]
+    pub condition: ::std::option::Option<i32>,
+    /// The number of restarts allowed within `window` before the service
+    /// is considered to be crash-looping. Zero allows unlimited restarts.
+    #[prost(uint32, optional, tag="2")]
+    pub max_retries: ::std::option::Option<u32>,
+    /// The period, in seconds, over which restarts are counted.
+    #[prost(uint64, optional, tag="3")]
+    pub window: ::std::option::Option<u64>,
+    /// The delay, in seconds, before the first restart. Each subsequent
+    /// restart within the window doubles the delay, up to `backoff_max`.
+    #[prost(uint64, optional, tag="4")]
+    pub backoff: ::std::option::Option<u64>,
+    /// The longest delay, in seconds, before a restart.
+    #[prost(uint64, optional, tag="5")]
+    pub backoff_max: ::std::option::Option<u64>,
+    /// The percentage of each delay that is randomized, so that services
+    /// which failed together don't restart in lockstep.
+    #[prost(uint32, optional, tag="6")]
+    pub backoff_jitter: ::std::option::Option<u32>,
+}
+/// A declarative health check, run in place of a `health_check` hook.
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HealthCheckProbe {
+    #[prost(enumeration="HealthCheckProbeKind", optional, tag="1")]
+    pub kind: ::std::option::Option<i32>,
+    /// The URL to request (http), or the `host:port` to connect to (tcp).
+    #[prost(string, optional, tag="2")]
+    pub target: ::std::option::Option<std::string::String>,
+    /// The response status an http probe expects.
+    #[prost(uint32, optional, tag="3")]
+    pub expected_status: ::std::option::Option<u32>,
+    /// A regular expression that the body of an http probe's response
+    /// must match.
+    #[prost(string, optional, tag="4")]
+    pub expected_body: ::std::option::Option<std::string::String>,
+    /// The command to run (exec), followed by its arguments.
+    #[prost(string, repeated, tag="5")]
+    pub command: ::std::vec::Vec<std::string::String>,
+    /// How long, in seconds, to wait for the probe to complete.
+    #[prost(uint64, optional, tag="6")]
+    pub timeout: ::std::option::Option<u64>,
+}
 /// Encapsulate all possible sources we can install packages from.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -112,6 +161,9 @@ pub enum InstallSource {
 pub enum ProcessState {
     Down = 0,
     Up = 1,
+    /// The process exited more often than its restart policy allows, and
+    /// will not be restarted again.
+    CrashLooping = 2,
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -141,6 +193,7 @@ pub enum UpdateStrategy {
     None = 0,
     AtOnce = 1,
     Rolling = 2,
+    Batched = 3,
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -151,4 +204,35 @@ pub enum BindingMode {
     Relaxed = 0,
     /// Service start-up is blocked until all binds are available
     Strict = 1,
+    /// Service start-up is blocked until all binds are available and
+    /// each bound service group has a member that has passed a health
+    /// check
+    Ordered = 2,
+}
+/// When a service's process should be restarted after it exits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartCondition {
+    /// Restart the process whenever it exits.
+    Always = 0,
+    /// Restart the process only when it exits unsuccessfully.
+    OnFailure = 1,
+    /// Leave the process down once it exits.
+    Never = 2,
+}
+/// How a health check probe determines whether a service is healthy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HealthCheckProbeKind {
+    /// Issue an HTTP GET request and check the response.
+    Http = 0,
+    /// Open a TCP connection.
+    Tcp = 1,
+    /// Run a command and treat its exit code like that of a
+    /// `health_check` hook.
+    Exec = 2,
 }
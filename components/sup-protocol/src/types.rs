@@ -47,6 +47,7 @@ impl fmt::Display for BindingMode {
         let value = match *self {
             BindingMode::Relaxed => "relaxed",
             BindingMode::Strict => "strict",
+            BindingMode::Ordered => "ordered",
         };
         write!(f, "{}", value)
     }
@@ -69,6 +70,7 @@ impl fmt::Display for ProcessState {
         let state = match *self {
             ProcessState::Down => "down",
             ProcessState::Up => "up",
+            ProcessState::CrashLooping => "crash-looping",
         };
         write!(f, "{}", state)
     }
@@ -92,10 +94,11 @@ impl FromStr for BindingMode {
         match value.to_lowercase().as_ref() {
             "relaxed" => Ok(BindingMode::Relaxed),
             "strict" => Ok(BindingMode::Strict),
+            "ordered" => Ok(BindingMode::Ordered),
             _ => {
                 Err(net::err(ErrCode::InvalidPayload,
                              format!("Invalid binding mode \"{}\", must be \
-                                      `relaxed` or `strict`.",
+                                      `relaxed`, `strict` or `ordered`.",
                                      value)))
             }
         }
@@ -109,10 +112,11 @@ impl FromStr for ProcessState {
         match value.to_lowercase().as_ref() {
             "0" => Ok(ProcessState::Down),
             "1" => Ok(ProcessState::Up),
+            "2" => Ok(ProcessState::CrashLooping),
             _ => {
                 Err(net::err(ErrCode::InvalidPayload,
                              format!("Invalid process state \"{:?}\", must \
-                                      be `up` or `down`.",
+                                      be `up`, `down` or `crash-looping`.",
                                      value)))
             }
         }
@@ -233,6 +237,7 @@ impl From<core::service::BindingMode> for BindingMode {
         match mode {
             core::service::BindingMode::Strict => BindingMode::Strict,
             core::service::BindingMode::Relaxed => BindingMode::Relaxed,
+            core::service::BindingMode::Ordered => BindingMode::Ordered,
         }
     }
 }
@@ -242,6 +247,7 @@ impl Into<core::service::BindingMode> for BindingMode {
         match self {
             BindingMode::Strict => core::service::BindingMode::Strict,
             BindingMode::Relaxed => core::service::BindingMode::Relaxed,
+            BindingMode::Ordered => core::service::BindingMode::Ordered,
         }
     }
 }
@@ -317,6 +323,7 @@ impl UpdateStrategy {
             UpdateStrategy::None => "none",
             UpdateStrategy::AtOnce => "at-once",
             UpdateStrategy::Rolling => "rolling",
+            UpdateStrategy::Batched => "batched",
         }
     }
 }
@@ -329,6 +336,7 @@ impl FromStr for UpdateStrategy {
             "none" => Ok(UpdateStrategy::None),
             "at-once" => Ok(UpdateStrategy::AtOnce),
             "rolling" => Ok(UpdateStrategy::Rolling),
+            "batched" => Ok(UpdateStrategy::Batched),
             _ => Err(net::err(ErrCode::InvalidPayload, "Invalid update strategy.")),
         }
     }
@@ -338,6 +346,117 @@ impl fmt::Display for UpdateStrategy {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}", self.as_str()) }
 }
 
+impl RestartCondition {
+    fn as_str(&self) -> &str {
+        match *self {
+            RestartCondition::Always => "always",
+            RestartCondition::OnFailure => "on-failure",
+            RestartCondition::Never => "never",
+        }
+    }
+}
+
+impl FromStr for RestartCondition {
+    type Err = NetErr;
+
+    fn from_str(condition: &str) -> Result<Self, Self::Err> {
+        match condition {
+            "always" => Ok(RestartCondition::Always),
+            "on-failure" => Ok(RestartCondition::OnFailure),
+            "never" => Ok(RestartCondition::Never),
+            _ => Err(net::err(ErrCode::InvalidPayload, "Invalid restart policy.")),
+        }
+    }
+}
+
+impl fmt::Display for RestartCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}", self.as_str()) }
+}
+
+impl HealthCheckProbeKind {
+    fn as_str(&self) -> &str {
+        match *self {
+            HealthCheckProbeKind::Http => "http",
+            HealthCheckProbeKind::Tcp => "tcp",
+            HealthCheckProbeKind::Exec => "exec",
+        }
+    }
+}
+
+impl FromStr for HealthCheckProbeKind {
+    type Err = NetErr;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "http" => Ok(HealthCheckProbeKind::Http),
+            "tcp" => Ok(HealthCheckProbeKind::Tcp),
+            "exec" => Ok(HealthCheckProbeKind::Exec),
+            _ => Err(net::err(ErrCode::InvalidPayload, "Invalid health check probe.")),
+        }
+    }
+}
+
+impl fmt::Display for HealthCheckProbeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}", self.as_str()) }
+}
+
+/// How many members of a service group the `batched` update strategy
+/// updates at a time, either as a fixed count or as a percentage of the
+/// group. Written as `3` or `25%`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum UpdateBatchSize {
+    Count(u32),
+    Percent(u32),
+}
+
+impl UpdateBatchSize {
+    /// The number of members in each batch of a group of `members`.
+    /// Percentages are rounded up, and every batch has at least one
+    /// member.
+    pub fn of(self, members: usize) -> usize {
+        let size = match self {
+            UpdateBatchSize::Count(count) => count as usize,
+            UpdateBatchSize::Percent(percent) => (members * percent as usize + 99) / 100,
+        };
+        size.max(1)
+    }
+}
+
+impl Default for UpdateBatchSize {
+    fn default() -> Self { UpdateBatchSize::Count(1) }
+}
+
+impl FromStr for UpdateBatchSize {
+    type Err = NetErr;
+
+    fn from_str(size: &str) -> Result<Self, Self::Err> {
+        let invalid = || net::err(ErrCode::InvalidPayload, "Invalid update batch size.");
+        let size = size.trim();
+        if size.ends_with('%') {
+            match size.trim_end_matches('%').parse() {
+                Ok(percent) if percent > 0 && percent <= 100 => {
+                    Ok(UpdateBatchSize::Percent(percent))
+                }
+                _ => Err(invalid()),
+            }
+        } else {
+            match size.parse() {
+                Ok(count) if count > 0 => Ok(UpdateBatchSize::Count(count)),
+                _ => Err(invalid()),
+            }
+        }
+    }
+}
+
+impl fmt::Display for UpdateBatchSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            UpdateBatchSize::Count(count) => write!(f, "{}", count),
+            UpdateBatchSize::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use toml;
@@ -453,4 +572,62 @@ mod test {
 
         assert!(toml.starts_with(r#"key = "at-once""#));
     }
+
+    #[test]
+    fn restart_condition_from_str() {
+        assert_eq!(RestartCondition::from_str("always").unwrap(),
+                   RestartCondition::Always);
+        assert_eq!(RestartCondition::from_str("on-failure").unwrap(),
+                   RestartCondition::OnFailure);
+        assert_eq!(RestartCondition::from_str("never").unwrap(),
+                   RestartCondition::Never);
+        assert!(RestartCondition::from_str("sometimes").is_err());
+    }
+
+    #[test]
+    fn restart_condition_to_string() {
+        assert_eq!("on-failure", RestartCondition::OnFailure.to_string());
+    }
+
+    #[test]
+    fn health_check_probe_kind_round_trips_through_strings() {
+        for kind in &[HealthCheckProbeKind::Http,
+                      HealthCheckProbeKind::Tcp,
+                      HealthCheckProbeKind::Exec]
+        {
+            assert_eq!(HealthCheckProbeKind::from_str(&kind.to_string()).unwrap(),
+                       *kind);
+        }
+        assert!(HealthCheckProbeKind::from_str("carrier-pigeon").is_err());
+    }
+
+    #[test]
+    fn update_batch_size_from_str() {
+        assert_eq!(UpdateBatchSize::from_str("3").unwrap(),
+                   UpdateBatchSize::Count(3));
+        assert_eq!(UpdateBatchSize::from_str("25%").unwrap(),
+                   UpdateBatchSize::Percent(25));
+        assert_eq!("25%", UpdateBatchSize::Percent(25).to_string());
+        for invalid in &["0", "0%", "101%", "-1", "some", "%"] {
+            assert!(UpdateBatchSize::from_str(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn update_batch_size_of_a_group() {
+        assert_eq!(UpdateBatchSize::Count(3).of(10), 3);
+        assert_eq!(UpdateBatchSize::Percent(25).of(10), 3);
+        assert_eq!(UpdateBatchSize::Percent(50).of(10), 5);
+        assert_eq!(UpdateBatchSize::Percent(10).of(3), 1);
+        assert_eq!(UpdateBatchSize::Percent(100).of(0), 1);
+    }
+
+    #[test]
+    fn process_state_round_trips_through_its_protocol_value() {
+        let state = ProcessState::CrashLooping;
+        let value = (state as i32).to_string();
+
+        assert_eq!(ProcessState::from_str(&value).unwrap(), state);
+        assert_eq!("crash-looping", state.to_string());
+    }
 }
@@ -1,4 +1,5 @@
-use crate::error::Error;
+use crate::{error::Error,
+            event};
 use habitat_butterfly::{member::{Health,
                                  Member,
                                  MemberList,
@@ -16,6 +17,7 @@ use habitat_common::outputln;
 use habitat_core::{self,
                    package::PackageIdent,
                    service::ServiceGroup};
+use habitat_sup_protocol::types::UpdateBatchSize;
 use serde::{ser::SerializeStruct,
             Serialize,
             Serializer};
@@ -144,7 +146,7 @@ impl CensusRing {
             }
         }
 
-        member_list.with_memberships_mlr(|Membership { member, health }| {
+        member_list.with_memberships_mlr(|Membership { member, health, .. }| {
                        for group in self.census_groups.values_mut() {
                            if let Some(census_member) = group.find_member_mut(&member.id) {
                                census_member.update_from_member(&member);
@@ -308,6 +310,11 @@ pub struct ServiceFile {
 pub struct ServiceConfig {
     pub incarnation: u64,
     pub value:       toml::value::Table,
+    /// The user key the configuration was encrypted by, if it was encrypted
+    pub author:      Option<String>,
+    /// The encrypted payload the configuration was gossiped as, if it was encrypted
+    #[serde(skip)]
+    pub sealed:      Option<String>,
 }
 
 #[derive(Debug)]
@@ -369,6 +376,12 @@ impl CensusGroup {
             .filter(|cm| cm.alive() || cm.suspect())
     }
 
+    /// Whether any active member's service has passed a health check
+    /// since it was last started.
+    pub fn has_healthy_member(&self) -> bool {
+        self.active_members().any(|cm| cm.health_check_passed)
+    }
+
     pub fn changed_service_files(&self) -> Vec<&ServiceFile> {
         self.changed_service_files
             .iter()
@@ -402,6 +415,47 @@ impl CensusGroup {
         None
     }
 
+    /// Whether every member in an earlier batch of a `batched` update
+    /// to `release` is running it and healthy, so that this member's batch
+    /// may update. Batches are taken in member id order from the alive
+    /// members other than the update leader, which always updates first.
+    pub fn earlier_batches_updated(&self,
+                                   release: &PackageIdent,
+                                   batch_size: UpdateBatchSize)
+                                   -> bool {
+        match (self.me(), self.update_leader()) {
+            (Some(me), Some(leader)) => {
+                Self::earlier_batches_updated_impl(self.population.values(),
+                                                   me,
+                                                   leader,
+                                                   release,
+                                                   batch_size)
+            }
+            _ => false,
+        }
+    }
+
+    fn earlier_batches_updated_impl<'a>(members: impl Iterator<Item = &'a CensusMember>,
+                                        me: &CensusMember,
+                                        leader: &CensusMember,
+                                        release: &PackageIdent,
+                                        batch_size: UpdateBatchSize)
+                                        -> bool {
+        let alive_members: Vec<_> = members.filter(|cm| cm.alive()).collect();
+        let size = batch_size.of(alive_members.len());
+        let followers: Vec<_> = alive_members.into_iter()
+                                             .filter(|cm| cm.member_id != leader.member_id)
+                                             .collect();
+        match followers.iter().position(|cm| cm.member_id == me.member_id) {
+            Some(position) => {
+                let batch_start = position / size * size;
+                followers[..batch_start].iter()
+                                        .all(|cm| cm.pkg.as_ref() == Some(release) && cm.healthy())
+            }
+            None => false,
+        }
+    }
+
     fn update_from_service_rumors(&mut self, rumors: &HashMap<String, ServiceRumor>) {
         for (member_id, service_rumor) in rumors.iter() {
             // Yeah - we are ourself - we're alive.
@@ -419,7 +473,7 @@ impl CensusGroup {
     }
 
     fn update_from_election_rumor(&mut self, election: &ElectionRumor) {
-        self.leader_id = None;
+        let previous_leader_id = self.leader_id.take();
         for census_member in self.population.values_mut() {
             if census_member.update_from_election_rumor(election) {
                 self.leader_id = Some(census_member.member_id.clone());
@@ -436,6 +490,24 @@ impl CensusGroup {
                 self.election_status = ElectionStatus::ElectionFinished;
             }
         }
+        if let Some(leader_id) = self.newly_elected_leader(previous_leader_id.as_ref()) {
+            event::leader_elected(&self.service_group,
+                                  leader_id,
+                                  election.term,
+                                  *leader_id == self.local_member_id);
+        }
+    }
+
+    /// The leader of this group, if an election in a group this
+    /// Supervisor is a member of has just finished with a leader other
+    /// than `previous_leader_id`.
+    fn newly_elected_leader(&self, previous_leader_id: Option<&MemberId>) -> Option<&MemberId> {
+        if self.election_status != ElectionStatus::ElectionFinished || self.me().is_none() {
+            return None;
+        }
+        self.leader_id
+            .as_ref()
+            .filter(|leader_id| previous_leader_id != Some(*leader_id))
     }
 
     fn update_from_election_update_rumor(&mut self, election: &ElectionUpdateRumor) {
@@ -468,7 +540,11 @@ impl CensusGroup {
                 {
                     self.service_config = Some(ServiceConfig { incarnation:
                                                                    service_config.incarnation,
-                                                               value:       config, });
+                                                               value:       config,
+                                                               author:
+                                                                   service_config.author(),
+                                                               sealed:
+                                                                   service_config.sealed(), });
                 }
             }
             Err(err) => warn!("{}", err),
@@ -579,12 +655,16 @@ pub struct CensusMember {
     pub update_election_is_running: bool,
     pub update_election_is_no_quorum: bool,
     pub update_election_is_finished: bool,
+    pub health_check_passed: bool,
+    pub ready: bool,
+    pub blocked_releases: Vec<PackageIdent>,
     pub sys: SysInfo,
 
     alive:     bool,
     suspect:   bool,
     confirmed: bool,
     departed:  bool,
+    pub tags: BTreeMap<String, String>,
     // Maps must be represented last in a serializable struct for the current version of the toml
     // crate. Additionally, this deserialization method is required to correct any ordering issues
     // with the table being serialized - https://docs.rs/toml/0.4.0/toml/ser/fn.tables_last.html
@@ -610,6 +690,12 @@ impl CensusMember {
         };
         self.sys = rumor.sys.clone();
         self.cfg = toml::from_slice(&rumor.cfg).unwrap_or_default();
+        self.health_check_passed = rumor.health_check_passed;
+        self.ready = rumor.ready;
+        self.blocked_releases = rumor.blocked_releases
+                                     .iter()
+                                     .filter_map(|r| PackageIdent::from_str(r).ok())
+                                     .collect();
     }
 
     fn update_from_election_rumor(&mut self, election: &ElectionRumor) -> bool {
@@ -648,6 +734,7 @@ impl CensusMember {
         self.sys.gossip_ip = member.address.to_string();
         self.sys.gossip_port = u32::from(member.gossip_port);
         self.persistent = true;
+        self.tags = member.tags.clone();
     }
 
     fn update_from_health(&mut self, health: Health) {
@@ -666,6 +753,12 @@ impl CensusMember {
     /// Is this member currently considered to be alive or not?
     pub fn alive(&self) -> bool { self.alive }
 
+    /// Has this member's service passed a health check since it was
+    /// started, and is it ready? A service without a readiness probe is
+    /// always ready, so the health check is what tells whether an
+    /// updated member is working.
+    pub fn healthy(&self) -> bool { self.health_check_passed && self.ready }
+
     pub fn suspect(&self) -> bool { self.suspect }
 
     pub fn confirmed(&self) -> bool { self.confirmed }
@@ -684,7 +777,7 @@ impl<'a> Serialize for CensusMemberProxy<'a> {
     fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
         where S: Serializer
     {
-        let mut strukt = serializer.serialize_struct("census_member", 24)?;
+        let mut strukt = serializer.serialize_struct("census_member", 28)?;
         strukt.serialize_field("member_id", &self.0.member_id)?;
         strukt.serialize_field("pkg", &self.0.pkg)?;
 
@@ -713,11 +806,20 @@ impl<'a> Serialize for CensusMemberProxy<'a> {
                                &self.0.update_election_is_no_quorum)?;
         strukt.serialize_field("update_election_is_finished",
                                &self.0.update_election_is_finished)?;
+        strukt.serialize_field("health_check_passed", &self.0.health_check_passed)?;
+        strukt.serialize_field("ready", &self.0.ready)?;
+        let blocked_releases: Vec<String> = self.0
+                                                .blocked_releases
+                                                .iter()
+                                                .map(ToString::to_string)
+                                                .collect();
+        strukt.serialize_field("blocked_releases", &blocked_releases)?;
         strukt.serialize_field("sys", &self.0.sys)?;
         strukt.serialize_field("alive", &self.0.alive)?;
         strukt.serialize_field("suspect", &self.0.suspect)?;
         strukt.serialize_field("confirmed", &self.0.confirmed)?;
         strukt.serialize_field("departed", &self.0.departed)?;
+        strukt.serialize_field("tags", &self.0.tags)?;
         strukt.serialize_field("cfg", &self.0.cfg)?;
         strukt.end()
     }
@@ -737,6 +839,7 @@ mod tests {
     use super::*;
     use crate::test_helpers::*;
     use habitat_butterfly::{member::{Health,
+                                     Member,
                                      MemberList},
                             rumor::{election::{self,
                                                Election as ElectionRumor,
@@ -771,6 +874,41 @@ mod tests {
         assert_eq!(members.next().unwrap().member_id, "member-b");
     }
 
+    #[test]
+    fn newly_elected_leader_only_reports_changes_in_our_groups() {
+        let (ring, sg_one, sg_two) = test_census_ring();
+        // We aren't a member of the first group, so its elections
+        // aren't ours to report.
+        let census_group_one = ring.census_group_for(&sg_one).unwrap();
+        assert!(census_group_one.newly_elected_leader(None).is_none());
+
+        let mut census_group_two = CensusGroup::new(sg_two, "member-b");
+        census_group_two.population
+                        .insert("member-b".to_string(), CensusMember::default());
+        census_group_two.election_status = ElectionStatus::ElectionFinished;
+        census_group_two.leader_id = Some("member-a".to_string());
+        assert_eq!(census_group_two.newly_elected_leader(None),
+                   Some(&"member-a".to_string()));
+        assert_eq!(census_group_two.newly_elected_leader(Some(&"member-b".to_string())),
+                   Some(&"member-a".to_string()));
+        assert!(census_group_two.newly_elected_leader(Some(&"member-a".to_string()))
+                                .is_none());
+
+        census_group_two.election_status = ElectionStatus::ElectionInProgress;
+        assert!(census_group_two.newly_elected_leader(None).is_none());
+    }
+
+    #[test]
+    fn member_tags_are_copied_from_the_member_list() {
+        let (ring, _, sg_two) = test_census_ring();
+        let census_group_two = ring.census_group_for(&sg_two).unwrap();
+        let mut members = census_group_two.members();
+        let member_a = members.next().unwrap();
+        assert_eq!(member_a.tags.get("zone").map(String::as_str),
+                   Some("us-east-1a"));
+        assert!(members.next().unwrap().tags.is_empty());
+    }
+
     #[test]
     fn census_ring_proxy_conforms_to_the_schema() {
         let (ring, ..) = test_census_ring();
@@ -834,6 +972,11 @@ mod tests {
         election_update_store.insert_rsw(election_update);
 
         let member_list = MemberList::new();
+        let mut member_a = Member::default();
+        member_a.id = "member-a".to_string();
+        member_a.tags
+                .insert("zone".to_string(), "us-east-1a".to_string());
+        member_list.insert_mlw(member_a, Health::Alive);
 
         let service_config_store: RumorStore<ServiceConfigRumor> = RumorStore::default();
         let service_file_store: RumorStore<ServiceFileRumor> = RumorStore::default();
@@ -869,11 +1012,15 @@ mod tests {
                        update_election_is_running: false,
                        update_election_is_no_quorum: false,
                        update_election_is_finished: false,
+                       health_check_passed: false,
+                       ready: true,
+                       blocked_releases: Vec::new(),
                        sys: SysInfo::default(),
                        alive: health == Health::Alive,
                        suspect: health == Health::Suspect,
                        confirmed: health == Health::Confirmed,
                        departed: health == Health::Departed,
+                       tags: BTreeMap::new(),
                        cfg: toml::value::Table::new(), }
     }
 
@@ -900,10 +1047,102 @@ mod tests {
         assert!(active_members.next().is_none());
     }
 
+    #[test]
+    fn has_healthy_member_only_considers_active_members() {
+        let sg: ServiceGroup =
+            "test-service.default".parse()
+                                  .expect("This should be a valid service group");
+        let mut census_group = CensusGroup::new(sg, &"live-one".to_string());
+        assert!(!census_group.has_healthy_member());
+
+        let mut departed = test_census_member("departed-one", Health::Departed);
+        departed.health_check_passed = true;
+        census_group.population
+                    .insert(departed.member_id.clone(), departed);
+        let live = test_census_member("live-one", Health::Alive);
+        census_group.population.insert(live.member_id.clone(), live);
+        assert!(!census_group.has_healthy_member());
+
+        census_group.population
+                    .get_mut("live-one")
+                    .unwrap()
+                    .health_check_passed = true;
+        assert!(census_group.has_healthy_member());
+    }
+
     fn assert_eq_member_ids(cm: Option<&CensusMember>, id: Option<&str>) {
         assert_eq!(cm.map(|cm| cm.member_id.as_str()), id);
     }
 
+    fn test_batch_member(id: &str, pkg: &str, ready: bool) -> CensusMember {
+        let mut member = test_census_member(id, Health::Alive);
+        member.pkg = Some(pkg.parse().unwrap());
+        member.health_check_passed = true;
+        member.ready = ready;
+        member
+    }
+
+    #[test]
+    fn earlier_batches_updated_lets_the_first_batch_go_at_once() {
+        let old = "core/redis/4.0.14/20190319155852";
+        let new = "core/redis/5.0.5/20190812120000";
+        let release = new.parse().unwrap();
+        let members = vec![test_batch_member("a", old, true),
+                           test_batch_member("b", new, true),
+                           test_batch_member("c", old, true),
+                           test_batch_member("d", old, true),
+                           test_batch_member("e", old, true),];
+        let leader = &members[1];
+        let size = UpdateBatchSize::Count(2);
+
+        for me in &[&members[0], &members[2]] {
+            assert!(CensusGroup::earlier_batches_updated_impl(members.iter(),
+                                                              me,
+                                                              leader,
+                                                              &release,
+                                                              size));
+        }
+        for me in &[&members[3], &members[4]] {
+            assert!(!CensusGroup::earlier_batches_updated_impl(members.iter(),
+                                                               me,
+                                                               leader,
+                                                               &release,
+                                                               size));
+        }
+    }
+
+    #[test]
+    fn earlier_batches_updated_waits_for_earlier_members_to_be_healthy() {
+        let old = "core/redis/4.0.14/20190319155852";
+        let new = "core/redis/5.0.5/20190812120000";
+        let release = new.parse().unwrap();
+        let mut members = vec![test_batch_member("a", new, true),
+                               test_batch_member("b", new, false),
+                               test_batch_member("c", old, true),
+                               test_census_member("d", Health::Confirmed),];
+        // A quarter of the three alive members rounds up to a batch of one.
+        let size = UpdateBatchSize::Percent(25);
+
+        assert!(!CensusGroup::earlier_batches_updated_impl(members.iter(),
+                                                           &members[2],
+                                                           &members[0],
+                                                           &release,
+                                                           size));
+        members[1].ready = true;
+        members[1].health_check_passed = false;
+        assert!(!CensusGroup::earlier_batches_updated_impl(members.iter(),
+                                                           &members[2],
+                                                           &members[0],
+                                                           &release,
+                                                           size));
+        members[1].health_check_passed = true;
+        assert!(CensusGroup::earlier_batches_updated_impl(members.iter(),
+                                                          &members[2],
+                                                          &members[0],
+                                                          &release,
+                                                          size));
+    }
+
     #[test]
     fn previous_peer_with_no_members() {
         let me = test_census_member("me", Health::Alive);
@@ -65,6 +65,20 @@ mod test {
                         "BIND" => ["service.group1", "service.group2"],
                         "PKG_IDENT_OR_ARTIFACT" => "core/redis");
 
+        assert_cli_cmd!(should_handle_multiple_tag_flags,
+                        no_feature_flags(),
+                        "hab-sup run --tag zone=us-east-1a --tag rack=12",
+                        "TAG" => ["zone=us-east-1a", "rack=12"]);
+
+        #[test]
+        fn tag_must_be_a_key_value_pair() {
+            for tag in &["zone", "=us-east-1a"] {
+                let cmd_vec = vec!["hab-sup", "run", "--tag", *tag];
+                assert!(cli(no_feature_flags()).get_matches_from_safe(cmd_vec)
+                                               .is_err());
+            }
+        }
+
         #[test]
         fn local_gossip_mode_and_listen_gossip_are_mutually_exclusive() {
             let cmd_vec = Vec::from_iter("hab-sup run --listen-gossip 1.1.1.1:1111 \
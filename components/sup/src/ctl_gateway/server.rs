@@ -292,7 +292,25 @@ impl SrvHandler {
                 Ok(CtlCommand::new(ctl_sender,
                                    msg.transaction(),
                                    move |state, req, _action_sender| {
-                                       commands::service_cfg_validate(state, req, m.clone())
+                                       commands::service_cfg_validate_msr(state, req, m.clone())
+                                   }))
+            }
+            "SvcCfgExplain" => {
+                let m = msg.parse::<protocol::ctl::SvcCfgExplain>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |state, req, _action_sender| {
+                                       commands::service_cfg_explain_msr(state, req, m.clone())
+                                   }))
+            }
+            "SvcCfgHistory" => {
+                let m = msg.parse::<protocol::ctl::SvcCfgHistory>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |state, req, _action_sender| {
+                                       commands::service_cfg_history_msr(state, req, m.clone())
                                    }))
             }
             "SvcLoad" => {
@@ -352,6 +370,69 @@ impl SrvHandler {
                                        commands::supervisor_depart(state, req, m.clone())
                                    }))
             }
+            "RingKeyAccept" => {
+                let m = msg.parse::<protocol::ctl::RingKeyAccept>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |state, req, _action_sender| {
+                                       commands::ring_key_accept_srkw(state, req, m.clone())
+                                   }))
+            }
+            "RingKeyPromote" => {
+                let m = msg.parse::<protocol::ctl::RingKeyPromote>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |state, req, _action_sender| {
+                                       commands::ring_key_promote_srkw(state, req, m.clone())
+                                   }))
+            }
+            "RingKeyRetire" => {
+                let m = msg.parse::<protocol::ctl::RingKeyRetire>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |state, req, _action_sender| {
+                                       commands::ring_key_retire_srkw(state, req, m.clone())
+                                   }))
+            }
+            "RingKeyList" => {
+                let m = msg.parse::<protocol::ctl::RingKeyList>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |state, req, _action_sender| {
+                                       commands::ring_key_list_srkr(state, req, m.clone())
+                                   }))
+            }
+            "RingStatus" => {
+                let m = msg.parse::<protocol::ctl::RingStatus>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |state, req, _action_sender| {
+                                       commands::ring_status_mlr_smr(state, req, m.clone())
+                                   }))
+            }
+            "RingRumors" => {
+                let m = msg.parse::<protocol::ctl::RingRumors>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |state, req, _action_sender| {
+                                       commands::ring_rumors_rsr_rhr(state, req, m.clone())
+                                   }))
+            }
+            "RingRumorPurge" => {
+                let m = msg.parse::<protocol::ctl::RingRumorPurge>()
+                           .map_err(HandlerError::from)?;
+                Ok(CtlCommand::new(ctl_sender,
+                                   msg.transaction(),
+                                   move |state, req, _action_sender| {
+                                       commands::ring_rumor_purge(state, req, m.clone())
+                                   }))
+            }
             _ => {
                 warn!("Unhandled message, {}", msg.message_id());
                 Err(HandlerError::from(io::Error::from(io::ErrorKind::InvalidData)))
@@ -97,6 +97,8 @@ pub enum Error {
     SpecWatcherGlob(glob::PatternError),
     StrFromUtf8Error(str::Utf8Error),
     StringFromUtf8Error(string::FromUtf8Error),
+    SupConfigFileIO(PathBuf, io::Error),
+    SupConfigFileParse(PathBuf, toml::de::Error),
     TLSError(rustls::TLSError),
     TomlEncode(toml::ser::Error),
     TryRecvError(mpsc::TryRecvError),
@@ -242,6 +244,16 @@ impl fmt::Display for Error {
             Error::SpecWatcherGlob(ref e) => e.to_string(),
             Error::StrFromUtf8Error(ref e) => e.to_string(),
             Error::StringFromUtf8Error(ref e) => e.to_string(),
+            Error::SupConfigFileIO(ref path, ref err) => {
+                format!("Unable to read Supervisor config file, {}, {}",
+                        path.display(),
+                        err)
+            }
+            Error::SupConfigFileParse(ref path, ref err) => {
+                format!("Unable to parse Supervisor config file, {}, {}",
+                        path.display(),
+                        err)
+            }
             Error::TLSError(ref e) => e.to_string(),
             Error::TomlEncode(ref e) => format!("Failed to encode TOML: {}", e),
             Error::TryRecvError(ref err) => err.to_string(),
@@ -1,8 +1,13 @@
 //! Main interface for a stream of events the Supervisor can send out
 //! in the course of its operations.
 //!
-//! Currently, the Supervisor is able to send events to a [NATS][1]
-//! server. The `init_stream` function must be called
+//! By default, the Supervisor sends events to a [NATS][1]
+//! server. Alternatively, the same events may be sent to a local
+//! JSON-lines file, a Unix domain socket, or an HTTP webhook (see the
+//! `sink` module). Events are written to an on-disk spool as they are
+//! sent, and delivered from there, so that they survive outages of the
+//! sink and restarts of the Supervisor (see the `spool` module). The
+//! `init_stream` function must be called
 //! before sending events to initialize the publishing thread in the
 //! background. Thereafter, you can pass "event" structs to the
 //! `event` function, which will publish the event to the stream.
@@ -12,43 +17,72 @@
 //! [1]:https://github.com/nats-io/nats-server
 
 mod error;
+mod sink;
+mod spool;
 mod stream;
 mod types;
 
 pub(crate) use self::types::ServiceMetadata;
-use self::types::{EventMessage,
-                  EventMetadata,
-                  HealthCheckEvent,
-                  ServiceStartedEvent,
-                  ServiceStoppedEvent,
-                  ServiceUpdateStartedEvent};
+use self::{sink::{EventSink,
+                  FileSink,
+                  WebhookSink},
+           spool::Spool,
+           stream::NatsSink,
+           types::{EventMessage,
+                   EventMetadata,
+                   HealthCheckEvent,
+                   HookFailedEvent,
+                   LeaderElectedEvent,
+                   ServiceBindChangedEvent,
+                   ServiceConfigAppliedEvent,
+                   ServiceCrashLoopingEvent,
+                   ServiceFileAppliedEvent,
+                   ServiceStartedEvent,
+                   ServiceStoppedEvent,
+                   ServiceUpdateFinishedEvent,
+                   ServiceUpdateRolledBackEvent,
+                   ServiceUpdateStartedEvent}};
 use crate::{manager::{service::{HealthCheckHookStatus,
                                 HealthCheckResult,
                                 ProcessOutput,
                                 Service,
                                 StandardStreams},
-                      sys::Sys},
-            sup_futures::FutureHandle};
+                      sys::Sys}};
 use clap::ArgMatches;
 pub use error::{Error,
                 Result};
-use futures::sync::mpsc::Sender;
-use habitat_common::types::{AutomateAuthToken,
-                            EventStreamConnectMethod,
-                            EventStreamMetadata,
-                            EventStreamServerCertificate};
-use habitat_core::package::ident::PackageIdent;
+use habitat_common::{templating::package::Pkg,
+                     types::{AutomateAuthToken,
+                             EventStreamConnectMethod,
+                             EventStreamMetadata,
+                             EventStreamServerCertificate,
+                             EventStreamSink,
+                             EventStreamSpoolLimits}};
+use habitat_core::{package::ident::PackageIdent,
+                   service::{ServiceBind,
+                             ServiceGroup}};
 use parking_lot::Mutex;
 use state::Container;
 use std::{net::SocketAddr,
-          sync::Once,
+          path::PathBuf,
+          sync::{mpsc::{SyncSender,
+                        TrySendError},
+                 Arc,
+                 Once},
           time::Duration};
-use tokio::runtime::Runtime;
 
 const SERVICE_STARTED_SUBJECT: &str = "habitat.event.service_started";
 const SERVICE_STOPPED_SUBJECT: &str = "habitat.event.service_stopped";
 const SERVICE_UPDATE_STARTED_SUBJECT: &str = "habitat.event.service_update_started";
 const HEALTHCHECK_SUBJECT: &str = "habitat.event.healthcheck";
+const SERVICE_UPDATE_FINISHED_SUBJECT: &str = "habitat.event.service_update_finished";
+const SERVICE_CONFIG_APPLIED_SUBJECT: &str = "habitat.event.service_config_applied";
+const SERVICE_FILE_APPLIED_SUBJECT: &str = "habitat.event.service_file_applied";
+const LEADER_ELECTED_SUBJECT: &str = "habitat.event.leader_elected";
+const HOOK_FAILED_SUBJECT: &str = "habitat.event.hook_failed";
+const SERVICE_BIND_CHANGED_SUBJECT: &str = "habitat.event.service_bind_changed";
+const SERVICE_CRASH_LOOPING_SUBJECT: &str = "habitat.event.service_crash_looping";
+const SERVICE_UPDATE_ROLLED_BACK_SUBJECT: &str = "habitat.event.service_update_rolled_back";
 
 static INIT: Once = Once::new();
 lazy_static! {
@@ -62,21 +96,25 @@ lazy_static! {
 }
 type EventStreamContainer = Mutex<EventStream>;
 
-/// Starts a new thread for sending events to a NATS Streaming
-/// server. Stashes the handle to the stream, as well as the core
-/// event information that will be a part of all events, in a global
-/// static reference for access later.
+/// Starts a new thread for sending events to the configured event
+/// sink, spooling them in `spool_dir` until they are delivered.
+/// Stashes the handle to the stream, as well as the core event
+/// information that will be a part of all events, in a global static
+/// reference for access later.
 pub fn init_stream(config: EventStreamConfig,
                    event_core: EventCore,
-                   runtime: &mut Runtime)
+                   spool_dir: PathBuf)
                    -> Result<()> {
     // call_once can't return a Result (or anything), so we'll fake it
     // by hanging onto any error we might receive.
     let mut return_value: Result<()> = Ok(());
 
     INIT.call_once(|| {
-            let conn_info = EventStreamConnectionInfo::new(&event_core.supervisor_id, config);
-            match stream::init_stream(conn_info, runtime) {
+            let event_stream = Spool::open(spool_dir, config.spool_limits).and_then(|spool| {
+                                   let sink = init_sink(config, &event_core)?;
+                                   sink::spawn(Arc::new(Mutex::new(spool)), sink)
+                               });
+            match event_stream {
                 Ok(event_stream) => {
                     EVENT_STREAM.set(EventStreamContainer::new(event_stream));
                     EVENT_CORE.set(event_core);
@@ -88,16 +126,34 @@ pub fn init_stream(config: EventStreamConfig,
     return_value
 }
 
-/// Stop the event stream future so we can gracefully shutdown the runtime.
+/// Create the sink that events will be published to.
+fn init_sink(config: EventStreamConfig, event_core: &EventCore) -> Result<Box<dyn EventSink>> {
+    let kind = config.sink;
+    let sink: Box<dyn EventSink> = match kind {
+        EventStreamSink::Nats => {
+            let conn_info = EventStreamConnectionInfo::new(&event_core.supervisor_id, config);
+            Box::new(NatsSink::connect(conn_info)?)
+        }
+        EventStreamSink::File => Box::new(FileSink::new(config.url)?),
+        #[cfg(unix)]
+        EventStreamSink::UnixSocket => Box::new(sink::UnixSocketSink::new(config.url)),
+        #[cfg(not(unix))]
+        EventStreamSink::UnixSocket => return Err(Error::UnsupportedSink(kind)),
+        EventStreamSink::Webhook => Box::new(WebhookSink::new(&config.url)?),
+    };
+    Ok(sink)
+}
+
+/// Stop the event stream's delivery thread. Events sent from then on
+/// are still spooled, and will be delivered the next time the
+/// Supervisor starts.
 ///
 /// `init_stream` and `stop_stream` cannot be called more than once. The singleton event stream can
 /// only be set once. If `init_stream` is called after calling `stop_stream` no new event stream
 /// will be started.
 pub fn stop_stream() {
     if let Some(e) = EVENT_STREAM.try_get::<EventStreamContainer>() {
-        if let Some(h) = e.lock().handle.take() {
-            h.terminate();
-        }
+        e.lock().wake.take();
     }
 }
 
@@ -109,8 +165,10 @@ pub struct EventStreamConfig {
     application:        String,
     site:               Option<String>,
     meta:               EventStreamMetadata,
-    token:              AutomateAuthToken,
+    token:              Option<AutomateAuthToken>,
     url:                String,
+    sink:               EventStreamSink,
+    spool_limits:       EventStreamSpoolLimits,
     connect_method:     EventStreamConnectMethod,
     server_certificate: Option<EventStreamServerCertificate>,
 }
@@ -125,10 +183,13 @@ impl<'a> From<&'a ArgMatches<'a>> for EventStreamConfig {
                                                  .expect("Required option for EventStream feature"),
                             site:               m.value_of("EVENT_STREAM_SITE").map(str::to_string),
                             meta:               EventStreamMetadata::from(m),
-                            token:              AutomateAuthToken::from(m),
+                            token:              m.value_of(AutomateAuthToken::ARG_NAME)
+                                                 .map(|_| AutomateAuthToken::from(m)),
                             url:                m.value_of("EVENT_STREAM_URL")
                                                  .map(str::to_string)
                                                  .expect("Required option for EventStream feature"),
+                            sink:               EventStreamSink::from(m),
+                            spool_limits:       EventStreamSpoolLimits::from(m),
                             connect_method:     EventStreamConnectMethod::from(m),
                             server_certificate: EventStreamServerCertificate::from_arg_matches(m), }
     }
@@ -140,7 +201,7 @@ pub struct EventStreamConnectionInfo {
     pub name:               String,
     pub verbose:            bool,
     pub cluster_uri:        String,
-    pub auth_token:         AutomateAuthToken,
+    pub auth_token:         Option<AutomateAuthToken>,
     pub connect_method:     EventStreamConnectMethod,
     pub server_certificate: Option<EventStreamServerCertificate>,
 }
@@ -218,6 +279,114 @@ pub fn service_update_started(service: &Service, update: &PackageIdent) {
     }
 }
 
+/// Send an event once a Service is running again after an update
+/// from `previous`.
+pub fn service_update_finished(service: &Service, previous: &PackageIdent) {
+    if stream_initialized() {
+        publish(SERVICE_UPDATE_FINISHED_SUBJECT,
+                ServiceUpdateFinishedEvent { event_metadata:         None,
+                                             service_metadata:
+                                                 Some(service.to_service_metadata()),
+                                             previous_package_ident: previous.to_string(), });
+    }
+}
+
+/// Send an event when gossiped configuration is applied to a Service.
+pub fn service_config_applied(service: &Service, incarnation: u64) {
+    if stream_initialized() {
+        publish(SERVICE_CONFIG_APPLIED_SUBJECT,
+                ServiceConfigAppliedEvent { event_metadata: None,
+                                            service_metadata:
+                                                Some(service.to_service_metadata()),
+                                            incarnation });
+    }
+}
+
+/// Send an event when a gossiped file is written for a Service.
+pub fn service_file_applied(service: &Service, filename: &str, incarnation: u64) {
+    if stream_initialized() {
+        publish(SERVICE_FILE_APPLIED_SUBJECT,
+                ServiceFileAppliedEvent { event_metadata: None,
+                                          service_metadata: Some(service.to_service_metadata()),
+                                          filename: filename.to_string(),
+                                          incarnation });
+    }
+}
+
+/// Send an event when an election in one of our service groups
+/// finishes with a new leader.
+pub fn leader_elected(service_group: &ServiceGroup, leader_id: &str, term: u64, is_local: bool) {
+    if stream_initialized() {
+        publish(LEADER_ELECTED_SUBJECT,
+                LeaderElectedEvent { event_metadata: None,
+                                     service_group: service_group.to_string(),
+                                     leader_id: leader_id.to_string(),
+                                     term,
+                                     is_local });
+    }
+}
+
+/// Send an event when a hook exits unsuccessfully. `exit_status` is
+/// `None` if the hook was terminated by a signal.
+pub fn hook_failed(pkg: &Pkg, hook: &str, exit_status: Option<i32>) {
+    if stream_initialized() {
+        publish(HOOK_FAILED_SUBJECT,
+                HookFailedEvent { event_metadata: None,
+                                  package_ident: pkg.ident.to_string(),
+                                  hook: hook.to_string(),
+                                  exit_status });
+    }
+}
+
+/// Send an event when the active members of a service group that
+/// `service` is bound to change.
+pub fn service_bind_changed(service: &Service,
+                            bind: &ServiceBind,
+                            added_members: Vec<String>,
+                            removed_members: Vec<String>) {
+    if stream_initialized() {
+        publish(SERVICE_BIND_CHANGED_SUBJECT,
+                ServiceBindChangedEvent { event_metadata: None,
+                                          service_metadata: Some(service.to_service_metadata()),
+                                          bind_name: bind.name().to_string(),
+                                          bind_service_group: bind.service_group().to_string(),
+                                          added_members,
+                                          removed_members });
+    }
+}
+
+/// Send an event when `service`'s process has exited more often than
+/// its restart policy allows.
+pub fn service_crash_looping(service: &Service, restarts: u32, window_seconds: u64) {
+    if stream_initialized() {
+        publish(SERVICE_CRASH_LOOPING_SUBJECT,
+                ServiceCrashLoopingEvent { event_metadata: None,
+                                           service_metadata:
+                                               Some(service.to_service_metadata()),
+                                           restarts,
+                                           window_seconds });
+    }
+}
+
+/// Send an event when an updated Service failed within its rollback
+/// window and is being rolled back to `previous`.
+// Takes metadata directly, rather than a `&Service`, because the
+// rollback is made once the Manager has let go of the service.
+pub fn service_update_rolled_back(service_metadata: ServiceMetadata,
+                                  failed: &PackageIdent,
+                                  previous: &PackageIdent,
+                                  reason: &str) {
+    if stream_initialized() {
+        publish(SERVICE_UPDATE_ROLLED_BACK_SUBJECT,
+                ServiceUpdateRolledBackEvent { event_metadata:         None,
+                                               service_metadata:
+                                                   Some(service_metadata),
+                                               failed_package_ident:   failed.to_string(),
+                                               previous_package_ident: previous.to_string(),
+                                               reason:                 reason.to_string(), });
+    }
+}
+
 // Takes metadata directly, rather than a `&Service` like other event
 // functions, because of how the asynchronous health checking
 // currently works. Revisit when async/await + Pin is all stabilized.
@@ -282,34 +451,44 @@ fn publish(subject: &'static str, mut event: impl EventMessage) {
 /// The subject and payload of an event message.
 #[derive(Debug)]
 struct EventPacket {
-    subject: &'static str,
+    subject: String,
     payload: Vec<u8>,
 }
 
 impl EventPacket {
-    fn new(subject: &'static str, payload: Vec<u8>) -> Self { Self { subject, payload } }
+    fn new(subject: &str, payload: Vec<u8>) -> Self {
+        Self { subject: subject.to_string(),
+               payload }
+    }
 }
 
 /// A lightweight handle for the event stream. All events get to the
 /// event stream through this.
 struct EventStream {
-    sender: Sender<EventPacket>,
-    handle: Option<FutureHandle>,
+    spool: Arc<Mutex<Spool>>,
+    /// Tells the delivery thread there are new events in the spool.
+    wake:  Option<SyncSender<()>>,
 }
 
 impl EventStream {
-    fn new(sender: Sender<EventPacket>, handle: FutureHandle) -> Self {
-        Self { sender,
-               handle: Some(handle) }
+    fn new(spool: Arc<Mutex<Spool>>, wake: SyncSender<()>) -> Self {
+        Self { spool,
+               wake: Some(wake) }
     }
 
-    /// Queues an event to be sent out.
+    /// Spools an event to be sent out.
     fn send(&mut self, event_packet: EventPacket) {
-        trace!("About to queue an event: {:?}", event_packet);
-        // If the server is down, the event stream channel may fill up. If this happens we are ok
-        // dropping events.
-        if let Err(e) = self.sender.try_send(event_packet) {
-            error!("Failed to queue event: {}", e);
+        trace!("About to spool an event: {:?}", event_packet);
+        if let Err(e) = self.spool.lock().push(&event_packet) {
+            error!("Failed to spool event: {}", e);
+            return;
+        }
+        if let Some(ref wake) = self.wake {
+            // A full channel means the delivery thread has yet to
+            // pick up an earlier wake, and will find this event too.
+            if let Err(TrySendError::Disconnected(_)) = wake.try_send(()) {
+                error!("Event delivery thread has stopped; event left in the spool");
+            }
         }
     }
 }
@@ -318,21 +497,33 @@ impl EventStream {
 mod tests {
     use super::*;
     use crate::prost::Message;
-    use futures::{future::Future,
-                  stream::Stream,
-                  sync::mpsc as futures_mpsc};
     #[cfg(windows)]
     use habitat_core::os::process::windows_child::ExitStatus;
     #[cfg(unix)]
     use std::{os::unix::process::ExitStatusExt,
               process::ExitStatus};
+    use tempfile::TempDir;
+
+    /// A sink that keeps every event it is sent.
+    #[derive(Default)]
+    struct CollectingSink(Vec<EventPacket>);
+
+    impl EventSink for CollectingSink {
+        fn publish(&mut self, packet: &EventPacket) -> Result<()> {
+            self.0.push(EventPacket::new(&packet.subject, packet.payload.clone()));
+            Ok(())
+        }
+    }
 
     #[test]
     #[cfg(any(unix, windows))]
     fn health_check_event() {
-        let (tx, rx) = futures_mpsc::channel(4);
-        let event_stream = EventStream { sender: tx,
-                                         handle: None, };
+        let tmpdir = TempDir::new().unwrap();
+        let limits = EventStreamSpoolLimits { max_bytes: 1024 * 1024,
+                                              max_age:   Duration::from_secs(60 * 60), };
+        let spool = Arc::new(Mutex::new(Spool::open(tmpdir.path(), limits).unwrap()));
+        let event_stream = EventStream { spool: Arc::clone(&spool),
+                                         wake:  None, };
         EVENT_STREAM.set(EventStreamContainer::new(event_stream));
         EVENT_CORE.set(EventCore { supervisor_id: String::from("supervisor_id"),
                                    ip_address:    "127.0.0.1:8080".parse().unwrap(),
@@ -369,7 +560,10 @@ mod tests {
         health_check(ServiceMetadata::default(),
                      HealthCheckResult::Unknown,
                      HealthCheckHookStatus::Ran(process_output, Duration::from_secs(15)));
-        let events = rx.take(4).collect().wait().unwrap();
+        let mut sink = CollectingSink::default();
+        spool::replay(&spool, &mut sink).unwrap();
+        let events = sink.0;
+        assert_eq!(events.len(), 4);
 
         let event = HealthCheckEvent::decode(&events[0].payload).unwrap();
         assert_eq!(event.result, 0);
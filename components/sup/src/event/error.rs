@@ -1,5 +1,6 @@
 //! Event subsystem-specific error handling
 
+use habitat_common::types::EventStreamSink;
 use habitat_http_client;
 use nats::{native_tls,
            NatsError};
@@ -17,6 +18,8 @@ pub enum Error {
     Io(io::Error),
     NativeTls(native_tls::Error),
     Nats(NatsError),
+    UnsupportedSink(EventStreamSink),
+    Webhook(String),
 }
 
 // TODO (CM): I would have like to have derived Fail on our Error
@@ -37,6 +40,10 @@ impl fmt::Display for Error {
             Error::Io(_) => "{}".fmt(f),
             Error::NativeTls(e) => format!("TLS error '{}'", e).fmt(f),
             Error::Nats(e) => format!("NATS event stream error '{}'", e).fmt(f),
+            Error::UnsupportedSink(s) => {
+                format!("The '{}' event sink is not supported on this platform", s).fmt(f)
+            }
+            Error::Webhook(e) => format!("Event webhook error '{}'", e).fmt(f),
         }
     }
 }
@@ -49,6 +56,8 @@ impl error::Error for Error {
             Error::Io(ref e) => Some(e),
             Error::Nats(ref e) => Some(e),
             Error::NativeTls(ref e) => Some(e),
+            Error::UnsupportedSink(_) => None,
+            Error::Webhook(_) => None,
         }
     }
 }
@@ -0,0 +1,270 @@
+//! Backends that events can be published to.
+//!
+//! Every sink receives exactly the same `EventPacket`s (a subject and
+//! the protobuf-encoded event), so consumers see identical payloads
+//! regardless of how they receive them. The file and Unix domain
+//! socket sinks write one JSON object per line, carrying the subject
+//! and the base64-encoded protobuf payload; the webhook sink POSTs the
+//! raw protobuf bytes with the subject in a header.
+
+use crate::{event::{spool::{self,
+                           Spool},
+                    Error,
+                    EventPacket,
+                    EventStream,
+                    Result},
+            PRODUCT,
+            VERSION};
+use habitat_http_client::ApiClient;
+use parking_lot::Mutex;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::{fs::{self,
+               File,
+               OpenOptions},
+          io::Write,
+          path::PathBuf,
+          sync::{mpsc::{self,
+                        Receiver,
+                        RecvTimeoutError},
+                 Arc},
+          thread,
+          time::{Duration,
+                 Instant}};
+
+/// The HTTP header that carries the subject of a webhook event.
+pub const WEBHOOK_SUBJECT_HEADER: &str = "X-Habitat-Event-Subject";
+
+/// How long a webhook request may take before it is considered to
+/// have failed.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the spool is replayed to the sink, even if no new events
+/// arrive. After a failure, this is also how long the sink is left
+/// alone before it is tried again.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Something that can deliver events to an external consumer.
+///
+/// Publishing happens on the event stream's delivery thread, one event
+/// at a time and in the order they were generated. A failure to
+/// publish leaves the event in the spool to be tried again later;
+/// implementations should try to re-establish any broken connection
+/// on the next event.
+pub(super) trait EventSink: Send {
+    fn publish(&mut self, packet: &EventPacket) -> Result<()>;
+}
+
+/// Start a thread that delivers the events in `spool` to `sink`,
+/// returning the handle through which events are spooled.
+pub(super) fn spawn(spool: Arc<Mutex<Spool>>, mut sink: Box<dyn EventSink>) -> Result<EventStream> {
+    let (wake_tx, wake_rx) = mpsc::sync_channel(1);
+    let delivery_spool = Arc::clone(&spool);
+    thread::Builder::new().name(String::from("event-delivery"))
+                          .spawn(move || deliver(&delivery_spool, &mut *sink, &wake_rx))?;
+    Ok(EventStream::new(spool, wake_tx))
+}
+
+/// Replay the spool to `sink` whenever `wake` signals that an event
+/// was spooled, and every `FLUSH_INTERVAL` regardless. Returns once
+/// the event stream is stopped; anything still in the spool is
+/// delivered the next time the Supervisor starts.
+fn deliver(spool: &Mutex<Spool>, sink: &mut dyn EventSink, wake: &Receiver<()>) {
+    loop {
+        let failed = match spool::replay(spool, sink) {
+            Ok(()) => false,
+            Err(e) => {
+                error!("Failed to publish events, '{}'", e);
+                true
+            }
+        };
+        let next_replay = Instant::now() + FLUSH_INTERVAL;
+        loop {
+            let now = Instant::now();
+            if now >= next_replay {
+                break;
+            }
+            match wake.recv_timeout(next_replay - now) {
+                // Events spooled while the sink is failing wait for
+                // the next replay.
+                Ok(()) if failed => {}
+                Ok(()) | Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+}
+
+/// Render a packet as a single line of JSON, including the trailing
+/// newline.
+fn json_line(packet: &EventPacket) -> Vec<u8> {
+    let mut line = json!({ "subject": packet.subject,
+                           "payload": base64::encode(&packet.payload) }).to_string()
+                                                                        .into_bytes();
+    line.push(b'\n');
+    line
+}
+
+/// Appends events to a local file as JSON lines.
+pub struct FileSink {
+    path: PathBuf,
+    file: File,
+}
+
+impl FileSink {
+    pub fn new<P>(path: P) -> Result<Self>
+        where P: Into<PathBuf>
+    {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(FileSink { path, file })
+    }
+}
+
+impl EventSink for FileSink {
+    fn publish(&mut self, packet: &EventPacket) -> Result<()> {
+        trace!("Appending event to {}", self.path.display());
+        self.file.write_all(&json_line(packet))?;
+        Ok(())
+    }
+}
+
+/// Writes events as JSON lines to a listener on a Unix domain socket.
+///
+/// The connection is established lazily, and is dropped and
+/// re-established on the next event whenever a write fails, so the
+/// listener may come and go while the Supervisor runs.
+#[cfg(unix)]
+pub struct UnixSocketSink {
+    path:   PathBuf,
+    stream: Option<UnixStream>,
+}
+
+#[cfg(unix)]
+impl UnixSocketSink {
+    pub fn new<P>(path: P) -> Self
+        where P: Into<PathBuf>
+    {
+        UnixSocketSink { path:   path.into(),
+                         stream: None, }
+    }
+}
+
+#[cfg(unix)]
+impl EventSink for UnixSocketSink {
+    fn publish(&mut self, packet: &EventPacket) -> Result<()> {
+        if self.stream.is_none() {
+            self.stream = Some(UnixStream::connect(&self.path)?);
+        }
+        let result = self.stream
+                         .as_mut()
+                         .expect("stream was just connected")
+                         .write_all(&json_line(packet));
+        if result.is_err() {
+            self.stream = None;
+        }
+        result.map_err(Error::from)
+    }
+}
+
+/// POSTs each event's protobuf payload to an HTTP(S) endpoint.
+///
+/// Requests give up after `WEBHOOK_TIMEOUT`, so an endpoint that
+/// stops responding holds up delivery no longer than one that can't be
+/// reached; the event stays in the spool and is tried again later.
+pub struct WebhookSink {
+    client: ApiClient,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str) -> Result<Self> {
+        Ok(WebhookSink { client: ApiClient::with_timeout(url,
+                                                         PRODUCT,
+                                                         VERSION,
+                                                         None,
+                                                         WEBHOOK_TIMEOUT)?, })
+    }
+}
+
+impl EventSink for WebhookSink {
+    fn publish(&mut self, packet: &EventPacket) -> Result<()> {
+        let response = self.client
+                           .post("")
+                           .header(WEBHOOK_SUBJECT_HEADER, packet.subject.as_str())
+                           .header("Content-Type", "application/x-protobuf")
+                           .body(packet.payload.clone())
+                           .send()
+                           .map_err(|e| Error::Webhook(e.to_string()))?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Webhook(format!("unexpected response status {}", response.status())))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn file_sink_appends_json_lines() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("events").join("events.jsonl");
+        let mut sink = FileSink::new(&path).unwrap();
+
+        sink.publish(&EventPacket::new("habitat.event.one", vec![1, 2, 3]))
+            .unwrap();
+        sink.publish(&EventPacket::new("habitat.event.two", vec![4, 5]))
+            .unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines = contents.lines()
+                            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+                            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["subject"], "habitat.event.one");
+        assert_eq!(base64::decode(lines[0]["payload"].as_str().unwrap()).unwrap(),
+                   vec![1, 2, 3]);
+        assert_eq!(lines[1]["subject"], "habitat.event.two");
+        assert_eq!(base64::decode(lines[1]["payload"].as_str().unwrap()).unwrap(),
+                   vec![4, 5]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn unix_socket_sink_writes_json_lines() {
+        use std::{io::{BufRead,
+                       BufReader},
+                  os::unix::net::UnixListener};
+
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("events.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let mut sink = UnixSocketSink::new(&path);
+
+        sink.publish(&EventPacket::new("habitat.event.one", vec![42]))
+            .unwrap();
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        let value = serde_json::from_str::<serde_json::Value>(&line).unwrap();
+        assert_eq!(value["subject"], "habitat.event.one");
+        assert_eq!(base64::decode(value["payload"].as_str().unwrap()).unwrap(),
+                   vec![42]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn unix_socket_sink_fails_without_a_listener() {
+        let tmpdir = TempDir::new().unwrap();
+        let mut sink = UnixSocketSink::new(tmpdir.path().join("nobody-home.sock"));
+        assert!(sink.publish(&EventPacket::new("habitat.event.one", vec![]))
+                    .is_err());
+    }
+}
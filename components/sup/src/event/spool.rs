@@ -0,0 +1,397 @@
+//! A durable, bounded, on-disk buffer of events.
+//!
+//! Every event is written to the spool as soon as it is generated, and
+//! is only removed once the sink has accepted it. Events are delivered
+//! from the spool on a thread of their own (see `sink::spawn`), so a
+//! slow or unavailable sink (e.g., the NATS server is down) never holds
+//! up the code generating them. Events accumulate on disk while the
+//! sink is unavailable, and are replayed in the order they were
+//! generated once it comes back, even if the Supervisor was restarted
+//! in the meantime.
+//!
+//! Each event is stored in its own file, named for its sequence
+//! number, as a JSON object holding the time it was spooled, its
+//! subject and its base64-encoded payload. The spool keeps an index of
+//! these files, so the directory is only read when it is opened. The
+//! spool is kept within the configured `EventStreamSpoolLimits` by
+//! discarding the oldest events.
+
+use crate::event::{sink::EventSink,
+                   EventPacket,
+                   Result};
+use habitat_common::types::EventStreamSpoolLimits;
+use parking_lot::Mutex;
+use std::{collections::VecDeque,
+          ffi::OsStr,
+          fs,
+          io,
+          path::{Path,
+                 PathBuf},
+          time::{Duration,
+                 SystemTime,
+                 UNIX_EPOCH}};
+
+const RECORD_EXTENSION: &str = "json";
+const TEMP_EXTENSION: &str = "tmp";
+
+/// The on-disk form of a spooled event.
+#[derive(Debug, Deserialize, Serialize)]
+struct Record {
+    /// Seconds since the Unix epoch.
+    spooled_at: u64,
+    subject: String,
+    payload: String,
+}
+
+/// A spooled event's file.
+#[derive(Clone, Debug)]
+struct Entry {
+    seq:        u64,
+    path:       PathBuf,
+    len:        u64,
+    spooled_at: u64,
+}
+
+pub(super) struct Spool {
+    dir:      PathBuf,
+    limits:   EventStreamSpoolLimits,
+    next_seq: u64,
+    /// Oldest first
+    entries:  VecDeque<Entry>,
+    /// The total size of the spooled events' files.
+    bytes:    u64,
+}
+
+impl Spool {
+    /// Open the spool in `dir`, creating it if necessary. Any events
+    /// left over from a previous run will be replayed first.
+    pub fn open<P>(dir: P, limits: EventStreamSpoolLimits) -> Result<Self>
+        where P: Into<PathBuf>
+    {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let mut spool = Spool { dir,
+                                limits,
+                                next_seq: 0,
+                                entries: VecDeque::new(),
+                                bytes: 0 };
+        spool.load()?;
+        if !spool.entries.is_empty() {
+            info!("Found {} undelivered events in {}",
+                  spool.entries.len(),
+                  spool.dir.display());
+        }
+        Ok(spool)
+    }
+
+    /// The number of events waiting to be delivered.
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    /// Add an event to the end of the spool, first discarding any
+    /// events needed to stay within the limits.
+    pub fn push(&mut self, packet: &EventPacket) -> Result<()> {
+        self.push_at(packet, SystemTime::now())
+    }
+
+    fn push_at(&mut self, packet: &EventPacket, now: SystemTime) -> Result<()> {
+        let spooled_at = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let record = Record { spooled_at,
+                              subject: packet.subject.clone(),
+                              payload: base64::encode(&packet.payload) };
+        let bytes = serde_json::to_vec(&record).expect("spool records are serializable");
+        self.prune(now, bytes.len() as u64)?;
+
+        let seq = self.next_seq;
+        let path = self.record_path(seq);
+        // Write to a temporary file first, so a crash can never leave a
+        // partial record behind.
+        let tmp_path = path.with_extension(TEMP_EXTENSION);
+        fs::write(&tmp_path, &bytes)?;
+        fs::rename(&tmp_path, &path)?;
+        self.next_seq += 1;
+        self.bytes += bytes.len() as u64;
+        self.entries.push_back(Entry { seq,
+                                       path,
+                                       len: bytes.len() as u64,
+                                       spooled_at });
+        Ok(())
+    }
+
+    /// Remove the event `seq` from the spool, unless it was already
+    /// discarded.
+    fn remove(&mut self, seq: u64) -> Result<()> {
+        match self.entries.front() {
+            Some(entry) if entry.seq == seq => {}
+            _ => return Ok(()),
+        }
+        let entry = self.entries.pop_front().expect("the spool has an oldest event");
+        self.bytes -= entry.len;
+        remove_record(&entry.path)
+    }
+
+    /// Discard expired events from the front of the spool, then the
+    /// oldest events until there is room for `incoming` more bytes.
+    fn prune(&mut self, now: SystemTime, incoming: u64) -> Result<()> {
+        let mut discarded = 0;
+        while let Some(entry) = self.entries.front() {
+            if !self.is_expired(entry.spooled_at, now)
+               && self.bytes + incoming <= self.limits.max_bytes
+            {
+                break;
+            }
+            let seq = entry.seq;
+            self.remove(seq)?;
+            discarded += 1;
+        }
+        if discarded > 0 {
+            warn!("Discarded {} undelivered events to stay within the event spool limits",
+                  discarded);
+        }
+        Ok(())
+    }
+
+    fn is_expired(&self, spooled_at: u64, now: SystemTime) -> bool {
+        let spooled_at = UNIX_EPOCH + Duration::from_secs(spooled_at);
+        now.duration_since(spooled_at)
+           .map(|age| age > self.limits.max_age)
+           .unwrap_or(false)
+    }
+
+    fn record_path(&self, seq: u64) -> PathBuf {
+        self.dir.join(format!("{:020}.{}", seq, RECORD_EXTENSION))
+    }
+
+    /// Index the events already in the spool's directory, oldest
+    /// first, discarding any that can't be read.
+    fn load(&mut self) -> Result<()> {
+        let mut entries = Vec::new();
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let dir_entry = dir_entry?;
+            let path = dir_entry.path();
+            if path.extension().and_then(OsStr::to_str) != Some(RECORD_EXTENSION) {
+                continue;
+            }
+            if let Some(seq) = path.file_stem()
+                                   .and_then(OsStr::to_str)
+                                   .and_then(|s| s.parse().ok())
+            {
+                self.next_seq = self.next_seq.max(seq + 1);
+                match read_record(&path) {
+                    Some(record) => {
+                        entries.push(Entry { seq,
+                                             len: dir_entry.metadata()?.len(),
+                                             spooled_at: record.spooled_at,
+                                             path })
+                    }
+                    None => remove_record(&path)?,
+                }
+            }
+        }
+        entries.sort_by_key(|e| e.seq);
+        self.bytes = entries.iter().map(|e| e.len).sum();
+        self.entries = entries.into();
+        Ok(())
+    }
+}
+
+/// Hand every spooled event to `sink` in order, removing each once it
+/// has been published. Stops at the first failure, leaving that event
+/// and everything after it in the spool. Events that have been waiting
+/// longer than the maximum age are discarded. The spool is only locked
+/// between events, so events can be spooled while others are being
+/// published.
+pub(super) fn replay(spool: &Mutex<Spool>, sink: &mut dyn EventSink) -> Result<()> {
+    let now = SystemTime::now();
+    let mut expired = 0;
+    loop {
+        let (entry, is_expired) = {
+            let spool = spool.lock();
+            match spool.entries.front() {
+                Some(entry) => (entry.clone(), spool.is_expired(entry.spooled_at, now)),
+                None => break,
+            }
+        };
+        if is_expired {
+            expired += 1;
+        } else if let Some(record) = read_record(&entry.path) {
+            let payload = base64::decode(&record.payload).unwrap_or_default();
+            sink.publish(&EventPacket::new(&record.subject, payload))?;
+        }
+        spool.lock().remove(entry.seq)?;
+    }
+    if expired > 0 {
+        warn!("Discarded {} events that were older than {}s",
+              expired,
+              spool.lock().limits.max_age.as_secs());
+    }
+    Ok(())
+}
+
+/// Read a spooled event, returning `None` (after logging) for a record
+/// that can't be read; there is nothing more useful to do with it than
+/// to discard it.
+fn read_record(path: &Path) -> Option<Record> {
+    match fs::read(path).map_err(|e| e.to_string()).and_then(|b| {
+                                                       serde_json::from_slice(&b).map_err(|e| {
+                                                                                     e.to_string()
+                                                                                 })
+                                                   }) {
+        Ok(record) => Some(record),
+        Err(e) => {
+            warn!("Discarding unreadable event spool record {}: {}",
+                  path.display(),
+                  e);
+            None
+        }
+    }
+}
+
+/// Delete a spooled event's file, which may already be gone.
+fn remove_record(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Error;
+    use std::io;
+    use tempfile::TempDir;
+
+    /// A sink that can be switched off, recording what it was sent.
+    #[derive(Default)]
+    struct TestSink {
+        down:      bool,
+        published: Vec<(String, Vec<u8>)>,
+    }
+
+    impl EventSink for TestSink {
+        fn publish(&mut self, packet: &EventPacket) -> Result<()> {
+            if self.down {
+                Err(Error::Io(io::Error::new(io::ErrorKind::NotConnected, "down")))
+            } else {
+                self.published
+                    .push((packet.subject.clone(), packet.payload.clone()));
+                Ok(())
+            }
+        }
+    }
+
+    fn limits() -> EventStreamSpoolLimits {
+        EventStreamSpoolLimits { max_bytes: 1024 * 1024,
+                                 max_age:   Duration::from_secs(60 * 60), }
+    }
+
+    fn packet(n: u8) -> EventPacket { EventPacket::new(&format!("habitat.event.{}", n), vec![n]) }
+
+    #[test]
+    fn replays_in_order_and_empties_the_spool() {
+        let tmpdir = TempDir::new().unwrap();
+        let spool = Mutex::new(Spool::open(tmpdir.path(), limits()).unwrap());
+        for n in 0..3 {
+            spool.lock().push(&packet(n)).unwrap();
+        }
+        assert_eq!(spool.lock().len(), 3);
+
+        let mut sink = TestSink::default();
+        replay(&spool, &mut sink).unwrap();
+        assert_eq!(sink.published,
+                   vec![("habitat.event.0".to_string(), vec![0]),
+                        ("habitat.event.1".to_string(), vec![1]),
+                        ("habitat.event.2".to_string(), vec![2])]);
+        assert_eq!(spool.lock().len(), 0);
+        assert_eq!(Spool::open(tmpdir.path(), limits()).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn keeps_events_while_the_sink_is_down() {
+        let tmpdir = TempDir::new().unwrap();
+        let spool = Mutex::new(Spool::open(tmpdir.path(), limits()).unwrap());
+        spool.lock().push(&packet(0)).unwrap();
+        spool.lock().push(&packet(1)).unwrap();
+
+        let mut sink = TestSink { down: true,
+                                  ..TestSink::default() };
+        assert!(replay(&spool, &mut sink).is_err());
+        assert_eq!(spool.lock().len(), 2);
+
+        sink.down = false;
+        replay(&spool, &mut sink).unwrap();
+        assert_eq!(sink.published.len(), 2);
+    }
+
+    #[test]
+    fn survives_being_reopened() {
+        let tmpdir = TempDir::new().unwrap();
+        {
+            let mut spool = Spool::open(tmpdir.path(), limits()).unwrap();
+            spool.push(&packet(0)).unwrap();
+            spool.push(&packet(1)).unwrap();
+        }
+        let spool = Mutex::new(Spool::open(tmpdir.path(), limits()).unwrap());
+        spool.lock().push(&packet(2)).unwrap();
+
+        let mut sink = TestSink::default();
+        replay(&spool, &mut sink).unwrap();
+        let subjects = sink.published
+                           .into_iter()
+                           .map(|(s, _)| s)
+                           .collect::<Vec<_>>();
+        assert_eq!(subjects,
+                   vec!["habitat.event.0", "habitat.event.1", "habitat.event.2"]);
+    }
+
+    #[test]
+    fn discards_the_oldest_events_beyond_max_bytes() {
+        let tmpdir = TempDir::new().unwrap();
+        let mut spool = Spool::open(tmpdir.path(), limits()).unwrap();
+        spool.push(&packet(0)).unwrap();
+        let record_len = spool.entries[0].len;
+        spool.limits.max_bytes = record_len * 2;
+
+        spool.push(&packet(1)).unwrap();
+        spool.push(&packet(2)).unwrap();
+        assert_eq!(spool.len(), 2);
+
+        let mut sink = TestSink::default();
+        replay(&Mutex::new(spool), &mut sink).unwrap();
+        assert_eq!(sink.published,
+                   vec![("habitat.event.1".to_string(), vec![1]),
+                        ("habitat.event.2".to_string(), vec![2])]);
+    }
+
+    #[test]
+    fn discards_events_older_than_max_age() {
+        let tmpdir = TempDir::new().unwrap();
+        let mut spool = Spool::open(tmpdir.path(), limits()).unwrap();
+        let two_hours_ago = SystemTime::now() - Duration::from_secs(2 * 60 * 60);
+        spool.push_at(&packet(0), two_hours_ago).unwrap();
+        spool.push(&packet(1)).unwrap();
+
+        let spool = Mutex::new(spool);
+        let mut sink = TestSink::default();
+        replay(&spool, &mut sink).unwrap();
+        assert_eq!(sink.published,
+                   vec![("habitat.event.1".to_string(), vec![1])]);
+        assert_eq!(spool.lock().len(), 0);
+    }
+
+    #[test]
+    fn discards_unreadable_records() {
+        let tmpdir = TempDir::new().unwrap();
+        fs::write(tmpdir.path().join(format!("{:020}.{}", 0, RECORD_EXTENSION)),
+                  b"not json").unwrap();
+        let mut spool = Spool::open(tmpdir.path(), limits()).unwrap();
+        assert_eq!(spool.len(), 0);
+        spool.push(&packet(1)).unwrap();
+        assert_eq!(spool.entries[0].seq, 1);
+
+        let mut sink = TestSink::default();
+        replay(&Mutex::new(spool), &mut sink).unwrap();
+        assert_eq!(sink.published,
+                   vec![("habitat.event.1".to_string(), vec![1])]);
+    }
+}
@@ -1,22 +1,16 @@
-use crate::{event::{Error,
-                    EventPacket,
-                    EventStream,
-                    EventStreamConnectionInfo,
-                    Result},
-            sup_futures};
-use futures::{future::Future,
-              sync::mpsc as futures_mpsc};
+use crate::event::{sink::EventSink,
+                   Error,
+                   EventPacket,
+                   EventStreamConnectionInfo,
+                   Result};
 use habitat_http_client;
 use nats::{native_tls::TlsConnector,
            Client};
 use std::{thread,
           time::{Duration,
                  Instant}};
-use tokio::{prelude::Stream,
-            runtime::Runtime};
 
 const NATS_SCHEME: &str = "nats://";
-const EVENT_CHANNEL_SIZE: usize = 1024;
 
 fn nats_uri(uri: &str, auth_token: &str) -> String {
     // Unconditionally, remove the scheme. We will add it back.
@@ -30,72 +24,68 @@ fn nats_uri(uri: &str, auth_token: &str) -> String {
     }
 }
 
-pub(super) fn init_stream(conn_info: EventStreamConnectionInfo,
-                          runtime: &mut Runtime)
-                          -> Result<EventStream> {
-    let (event_tx, event_rx) = futures_mpsc::channel(EVENT_CHANNEL_SIZE);
+/// Publishes events to a NATS Streaming server.
+pub(super) struct NatsSink {
+    client: Client,
+}
 
-    let EventStreamConnectionInfo { name,
-                                    verbose,
-                                    cluster_uri,
-                                    auth_token,
-                                    connect_method,
-                                    server_certificate, } = conn_info;
-    let uri = nats_uri(&cluster_uri, &auth_token.to_string());
+impl NatsSink {
+    pub(super) fn connect(conn_info: EventStreamConnectionInfo) -> Result<Self> {
+        let EventStreamConnectionInfo { name,
+                                        verbose,
+                                        cluster_uri,
+                                        auth_token,
+                                        connect_method,
+                                        server_certificate, } = conn_info;
+        let auth_token = auth_token.map(|t| t.to_string()).unwrap_or_default();
+        let uri = nats_uri(&cluster_uri, &auth_token);
 
-    let mut tls_config = TlsConnector::builder();
-    for certificate in habitat_http_client::certificates(None)? {
-        tls_config.add_root_certificate(certificate);
-    }
-    if let Some(certificate) = server_certificate {
-        tls_config.add_root_certificate(certificate.into());
-    }
-    let tls_config = tls_config.build()?;
+        let mut tls_config = TlsConnector::builder();
+        for certificate in habitat_http_client::certificates(None)? {
+            tls_config.add_root_certificate(certificate);
+        }
+        if let Some(certificate) = server_certificate {
+            tls_config.add_root_certificate(certificate.into());
+        }
+        let tls_config = tls_config.build()?;
 
-    // Note: With the way we are using the client, we will not respond to pings from the server
-    // (https://nats-io.github.io/docs/nats_protocol/nats-protocol.html#pingpong).
-    // Instead, we rely on publishing events to keep us connected to the server. This is completely
-    // valid according to the protocol, and in fact, the server will not send pings if there is
-    // other traffic from a client. If we have no events for an extended period of time, we will
-    // be automatically disconnected (because we do not respond to pings). When the next event comes
-    // in we will try to reconnect.
-    let mut client = Client::new(uri.as_ref())?;
-    client.set_name(&name);
-    client.set_synchronous(verbose);
-    client.set_tls_config(tls_config);
+        // Note: With the way we are using the client, we will not respond to pings from the
+        // server (https://nats-io.github.io/docs/nats_protocol/nats-protocol.html#pingpong).
+        // Instead, we rely on publishing events to keep us connected to the server. This is
+        // completely valid according to the protocol, and in fact, the server will not send pings
+        // if there is other traffic from a client. If we have no events for an extended period of
+        // time, we will be automatically disconnected (because we do not respond to pings). When
+        // the next event comes in we will try to reconnect.
+        let mut client = Client::new(uri.as_ref())?;
+        client.set_name(&name);
+        client.set_synchronous(verbose);
+        client.set_tls_config(tls_config);
 
-    // Try to establish an intial connection to the NATS server.
-    let start = Instant::now();
-    let maybe_timeout = connect_method.into();
-    while let Err(e) = client.connect() {
-        if let Some(timeout) = maybe_timeout {
-            if Instant::now() > start + timeout {
-                return Err(Error::ConnectEventServer);
+        // Try to establish an intial connection to the NATS server.
+        let start = Instant::now();
+        let maybe_timeout = connect_method.into();
+        while let Err(e) = client.connect() {
+            if let Some(timeout) = maybe_timeout {
+                if Instant::now() > start + timeout {
+                    return Err(Error::ConnectEventServer);
+                }
+                error!("Failed to connect to NATS server '{}'. Retrying...", e);
+            } else {
+                warn!("Failed to connect to NATS server '{}'.", e);
+                break;
             }
-            error!("Failed to connect to NATS server '{}'. Retrying...", e);
-        } else {
-            warn!("Failed to connect to NATS server '{}'.", e);
-            break;
+            thread::sleep(Duration::from_secs(1));
         }
-        thread::sleep(Duration::from_secs(1));
-    }
 
-    let (handle, event_handler) =
-        sup_futures::cancelable_future(event_rx.for_each(move |packet: EventPacket| {
-                                                   if let Err(e) = client.publish(packet.subject,
-                                                                                  &packet.payload)
-                                                   {
-                                                       error!("Failed to publish event to '{}', \
-                                                               '{}'",
-                                                              packet.subject, e);
-                                                   }
-                                                   Ok(())
-                                               }));
-    // Convert the error type so `spawn` can handle it.
-    let event_handler = event_handler.map_err(|_| ());
-    runtime.spawn(event_handler);
+        Ok(NatsSink { client })
+    }
+}
 
-    Ok(EventStream::new(event_tx, handle))
+impl EventSink for NatsSink {
+    fn publish(&mut self, packet: &EventPacket) -> Result<()> {
+        self.client.publish(&packet.subject, &packet.payload)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
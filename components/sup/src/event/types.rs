@@ -50,6 +50,7 @@ impl Service {
             }
             DomainUpdateStrategy::AtOnce => UpdateStrategy::AtOnce,
             DomainUpdateStrategy::Rolling => UpdateStrategy::Rolling,
+            DomainUpdateStrategy::Batched => UpdateStrategy::Batched,
         };
 
         Some(UpdateConfig { strategy: strategy.into(),
@@ -104,3 +105,11 @@ event_msg_impl!(ServiceStartedEvent);
 event_msg_impl!(ServiceStoppedEvent);
 event_msg_impl!(ServiceUpdateStartedEvent);
 event_msg_impl!(HealthCheckEvent);
+event_msg_impl!(ServiceUpdateFinishedEvent);
+event_msg_impl!(ServiceConfigAppliedEvent);
+event_msg_impl!(ServiceFileAppliedEvent);
+event_msg_impl!(LeaderElectedEvent);
+event_msg_impl!(HookFailedEvent);
+event_msg_impl!(ServiceBindChangedEvent);
+event_msg_impl!(ServiceCrashLoopingEvent);
+event_msg_impl!(ServiceUpdateRolledBackEvent);
@@ -1,3 +1,6 @@
+mod feed;
+
+pub use self::feed::GatewayFeed;
 use crate::manager::{self,
                      service::{HealthCheckHook,
                                HealthCheckResult}};
@@ -5,6 +8,7 @@ use actix_web::{dev::{Body,
                       Service,
                       ServiceRequest,
                       ServiceResponse},
+                error,
                 http::{self,
                        StatusCode},
                 web::{self,
@@ -15,9 +19,10 @@ use actix_web::{dev::{Body,
                 HttpResponse,
                 HttpServer,
                 Scope};
-use futures::future::{ok,
-                      Either,
-                      Future};
+use futures::{future::{ok,
+                       Either,
+                       Future},
+              Stream};
 use habitat_common::{self,
                      templating::hooks,
                      types::HttpListenAddr,
@@ -79,9 +84,11 @@ habitat_core::env_config!(
 
 #[derive(Default, Serialize)]
 struct HealthCheckBody {
-    status: String,
-    stdout: String,
-    stderr: String,
+    status:    String,
+    liveness:  String,
+    readiness: String,
+    stdout:    String,
+    stderr:    String,
 }
 
 impl Into<StatusCode> for HealthCheckResult {
@@ -286,11 +293,15 @@ fn services_routes() -> Scope {
                                   web::get().to(config_without_org_gsr))
                            .route("/{svc}/{group}/health",
                                   web::get().to(health_without_org_gsr))
+                           .route("/{svc}/{group}/rollout",
+                                  web::get().to(rollout_without_org_gsr))
                            .route("/{svc}/{group}/{org}", web::get().to(service_with_org_gsr))
                            .route("/{svc}/{group}/{org}/config",
                                   web::get().to(config_with_org_gsr))
                            .route("/{svc}/{group}/{org}/health",
                                   web::get().to(health_with_org_gsr))
+                           .route("/{svc}/{group}/{org}/rollout",
+                                  web::get().to(rollout_with_org_gsr))
 }
 
 fn routes() -> Scope {
@@ -300,6 +311,8 @@ fn routes() -> Scope {
                                                        .wrap_fn(redact_http_middleware))
                    .service(web::resource("/census").route(web::get().to(census_gsr))
                                                     .wrap_fn(redact_http_middleware))
+                   .service(web::resource("/events").route(web::get().to(events_gsw))
+                                                    .wrap_fn(redact_http_middleware))
                    .route("/metrics", web::get().to(metrics))
 }
 
@@ -326,6 +339,21 @@ fn census_gsr(state: Data<AppState>) -> HttpResponse {
     json_response(data)
 }
 
+/// Stream changes to the census, service health, and the set of
+/// loaded services to the client as Server-Sent Events.
+///
+/// # Locking (see locking.md)
+/// * `GatewayState::inner` (write)
+#[allow(clippy::needless_pass_by_value)]
+fn events_gsw(state: Data<AppState>) -> HttpResponse {
+    let changes = state.gateway_state.lock_gsw().subscribe();
+    HttpResponse::Ok().content_type("text/event-stream")
+                      .header(http::header::CACHE_CONTROL, "no-cache")
+                      .streaming(changes.map_err(|_| {
+                                            error::ErrorInternalServerError("event feed closed")
+                                        }))
+}
+
 /// # Locking (see locking.md)
 /// * `GatewayState::inner` (read)
 #[allow(clippy::needless_pass_by_value)]
@@ -396,13 +424,20 @@ fn health_gsr(svc: String, group: String, org: Option<&str>, state: &AppState) -
         Err(_) => return HttpResponse::BadRequest().finish(),
     };
 
-    if let Some(health_check) = state.gateway_state.lock_gsr().health_of(&service_group) {
+    let (health_check, readiness) = {
+        let gateway_state = state.gateway_state.lock_gsr();
+        (gateway_state.health_of(&service_group), gateway_state.readiness_of(&service_group))
+    };
+    if let Some(health_check) = health_check {
         let mut body = HealthCheckBody::default();
         let stdout_path = hooks::stdout_log_path::<HealthCheckHook>(&service_group);
         let stderr_path = hooks::stderr_log_path::<HealthCheckHook>(&service_group);
         let http_status: StatusCode = health_check.into();
+        let readiness = readiness.unwrap_or(HealthCheckResult::Unknown);
 
         body.status = health_check.to_string();
+        body.liveness = health_check.to_string();
+        body.readiness = readiness.to_string();
         if let Ok(mut file) = File::open(&stdout_path) {
             let _ = file.read_to_string(&mut body.stdout);
         }
@@ -418,6 +453,42 @@ fn health_gsr(svc: String, group: String, org: Option<&str>, state: &AppState) -
     }
 }
 
+/// # Locking (see locking.md)
+/// * `GatewayState::inner` (read)
+#[allow(clippy::needless_pass_by_value)]
+fn rollout_with_org_gsr(path: Path<(String, String, String)>,
+                        state: Data<AppState>)
+                        -> HttpResponse {
+    let (svc, group, org) = path.into_inner();
+    rollout_gsr(svc, group, Some(&org), &state)
+}
+
+/// # Locking (see locking.md)
+/// * `GatewayState::inner` (read)
+#[allow(clippy::needless_pass_by_value)]
+fn rollout_without_org_gsr(path: Path<(String, String)>, state: Data<AppState>) -> HttpResponse {
+    let (svc, group) = path.into_inner();
+    rollout_gsr(svc, group, None, &state)
+}
+
+/// # Locking (see locking.md)
+/// * `GatewayState::inner` (read)
+fn rollout_gsr(svc: String, group: String, org: Option<&str>, state: &AppState) -> HttpResponse {
+    let service_group = match ServiceGroup::new(None, svc, group, org) {
+        Ok(sg) => sg,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    match state.gateway_state.lock_gsr().rollout_of(&service_group) {
+        Some(rollout) => HttpResponse::Ok().json(rollout),
+        None => {
+            debug!("Didn't find any rollout data for service group {:?}",
+                   &service_group);
+            HttpResponse::NotFound().finish()
+        }
+    }
+}
+
 /// # Locking (see locking.md)
 /// * `GatewayState::inner` (read)
 #[allow(clippy::needless_pass_by_value)]
@@ -0,0 +1,339 @@
+//! A push-based feed of changes to the data served by the HTTP
+//! gateway.
+//!
+//! Rather than repeatedly polling `/census`, `/services` and the
+//! health endpoints, clients may hold open a connection to `/events`
+//! and receive a [Server-Sent Event][1] whenever the census of a
+//! service group changes, a service's health check result changes, or
+//! a service is loaded or unloaded. Each message names the service
+//! group it concerns and carries a diff of what changed.
+//!
+//! [1]:https://html.spec.whatwg.org/multipage/server-sent-events.html
+
+use crate::manager::service::HealthCheckResult;
+use bytes::Bytes;
+use futures::sync::mpsc::{self,
+                          Receiver,
+                          Sender};
+use habitat_core::service::ServiceGroup;
+use serde_json::{self,
+                 Map,
+                 Value as Json};
+use std::collections::BTreeSet;
+
+/// How many messages may be waiting to be sent to a single client.
+/// Clients that fall further behind than this are disconnected.
+const SUBSCRIBER_BUFFER_SIZE: usize = 256;
+
+/// The kinds of change that are published on the feed. These are
+/// used as the SSE `event` field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    Census,
+    Health,
+    ServiceLoaded,
+    ServiceUnloaded,
+}
+
+impl ChangeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Census => "census",
+            ChangeKind::Health => "health",
+            ChangeKind::ServiceLoaded => "service_loaded",
+            ChangeKind::ServiceUnloaded => "service_unloaded",
+        }
+    }
+}
+
+/// A single change to one service group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub kind:          ChangeKind,
+    pub service_group: String,
+    pub diff:          Json,
+}
+
+impl Change {
+    /// Render this change as a Server-Sent Event.
+    fn to_sse(&self) -> Bytes {
+        let data = json!({ "service_group": self.service_group,
+                           "diff": self.diff });
+        Bytes::from(format!("event: {}\ndata: {}\n\n", self.kind.as_str(), data))
+    }
+}
+
+/// The set of clients currently listening for changes.
+#[derive(Debug, Default)]
+pub struct GatewayFeed {
+    subscribers: Vec<Sender<Bytes>>,
+}
+
+impl GatewayFeed {
+    /// Register a new client, returning the stream of SSE-formatted
+    /// messages it should be sent.
+    pub fn subscribe(&mut self) -> Receiver<Bytes> {
+        let (mut tx, rx) = mpsc::channel(SUBSCRIBER_BUFFER_SIZE);
+        // An SSE comment, so clients know they're connected before
+        // anything has changed.
+        tx.try_send(Bytes::from_static(b": connected\n\n")).ok();
+        self.subscribers.push(tx);
+        rx
+    }
+
+    pub fn has_subscribers(&self) -> bool { !self.subscribers.is_empty() }
+
+    /// Send changes to every client, dropping those that have
+    /// disconnected or are too far behind.
+    pub fn publish<I>(&mut self, changes: I)
+        where I: IntoIterator<Item = Change>
+    {
+        for change in changes {
+            let message = change.to_sse();
+            let subscribers = std::mem::replace(&mut self.subscribers, Vec::new());
+            self.subscribers =
+                subscribers.into_iter()
+                           .filter_map(|mut s| s.try_send(message.clone()).ok().map(|_| s))
+                           .collect();
+        }
+    }
+
+    /// Publish the differences between two renderings of the
+    /// `/census` data.
+    pub fn census_changed(&mut self, old: &str, new: &str) {
+        if self.has_subscribers() {
+            self.publish(census_changes(old, new));
+        }
+    }
+
+    /// Publish the differences between two renderings of the
+    /// `/services` data.
+    pub fn services_changed(&mut self, old: &str, new: &str) {
+        if self.has_subscribers() {
+            self.publish(services_changes(old, new));
+        }
+    }
+
+    /// Publish a change in a service group's health check result.
+    pub fn health_changed(&mut self,
+                          service_group: &ServiceGroup,
+                          old: Option<HealthCheckResult>,
+                          new: Option<HealthCheckResult>) {
+        if old == new || !self.has_subscribers() {
+            return;
+        }
+        let to_json = |h: Option<HealthCheckResult>| {
+            h.map(|h| Json::String(h.to_string())).unwrap_or(Json::Null)
+        };
+        if let Some(diff) = diff(&to_json(old), &to_json(new)) {
+            self.publish(Some(Change { kind: ChangeKind::Health,
+                                       service_group: service_group.to_string(),
+                                       diff }));
+        }
+    }
+}
+
+/// Compute a structural diff between two JSON values.
+///
+/// Objects are compared key by key, and only keys whose values differ
+/// appear in the result. Anything else that differs is reported as
+/// `{"old": ..., "new": ...}`; a key missing on one side is reported
+/// as `null` on that side. Returns `None` if the values are equal.
+pub fn diff(old: &Json, new: &Json) -> Option<Json> {
+    match (old, new) {
+        (Json::Object(old), Json::Object(new)) => {
+            let keys = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+            let changes = keys.into_iter()
+                              .filter_map(|k| {
+                                  let o = old.get(k).unwrap_or(&Json::Null);
+                                  let n = new.get(k).unwrap_or(&Json::Null);
+                                  diff(o, n).map(|d| (k.clone(), d))
+                              })
+                              .collect::<Map<_, _>>();
+            if changes.is_empty() {
+                None
+            } else {
+                Some(Json::Object(changes))
+            }
+        }
+        (o, n) if o == n => None,
+        (o, n) => Some(json!({ "old": o, "new": n })),
+    }
+}
+
+/// Per-group changes between two renderings of the census. Only the
+/// `census_groups` are considered; the bookkeeping counters that
+/// surround them change on every update.
+fn census_changes(old: &str, new: &str) -> Vec<Change> {
+    let census_groups = |census: &Json| {
+        census.get("census_groups")
+              .and_then(Json::as_object)
+              .cloned()
+    };
+    let old = groups_by_name(old, census_groups);
+    let new = groups_by_name(new, census_groups);
+
+    let names = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+    names.into_iter()
+         .filter_map(|sg| {
+             let o = old.get(sg).unwrap_or(&Json::Null);
+             let n = new.get(sg).unwrap_or(&Json::Null);
+             diff(o, n).map(|diff| {
+                           Change { kind: ChangeKind::Census,
+                                    service_group: sg.clone(),
+                                    diff }
+                       })
+         })
+         .collect()
+}
+
+/// Services that were loaded or unloaded between two renderings of
+/// the `/services` data.
+fn services_changes(old: &str, new: &str) -> Vec<Change> {
+    let by_group = |services: &Json| {
+        services.as_array().map(|services| {
+                               services.iter()
+                                       .filter_map(|s| {
+                                           s["service_group"].as_str()
+                                                             .map(|sg| (sg.to_string(), s.clone()))
+                                       })
+                                       .collect::<Map<_, _>>()
+                           })
+    };
+    let old = groups_by_name(old, by_group);
+    let new = groups_by_name(new, by_group);
+
+    let removed = old.iter()
+                     .filter(|(sg, _)| !new.contains_key(*sg))
+                     .map(|(sg, s)| {
+                         Change { kind:          ChangeKind::ServiceUnloaded,
+                                  service_group: sg.clone(),
+                                  diff:          json!({ "old": s, "new": Json::Null }), }
+                     });
+    let added = new.iter()
+                   .filter(|(sg, _)| !old.contains_key(*sg))
+                   .map(|(sg, s)| {
+                       Change { kind:          ChangeKind::ServiceLoaded,
+                                service_group: sg.clone(),
+                                diff:          json!({ "old": Json::Null, "new": s }), }
+                   });
+    removed.chain(added).collect()
+}
+
+/// Parse gateway JSON and extract a map of service group name to the
+/// JSON describing it. Unparseable (e.g., not yet populated) data is
+/// treated as empty.
+fn groups_by_name<F>(data: &str, extract: F) -> Map<String, Json>
+    where F: Fn(&Json) -> Option<Map<String, Json>>
+{
+    serde_json::from_str::<Json>(data).ok()
+                                      .and_then(|json| extract(&json))
+                                      .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{Future,
+                  Stream};
+    use std::str::FromStr;
+
+    #[test]
+    fn equal_values_have_no_diff() {
+        let value = json!({ "a": 1, "b": { "c": [1, 2, 3] } });
+        assert_eq!(diff(&value, &value), None);
+    }
+
+    #[test]
+    fn diff_only_includes_changed_keys() {
+        let old = json!({ "same": 1, "changed": { "x": 1, "y": 2 }, "removed": true });
+        let new = json!({ "same": 1, "changed": { "x": 1, "y": 3 }, "added": "yes" });
+        assert_eq!(diff(&old, &new),
+                   Some(json!({ "changed": { "y": { "old": 2, "new": 3 } },
+                                "removed": { "old": true, "new": null },
+                                "added": { "old": null, "new": "yes" } })));
+    }
+
+    #[test]
+    fn census_changes_are_reported_per_group() {
+        let old = json!({ "changed": false,
+                          "last_service_counter": 1,
+                          "census_groups": {
+                              "redis.default": { "leader_id": "a" },
+                              "nginx.default": { "leader_id": null }
+                          } }).to_string();
+        let new = json!({ "changed": true,
+                          "last_service_counter": 2,
+                          "census_groups": {
+                              "redis.default": { "leader_id": "b" },
+                              "nginx.default": { "leader_id": null }
+                          } }).to_string();
+
+        assert_eq!(census_changes(&old, &new),
+                   vec![Change { kind:          ChangeKind::Census,
+                                 service_group: "redis.default".to_string(),
+                                 diff:          json!({ "leader_id": { "old": "a", "new": "b" } }), }]);
+    }
+
+    #[test]
+    fn census_changes_from_empty_data() {
+        let new = json!({ "census_groups": { "redis.default": { "leader_id": "a" } } }).to_string();
+        let changes = census_changes("", &new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].service_group, "redis.default");
+    }
+
+    #[test]
+    fn services_changes_reports_loads_and_unloads() {
+        let old = json!([{ "service_group": "redis.default" },
+                         { "service_group": "nginx.default" }]).to_string();
+        let new = json!([{ "service_group": "nginx.default", "pid": 42 },
+                         { "service_group": "postgres.default" }]).to_string();
+
+        let changes = services_changes(&old, &new);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].kind, ChangeKind::ServiceUnloaded);
+        assert_eq!(changes[0].service_group, "redis.default");
+        assert_eq!(changes[1].kind, ChangeKind::ServiceLoaded);
+        assert_eq!(changes[1].service_group, "postgres.default");
+    }
+
+    #[test]
+    fn subscribers_receive_server_sent_events() {
+        let mut feed = GatewayFeed::default();
+        assert!(!feed.has_subscribers());
+        let rx = feed.subscribe();
+        assert!(feed.has_subscribers());
+
+        let sg = ServiceGroup::from_str("redis.default").unwrap();
+        feed.health_changed(&sg, None, Some(HealthCheckResult::Ok));
+        feed.health_changed(&sg,
+                            Some(HealthCheckResult::Ok),
+                            Some(HealthCheckResult::Ok));
+        feed.health_changed(&sg,
+                            Some(HealthCheckResult::Ok),
+                            Some(HealthCheckResult::Critical));
+        drop(feed);
+
+        let messages = rx.collect().wait().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0], Bytes::from_static(b": connected\n\n"));
+        let expected = |diff: Json| {
+            format!("event: health\ndata: {}\n\n",
+                    json!({ "diff": diff, "service_group": "redis.default" }))
+        };
+        assert_eq!(messages[1],
+                   Bytes::from(expected(json!({ "old": null, "new": "OK" }))));
+        assert_eq!(messages[2],
+                   Bytes::from(expected(json!({ "old": "OK", "new": "CRITICAL" }))));
+    }
+
+    #[test]
+    fn disconnected_subscribers_are_dropped() {
+        let mut feed = GatewayFeed::default();
+        drop(feed.subscribe());
+        let sg = ServiceGroup::from_str("redis.default").unwrap();
+        feed.health_changed(&sg, None, Some(HealthCheckResult::Ok));
+        assert!(!feed.has_subscribers());
+    }
+}
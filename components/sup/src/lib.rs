@@ -66,6 +66,7 @@ pub mod event;
 pub mod http_gateway;
 pub mod logger; // must be pub if used in the `hab-sup` binary
 pub mod manager;
+pub mod sup_config;
 mod sup_futures;
 mod sys;
 #[cfg(test)]
@@ -24,9 +24,14 @@ use crate::sup::{cli::cli,
                            ManagerConfig,
                            TLSConfig,
                            PROC_LOCK_FILE},
+                 sup_config::SupConfigFile,
                  util};
 use clap::ArgMatches;
-use hab::cli::parse_optional_arg;
+use hab::cli::{parse_health_check_probe,
+               parse_optional_arg,
+               parse_readiness_probe,
+               parse_restart_policy};
+use habitat_butterfly::server::timing::Timing;
 use habitat_common::{cli::cache_key_path_from_matches,
                      command::package::install::InstallSource,
                      liveliness_checker,
@@ -57,7 +62,8 @@ use habitat_sup_protocol::{self as sup_proto,
                                    ServiceBind,
                                    Topology,
                                    UpdateStrategy}};
-use std::{env,
+use std::{collections::BTreeMap,
+          env,
           io::{self,
                Write},
           net::{IpAddr,
@@ -186,7 +192,8 @@ fn sub_run_rsr_imlw_mlw_gsw_smw_rhw_msw(m: &ArgMatches,
                                         -> Result<()> {
     set_supervisor_logging_options(m);
 
-    let cfg = mgrcfg_from_sup_run_matches(m, feature_flags)?;
+    let sup_config = SupConfigFile::load(SupConfigFile::path())?;
+    let cfg = mgrcfg_from_sup_run_matches(m, feature_flags, &sup_config)?;
 
     let sys_ip = m.value_of("SYS_IP_ADDRESS")
                   .and_then(|s| IpAddr::from_str(s).ok())
@@ -257,7 +264,8 @@ fn sub_term() -> Result<()> {
 ////////////////////////////////////////////////////////////////////////
 
 fn mgrcfg_from_sup_run_matches(m: &ArgMatches,
-                               feature_flags: FeatureFlag)
+                               feature_flags: FeatureFlag,
+                               sup_config: &SupConfigFile)
                                -> Result<ManagerConfig> {
     let cache_key_path = cache_key_path_from_matches(m);
 
@@ -278,6 +286,9 @@ fn mgrcfg_from_sup_run_matches(m: &ArgMatches,
         organization: m.value_of("ORGANIZATION").map(str::to_string),
         gossip_permanent: m.is_present("PERMANENT_PEER"),
         ring_key: get_ring_key(m, &cache_key_path_from_matches(m))?,
+        member_keys_path: m.value_of("MEMBER_KEYS").map(PathBuf::from),
+        gossip_timing: get_gossip_timing(m, sup_config)?,
+        tags: get_tags(m),
         gossip_peers: get_peers(m)?,
         watch_peer_file: m.value_of("PEER_WATCH_FILE").map(str::to_string),
         gossip_listen: if m.is_present("LOCAL_GOSSIP_MODE") {
@@ -309,6 +320,57 @@ fn mgrcfg_from_sup_run_matches(m: &ArgMatches,
 // Various CLI Parsing Functions
 ////////////////////////////////////////////////////////////////////////
 
+fn get_tags(matches: &ArgMatches) -> BTreeMap<String, String> {
+    matches.values_of("TAG")
+           .into_iter()
+           .flatten()
+           .filter_map(|tag| {
+               let mut parts = tag.splitn(2, '=');
+               match (parts.next(), parts.next()) {
+                   (Some(key), Some(value)) => Some((key.to_string(), value.to_string())),
+                   _ => None,
+               }
+           })
+           .collect()
+}
+
+/// Resolve the gossip timing. Each value is taken from CLI args, the Supervisor config file, or
+/// (failing those) the default.
+fn get_gossip_timing(m: &ArgMatches, sup_config: &SupConfigFile) -> Result<Timing> {
+    fn value<T: FromStr>(m: &ArgMatches, name: &str, from_file: Option<T>) -> Option<T> {
+        m.value_of(name)
+         .and_then(|s| s.parse().ok())
+         .or(from_file)
+    }
+
+    let default = Timing::default();
+    #[rustfmt::skip]
+    let timing = Timing {
+        ping_ms: value(m, "GOSSIP_PING_MS", sup_config.gossip_ping_ms)
+            .unwrap_or(default.ping_ms),
+        pingreq_ms: value(m, "GOSSIP_PINGREQ_MS", sup_config.gossip_pingreq_ms)
+            .unwrap_or(default.pingreq_ms),
+        pingreq_targets: value(m, "GOSSIP_PINGREQ_TARGETS", sup_config.gossip_pingreq_targets)
+            .unwrap_or(default.pingreq_targets),
+        suspicion_timeout_protocol_periods:
+            value(m, "GOSSIP_SUSPICION_PERIODS", sup_config.gossip_suspicion_periods)
+            .unwrap_or(default.suspicion_timeout_protocol_periods),
+        gossip_period_ms: value(m, "GOSSIP_PERIOD_MS", sup_config.gossip_period_ms)
+            .unwrap_or(default.gossip_period_ms),
+        gossip_fanout: value(m, "GOSSIP_FANOUT", sup_config.gossip_fanout)
+            .unwrap_or(default.gossip_fanout),
+        departure_timeout_ms:
+            value(m, "GOSSIP_DEPARTURE_TIMEOUT_MS", sup_config.gossip_departure_timeout_ms)
+            .unwrap_or(default.departure_timeout_ms),
+        rumor_ttl_ms: value(m, "RUMOR_TTL", sup_config.rumor_ttl)
+            .map(|secs: i64| secs.saturating_mul(1000))
+            .unwrap_or(default.rumor_ttl_ms),
+        ..default
+    };
+    timing.validate()?;
+    Ok(timing)
+}
+
 fn get_peers(matches: &ArgMatches) -> Result<Vec<SocketAddr>> {
     // TODO fn: Clean this up--using a for loop doesn't feel good however an iterator was
     // causing a lot of developer/compiler type confusion
@@ -502,6 +564,15 @@ fn svc_load_from_input(m: &ArgMatches) -> Result<sup_proto::ctl::SvcLoad> {
     msg.update_strategy = get_strategy_from_input(m).map(|v| v as i32);
     msg.shutdown_timeout =
         parse_optional_arg::<ShutdownTimeout>("SHUTDOWN_TIMEOUT", m).map(u32::from);
+    msg.restart_policy = parse_restart_policy(m);
+    msg.health_check_probe = parse_health_check_probe(m);
+    msg.readiness_probe = parse_readiness_probe(m);
+    msg.liveness_threshold = parse_optional_arg("LIVENESS_THRESHOLD", m);
+    msg.rollback_window = parse_optional_arg("ROLLBACK_WINDOW", m);
+    msg.update_batch_size = parse_optional_arg("UPDATE_BATCH_SIZE", m);
+    msg.update_canary_soak = parse_optional_arg("UPDATE_CANARY_SOAK", m);
+    msg.election_preferred_zone = parse_optional_arg("ELECTION_PREFERRED_ZONE", m);
+    msg.election_min_zones = parse_optional_arg("ELECTION_MIN_ZONES", m);
     Ok(msg)
 }
 
@@ -530,13 +601,22 @@ mod test {
         fn cmd_vec_from_cmd_str(cmd: &str) -> Vec<&str> { Vec::from_iter(cmd.split_whitespace()) }
 
         fn config_from_cmd_vec(cmd_vec: Vec<&str>) -> ManagerConfig {
+            config_from_matches(&matches_from_cmd_vec(cmd_vec), &SupConfigFile::default())
+                .expect("Could not get config")
+        }
+
+        fn matches_from_cmd_vec(cmd_vec: Vec<&str>) -> ArgMatches<'static> {
             let matches = cli(no_feature_flags()).get_matches_from_safe(cmd_vec)
                                                  .expect("Error while getting matches");
             let (_, sub_matches) = matches.subcommand();
-            let sub_matches = sub_matches.expect("Error getting sub command matches");
+            sub_matches.expect("Error getting sub command matches")
+                       .clone()
+        }
 
-            mgrcfg_from_sup_run_matches(&sub_matches, no_feature_flags()).expect("Could not get \
-                                                                                  config")
+        fn config_from_matches(matches: &ArgMatches,
+                               sup_config: &SupConfigFile)
+                               -> Result<ManagerConfig> {
+            mgrcfg_from_sup_run_matches(matches, no_feature_flags(), sup_config)
         }
 
         #[test]
@@ -686,6 +766,86 @@ mod test {
             assert_eq!(config.watch_peer_file, None);
         }
 
+        #[test]
+        fn member_keys_path_should_be_set() {
+            let member_keys = TempDir::new().expect("Could not create tempdir");
+            let path = member_keys.path().to_str().unwrap();
+            let config = config_from_cmd_str(&format!("hab-sup run --member-keys {}", path));
+            assert_eq!(config.member_keys_path, Some(PathBuf::from(path)));
+
+            let config = config_from_cmd_str("hab-sup run");
+            assert_eq!(config.member_keys_path, None);
+        }
+
+        #[test]
+        fn rumor_ttl_should_be_set() {
+            let config = config_from_cmd_str("hab-sup run --rumor-ttl 3600");
+            assert_eq!(config.gossip_timing.rumor_ttl_ms, 3_600_000);
+
+            let config = config_from_cmd_str("hab-sup run");
+            assert_eq!(config.gossip_timing.rumor_ttl_ms,
+                       Timing::default().rumor_ttl_ms);
+        }
+
+        #[test]
+        fn gossip_timing_should_be_set() {
+            let config = config_from_cmd_str("hab-sup run --gossip-ping-ms 500 \
+                                              --gossip-pingreq-ms 1200 \
+                                              --gossip-pingreq-targets 3 \
+                                              --gossip-suspicion-periods 5 \
+                                              --gossip-period-ms 250 --gossip-fanout 8 \
+                                              --gossip-departure-timeout-ms 60000");
+            let timing = config.gossip_timing;
+            assert_eq!(timing.ping_ms, 500);
+            assert_eq!(timing.pingreq_ms, 1200);
+            assert_eq!(timing.pingreq_targets, 3);
+            assert_eq!(timing.suspicion_timeout_protocol_periods, 5);
+            assert_eq!(timing.gossip_period_ms, 250);
+            assert_eq!(timing.gossip_fanout, 8);
+            assert_eq!(timing.departure_timeout_ms, 60_000);
+
+            let config = config_from_cmd_str("hab-sup run");
+            assert_eq!(config.gossip_timing.ping_ms, Timing::default().ping_ms);
+            assert_eq!(config.gossip_timing.gossip_fanout,
+                       Timing::default().gossip_fanout);
+        }
+
+        #[test]
+        fn gossip_timing_flags_win_over_the_sup_config_file() {
+            let sup_config = SupConfigFile { gossip_ping_ms: Some(500),
+                                             gossip_fanout: Some(8),
+                                             ..SupConfigFile::default() };
+            let cmd = "hab-sup run --gossip-fanout 2";
+            let matches = matches_from_cmd_vec(cmd_vec_from_cmd_str(cmd));
+            let config = config_from_matches(&matches, &sup_config).expect("Could not get config");
+            assert_eq!(config.gossip_timing.ping_ms, 500);
+            assert_eq!(config.gossip_timing.gossip_fanout, 2);
+        }
+
+        #[test]
+        fn gossip_timing_is_validated() {
+            let cmd = "hab-sup run --gossip-ping-ms 1000 --gossip-pingreq-ms 1500";
+            let matches = matches_from_cmd_vec(cmd_vec_from_cmd_str(cmd));
+            match config_from_matches(&matches, &SupConfigFile::default()) {
+                Err(Error::ButterflyError(habitat_butterfly::error::Error::InvalidTiming(_))) => (),
+                other => panic!("Expected an invalid timing error, got {:?}", other),
+            }
+        }
+
+        #[test]
+        fn tags_should_be_set() {
+            let config = config_from_cmd_str("hab-sup run --tag zone=us-east-1a --tag rack=12 \
+                                              --tag note=a=b");
+            let mut expected = BTreeMap::new();
+            expected.insert("zone".to_string(), "us-east-1a".to_string());
+            expected.insert("rack".to_string(), "12".to_string());
+            expected.insert("note".to_string(), "a=b".to_string());
+            assert_eq!(config.tags, expected);
+
+            let config = config_from_cmd_str("hab-sup run");
+            assert!(config.tags.is_empty());
+        }
+
         #[test]
         fn ring_key_is_set_properly_by_name() {
             let key_cache = TempDir::new().expect("Could not create tempdir");
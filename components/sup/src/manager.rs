@@ -6,6 +6,7 @@ pub mod commands;
 mod file_watcher;
 mod peer_watcher;
 mod periodic;
+mod rollback;
 mod self_updater;
 mod service_updater;
 mod spec_dir;
@@ -16,16 +17,21 @@ mod user_config_watcher;
 use self::{action::{ShutdownInput,
                     SupervisorAction},
            peer_watcher::PeerWatcher,
+           rollback::{Rollback,
+                      UpdateVerification,
+                      Verdict},
            self_updater::{SelfUpdater,
                           SUP_PKG_IDENT},
            service::{ConfigRendering,
                      DesiredState,
                      HealthCheckResult,
+                     RestartTracker,
                      Service,
                      ServiceProxy,
                      ServiceSpec,
                      Topology},
-           service_updater::ServiceUpdater,
+           service_updater::{RolloutStatus,
+                             ServiceUpdater},
            spec_dir::SpecDir,
            spec_watcher::SpecWatcher,
            sys::Sys,
@@ -41,13 +47,16 @@ use crate::{census::{CensusRing,
                     EventCore,
                     EventStreamConfig},
             http_gateway,
+            util,
             VERSION};
 use cpu_time::ProcessTime;
 use futures::{future,
               prelude::*,
               sync::{mpsc as fut_mpsc,
                      oneshot}};
-use habitat_butterfly::{member::Member,
+use habitat_butterfly::{identity::MemberIdentity,
+                        member::Member,
+                        rumor::election::ElectionPolicy,
                         server::{timing::Timing,
                                  ServerProxy,
                                  Suitability}};
@@ -56,6 +65,7 @@ use habitat_common::{liveliness_checker,
                      types::{GossipListenAddr,
                              HttpListenAddr,
                              ListenCtlAddr},
+                     ui::UI,
                      FeatureFlag};
 #[cfg(unix)]
 use habitat_core::os::{process::{ShutdownSignal,
@@ -69,7 +79,8 @@ use habitat_core::{crypto::SymKey,
                                  ShutdownTimeout},
                    package::{Identifiable,
                              PackageIdent,
-                             PackageInstall},
+                             PackageInstall,
+                             PackageTarget},
                    service::ServiceGroup,
                    util::ToI64,
                    ChannelIdent};
@@ -89,7 +100,8 @@ use rustls::{internal::pemfile,
              RootCertStore,
              ServerConfig};
 use serde_json;
-use std::{collections::{HashMap,
+use std::{collections::{BTreeMap,
+                        HashMap,
                         HashSet},
           ffi::OsStr,
           fs::{self,
@@ -100,6 +112,7 @@ use std::{collections::{HashMap,
                Read,
                Write},
           iter::IntoIterator,
+          mem,
           net::{IpAddr,
                 SocketAddr},
           path::{Path,
@@ -112,7 +125,8 @@ use std::{collections::{HashMap,
                  Condvar,
                  Mutex},
           thread,
-          time::Duration as StdDuration};
+          time::{Duration as StdDuration,
+                 Instant}};
 use time::{self,
            Duration as TimeDuration,
            SteadyTime,
@@ -125,6 +139,8 @@ use winapi::{shared::minwindef::PDWORD,
              um::processthreadsapi};
 
 const MEMBER_ID_FILE: &str = "MEMBER_ID";
+/// Where events are held until they are delivered, relative to the data path.
+const EVENT_SPOOL_DIR: &str = "event-spool";
 pub const PROC_LOCK_FILE: &str = "LOCK";
 
 static LOGKEY: &str = "MR";
@@ -199,7 +215,9 @@ impl ShutdownConfig {
 /// persistence data.
 #[derive(Debug, Serialize)]
 pub struct FsCfg {
-    pub sup_root: PathBuf,
+    pub sup_root:       PathBuf,
+    /// Where the Supervisor finds keys, including those that decrypt services' secrets
+    pub cache_key_path: PathBuf,
 
     data_path:      PathBuf,
     specs_path:     PathBuf,
@@ -208,15 +226,17 @@ pub struct FsCfg {
 }
 
 impl FsCfg {
-    fn new<T>(sup_root: T) -> Self
-        where T: Into<PathBuf>
+    fn new<T, U>(sup_root: T, cache_key_path: U) -> Self
+        where T: Into<PathBuf>,
+              U: Into<PathBuf>
     {
         let sup_root = sup_root.into();
         FsCfg { specs_path: sup_root.join("specs"),
                 data_path: sup_root.join("data"),
                 member_id_file: sup_root.join(MEMBER_ID_FILE),
                 proc_lock_file: sup_root.join(PROC_LOCK_FILE),
-                sup_root }
+                sup_root,
+                cache_key_path: cache_key_path.into() }
     }
 }
 
@@ -234,6 +254,9 @@ pub struct ManagerConfig {
     pub gossip_peers:        Vec<SocketAddr>,
     pub gossip_permanent:    bool,
     pub ring_key:            Option<SymKey>,
+    pub member_keys_path:    Option<PathBuf>,
+    pub gossip_timing:       Timing,
+    pub tags:                BTreeMap<String, String>,
     pub organization:        Option<String>,
     pub watch_peer_file:     Option<String>,
     pub tls_config:          Option<TLSConfig>,
@@ -342,6 +365,7 @@ pub struct ManagerState {
     cfg: ManagerConfig,
     services: Arc<sync::ManagerServices>,
     gateway_state: Arc<sync::GatewayState>,
+    butterfly: habitat_butterfly::Server,
 }
 
 pub(crate) mod sync {
@@ -364,6 +388,14 @@ pub(crate) mod sync {
         pub fn health_of(&self, service_group: &ServiceGroup) -> Option<HealthCheckResult> {
             self.0.health_check_data.get(service_group).copied()
         }
+
+        pub fn readiness_of(&self, service_group: &ServiceGroup) -> Option<HealthCheckResult> {
+            self.0.readiness_data.get(service_group).copied()
+        }
+
+        pub fn rollout_of(&self, service_group: &ServiceGroup) -> Option<&RolloutStatus> {
+            self.0.rollout_data.get(service_group)
+        }
     }
 
     pub struct GatewayStateWriteGuard<'a>(WriteGuard<'a, GatewayStateInner>);
@@ -371,19 +403,44 @@ pub(crate) mod sync {
     impl<'a> GatewayStateWriteGuard<'a> {
         fn new(lock: &'a Lock<GatewayStateInner>) -> Self { Self(lock.write()) }
 
-        pub fn set_census_data(&mut self, new_data: String) { self.0.census_data = new_data }
+        pub fn set_census_data(&mut self, new_data: String) {
+            let inner = &mut *self.0;
+            inner.feed.census_changed(&inner.census_data, &new_data);
+            inner.census_data = new_data
+        }
 
         pub fn set_butterfly_data(&mut self, new_data: String) { self.0.butterfly_data = new_data }
 
-        pub fn set_services_data(&mut self, new_data: String) { self.0.services_data = new_data }
+        pub fn set_services_data(&mut self, new_data: String) {
+            let inner = &mut *self.0;
+            inner.feed.services_changed(&inner.services_data, &new_data);
+            inner.services_data = new_data
+        }
 
         pub fn remove(&mut self, service_group: &ServiceGroup) {
-            self.0.health_check_data.remove(service_group);
+            let old = self.0.health_check_data.remove(service_group);
+            self.0.readiness_data.remove(service_group);
+            self.0.rollout_data.remove(service_group);
+            self.0.feed.health_changed(service_group, old, None);
         }
 
         pub fn set_health_of(&mut self, service_group: ServiceGroup, value: HealthCheckResult) {
-            self.0.health_check_data.insert(service_group, value);
+            let old = self.0
+                          .health_check_data
+                          .insert(service_group.clone(), value);
+            self.0.feed.health_changed(&service_group, old, Some(value));
+        }
+
+        pub fn set_readiness_of(&mut self, service_group: ServiceGroup, value: HealthCheckResult) {
+            self.0.readiness_data.insert(service_group, value);
         }
+
+        pub fn set_rollout_of(&mut self, service_group: ServiceGroup, value: RolloutStatus) {
+            self.0.rollout_data.insert(service_group, value);
+        }
+
+        /// Register a client of the HTTP gateway's `/events` feed.
+        pub fn subscribe(&mut self) -> fut_mpsc::Receiver<bytes::Bytes> { self.0.feed.subscribe() }
     }
 
     /// All the data that is ultimately served from the Supervisor's HTTP
@@ -414,6 +471,14 @@ pub(crate) mod sync {
         /// Data returned by /services/<SERVICE_NAME>/<GROUP_NAME>/health
         /// endpoint
         health_check_data: HashMap<ServiceGroup, HealthCheckResult>,
+        /// Readiness, also returned by
+        /// /services/<SERVICE_NAME>/<GROUP_NAME>/health
+        readiness_data: HashMap<ServiceGroup, HealthCheckResult>,
+        /// Data returned by /services/<SERVICE_NAME>/<GROUP_NAME>/rollout
+        rollout_data: HashMap<ServiceGroup, RolloutStatus>,
+        /// Clients of the /events endpoint, which are notified as the
+        /// data above changes
+        feed: http_gateway::GatewayFeed,
     }
 
     type ManagerServicesInner = HashMap<PackageIdent, Service>;
@@ -424,6 +489,8 @@ pub(crate) mod sync {
         fn new(lock: &'a Lock<ManagerServicesInner>) -> Self { Self(lock.read()) }
 
         pub fn services(&self) -> impl Iterator<Item = &Service> { self.0.values() }
+
+        pub fn get(&self, key: &PackageIdent) -> Option<&Service> { self.0.get(key) }
     }
 
     pub struct ManagerServicesWriteGuard<'a>(WriteGuard<'a, ManagerServicesInner>);
@@ -481,6 +548,16 @@ pub(crate) mod sync {
                 .and_then(Service::suitability)
                 .unwrap_or_else(u64::min_value)
         }
+
+        /// # Locking (see locking.md)
+        /// * `ManagerServices::inner` (read)
+        fn election_policy_for_msr(&self, service_group: &str) -> ElectionPolicy {
+            self.lock_msr()
+                .services()
+                .find(|svc| svc.service_group.as_ref() == service_group)
+                .map(|svc| svc.election_policy.clone())
+                .unwrap_or_default()
+        }
     }
 }
 
@@ -509,6 +586,8 @@ pub struct Manager {
     service_states:      HashMap<PackageIdent, Timespec>,
     sys:                 Arc<Sys>,
     http_disable:        bool,
+    /// The timing the butterfly server is started with.
+    gossip_timing:       Timing,
 
     /// Collects the identifiers of all services that are currently
     /// doing something asynchronously (like shutting down, or running
@@ -525,6 +604,21 @@ pub struct Manager {
     // the different operations.
     busy_services: Arc<Mutex<HashSet<PackageIdent>>>,
     services_need_reconciliation: ReconciliationFlag,
+    /// The package each service was running before it was stopped to
+    /// be updated, keyed by spec identifier, so we can announce when
+    /// the update has finished.
+    updates_in_progress: HashMap<PackageIdent, PackageIdent>,
+    /// The restart history of each service that is being restarted,
+    /// keyed by spec identifier, to be handed on to the `Service`
+    /// that replaces it.
+    restart_trackers: HashMap<PackageIdent, RestartTracker>,
+    /// How many health checks in a row were critical for each service
+    /// that is being restarted, keyed by spec identifier, to be handed
+    /// on to the `Service` that replaces it.
+    critical_checks: HashMap<PackageIdent, usize>,
+    /// Updated services that are still within their rollback window,
+    /// keyed by spec identifier.
+    update_verifications: HashMap<PackageIdent, UpdateVerification>,
 
     feature_flags: FeatureFlag,
     /// The runtime for spawning various `Manager` futures. Eventually, `Manager` could become a
@@ -542,7 +636,7 @@ impl Manager {
     /// * `MemberList::initial_members` (write)
     pub fn load_imlw(cfg: ManagerConfig, launcher: LauncherCli, sys_ip: IpAddr) -> Result<Manager> {
         let state_path = cfg.sup_root();
-        let fs_cfg = FsCfg::new(state_path);
+        let fs_cfg = FsCfg::new(state_path, cfg.cache_key_path.clone());
         Self::create_state_path_dirs(&fs_cfg)?;
         Self::clean_dirty_state(&fs_cfg)?;
         if env::var(LAUNCHER_LOCK_CLEAN_ENV).is_ok() {
@@ -577,7 +671,7 @@ impl Manager {
                 sys_ip: IpAddr)
                 -> Result<Manager> {
         debug!("new(cfg: {:?}, fs_cfg: {:?}", cfg, fs_cfg);
-        let mut runtime =
+        let runtime =
             RuntimeBuilder::new().name_prefix("tokio-")
                                  .core_threads(TokioThreadCount::configured_value().into())
                                  .build()
@@ -600,18 +694,25 @@ impl Manager {
                                cfg.ctl_listen,
                                cfg.http_listen,
                                sys_ip);
-        let member = Self::load_member(&mut sys, &fs_cfg)?;
+        let mut member = Self::load_member(&mut sys, &fs_cfg)?;
+        member.tags = cfg.tags.clone();
         let services = Arc::default();
         let suitability_lookup = Arc::clone(&services) as Arc<dyn Suitability>;
 
-        let server = habitat_butterfly::Server::new(sys.gossip_listen(),
-                                                    sys.gossip_listen(),
-                                                    member,
-                                                    cfg.ring_key,
-                                                    None,
-                                                    Some(&fs_cfg.data_path),
-                                                    suitability_lookup)?;
+        let mut server = habitat_butterfly::Server::new(sys.gossip_listen(),
+                                                        sys.gossip_listen(),
+                                                        member,
+                                                        cfg.ring_key,
+                                                        None,
+                                                        Some(&fs_cfg.data_path),
+                                                        suitability_lookup)?;
         outputln!("Supervisor Member-ID {}", sys.member_id);
+        if let Some(ref path) = cfg.member_keys_path {
+            let identity = MemberIdentity::load(&sys.member_id, path)?;
+            outputln!("Signing gossip with member key {}",
+                      identity.name_with_rev());
+            server.set_member_identity(identity);
+        }
         for peer_addr in &cfg.gossip_peers {
             let mut peer = Member::default();
             peer.address = format!("{}", peer_addr.ip());
@@ -650,12 +751,15 @@ impl Manager {
             let ec = EventCore::new(&es_config, &sys, fqdn);
             // unwrap won't fail here; if there were an issue, from_env()
             // would have already propagated an error up the stack.
-            event::init_stream(es_config, ec, &mut runtime)?;
+            event::init_stream(es_config,
+                               ec,
+                               fs_cfg.data_path.join(EVENT_SPOOL_DIR))?;
         }
 
         Ok(Manager { state: Arc::new(ManagerState { cfg: cfg_static,
                                                     services,
-                                                    gateway_state: Arc::default() }),
+                                                    gateway_state: Arc::default(),
+                                                    butterfly: server.clone() }),
                      self_updater,
                      updater: Arc::new(Mutex::new(ServiceUpdater::new(server.clone()))),
                      census_ring: CensusRing::new(sys.member_id.clone()),
@@ -670,8 +774,13 @@ impl Manager {
                      service_states: HashMap::new(),
                      sys: Arc::new(sys),
                      http_disable: cfg.http_disable,
+                     gossip_timing: cfg.gossip_timing,
                      busy_services: Arc::new(Mutex::new(HashSet::new())),
                      services_need_reconciliation: ReconciliationFlag::new(false),
+                     updates_in_progress: HashMap::new(),
+                     restart_trackers: HashMap::new(),
+                     critical_checks: HashMap::new(),
+                     update_verifications: HashMap::new(),
                      feature_flags: cfg.feature_flags,
                      runtime })
     }
@@ -761,11 +870,11 @@ impl Manager {
     /// * `ManagerServices::inner` (read)
     fn add_service_rsw_mlw_rhw_msr(&mut self, spec: ServiceSpec) {
         let ident = spec.ident.clone();
-        let service = match Service::new(self.sys.clone(),
-                                         spec,
-                                         self.fs_cfg.clone(),
-                                         self.organization.as_ref().map(String::as_str),
-                                         self.state.gateway_state.clone())
+        let mut service = match Service::new(self.sys.clone(),
+                                             spec,
+                                             self.fs_cfg.clone(),
+                                             self.organization.as_ref().map(String::as_str),
+                                             self.state.gateway_state.clone())
         {
             Ok(service) => {
                 outputln!("Starting {} ({})", ident, service.pkg.ident);
@@ -773,6 +882,10 @@ impl Manager {
             }
             Err(err) => {
                 outputln!("Unable to start {}, {}", ident, err);
+                self.updates_in_progress.remove(&ident);
+                self.restart_trackers.remove(&ident);
+                self.critical_checks.remove(&ident);
+                self.update_verifications.remove(&ident);
                 // Remove the spec file so it does not look like this service is loaded.
                 self.remove_spec_file(&ident).ok();
                 return;
@@ -821,7 +934,26 @@ impl Manager {
             .expect("Updater lock poisoned")
             .add(&service);
 
+        if let Some(restart_tracker) = self.restart_trackers.remove(&service.spec_ident) {
+            service.restart_tracker = restart_tracker;
+        }
+        if let Some(critical_checks) = self.critical_checks.remove(&service.spec_ident) {
+            service.resume_critical_checks(critical_checks);
+        }
+
         event::service_started(&service);
+        if let Some(previous) = self.updates_in_progress.remove(&service.spec_ident) {
+            event::service_update_finished(&service, &previous);
+            if service.rollback_window > 0 {
+                let window = StdDuration::from_secs(service.rollback_window);
+                self.update_verifications.insert(service.spec_ident.clone(),
+                                                 UpdateVerification::new(previous,
+                                                                         service.pkg
+                                                                                .ident
+                                                                                .clone(),
+                                                                         window));
+            }
+        }
 
         self.state
             .services
@@ -879,8 +1011,7 @@ impl Manager {
 
         outputln!("Starting gossip-listener on {}",
                   self.butterfly.gossip_addr());
-        self.butterfly
-            .start_rsw_mlw_smw_rhw_msr(&Timing::default())?;
+        self.butterfly.start_rsw_mlw_smw_rhw_msr(&self.gossip_timing)?;
         debug!("gossip-listener started");
         self.persist_state_rsr_mlr_gsw_msr();
         let http_listen_addr = self.sys.http_listen();
@@ -1102,6 +1233,9 @@ impl Manager {
                 }
             }
 
+            self.roll_back_failed_updates_msr();
+            self.publish_rollouts_msr_gsw();
+
             // This is really only needed until everything is running
             // in futures.
             let now = time::get_time();
@@ -1189,15 +1323,18 @@ impl Manager {
         let mut updater = self.updater.lock().expect("Updater lock poisoned");
 
         let mut state_services = self.state.services.lock_msw();
+        let census_ring = &self.census_ring;
+        let updates_in_progress = &mut self.updates_in_progress;
         let idents_to_restart: Vec<_> = state_services.iter()
             .filter_map(|(current_ident, service)| {
                 if service.needs_restart {
                     Some(current_ident.clone())
                 } else if let Some(new_ident) =
-                    updater.check_for_updated_package_rsw_mlr_rhw(&service, &self.census_ring)
+                    updater.check_for_updated_package_rsw_mlr_rhw(&service, census_ring)
                 {
                     outputln!("Updating from {} to {}", current_ident, new_ident);
                     event::service_update_started(&service, &new_ident);
+                    updates_in_progress.insert(current_ident.clone(), service.pkg.ident.clone());
                     Some(current_ident.clone())
                 } else {
                     trace!("No update found for {}", current_ident);
@@ -1210,11 +1347,127 @@ impl Manager {
         for current_ident in idents_to_restart {
             // unwrap is safe because we've to the write lock, and we
             // know there's a value present at this key.
-            services_to_restart.push(state_services.remove(&current_ident).unwrap());
+            let mut service = state_services.remove(&current_ident).unwrap();
+            if service.needs_restart {
+                let restart_tracker = mem::replace(&mut service.restart_tracker,
+                                                   RestartTracker::default());
+                self.critical_checks
+                    .insert(current_ident.clone(), service.critical_checks());
+                self.restart_trackers.insert(current_ident, restart_tracker);
+            }
+            services_to_restart.push(service);
         }
         services_to_restart
     }
 
+    /// Check each updated service that is within its rollback window,
+    /// rolling back those whose new release has failed.
+    ///
+    /// # Locking (see locking.md)
+    /// * `ManagerServices::inner` (read)
+    fn roll_back_failed_updates_msr(&mut self) {
+        let now = Instant::now();
+        let mut finished = Vec::new();
+        let mut rollbacks = Vec::new();
+        {
+            let services = self.state.services.lock_msr();
+            for (ident, verification) in &self.update_verifications {
+                let service = match services.get(ident) {
+                    Some(service) => service,
+                    // The service is being restarted; it is judged once it is back, even if
+                    // its window has passed in the meantime.
+                    None => continue,
+                };
+                match verification.check(service.critical_checks(), service.crash_looping(), now)
+                {
+                    Verdict::Pending => continue,
+                    Verdict::Verified => {
+                        outputln!(preamble service.service_group,
+                                  "Keeping the update to {}",
+                                  verification.release);
+                    }
+                    Verdict::Failed(reason) => {
+                        rollbacks.push(Rollback { service_group: service.service_group.clone(),
+                                                  service_metadata:
+                                                      service.to_service_metadata(),
+                                                  spec: service.to_spec(),
+                                                  previous: verification.previous.clone(),
+                                                  release: verification.release.clone(),
+                                                  reason });
+                    }
+                }
+                finished.push(ident.clone());
+            }
+        }
+        // Reinstalling the previous release can take a while, so it is
+        // done once the services are let go of.
+        for rollback in rollbacks {
+            self.roll_back_update(rollback);
+        }
+        for ident in finished {
+            self.update_verifications.remove(&ident);
+        }
+    }
+
+    /// Publish the update rollout progress of each service to the HTTP
+    /// gateway.
+    ///
+    /// # Locking (see locking.md)
+    /// * `ManagerServices::inner` (read)
+    /// * `GatewayState::inner` (write)
+    fn publish_rollouts_msr_gsw(&self) {
+        let updater = self.updater.lock().expect("Updater lock poisoned");
+        let services = self.state.services.lock_msr();
+        let mut gateway_state = self.state.gateway_state.lock_gsw();
+        for service in services.services() {
+            if let Some(status) = updater.rollout_status(service, &self.census_ring) {
+                gateway_state.set_rollout_of(service.service_group.clone(), status);
+            }
+        }
+    }
+
+    /// Block the release an updated service failed on, so that the
+    /// service is restarted on the release it ran before the update.
+    fn roll_back_update(&self, rollback: Rollback) {
+        let Rollback { service_group,
+                       service_metadata,
+                       mut spec,
+                       previous,
+                       release,
+                       reason, } = rollback;
+        outputln!(preamble service_group,
+                  "Rolling back from {} to {}; {}",
+                  release,
+                  previous,
+                  reason);
+        if util::pkg::installed(&previous).is_none() {
+            let install_source = (previous.clone(), PackageTarget::active_target()).into();
+            if let Err(err) = util::pkg::install(&mut UI::with_sinks(),
+                                                 &spec.bldr_url,
+                                                 &install_source,
+                                                 &spec.channel)
+            {
+                outputln!(preamble service_group,
+                          "Unable to reinstall {}: {}",
+                          previous,
+                          err);
+                return;
+            }
+        }
+        // Writing the spec restarts the service on the release it is
+        // rolled back to.
+        spec.blocked_releases.push(release.clone());
+        spec.rolled_back_to = Some(previous.clone());
+        if let Err(err) = self.state.cfg.save_spec_for(&spec) {
+            outputln!(preamble service_group,
+                      "Unable to block {}: {}",
+                      release,
+                      err);
+            return;
+        }
+        event::service_update_rolled_back(service_metadata, &release, &previous, reason);
+    }
+
     /// Returns a Vec of futures for shutting down those services that
     /// need to be updated.
     ///
@@ -1536,6 +1789,10 @@ impl Manager {
     {
         ops.into_iter()
            .filter_map(|op| {
+               if let ServiceOperation::Stop(ref spec) = op {
+                   // An unloaded or stopped service has no update left to verify.
+                   self.update_verifications.remove(&spec.ident);
+               }
                match op {
                    ServiceOperation::Stop(spec)
                    | ServiceOperation::Restart { to_stop: spec, .. } => {
@@ -1942,6 +2199,9 @@ mod test {
                             gossip_peers:        vec![],
                             gossip_permanent:    false,
                             ring_key:            None,
+                            member_keys_path:    None,
+                            gossip_timing:       Timing::default(),
+                            tags:                BTreeMap::new(),
                             organization:        None,
                             watch_peer_file:     None,
                             tls_config:          None,
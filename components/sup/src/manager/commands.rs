@@ -9,16 +9,25 @@ use crate::{ctl_gateway::CtlRequest,
                                 ProcessState},
                       ManagerState},
             util};
-use habitat_butterfly as butterfly;
+use habitat_butterfly::{self as butterfly,
+                        member::{Health,
+                                 Member,
+                                 Membership},
+                        rumor::election::ElectionStatus};
 use habitat_common::{command::package::install::InstallSource,
                      outputln,
-                     templating::package::Pkg,
+                     templating::{config::{inline_toml,
+                                           CfgLayer},
+                                  package::Pkg},
                      ui::UIWriter};
-use habitat_core::{package::{Identifiable,
+use habitat_core::{crypto::{keys::parse_name_with_rev,
+                            SymKey},
+                   package::{Identifiable,
                              PackageIdent,
                              PackageTarget},
                    service::ServiceGroup};
 use habitat_sup_protocol::{self as protocol,
+                           codec::SrvMessage,
                            net::{self,
                                  ErrCode,
                                  NetResult}};
@@ -57,60 +66,170 @@ pub fn service_cfg_msr(mgr: &ManagerState,
     Err(net::err(ErrCode::NotFound, format!("Service not loaded, {}", ident)))
 }
 
-pub fn service_cfg_validate(_mgr: &ManagerState,
-                            req: &mut CtlRequest,
-                            opts: protocol::ctl::SvcValidateCfg)
-                            -> NetResult<()> {
+/// Check new configuration for a service group against the schema of the package, if the
+/// service is loaded here. A service we only know of through rumors can't be checked, and the
+/// configuration is accepted.
+///
+/// # Locking (see locking.md)
+/// * `ManagerServices::inner` (read)
+pub fn service_cfg_validate_msr(mgr: &ManagerState,
+                                req: &mut CtlRequest,
+                                opts: protocol::ctl::SvcValidateCfg)
+                                -> NetResult<()> {
     let cfg = opts.cfg.ok_or_else(err_update_client)?;
-    let format = opts.format
-                     .and_then(protocol::types::service_cfg::Format::from_i32)
-                     .unwrap_or_default();
-    if cfg.len() > protocol::butterfly::MAX_SVC_CFG_SIZE {
-        return Err(net::err(ErrCode::EntityTooLarge, "Configuration too large."));
-    }
-    if format != protocol::types::service_cfg::Format::Toml {
-        return Err(net::err(ErrCode::NotSupported,
-                            format!("Configuration format {} not available.",
-                                    format)));
+    let new_cfg = parse_svc_cfg(&cfg, opts.format)?;
+    if let Some(service_group) = opts.service_group {
+        let service_group: ServiceGroup = service_group.into();
+        let services = mgr.services.lock_msr();
+        if let Some(service) = services.services().find(|s| s.service_group == service_group) {
+            for error in service.cfg.validate_gossip(&new_cfg).unwrap_or_default() {
+                req.reply_partial(net::err(ErrCode::InvalidPayload, error));
+            }
+        }
     }
-    let _new_cfg: toml::value::Table = toml::from_slice(&cfg).map_err(|e| {
-                                                                 net::err(
-            ErrCode::BadPayload,
-            format!("Unable to decode configuration as {}, {}", format, e),
-        )
-                                                             })?;
     req.reply_complete(net::ok());
     Ok(())
-    // JW TODO: Hold off on validation until we can validate services which aren't currently
-    // loaded in the Supervisor but are known through rumor propagation.
-    // let service_group: ServiceGroup = opts.service_group.into();
-    // for service in mgr.services.read().unwrap().iter() {
-    //     if service.service_group != service_group {
-    //         continue;
-    //     }
-    //     if let Some(interface) = service.cfg.interface() {
-    //         match Cfg::validate(interface, &new_cfg) {
-    //             None => req.reply_complete(net::ok()),
-    //             Some(errors) => {
-    //                 for error in errors {
-    //                     req.reply_partial(net::err(ErrCode::InvalidPayload, error));
-    //                 }
-    //                 req.reply_complete(net::ok());
-    //             }
-    //         }
-    //         return Ok(());
-    //     } else {
-    //         // No interface, this service can't be configured.
-    //         return Err(net::err(
-    //             ErrCode::NotFound,
-    //             "Service has no configurable attributes.",
-    //         ));
-    //     }
-    // }
-    // Err(net::err(
-    //     ErrCode::NotFound,
-    //     format!("Service not loaded, {}", service_group),
-    // ))
+}
+
+/// Explain the effective configuration of a loaded service: the layer each value came from and
+/// the values it overrides. Given configuration a `hab config apply` would set, also list the
+/// keys, templates and hooks applying it would change, without applying it.
+///
+/// # Locking (see locking.md)
+/// * `ManagerServices::inner` (read)
+pub fn service_cfg_explain_msr(mgr: &ManagerState,
+                               req: &mut CtlRequest,
+                               opts: protocol::ctl::SvcCfgExplain)
+                               -> NetResult<()> {
+    let pending = match opts.cfg {
+        Some(ref cfg) => Some(parse_svc_cfg(cfg, opts.format)?),
+        None => None,
+    };
+    let ident: Option<PackageIdent> = opts.ident.map(Into::into);
+    let service_group: Option<ServiceGroup> = opts.service_group.map(Into::into);
+    if ident.is_none() && service_group.is_none() {
+        return Err(err_update_client());
+    }
+    let services = mgr.services.lock_msr();
+    let service = services.services().find(|s| {
+                                          ident.as_ref()
+                                               .map_or(true, |i| s.pkg.ident.satisfies(i))
+                                          && service_group.as_ref()
+                                                          .map_or(true, |g| s.service_group == *g)
+                                      });
+    let service = match service {
+        Some(service) => service,
+        None => {
+            let target = service_group.map(|g| g.to_string())
+                                      .or_else(|| ident.map(|i| i.to_string()))
+                                      .unwrap_or_default();
+            return Err(net::err(ErrCode::NotFound, format!("Service not loaded, {}", target)));
+        }
+    };
+    let incarnation = service.cfg.gossip_incarnation;
+    let keys = service.cfg
+                      .explain()
+                      .into_iter()
+                      .map(|key| {
+                          protocol::ctl::CfgKey {
+                              key: Some(key.key),
+                              value: Some(inline_toml(&key.value)),
+                              incarnation: if key.layer == CfgLayer::Gossip {
+                                  Some(incarnation)
+                              } else {
+                                  None
+                              },
+                              layer: Some(key.layer.to_string()),
+                              overridden: key.overridden
+                                             .iter()
+                                             .map(|(layer, value)| {
+                                                 protocol::ctl::CfgOverriddenValue {
+                                                     layer: Some(layer.to_string()),
+                                                     value: Some(inline_toml(value)),
+                                                 }
+                                             })
+                                             .collect(),
+                          }
+                      })
+                      .collect();
+    let mut msg = protocol::ctl::SvcCfgExplanation { service_group:
+                                                         Some(service.service_group
+                                                                     .clone()
+                                                                     .into()),
+                                                     incarnation: Some(incarnation),
+                                                     keys,
+                                                     ..Default::default() };
+    if let Some(pending) = pending {
+        msg.changes = service.cfg
+                             .diff_gossip(&pending)
+                             .into_iter()
+                             .map(|change| {
+                                 protocol::ctl::CfgKeyChange { key:     Some(change.key),
+                                                               current:
+                                                                   change.current
+                                                                         .as_ref()
+                                                                         .map(inline_toml),
+                                                               pending:
+                                                                   change.pending
+                                                                         .as_ref()
+                                                                         .map(inline_toml), }
+                             })
+                             .collect();
+        let (templates, hooks) = service.changed_by_gossip(&pending)?;
+        msg.templates = templates;
+        msg.hooks = hooks.into_iter().map(str::to_string).collect();
+    }
+    req.reply_complete(msg);
+    Ok(())
+}
+
+/// List the versions of a service group's configuration applied to a loaded service, newest
+/// first, or a single version with its configuration.
+///
+/// # Locking (see locking.md)
+/// * `ManagerServices::inner` (read)
+pub fn service_cfg_history_msr(mgr: &ManagerState,
+                               req: &mut CtlRequest,
+                               opts: protocol::ctl::SvcCfgHistory)
+                               -> NetResult<()> {
+    let service_group: ServiceGroup = opts.service_group.ok_or_else(err_update_client)?.into();
+    let services = mgr.services.lock_msr();
+    let service = services.services()
+                          .find(|s| s.service_group == service_group)
+                          .ok_or_else(|| {
+                              net::err(ErrCode::NotFound,
+                                       format!("Service not loaded, {}", service_group))
+                          })?;
+    let history = service.cfg_history();
+    let versions = match opts.incarnation {
+        Some(incarnation) => {
+            let version = history.get(incarnation).ok_or_else(|| {
+                              net::err(ErrCode::NotFound,
+                                       format!("Configuration version {} of {} is not in its \
+                                                history",
+                                               incarnation, service_group))
+                          })?;
+            vec![(version, Some(version.cfg.clone().into_bytes()))]
+        }
+        None => history.versions().map(|version| (version, None)).collect(),
+    };
+    let versions =
+        versions.into_iter()
+                .map(|(version, cfg)| {
+                    protocol::ctl::SvcCfgVersion { incarnation: Some(version.incarnation),
+                                                   author: version.author.clone(),
+                                                   applied_at: Some(version.applied_at),
+                                                   hash: Some(version.hash.clone()),
+                                                   cfg,
+                                                   encrypted: Some(version.encrypted) }
+                })
+                .collect();
+    req.reply_complete(protocol::ctl::SvcCfgVersions { service_group:
+                                                           Some(service_group.into()),
+                                                       incarnation:
+                                                           Some(service.cfg.gossip_incarnation),
+                                                       versions });
+    Ok(())
 }
 
 pub fn service_cfg_set(mgr: &ManagerState,
@@ -129,7 +248,8 @@ pub fn service_cfg_set(mgr: &ManagerState,
               service_group,);
     let mut client =
         match butterfly::client::Client::new(&mgr.cfg.gossip_listen.local_addr().to_string(),
-                                             mgr.cfg.ring_key.clone())
+                                             mgr.butterfly.ring_keys_srkr().primary().cloned(),
+                                             mgr.butterfly.member_identity().cloned())
         {
             Ok(client) => client,
             Err(err) => {
@@ -162,7 +282,8 @@ pub fn service_file_put(mgr: &ManagerState,
               service_group,);
     let mut client =
         match butterfly::client::Client::new(&mgr.cfg.gossip_listen.local_addr().to_string(),
-                                             mgr.cfg.ring_key.clone())
+                                             mgr.butterfly.ring_keys_srkr().primary().cloned(),
+                                             mgr.butterfly.member_identity().cloned())
         {
             Ok(client) => client,
             Err(err) => {
@@ -293,7 +414,8 @@ pub fn supervisor_depart(mgr: &ManagerState,
     let member_id = opts.member_id.ok_or_else(err_update_client)?;
     let mut client =
         match butterfly::client::Client::new(&mgr.cfg.gossip_listen.local_addr().to_string(),
-                                             mgr.cfg.ring_key.clone())
+                                             mgr.butterfly.ring_keys_srkr().primary().cloned(),
+                                             mgr.butterfly.member_identity().cloned())
         {
             Ok(client) => client,
             Err(err) => {
@@ -311,6 +433,202 @@ pub fn supervisor_depart(mgr: &ManagerState,
     }
 }
 
+/// # Locking (see locking.md)
+/// * `Server::ring_keys` (write)
+pub fn ring_key_accept_srkw(mgr: &ManagerState,
+                            req: &mut CtlRequest,
+                            opts: protocol::ctl::RingKeyAccept)
+                            -> NetResult<()> {
+    let name = opts.name.ok_or_else(err_update_client)?;
+    let ring_key = if parse_name_with_rev(&name).is_ok() {
+        SymKey::get_pair_for(&name, &mgr.cfg.cache_key_path)
+    } else {
+        SymKey::get_latest_pair_for(&name, &mgr.cfg.cache_key_path)
+    };
+    let ring_key = ring_key.map_err(|e| net::err(ErrCode::NotFound, e.to_string()))?;
+    outputln!("Accepting gossip encrypted with ring key {}",
+              ring_key.name_with_rev());
+    mgr.butterfly.accept_ring_key_srkw(ring_key);
+    req.reply_complete(net::ok());
+    Ok(())
+}
+
+/// # Locking (see locking.md)
+/// * `Server::ring_keys` (write)
+pub fn ring_key_promote_srkw(mgr: &ManagerState,
+                             req: &mut CtlRequest,
+                             opts: protocol::ctl::RingKeyPromote)
+                             -> NetResult<()> {
+    let name_with_rev = opts.name_with_rev.ok_or_else(err_update_client)?;
+    mgr.butterfly
+       .promote_ring_key_srkw(&name_with_rev)
+       .map_err(|e| net::err(ErrCode::NotFound, e.to_string()))?;
+    outputln!("Encrypting gossip with ring key {}", name_with_rev);
+    req.reply_complete(net::ok());
+    Ok(())
+}
+
+/// # Locking (see locking.md)
+/// * `Server::ring_keys` (write)
+pub fn ring_key_retire_srkw(mgr: &ManagerState,
+                            req: &mut CtlRequest,
+                            opts: protocol::ctl::RingKeyRetire)
+                            -> NetResult<()> {
+    let name_with_rev = opts.name_with_rev.ok_or_else(err_update_client)?;
+    mgr.butterfly
+       .retire_ring_key_srkw(&name_with_rev)
+       .map_err(|e| {
+           match e {
+               butterfly::error::Error::PrimaryRingKey(_) => {
+                   net::err(ErrCode::Conflict, e.to_string())
+               }
+               _ => net::err(ErrCode::NotFound, e.to_string()),
+           }
+       })?;
+    outputln!("No longer accepting gossip encrypted with ring key {}",
+              name_with_rev);
+    req.reply_complete(net::ok());
+    Ok(())
+}
+
+/// # Locking (see locking.md)
+/// * `Server::ring_keys` (read)
+pub fn ring_key_list_srkr(mgr: &ManagerState,
+                          req: &mut CtlRequest,
+                          _opts: protocol::ctl::RingKeyList)
+                          -> NetResult<()> {
+    let ring_keys = mgr.butterfly.ring_keys_srkr();
+    let primary = ring_keys.primary().map(SymKey::name_with_rev);
+    let accepted = ring_keys.iter()
+                            .map(SymKey::name_with_rev)
+                            .filter(|name_with_rev| Some(name_with_rev) != primary.as_ref())
+                            .collect();
+    req.reply_complete(protocol::ctl::RingKeyStatus { primary, accepted });
+    Ok(())
+}
+
+/// # Locking (see locking.md)
+/// * `MemberList::entries` (read)
+/// * `Server::member` (read)
+pub fn ring_status_mlr_smr(mgr: &ManagerState,
+                           req: &mut CtlRequest,
+                           _opts: protocol::ctl::RingStatus)
+                           -> NetResult<()> {
+    let myself = mgr.butterfly.myself().lock_smr().to_member();
+    let health = if myself.departed {
+        Health::Departed
+    } else {
+        Health::Alive
+    };
+    let mut members = vec![ring_member(myself, health, true)];
+    mgr.butterfly
+       .member_list
+       .with_memberships_mlr(|Membership { member, health, .. }| {
+           if member.id != mgr.butterfly.member_id() {
+               members.push(ring_member(member, health, false));
+           }
+           Ok(())
+       })
+       .map_err(|e| net::err(ErrCode::Internal, e.to_string()))?;
+    members.sort_by(|a, b| a.member_id.cmp(&b.member_id));
+    reply_all(req, members);
+    Ok(())
+}
+
+fn ring_member(member: Member, health: Health, myself: bool) -> protocol::ctl::RingMember {
+    protocol::ctl::RingMember { member_id:   Some(member.id),
+                                address:     Some(member.address),
+                                swim_port:   Some(u32::from(member.swim_port)),
+                                gossip_port: Some(u32::from(member.gossip_port)),
+                                health:      Some(health.to_string()),
+                                incarnation: Some(member.incarnation.to_u64()),
+                                persistent:  Some(member.persistent),
+                                myself:      Some(myself), }
+}
+
+/// # Locking (see locking.md)
+/// * `RumorStore::list` (read)
+/// * `RumorHeat::inner` (read)
+pub fn ring_rumors_rsr_rhr(mgr: &ManagerState,
+                           req: &mut CtlRequest,
+                           _opts: protocol::ctl::RingRumors)
+                           -> NetResult<()> {
+    let mut rumors =
+        mgr.butterfly
+           .rumor_summaries_rsr_rhr()
+           .into_iter()
+           .map(|summary| {
+               let (term, status) = match summary.election {
+                   Some((term, status)) => (Some(term), Some(election_status(status).to_string())),
+                   None => (None, None),
+               };
+               protocol::ctl::RingRumor { kind: Some(summary.key.kind.to_string()),
+                                          id: Some(summary.key.id),
+                                          key: Some(summary.key.key),
+                                          incarnation: summary.incarnation,
+                                          term,
+                                          election_status: status,
+                                          shared: Some(summary.shared as u64),
+                                          cooled: Some(summary.cooled as u64) }
+           })
+           .collect::<Vec<_>>();
+    rumors.sort_by(|a, b| (&a.kind, &a.key, &a.id).cmp(&(&b.kind, &b.key, &b.id)));
+    reply_all(req, rumors);
+    Ok(())
+}
+
+pub fn ring_rumor_purge(mgr: &ManagerState,
+                        req: &mut CtlRequest,
+                        opts: protocol::ctl::RingRumorPurge)
+                        -> NetResult<()> {
+    let service_group: ServiceGroup = opts.service_group.ok_or_else(err_update_client)?.into();
+    let mut client =
+        match butterfly::client::Client::new(&mgr.cfg.gossip_listen.local_addr().to_string(),
+                                             mgr.butterfly.ring_keys_srkr().primary().cloned(),
+                                             mgr.butterfly.member_identity().cloned())
+        {
+            Ok(client) => client,
+            Err(err) => {
+                outputln!("Failed to connect to own gossip server, {}", err);
+                return Err(net::err(ErrCode::Internal, err.to_string()));
+            }
+        };
+    outputln!("Purging the rumors of {}", service_group);
+    match client.send_purge(&service_group) {
+        Ok(()) => {
+            req.reply_complete(net::ok());
+            Ok(())
+        }
+        Err(e) => Err(net::err(ErrCode::Internal, e.to_string())),
+    }
+}
+
+fn election_status(status: ElectionStatus) -> &'static str {
+    match status {
+        ElectionStatus::Running => "running",
+        ElectionStatus::NoQuorum => "no-quorum",
+        ElectionStatus::Finished => "finished",
+    }
+}
+
+/// Reply with each message in turn, or with `NetOk` if there are none.
+fn reply_all<T>(req: &mut CtlRequest, msgs: Vec<T>)
+    where T: Into<SrvMessage> + fmt::Debug
+{
+    if msgs.is_empty() {
+        req.reply_complete(net::ok());
+    } else {
+        let mut list = msgs.into_iter().peekable();
+        while let Some(msg) = list.next() {
+            if list.peek().is_some() {
+                req.reply_partial(msg);
+            } else {
+                req.reply_complete(msg);
+            }
+        }
+    }
+}
+
 /// # Locking (see locking.md)
 /// * `GatewayState::inner` (read)
 pub fn service_status_gsr(mgr: &ManagerState,
@@ -350,6 +668,25 @@ pub fn service_status_gsr(mgr: &ManagerState,
 
 ////////////////////////////////////////////////////////////////////////
 // Private helper functions
+/// Decode configuration sent to be applied to a service group, as `hab config apply` does.
+fn parse_svc_cfg(cfg: &[u8], format: Option<i32>) -> NetResult<toml::value::Table> {
+    let format = format.and_then(protocol::types::service_cfg::Format::from_i32)
+                       .unwrap_or_default();
+    if cfg.len() > protocol::butterfly::MAX_SVC_CFG_SIZE {
+        return Err(net::err(ErrCode::EntityTooLarge, "Configuration too large."));
+    }
+    if format != protocol::types::service_cfg::Format::Toml {
+        return Err(net::err(ErrCode::NotSupported,
+                            format!("Configuration format {} not available.",
+                                    format)));
+    }
+    toml::from_slice(cfg).map_err(|e| {
+                             net::err(ErrCode::BadPayload,
+                                      format!("Unable to decode configuration as {}, {}",
+                                              format, e))
+                         })
+}
+
 fn err_update_client() -> net::NetErr { net::err(ErrCode::UpdateClient, "client out of date") }
 
 #[derive(Deserialize)]
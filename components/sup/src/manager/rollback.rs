@@ -0,0 +1,127 @@
+//! Rolling back service updates that fail soon after they are applied.
+//!
+//! A service loaded with a rollback window is watched for that long
+//! after each update. If its health check is critical several times in
+//! a row, or its process crash-loops, within the window, or its health
+//! check is still critical when the window closes, the Manager blocks
+//! the new release and restarts the service on the release it ran
+//! before the update. Blocked releases are recorded in the service's
+//! spec, so they are never run or updated to again.
+
+use super::service::ServiceSpec;
+use crate::event::ServiceMetadata;
+use habitat_core::{package::PackageIdent,
+                   service::ServiceGroup};
+use std::time::{Duration,
+                Instant};
+
+/// How many health checks in a row must be critical for a release to
+/// fail within its window.
+pub const CRITICAL_CHECKS_TO_FAIL: usize = 3;
+
+/// An update whose new release is still within its rollback window.
+#[derive(Debug)]
+pub struct UpdateVerification {
+    /// The release that ran before the update.
+    pub previous: PackageIdent,
+    /// The release being verified.
+    pub release: PackageIdent,
+    deadline: Instant,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    /// The window has not yet passed.
+    Pending,
+    /// The release stayed healthy for the whole window.
+    Verified,
+    /// The release failed, for the given reason.
+    Failed(&'static str),
+}
+
+impl UpdateVerification {
+    pub fn new(previous: PackageIdent, release: PackageIdent, window: Duration) -> Self {
+        Self { previous,
+               release,
+               deadline: Instant::now() + window }
+    }
+
+    /// Judge the release from how many health checks of the service in
+    /// a row have been critical and whether its process is
+    /// crash-looping.
+    pub fn check(&self, critical_checks: usize, crash_looping: bool, now: Instant) -> Verdict {
+        if crash_looping {
+            Verdict::Failed("its process is crash-looping")
+        } else if critical_checks >= CRITICAL_CHECKS_TO_FAIL {
+            Verdict::Failed("its health check is critical again and again")
+        } else if now < self.deadline {
+            Verdict::Pending
+        } else if critical_checks > 0 {
+            Verdict::Failed("its health check is still critical after its rollback window")
+        } else {
+            Verdict::Verified
+        }
+    }
+}
+
+/// A failed update, taken from its service so it can be rolled back
+/// without holding on to the service.
+#[derive(Debug)]
+pub struct Rollback {
+    pub service_group:    ServiceGroup,
+    pub service_metadata: ServiceMetadata,
+    /// The spec of the service, which the rollback rewrites.
+    pub spec:             ServiceSpec,
+    /// The release to roll back to.
+    pub previous:         PackageIdent,
+    /// The release that failed.
+    pub release:          PackageIdent,
+    pub reason:           &'static str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verification() -> UpdateVerification {
+        UpdateVerification::new("core/redis/4.0.14/20190319155852".parse().unwrap(),
+                                "core/redis/5.0.5/20190812120000".parse().unwrap(),
+                                Duration::from_secs(60))
+    }
+
+    #[test]
+    fn a_healthy_release_is_pending_until_the_window_passes() {
+        let verification = verification();
+        let now = Instant::now();
+
+        assert_eq!(verification.check(0, false, now), Verdict::Pending);
+        assert_eq!(verification.check(0, false, now + Duration::from_secs(61)),
+                   Verdict::Verified);
+    }
+
+    #[test]
+    fn a_single_critical_health_check_does_not_fail_the_release() {
+        assert_eq!(verification().check(1, false, Instant::now()),
+                   Verdict::Pending);
+    }
+
+    #[test]
+    fn critical_health_checks_in_a_row_fail_the_release() {
+        assert_eq!(verification().check(CRITICAL_CHECKS_TO_FAIL, false, Instant::now()),
+                   Verdict::Failed("its health check is critical again and again"));
+    }
+
+    #[test]
+    fn a_health_check_still_critical_after_the_window_fails_the_release() {
+        let now = Instant::now() + Duration::from_secs(61);
+        assert_eq!(verification().check(1, false, now),
+                   Verdict::Failed("its health check is still critical after its rollback \
+                                    window"));
+    }
+
+    #[test]
+    fn crash_looping_fails_the_release() {
+        assert_eq!(verification().check(0, true, Instant::now()),
+                   Verdict::Failed("its process is crash-looping"));
+    }
+}
@@ -8,27 +8,37 @@
 // here. Ideally, those would exist only at the periphery of the
 // system, and we'd use separate internal types for our core logic.
 
+mod cfg_history;
 mod context;
 mod health;
 mod hook_runner;
 mod hooks;
 #[cfg(windows)]
 mod pipe_hook_client;
+mod probe;
+mod restart;
 mod spawned_future;
 pub mod spec;
 mod supervisor;
 mod terminator;
 
-use self::{context::RenderContext,
+use self::{cfg_history::CfgVersion,
+           context::RenderContext,
            hook_runner::HookRunner,
            hooks::{HookCompileTable,
                    HookTable},
+           restart::RestartDecision,
            supervisor::Supervisor};
-pub use self::{health::{HealthCheckHookStatus,
+pub use self::{cfg_history::CfgHistory,
+               health::{HealthCheckHookStatus,
                         HealthCheckResult},
                hooks::{HealthCheckHook,
                        ProcessOutput,
                        StandardStreams},
+               probe::HealthCheckProbe,
+               restart::{RestartCondition,
+                         RestartPolicy,
+                         RestartTracker},
                spec::{DesiredState,
                       ServiceSpec}};
 use crate::{census::{CensusGroup,
@@ -37,6 +47,7 @@ use crate::{census::{CensusGroup,
                      ServiceFile},
             error::{Error,
                     Result},
+            event,
             manager::{sync::GatewayState,
                       FsCfg,
                       ShutdownConfig,
@@ -45,7 +56,8 @@ use crate::{census::{CensusGroup,
 use futures::{future,
               Future,
               IntoFuture};
-use habitat_butterfly::rumor::service::Service as ServiceRumor;
+use habitat_butterfly::rumor::{election::ElectionPolicy,
+                               service::Service as ServiceRumor};
 #[cfg(windows)]
 use habitat_common::templating::package::DEFAULT_USER;
 pub use habitat_common::templating::{config::{Cfg,
@@ -55,16 +67,22 @@ pub use habitat_common::templating::{config::{Cfg,
                                                PkgProxy}};
 use habitat_common::{outputln,
                      templating::{config::CfgRenderer,
-                                  hooks::Hook}};
+                                  hooks::Hook,
+                                  secrets::{EnvSecretProvider,
+                                            FileSecretProvider,
+                                            SecretProviders}}};
 #[cfg(windows)]
 use habitat_core::os::users;
 use habitat_core::{crypto::hash,
                    fs::{atomic_write,
+                        pkg_root_path,
                         svc_hooks_path,
+                        svc_secrets_path,
                         SvcDir,
                         FS_ROOT_PATH},
                    os::process::ShutdownTimeout,
-                   package::{metadata::Bind,
+                   package::{list::package_list_for_ident,
+                             metadata::Bind,
                              PackageIdent,
                              PackageInstall},
                    service::{HealthCheckInterval,
@@ -75,6 +93,7 @@ use habitat_launcher_client::LauncherCli;
 use habitat_sup_protocol::types::BindingMode;
 pub use habitat_sup_protocol::types::{ProcessState,
                                       Topology,
+                                      UpdateBatchSize,
                                       UpdateStrategy};
 use parking_lot::RwLock;
 use prometheus::{HistogramTimer,
@@ -82,18 +101,26 @@ use prometheus::{HistogramTimer,
 use serde::{ser::SerializeStruct,
             Serialize,
             Serializer};
+use serde_json;
 use std::{self,
-          collections::HashSet,
+          borrow::Cow,
+          collections::{BTreeSet,
+                        HashMap,
+                        HashSet},
           fmt,
           fs,
           ops::Deref,
           path::{Path,
                  PathBuf},
           result,
-          sync::{Arc,
-                 Mutex}};
+          sync::{atomic::{AtomicUsize,
+                          Ordering},
+                 Arc,
+                 Mutex},
+          time::Instant};
 use time::Timespec;
 use tokio::runtime::TaskExecutor;
+use toml;
 
 static LOGKEY: &str = "SR";
 
@@ -187,9 +214,23 @@ pub struct Service {
     pub sys:                 Arc<Sys>,
     pub user_config_updated: bool,
     pub shutdown_timeout:    Option<ShutdownTimeout>,
+    pub restart_policy:      RestartPolicy,
+    pub health_check_probe:  Option<HealthCheckProbe>,
+    pub readiness_probe:     Option<HealthCheckProbe>,
+    pub liveness_threshold:  u32,
+    pub rollback_window:     u64,
+    pub blocked_releases:    Vec<PackageIdent>,
+    pub rolled_back_to:      Option<PackageIdent>,
+    pub update_batch_size:   UpdateBatchSize,
+    pub update_canary_soak:  u64,
+    pub election_policy:     ElectionPolicy,
     // TODO (DM): This flag is a temporary hack to signal to the `Manager` that this service needs
     // to be restarted. As we continue refactoring lifecycle hooks this flag should be removed.
     pub needs_restart: bool,
+    /// Handed on by the `Manager` to the `Service` that replaces this
+    /// one when it is restarted, so the restart policy sees every
+    /// restart.
+    pub restart_tracker: RestartTracker,
     // TODO (DM): The need to track initialization state across ticks would be removed if we
     // migrated away from the event loop architecture to an architecture that had a top level
     // `Service` future. See https://github.com/habitat-sh/habitat/issues/7112
@@ -207,6 +248,27 @@ pub struct Service {
     // hook, we need to wrap some Arc<Mutex<_>> protection around it
     // :(
     health_check_result: Arc<Mutex<HealthCheckResult>>,
+    /// Whether the service has passed a health check since it was
+    /// started. This is gossiped, so that services binding to this
+    /// one in `ordered` mode know when they may start.
+    health_check_passed: bool,
+    /// The result of the readiness check, or of the health check if no
+    /// readiness check was given. Written by the health check future.
+    readiness_result: Arc<Mutex<HealthCheckResult>>,
+    /// Whether the service is ready for its consumers. This is
+    /// gossiped, so that members that are not ready are left out of
+    /// `eachAlive` in the templates of services bound to this one. A
+    /// service without a readiness probe is always ready.
+    ready: bool,
+    /// Consecutive critical health checks, counted by the health check
+    /// future.
+    liveness_failures: Arc<AtomicUsize>,
+    /// Consecutive critical health checks. Unlike `liveness_failures`,
+    /// these are handed on by the `Manager` to the `Service` that
+    /// replaces this one when it is restarted, so a release that keeps
+    /// being restarted for failing its health check is still rolled
+    /// back.
+    critical_checks: Arc<AtomicUsize>,
     last_election_status: ElectionStatus,
     /// The mapping of bind name to a service group, specified by the
     /// user when the service definition was loaded into the Supervisor.
@@ -232,7 +294,17 @@ pub struct Service {
     /// that should be reconciled against the current state of the
     /// census.
     unsatisfied_binds: HashSet<ServiceBind>,
+    /// The active members of each bound service group, as of the last
+    /// census change, so changes to them can be reported.
+    bind_members: HashMap<ServiceBind, BTreeSet<String>>,
     hooks: HookTable,
+    /// The context templates and hooks were last rendered with, so we
+    /// can work out what new configuration would re-render. It holds
+    /// the service's secrets, so we never serialize it.
+    last_render_context: Option<serde_json::Value>,
+    /// The versions of the service group's configuration applied to
+    /// the service, so an earlier version can be applied again.
+    cfg_history: CfgHistory,
     config_from: Option<PathBuf>,
     manager_fs_cfg: Arc<FsCfg>,
     supervisor: Arc<Mutex<Supervisor>>,
@@ -267,16 +339,32 @@ impl Service {
                                               organization)?;
         let config_root = Self::config_root(&pkg, spec.config_from.as_ref());
         let hooks_root = Self::hooks_root(&pkg, spec.config_from.as_ref());
+        let cfg = Cfg::new(&pkg, spec.config_from.as_ref())?;
+        if let Some(errors) = cfg.validate_schema() {
+            return Err(habitat_common::Error::InvalidConfig(format!("The configuration of {}",
+                                                                    service_group),
+                                                            errors).into());
+        }
         Ok(Service { sys,
-                     cfg: Cfg::new(&pkg, spec.config_from.as_ref())?,
+                     cfg,
                      config_renderer: CfgRenderer::new(&config_root)?,
                      bldr_url: spec.bldr_url,
                      channel: spec.channel,
                      desired_state: spec.desired_state,
                      health_check_result: Arc::new(Mutex::new(HealthCheckResult::Unknown)),
+                     health_check_passed: false,
+                     readiness_result: Arc::new(Mutex::new(HealthCheckResult::Unknown)),
+                     ready: spec.readiness_probe.is_none(),
+                     liveness_failures: Arc::new(AtomicUsize::new(0)),
+                     critical_checks: Arc::new(AtomicUsize::new(0)),
                      hooks: HookTable::load(&pkg.name,
                                             &hooks_root,
                                             svc_hooks_path(&service_group.service())),
+                     last_render_context: None,
+                     cfg_history:
+                         CfgHistory::load(manager_fs_cfg.data_path
+                                                        .join(format!("{}.cfg-history",
+                                                                      service_group))),
                      last_election_status: ElectionStatus::None,
                      user_config_updated: false,
                      needs_restart: false,
@@ -289,6 +377,7 @@ impl Service {
                      binds: spec.binds,
                      all_pkg_binds,
                      unsatisfied_binds: HashSet::new(),
+                     bind_members: HashMap::new(),
                      binding_mode: spec.binding_mode,
                      spec_ident: spec.ident,
                      spec_file,
@@ -301,7 +390,20 @@ impl Service {
                      health_check_handle: None,
                      post_run_handle: None,
                      initialize_handle: None,
-                     shutdown_timeout: spec.shutdown_timeout })
+                     shutdown_timeout: spec.shutdown_timeout,
+                     restart_policy: spec.restart_policy,
+                     health_check_probe: spec.health_check_probe,
+                     readiness_probe: spec.readiness_probe,
+                     liveness_threshold: spec.liveness_threshold,
+                     rollback_window: spec.rollback_window,
+                     blocked_releases: spec.blocked_releases,
+                     rolled_back_to: spec.rolled_back_to,
+                     update_batch_size: spec.update_batch_size,
+                     update_canary_soak: spec.update_canary_soak,
+                     election_policy: ElectionPolicy { preferred_zone:
+                                                           spec.election_preferred_zone,
+                                                       min_zones: spec.election_min_zones, },
+                     restart_tracker: RestartTracker::default() })
     }
 
     // And now prepare yourself for a little horribleness...Ready?
@@ -354,7 +456,22 @@ impl Service {
                -> Result<Service> {
         // The package for a spec should already be installed.
         let fs_root_path = Path::new(&*FS_ROOT_PATH);
-        let package = PackageInstall::load(&spec.ident, Some(fs_root_path))?;
+        let mut package = PackageInstall::load(&spec.ident, Some(fs_root_path))?;
+        // A release that was rolled back is never run again, even
+        // though it is still installed. The release it was rolled back
+        // to runs in its place.
+        if spec.blocked_releases.contains(package.ident()) {
+            let release = match spec.rolled_back_to {
+                Some(ref release) if !spec.blocked_releases.contains(release) => release.clone(),
+                _ => {
+                    let installed =
+                        package_list_for_ident(&pkg_root_path(Some(fs_root_path)), &spec.ident)?;
+                    newest_unblocked(installed, &spec.blocked_releases)
+                        .ok_or_else(|| habitat_core::Error::PackageNotFound(spec.ident.clone()))?
+                }
+            };
+            package = PackageInstall::load(&release, Some(fs_root_path))?;
+        }
         Ok(Self::with_package(sys,
                               &package,
                               spec,
@@ -382,6 +499,7 @@ impl Service {
         match result {
             Ok(_) => {
                 self.needs_restart = false;
+                self.restart_tracker.process_started();
                 self.start_health_checks(executor);
             }
             Err(e) => {
@@ -402,9 +520,14 @@ impl Service {
                            self.pkg.clone(),
                            self.svc_encrypted_password.clone(),
                            self.to_service_metadata(),
+                           self.health_check_probe.clone(),
+                           self.readiness_probe.clone(),
                            Arc::clone(&self.supervisor),
                            self.health_check_interval,
                            Arc::clone(&self.health_check_result),
+                           Arc::clone(&self.readiness_result),
+                           Arc::clone(&self.liveness_failures),
+                           Arc::clone(&self.critical_checks),
                            Arc::clone(&self.gateway_state))
     }
 
@@ -518,6 +641,19 @@ impl Service {
          })
     }
 
+    /// Whether the process exited too often to be restarted again.
+    pub fn crash_looping(&self) -> bool { self.restart_tracker.crash_looping() }
+
+    /// How many health checks in a row have been critical.
+    pub fn critical_checks(&self) -> usize { self.critical_checks.load(Ordering::Relaxed) }
+
+    /// Carry on counting critical health checks from where the
+    /// `Service` this one replaces left off.
+    pub fn resume_critical_checks(&self, critical_checks: usize) {
+        self.critical_checks
+            .store(critical_checks, Ordering::Relaxed);
+    }
+
     pub fn last_state_change(&self) -> Timespec {
         self.supervisor
             .lock()
@@ -527,32 +663,58 @@ impl Service {
 
     /// Performs updates and executes hooks.
     ///
-    /// Returns `true` if the service was marked to be restarted or reconfigured.
+    /// Returns `true` if the service was marked to be restarted or
+    /// reconfigured, has just passed its first health check, or has
+    /// become ready or stopped being ready, so that its rumor should
+    /// be gossiped again.
     pub fn tick(&mut self,
                 census_ring: &CensusRing,
                 launcher: &LauncherCli,
                 executor: &TaskExecutor)
                 -> bool {
         // We may need to block the service from starting until all
-        // its binds are satisfied
+        // its binds are satisfied, and possibly until the services it
+        // binds to are healthy
         if !self.initialized() {
             match self.binding_mode {
                 BindingMode::Relaxed => (),
-                BindingMode::Strict => {
+                BindingMode::Strict | BindingMode::Ordered => {
                     self.validate_binds(census_ring);
                     if !self.unsatisfied_binds.is_empty() {
                         outputln!(preamble self.service_group, "Waiting for service binds...");
                         return false;
                     }
+                    if self.binding_mode == BindingMode::Ordered
+                       && !self.bound_groups_healthy(census_ring)
+                    {
+                        outputln!(preamble self.service_group,
+                                  "Waiting for bound services to pass a health check...");
+                        return false;
+                    }
                 }
             }
         }
 
+        let health_check_result = *self.health_check_result
+                                       .lock()
+                                       .expect("Could not unlock health_check_result");
+        let first_health_check_passed =
+            !self.health_check_passed && health_check_result == HealthCheckResult::Ok;
+        if first_health_check_passed {
+            outputln!(preamble self.service_group, "Passed its first health check");
+            self.health_check_passed = true;
+        }
+        let readiness_changed = self.update_readiness();
+        if self.liveness_failed() {
+            return true;
+        }
+
         // Binds may become unsatisfied as a service is running (e.g.,
         // service members disappear, etc.) This can affect the data
         // we pass to templates, so we must account for it here.
         if census_ring.changed() {
             self.validate_binds(census_ring);
+            self.check_bind_members(census_ring);
         }
 
         // TODO (DM): As a temporary fix, we return this `template_data_changed` boolean which does
@@ -610,7 +772,49 @@ impl Service {
                 }
             }
         }
-        template_data_changed
+        template_data_changed || first_health_check_passed || readiness_changed
+    }
+
+    /// Take up the latest result of the readiness probe. Returns `true`
+    /// if the service became ready or stopped being ready.
+    fn update_readiness(&mut self) -> bool {
+        if self.readiness_probe.is_none() {
+            return false;
+        }
+        let readiness = *self.readiness_result
+                             .lock()
+                             .expect("Could not unlock readiness_result");
+        let ready = match readiness {
+            HealthCheckResult::Ok | HealthCheckResult::Warning => true,
+            HealthCheckResult::Critical | HealthCheckResult::Unknown => false,
+        };
+        if ready == self.ready {
+            return false;
+        }
+        if ready {
+            outputln!(preamble self.service_group, "Ready for its consumers");
+        } else {
+            outputln!(preamble self.service_group,
+                      "No longer ready for its consumers; readiness is {}",
+                      readiness);
+        }
+        self.ready = ready;
+        true
+    }
+
+    /// Mark the service to be restarted if its health check has been
+    /// critical `liveness_threshold` times in a row.
+    fn liveness_failed(&mut self) -> bool {
+        let threshold = self.liveness_threshold as usize;
+        if threshold == 0 || self.liveness_failures.load(Ordering::Relaxed) < threshold {
+            return false;
+        }
+        outputln!(preamble self.service_group,
+                  "Health check has been critical {} times in a row; restarting",
+                  threshold);
+        self.liveness_failures.store(0, Ordering::Relaxed);
+        self.needs_restart = true;
+        true
     }
 
     pub fn to_spec(&self) -> ServiceSpec {
@@ -631,6 +835,17 @@ impl Service {
         }
         spec.health_check_interval = self.health_check_interval;
         spec.shutdown_timeout = self.shutdown_timeout;
+        spec.restart_policy = self.restart_policy;
+        spec.health_check_probe = self.health_check_probe.clone();
+        spec.readiness_probe = self.readiness_probe.clone();
+        spec.liveness_threshold = self.liveness_threshold;
+        spec.rollback_window = self.rollback_window;
+        spec.blocked_releases = self.blocked_releases.clone();
+        spec.rolled_back_to = self.rolled_back_to.clone();
+        spec.update_batch_size = self.update_batch_size;
+        spec.update_canary_soak = self.update_canary_soak;
+        spec.election_preferred_zone = self.election_policy.preferred_zone.clone();
+        spec.election_min_zones = self.election_policy.min_zones;
         spec
     }
 
@@ -705,6 +920,39 @@ impl Service {
         }
     }
 
+    /// Whether every bound service group has an active member that
+    /// has passed a health check.
+    fn bound_groups_healthy(&self, census_ring: &CensusRing) -> bool {
+        self.binds.iter().all(|bind| {
+                             census_ring.census_group_for(bind.service_group())
+                                        .map_or(false, CensusGroup::has_healthy_member)
+                         })
+    }
+
+    /// Report any change in the active members of each bound service
+    /// group since the last time the census changed. Nothing is
+    /// reported the first time a group's members are seen.
+    fn check_bind_members(&mut self, census_ring: &CensusRing) {
+        for bind in self.binds.iter() {
+            let members = census_ring.census_group_for(bind.service_group())
+                                     .map(|group| {
+                                         group.active_members()
+                                              .map(|m| m.member_id.to_string())
+                                              .collect()
+                                     })
+                                     .unwrap_or_default();
+            if let Some(previous) = self.bind_members.insert(bind.clone(), members) {
+                let current = &self.bind_members[bind];
+                if previous != *current {
+                    event::service_bind_changed(self,
+                                                bind,
+                                                current.difference(&previous).cloned().collect(),
+                                                previous.difference(current).cloned().collect());
+                }
+            }
+        }
+    }
+
     /// Evaluate the suitability of the given `ServiceBind` based on
     /// current census information.
     fn current_bind_status<'a>(&'a self,
@@ -780,7 +1028,8 @@ impl Service {
     }
 
     /// Updates the service configuration with data from a census group if the census group has
-    /// newer data than the current configuration.
+    /// newer data than the current configuration. Data that doesn't match the package's schema is
+    /// skipped over.
     ///
     /// Returns `true` if the configuration was updated.
     fn update_gossip(&mut self, census_group: &CensusGroup) -> bool {
@@ -789,8 +1038,32 @@ impl Service {
                 if config.incarnation <= self.cfg.gossip_incarnation {
                     return false;
                 }
+                if let Some(errors) = self.cfg.validate_gossip(&config.value) {
+                    outputln!(preamble self.service_group,
+                              "Rejected configuration version {}, it does not match the \
+                               configuration schema: {}",
+                              config.incarnation,
+                              errors.join(", "));
+                    self.cfg.gossip_incarnation = config.incarnation;
+                    return false;
+                }
                 self.cfg
                     .set_gossip(config.incarnation, config.value.clone());
+                let recorded = CfgVersion::new(config.incarnation,
+                                               config.author.clone(),
+                                               &config.value,
+                                               config.sealed.clone())
+                                  .map_err(|e| e.to_string())
+                                  .and_then(|v| {
+                                      self.cfg_history.record(v).map_err(|e| e.to_string())
+                                  });
+                if let Err(err) = recorded {
+                    outputln!(preamble self.service_group,
+                              "Unable to record configuration version {} in its history, {}",
+                              config.incarnation,
+                              err);
+                }
+                event::service_config_applied(self, config.incarnation);
                 true
             }
             None => false,
@@ -815,10 +1088,24 @@ impl Service {
         }
 
         let template_update = if template_data_changed || census_ring.changed() {
-            let ctx = self.render_context(census_ring);
-            TemplateUpdate::new(self.compile_hooks(&ctx),
-                                self.compile_configuration(&ctx),
-                                self.hooks.reconfigure.is_some() || self.hooks.reload.is_some())
+            let rendered = self.render_context(census_ring).map(|ctx| {
+                let update = TemplateUpdate::new(self.compile_hooks(&ctx),
+                                                 self.compile_configuration(&ctx),
+                                                 self.hooks.reconfigure.is_some()
+                                                 || self.hooks.reload.is_some());
+                (update, serde_json::to_value(&ctx).ok())
+            });
+            match rendered {
+                Ok((update, ctx)) => {
+                    self.last_render_context = ctx;
+                    update
+                }
+                Err(e) => {
+                    outputln!(preamble self.service_group,
+                              "Not rendering templates, unable to resolve secrets: {}", e);
+                    TemplateUpdate::default()
+                }
+            }
         } else {
             TemplateUpdate::default()
         };
@@ -841,6 +1128,12 @@ impl Service {
                                           self.sys.as_sys_info().clone(),
                                           exported);
         rumor.incarnation = incarnation;
+        rumor.health_check_passed = self.health_check_passed;
+        rumor.ready = self.ready;
+        rumor.blocked_releases = self.blocked_releases
+                                     .iter()
+                                     .map(ToString::to_string)
+                                     .collect();
         rumor
     }
 
@@ -1057,8 +1350,10 @@ impl Service {
             }
             InitializationState::Initialized => {
                 // If the service is initialized and the process is not running, the process
-                // unexpectedly died and needs to be restarted.
-                if !up || template_update.needs_restart() {
+                // unexpectedly died and may need to be restarted.
+                if !up {
+                    return self.process_exited(launcher);
+                } else if template_update.needs_restart() {
                     // TODO (DM): This flag is a hack. We have the `TaskExecutor` here. We could
                     // just schedule the `stop` future, but the `Manager` wraps
                     // the `stop` future with additional functionality. Can we
@@ -1075,6 +1370,88 @@ impl Service {
         false
     }
 
+    /// Whether the process which just exited did so unsuccessfully.
+    /// Only an `on-failure` policy needs to know, so the Launcher is
+    /// asked only then. An exit the Launcher can't confirm was
+    /// successful counts as a failure.
+    fn exited_unsuccessfully(&self, launcher: &LauncherCli) -> bool {
+        let pid = self.supervisor
+                      .lock()
+                      .expect("Couldn't lock supervisor")
+                      .take_exited_pid();
+        let pid = match pid {
+            Some(pid) if self.restart_policy.condition == RestartCondition::OnFailure => pid,
+            _ => return true,
+        };
+        match launcher.exit_status(pid) {
+            Ok(Some(status)) => {
+                match status.exit_code {
+                    Some(code) => {
+                        outputln!(preamble self.service_group, "Process exited with code {}", code)
+                    }
+                    None => {
+                        outputln!(preamble self.service_group, "Process was killed by a signal")
+                    }
+                }
+                !status.success()
+            }
+            Ok(None) => true,
+            Err(err) => {
+                warn!("Couldn't get the exit status of {} from the Launcher, {}",
+                      self.service_group,
+                      err);
+                true
+            }
+        }
+    }
+
+    /// Apply the restart policy to a process that unexpectedly
+    /// exited. Returns `true` if the service was marked to be
+    /// restarted.
+    fn process_exited(&mut self, launcher: &LauncherCli) -> bool {
+        let failed = self.exited_unsuccessfully(launcher);
+        let now = Instant::now();
+        match self.restart_tracker
+                  .process_exited(&self.restart_policy, failed, now)
+        {
+            Some(RestartDecision::RestartAt(at)) => {
+                outputln!(preamble self.service_group,
+                          "Process exited unexpectedly; restarting in {}s (restart {} of the last \
+                           {}s)",
+                          (at - now).as_secs(),
+                          self.restart_tracker.restarts(),
+                          self.restart_policy.window);
+            }
+            Some(RestartDecision::CrashLooping) => {
+                outputln!(preamble self.service_group,
+                          "Process exited after {} restarts in {}s; it is crash-looping and will \
+                           not be restarted",
+                          self.restart_tracker.restarts(),
+                          self.restart_policy.window);
+                self.supervisor
+                    .lock()
+                    .expect("Couldn't lock supervisor")
+                    .crash_looping();
+                event::service_crash_looping(self,
+                                             self.restart_tracker.restarts(),
+                                             self.restart_policy.window);
+            }
+            Some(RestartDecision::StayDown) => {
+                outputln!(preamble self.service_group,
+                          "Process exited; the restart policy is `{}`, so it will not be \
+                           restarted",
+                          self.restart_policy.condition);
+            }
+            None => {}
+        }
+        if self.restart_tracker.restart_due(now) {
+            self.needs_restart = true;
+            true
+        } else {
+            false
+        }
+    }
+
     /// Run file-updated hook if present.
     fn file_updated(&self) -> bool {
         let _timer = hook_timer("file-updated");
@@ -1106,6 +1483,7 @@ impl Service {
             if self.cache_service_file(&service_file) {
                 outputln!(preamble self.service_group, "Service file updated, {}",
                     service_file.filename);
+                event::service_file_applied(self, &service_file.filename, service_file.incarnation);
                 updated = true;
             }
         }
@@ -1113,18 +1491,60 @@ impl Service {
     }
 
     /// Helper for constructing a new render context for the service.
-    fn render_context<'a>(&'a self, census: &'a CensusRing) -> RenderContext<'a> {
+    ///
+    /// This is the only place the service's secrets are resolved.
+    fn render_context<'a>(&'a self, census: &'a CensusRing) -> Result<RenderContext<'a>> {
+        let cfg = self.cfg
+                      .with_secrets_resolved(&self.secret_providers())?;
         // Unsatisfied binds are filtered out; you only get bind
         // information in the render context if they actually satisfy
         // the contract!
-        RenderContext::new(&self.service_group,
-                           &self.sys,
-                           &self.pkg,
-                           &self.cfg,
-                           census,
-                           self.binds
-                               .iter()
-                               .filter(|b| !self.unsatisfied_binds.contains(b)))
+        Ok(RenderContext::new(&self.service_group,
+                              &self.sys,
+                              &self.pkg,
+                              Cow::Owned(cfg),
+                              census,
+                              self.binds
+                                  .iter()
+                                  .filter(|b| !self.unsatisfied_binds.contains(b))))
+    }
+
+    /// The versions of the service group's configuration applied to the service.
+    pub fn cfg_history(&self) -> &CfgHistory { &self.cfg_history }
+
+    /// Lists the configuration templates and hooks whose content applying `gossip` to the
+    /// service group would change, by rendering them with the context they were last rendered
+    /// with, and with its configuration replaced. Nothing is written.
+    pub fn changed_by_gossip(&self,
+                             gossip: &toml::value::Table)
+                             -> Result<(Vec<String>, Vec<&'static str>)> {
+        let current = match self.last_render_context {
+            Some(ref ctx) => ctx,
+            None => return Ok((Vec::new(), Vec::new())),
+        };
+        let mut cfg = self.cfg.clone();
+        cfg.set_gossip(self.cfg.gossip_incarnation, gossip.clone());
+        let cfg = cfg.with_secrets_resolved(&self.secret_providers())?;
+        let mut pending = current.clone();
+        pending["cfg"] = serde_json::to_value(&cfg).map_err(|e| {
+                             habitat_common::Error::RenderContextSerialization(e)
+                         })?;
+        Ok((self.config_renderer.changed_by(current, &pending)?,
+            self.hooks.changed_by(current, &pending)?))
+    }
+
+    /// The providers secret references in the service's configuration are resolved from, in
+    /// order: files encrypted for the service key, if its service group has an organization, and
+    /// then the environment of the Supervisor.
+    fn secret_providers(&self) -> SecretProviders {
+        let mut providers = SecretProviders::default();
+        if self.service_group.org().is_some() {
+            providers.push(FileSecretProvider::new(svc_secrets_path(self.service_group.service()),
+                                                   self.service_group.as_ref(),
+                                                   &self.manager_fs_cfg.cache_key_path));
+        }
+        providers.push(EnvSecretProvider);
+        providers
     }
 
     // Returns `false` if the write fails.
@@ -1205,6 +1625,15 @@ impl fmt::Display for Service {
     }
 }
 
+/// The newest of the `installed` releases that has not been blocked.
+fn newest_unblocked(installed: Vec<PackageIdent>,
+                    blocked: &[PackageIdent])
+                    -> Option<PackageIdent> {
+    installed.into_iter()
+             .filter(|ident| !blocked.contains(ident))
+             .max()
+}
+
 // This returns a HistogramTimer that we can use to track how long hooks take to execute. Note that
 // times will get tracked automatically when the HistogramTimer goes out of scope.
 fn hook_timer(name: &str) -> HistogramTimer {
@@ -1247,9 +1676,9 @@ impl<'a> Serialize for ServiceProxy<'a> {
         where S: Serializer
     {
         let num_fields: usize = if self.config_rendering == ConfigRendering::Full {
-            27
+            38
         } else {
-            26
+            37
         };
 
         let s = &self.service;
@@ -1266,6 +1695,8 @@ impl<'a> Serialize for ServiceProxy<'a> {
         strukt.serialize_field("channel", &s.channel)?;
         strukt.serialize_field("config_from", &s.config_from)?;
         strukt.serialize_field("desired_state", &s.desired_state)?;
+        strukt.serialize_field("election_min_zones", &s.election_policy.min_zones)?;
+        strukt.serialize_field("election_preferred_zone", &s.election_policy.preferred_zone)?;
         strukt.serialize_field("health_check", &s.health_check_result)?;
         strukt.serialize_field("hooks", &s.hooks)?;
         strukt.serialize_field("initialized", &s.initialized())?;
@@ -1286,9 +1717,20 @@ impl<'a> Serialize for ServiceProxy<'a> {
         strukt.serialize_field("spec_identifier", &s.spec_ident.to_string())?;
         strukt.serialize_field("svc_encrypted_password", &s.svc_encrypted_password)?;
         strukt.serialize_field("health_check_interval", &s.health_check_interval)?;
+        strukt.serialize_field("health_check_probe", &s.health_check_probe)?;
+        strukt.serialize_field("readiness", &s.readiness_result)?;
+        strukt.serialize_field("readiness_probe", &s.readiness_probe)?;
+        strukt.serialize_field("liveness_threshold", &s.liveness_threshold)?;
+        strukt.serialize_field("rollback_window", &s.rollback_window)?;
+        let blocked_releases: Vec<String> =
+            s.blocked_releases.iter().map(ToString::to_string).collect();
+        strukt.serialize_field("blocked_releases", &blocked_releases)?;
+        strukt.serialize_field("restart_policy", &s.restart_policy)?;
         strukt.serialize_field("sys", &s.sys)?;
         strukt.serialize_field("topology", &s.topology)?;
         strukt.serialize_field("update_strategy", &s.update_strategy)?;
+        strukt.serialize_field("update_batch_size", &s.update_batch_size.to_string())?;
+        strukt.serialize_field("update_canary_soak", &s.update_canary_soak)?;
         strukt.serialize_field("user_config_updated", &s.user_config_updated)?;
         strukt.end()
     }
@@ -1333,7 +1775,7 @@ mod tests {
                                                                              should've loaded my \
                                                                              spec, but it didn't");
         let asys = Arc::new(sys);
-        let fscfg = FsCfg::new("/tmp");
+        let fscfg = FsCfg::new("/tmp", "/tmp/keys");
         let afs = Arc::new(fscfg);
 
         let gs = Arc::default();
@@ -1365,4 +1807,19 @@ mod tests {
                                                                    JSON but failed");
         assert_valid(&json_without_config, "http_gateway_services_schema.json");
     }
+
+    #[test]
+    fn blocked_releases_are_not_chosen() {
+        let ident = |s: &str| PackageIdent::from_str(s).unwrap();
+        let installed = vec![ident("core/redis/4.0.14/20190319155852"),
+                             ident("core/redis/5.0.5/20190812120000"),
+                             ident("core/redis/5.0.6/20190905120000")];
+
+        assert_eq!(newest_unblocked(installed.clone(), &[]),
+                   Some(ident("core/redis/5.0.6/20190905120000")));
+        assert_eq!(newest_unblocked(installed.clone(),
+                                    &[ident("core/redis/5.0.6/20190905120000")]),
+                   Some(ident("core/redis/5.0.5/20190812120000")));
+        assert_eq!(newest_unblocked(installed.clone(), &installed), None);
+    }
 }
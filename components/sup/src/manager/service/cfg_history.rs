@@ -0,0 +1,230 @@
+//! A bounded history of the configuration applied to a service
+//! group.
+//!
+//! Applying new configuration to a service group replaces the previous
+//! version in gossip, so each Supervisor running one of its services
+//! keeps the last few versions it applied on disk. Any of them can be
+//! gossiped again, under a new incarnation, with `hab config rollback`.
+//!
+//! An encrypted configuration is kept as the payload it was gossiped
+//! as, so its secrets are never written out in the clear.
+
+use habitat_core::{crypto::hash,
+                   fs::AtomicWriter};
+use serde_json;
+use std::{collections::VecDeque,
+          fs::File,
+          io::{self,
+               Write},
+          path::PathBuf};
+use time;
+use toml;
+
+/// How many versions of its configuration a service keeps.
+pub const CFG_HISTORY_SIZE: usize = 10;
+
+#[cfg(unix)]
+const CFG_HISTORY_PERMISSIONS: u32 = 0o600;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CfgVersion {
+    pub incarnation: u64,
+    /// The name with revision of the user key the configuration was
+    /// encrypted by, if it was encrypted.
+    pub author:      Option<String>,
+    /// When the Supervisor applied the configuration, in seconds since
+    /// the Unix epoch.
+    pub applied_at:  i64,
+    /// The hash of `cfg`.
+    pub hash:        String,
+    /// The configuration, as TOML, or as the payload it was gossiped
+    /// as if it was encrypted.
+    pub cfg:         String,
+    /// If `cfg` is an encrypted payload.
+    #[serde(default)]
+    pub encrypted:   bool,
+}
+
+impl CfgVersion {
+    /// A version of the configuration `cfg`. An encrypted
+    /// configuration is kept as `sealed`, the payload it was gossiped
+    /// as.
+    pub fn new(incarnation: u64,
+               author: Option<String>,
+               cfg: &toml::value::Table,
+               sealed: Option<String>)
+               -> Result<Self, toml::ser::Error> {
+        let (cfg, encrypted) = match sealed {
+            Some(sealed) => (sealed, true),
+            None => (toml::to_string(&toml::Value::Table(cfg.clone()))?, false),
+        };
+        Ok(Self { incarnation,
+                  author,
+                  applied_at: time::get_time().sec,
+                  hash: hash::hash_string(&cfg),
+                  cfg,
+                  encrypted })
+    }
+}
+
+#[derive(Debug)]
+pub struct CfgHistory {
+    path:     PathBuf,
+    /// Oldest first
+    versions: VecDeque<CfgVersion>,
+}
+
+impl CfgHistory {
+    /// Loads the history kept at `path`. A missing history is empty,
+    /// as is one that can't be read, which is reported.
+    pub fn load<P>(path: P) -> Self
+        where P: Into<PathBuf>
+    {
+        let path = path.into();
+        let versions = match File::open(&path) {
+            Ok(file) => {
+                serde_json::from_reader(file).unwrap_or_else(|err| {
+                                                 warn!("Ignoring configuration history {}, {}",
+                                                       path.display(),
+                                                       err);
+                                                 VecDeque::new()
+                                             })
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => VecDeque::new(),
+            Err(err) => {
+                warn!("Ignoring configuration history {}, {}", path.display(), err);
+                VecDeque::new()
+            }
+        };
+        Self { path, versions }
+    }
+
+    /// Records a newly applied version, forgetting the oldest versions
+    /// beyond `CFG_HISTORY_SIZE`, and saves the history. A version no
+    /// newer than the latest one is already recorded, as when a
+    /// restarted Supervisor applies its configuration again, and is
+    /// skipped. Only the Supervisor's user can read the history.
+    pub fn record(&mut self, version: CfgVersion) -> io::Result<()> {
+        if self.latest()
+               .map_or(false, |latest| latest.incarnation >= version.incarnation)
+        {
+            return Ok(());
+        }
+        self.versions.push_back(version);
+        while self.versions.len() > CFG_HISTORY_SIZE {
+            self.versions.pop_front();
+        }
+        let versions = serde_json::to_vec(&self.versions)?;
+        AtomicWriter::new(&self.path)?.with_writer(|f| {
+                                          #[cfg(unix)]
+                                          {
+                                              use std::{fs::Permissions,
+                                                        os::unix::fs::PermissionsExt};
+                                              f.set_permissions(Permissions::from_mode(
+                                                  CFG_HISTORY_PERMISSIONS,
+                                              ))?;
+                                          }
+                                          f.write_all(&versions)
+                                      })
+    }
+
+    pub fn latest(&self) -> Option<&CfgVersion> { self.versions.back() }
+
+    pub fn get(&self, incarnation: u64) -> Option<&CfgVersion> {
+        self.versions.iter().find(|v| v.incarnation == incarnation)
+    }
+
+    /// The recorded versions, newest first.
+    pub fn versions(&self) -> impl Iterator<Item = &CfgVersion> { self.versions.iter().rev() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn version(incarnation: u64, cfg: &str) -> CfgVersion {
+        CfgVersion::new(incarnation, None, &toml::from_str(cfg).unwrap(), None).unwrap()
+    }
+
+    #[test]
+    fn records_versions_newest_first_and_survives_a_reload() {
+        let dir = TempDir::new().expect("Could not create tempdir");
+        let path = dir.path().join("redis.default.cfg-history");
+        let mut history = CfgHistory::load(&path);
+        assert!(history.latest().is_none());
+
+        history.record(version(1, "port = 1")).unwrap();
+        history.record(version(2, "port = 2")).unwrap();
+
+        let history = CfgHistory::load(&path);
+        let incarnations: Vec<u64> = history.versions().map(|v| v.incarnation).collect();
+        assert_eq!(incarnations, vec![2, 1]);
+        assert_eq!(history.get(1).unwrap().cfg, "port = 1\n");
+        assert!(!history.get(1).unwrap().encrypted);
+    }
+
+    #[test]
+    fn keeps_encrypted_versions_as_they_were_gossiped() {
+        let dir = TempDir::new().expect("Could not create tempdir");
+        let path = dir.path().join("history");
+        let mut history = CfgHistory::load(&path);
+        let version = CfgVersion::new(1,
+                                      Some("ops-20190101000000".to_string()),
+                                      &toml::from_str("password = \"hunter2\"").unwrap(),
+                                      Some("BOX-1\nsealed".to_string())).unwrap();
+        history.record(version).unwrap();
+
+        let history = CfgHistory::load(&path);
+        let version = history.get(1).unwrap();
+        assert!(version.encrypted);
+        assert_eq!(version.cfg, "BOX-1\nsealed");
+        assert_eq!(version.author, Some("ops-20190101000000".to_string()));
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("hunter2"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn only_the_supervisor_can_read_the_history() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().expect("Could not create tempdir");
+        let path = dir.path().join("history");
+        let mut history = CfgHistory::load(&path);
+        history.record(version(1, "port = 1")).unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, CFG_HISTORY_PERMISSIONS);
+    }
+
+    #[test]
+    fn skips_versions_no_newer_than_the_latest() {
+        let dir = TempDir::new().expect("Could not create tempdir");
+        let mut history = CfgHistory::load(dir.path().join("history"));
+        history.record(version(2, "port = 2")).unwrap();
+        history.record(version(2, "port = 3")).unwrap();
+        history.record(version(1, "port = 1")).unwrap();
+
+        assert_eq!(history.versions().count(), 1);
+        assert_eq!(history.latest().unwrap().cfg, "port = 2\n");
+    }
+
+    #[test]
+    fn forgets_the_oldest_versions() {
+        let dir = TempDir::new().expect("Could not create tempdir");
+        let mut history = CfgHistory::load(dir.path().join("history"));
+        for incarnation in 1..=(CFG_HISTORY_SIZE as u64 + 2) {
+            history.record(version(incarnation, "port = 1")).unwrap();
+        }
+
+        assert_eq!(history.versions().count(), CFG_HISTORY_SIZE);
+        assert!(history.get(2).is_none());
+        assert!(history.get(3).is_some());
+    }
+
+    #[test]
+    fn the_hash_identifies_the_content() {
+        assert_eq!(version(1, "port = 1").hash, version(2, "port = 1").hash);
+        assert_ne!(version(1, "port = 1").hash, version(1, "port = 2").hash);
+    }
+}
@@ -82,15 +82,17 @@ impl<'a> RenderContext<'a> {
     /// structures, safely and selectively exposing the data to users
     /// in their templates.
     ///
-    /// Note that we wrap everything except the `Cfg`, to which we
-    /// maintain a direct reference. The serialization logic for this
-    /// is already complex, and exactly what we need. Because of the
-    /// nature of `Cfg`s behavior, we should be safe relying on that
-    /// implementation for the foreseeable future.
+    /// Note that we wrap everything except the `Cfg`, which we
+    /// maintain directly. The serialization logic for this is already
+    /// complex, and exactly what we need. Because of the nature of
+    /// `Cfg`s behavior, we should be safe relying on that
+    /// implementation for the foreseeable future. It is usually owned,
+    /// being a copy with the service's secrets resolved (see
+    /// `Cfg::with_secrets_resolved`).
     pub fn new<T>(service_group: &ServiceGroup,
                   sys: &'a Sys,
                   pkg: &'a Pkg,
-                  cfg: &'a Cfg,
+                  cfg: Cow<'a, Cfg>,
                   census: &'a CensusRing,
                   bindings: T)
                   -> RenderContext<'a>
@@ -100,7 +102,7 @@ impl<'a> RenderContext<'a> {
                                  .expect("Census Group missing from list!");
         RenderContext { sys:  SystemInfo::from_sys(sys),
                         pkg:  Package::from_pkg(pkg),
-                        cfg:  Cow::Borrowed(cfg),
+                        cfg,
                         svc:  Svc::new(census_group),
                         bind: Binds::new(bindings, census), }
     }
@@ -411,6 +413,8 @@ struct SvcMember<'a> {
     suspect: Cow<'a, bool>,
     confirmed: Cow<'a, bool>,
     departed: Cow<'a, bool>,
+    ready: Cow<'a, bool>,
+    tags: Cow<'a, BTreeMap<String, String>>,
     cfg: Cow<'a, toml::value::Table>,
 }
 
@@ -445,6 +449,10 @@ impl<'a> SvcMember<'a> {
                     confirmed: Cow::Owned(c.confirmed()),
                     departed:  Cow::Owned(c.departed()),
 
+                    ready: Cow::Borrowed(&c.ready),
+
+                    tags: Cow::Borrowed(&c.tags),
+
                     cfg: Cow::Borrowed(&c.cfg), }
     }
 }
@@ -509,6 +517,9 @@ impl<'a> Serialize for SvcMember<'a> {
         map.serialize_entry("suspect", &self.suspect)?;
         map.serialize_entry("confirmed", &self.confirmed)?;
         map.serialize_entry("departed", &self.departed)?;
+        map.serialize_entry("ready", &self.ready)?;
+
+        map.serialize_entry("tags", &self.tags)?;
 
         map.serialize_entry("cfg", &self.cfg)?;
 
@@ -642,6 +653,8 @@ two = 2
                     suspect: Cow::Owned(false),
                     confirmed: Cow::Owned(false),
                     departed: Cow::Owned(false),
+                    ready: Cow::Owned(true),
+                    tags: Cow::Owned(BTreeMap::new()),
                     cfg: Cow::Owned(toml::value::Table::new()), }
     }
 
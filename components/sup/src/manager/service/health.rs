@@ -3,6 +3,8 @@ use crate::{error::Error,
                               ServiceMetadata as ServiceEventMetadata},
                       service::{hook_runner,
                                 hooks::HealthCheckHook,
+                                probe::HealthCheckProbe,
+                                spawned_future::SpawnedFuture,
                                 supervisor::Supervisor,
                                 ProcessOutput},
                       sync::GatewayState}};
@@ -11,6 +13,7 @@ use futures::{future::{self,
                        Either,
                        Future,
                        Loop},
+              sync::oneshot,
               IntoFuture};
 use habitat_common::{outputln,
                      templating::package::Pkg};
@@ -19,8 +22,11 @@ use habitat_core::service::{HealthCheckInterval,
 use std::{convert::TryFrom,
           fmt,
           ops::Deref,
-          sync::{Arc,
+          sync::{atomic::{AtomicUsize,
+                          Ordering},
+                 Arc,
                  Mutex},
+          thread,
           time::{Duration,
                  Instant}};
 
@@ -65,6 +71,8 @@ impl fmt::Display for HealthCheckResult {
 pub enum HealthCheckHookStatus {
     Ran(ProcessOutput, Duration),
     FailedToRun(Duration),
+    /// An HTTP or TCP probe was run in place of a hook.
+    Probed(HealthCheckResult, Duration),
     NoHook,
 }
 
@@ -73,6 +81,7 @@ impl HealthCheckHookStatus {
         match &self {
             Self::Ran(_, duration) => Some(*duration),
             Self::FailedToRun(duration) => Some(*duration),
+            Self::Probed(_, duration) => Some(*duration),
             Self::NoHook => None,
         }
     }
@@ -81,11 +90,53 @@ impl HealthCheckHookStatus {
         match self {
             Self::Ran(process_output, _) => Some(process_output),
             Self::FailedToRun(_) => None,
+            Self::Probed(..) => None,
             Self::NoHook => None,
         }
     }
 }
 
+/// Interpret the outcome of a readiness probe. Unlike a health check,
+/// there is no fallback to the supervisor's status; a probe that could
+/// not be run leaves readiness unknown.
+fn readiness_of(status: &HealthCheckHookStatus) -> HealthCheckResult {
+    match status {
+        HealthCheckHookStatus::Ran(output, _) => {
+            output.exit_status()
+                  .code()
+                  .and_then(|code| HealthCheckResult::try_from(code).ok())
+                  .unwrap_or(HealthCheckResult::Unknown)
+        }
+        HealthCheckHookStatus::Probed(result, _) => *result,
+        HealthCheckHookStatus::FailedToRun(_) | HealthCheckHookStatus::NoHook => {
+            HealthCheckResult::Unknown
+        }
+    }
+}
+
+/// Run a probe on a separate thread, since probes block.
+fn run_probe(probe: HealthCheckProbe,
+             service_group: Arc<ServiceGroup>,
+             package: Pkg,
+             svc_encrypted_password: Option<String>)
+             -> SpawnedFuture<HealthCheckHookStatus> {
+    let (tx, rx) = oneshot::channel();
+    let handle_result = thread::Builder::new().name(format!("probe-{}", service_group))
+                                              .spawn(move || {
+                                                  let password =
+                                                      svc_encrypted_password.as_ref()
+                                                                            .map(String::as_str);
+                                                  tx.send(probe.run(&service_group,
+                                                                    &package,
+                                                                    password))
+                                                    .ok();
+                                              });
+    match handle_result {
+        Ok(_handle) => rx.into(),
+        Err(io_err) => io_err.into(),
+    }
+}
+
 /// All state needed for checking the health of a service over time.
 #[derive(Clone)]
 pub struct State {
@@ -97,6 +148,14 @@ pub struct State {
 
     service_event_metadata: ServiceEventMetadata,
 
+    /// A declarative health check to run in place of the hook, if one
+    /// was given when the service was loaded.
+    probe: Option<HealthCheckProbe>,
+
+    /// A check deciding whether the service is ready for its
+    /// consumers. Without one, readiness follows the health check.
+    readiness_probe: Option<HealthCheckProbe>,
+
     /// A reference to the process supervisor for the service. This is
     /// used to create a "proxy health check" for services that do not
     /// provide their own health check hook.
@@ -111,6 +170,17 @@ pub struct State {
     /// store the result of the health check here.
     service_health_result: Arc<Mutex<HealthCheckResult>>,
 
+    /// A reference to the service's current readiness.
+    service_readiness_result: Arc<Mutex<HealthCheckResult>>,
+
+    /// The number of consecutive health checks that have been
+    /// critical. The service uses this to decide when to restart.
+    liveness_failures: Arc<AtomicUsize>,
+
+    /// The same count, which restarting the service does not reset.
+    /// The Manager uses this to decide whether an update failed.
+    critical_checks: Arc<AtomicUsize>,
+
     /// A reference to the Supervisor's gateway state. We also store
     /// the status in here for making it available via the HTTP
     /// gateway.
@@ -124,9 +194,14 @@ impl State {
                package: Pkg,
                svc_encrypted_password: Option<String>,
                service_event_metadata: ServiceEventMetadata,
+               probe: Option<HealthCheckProbe>,
+               readiness_probe: Option<HealthCheckProbe>,
                supervisor: Arc<Mutex<Supervisor>>,
                nominal_interval: HealthCheckInterval,
                service_health_result: Arc<Mutex<HealthCheckResult>>,
+               service_readiness_result: Arc<Mutex<HealthCheckResult>>,
+               liveness_failures: Arc<AtomicUsize>,
+               critical_checks: Arc<AtomicUsize>,
                gateway_state: Arc<GatewayState>)
                -> Self {
         State { hook,
@@ -134,9 +209,14 @@ impl State {
                 package,
                 svc_encrypted_password,
                 service_event_metadata,
+                probe,
+                readiness_probe,
                 supervisor,
                 nominal_interval,
                 service_health_result,
+                service_readiness_result,
+                liveness_failures,
+                critical_checks,
                 gateway_state }
     }
 
@@ -145,9 +225,9 @@ impl State {
     // # Locking (see locking.md)
     // # `GatewayState::inner` (write)
     pub fn init_gateway_state_gsw(self) {
-        self.gateway_state
-            .lock_gsw()
-            .set_health_of(self.service_group, HealthCheckResult::Unknown);
+        let mut gateway_state = self.gateway_state.lock_gsw();
+        gateway_state.set_health_of(self.service_group.clone(), HealthCheckResult::Unknown);
+        gateway_state.set_readiness_of(self.service_group, HealthCheckResult::Unknown);
     }
 
     /// Creates a future that runs the health check and then waits for
@@ -161,34 +241,54 @@ impl State {
                     package,
                     svc_encrypted_password,
                     service_event_metadata,
+                    probe,
+                    readiness_probe,
                     supervisor,
                     nominal_interval,
                     service_health_result,
+                    service_readiness_result,
+                    liveness_failures,
+                    critical_checks,
                     gateway_state, } = self;
 
         // Use an Arc to avoid having to have full clones everywhere. :/
         let service_group = Arc::new(service_group);
         let service_group_ref = Arc::clone(&service_group);
 
-        if let Some(hook) = hook {
+        let readiness = if let Some(readiness_probe) = readiness_probe {
+            Either::A(run_probe(readiness_probe,
+                                Arc::clone(&service_group),
+                                package.clone(),
+                                svc_encrypted_password.clone()).map(Some))
+        } else {
+            Either::B(future::ok(None))
+        };
+
+        if let Some(probe) = probe {
+            Either::A(Either::A(run_probe(probe,
+                                          Arc::clone(&service_group),
+                                          package.clone(),
+                                          svc_encrypted_password)))
+        } else if let Some(hook) = hook {
             let hr = hook_runner::HookRunner::new(hook,
                                                   service_group.deref().clone(),
                                                   package.clone(),
                                                   svc_encrypted_password);
-            Either::A(hr.into_future().map(|(output, duration)| {
-                                          if let Some(output) = output {
-                                              HealthCheckHookStatus::Ran(output, duration)
-                                          } else {
-                                              HealthCheckHookStatus::FailedToRun(duration)
-                                          }
-                                      }))
+            Either::A(Either::B(hr.into_future().map(|(output, duration)| {
+                                                    if let Some(output) = output {
+                                                        HealthCheckHookStatus::Ran(output, duration)
+                                                    } else {
+                                                        HealthCheckHookStatus::FailedToRun(duration)
+                                                    }
+                                                })))
         } else {
             Either::B(lazy(|| Ok(HealthCheckHookStatus::NoHook)))
-        }.map_err(move |e| {
+        }.join(readiness)
+         .map_err(move |e| {
              error!("Error running health check hook for {}: {:?}",
                     service_group_ref, e)
          })
-         .and_then(move |health_check_hook_status| {
+         .and_then(move |(health_check_hook_status, readiness_status)| {
              let health_check_result = match &health_check_hook_status {
                  HealthCheckHookStatus::Ran(output, _) => {
                      // The hook ran. Try and convert its exit status to a `HealthCheckResult`.
@@ -211,6 +311,7 @@ impl State {
                      // unknown.
                      HealthCheckResult::Unknown
                  }
+                 HealthCheckHookStatus::Probed(result, _) => *result,
                  HealthCheckHookStatus::NoHook => {
                      //  There was no hook to run. Use the supervisor status as a healthcheck.
                      if supervisor.lock()
@@ -225,6 +326,17 @@ impl State {
                  }
              };
 
+             let readiness_result = readiness_status.as_ref()
+                                                    .map(readiness_of)
+                                                    .unwrap_or(health_check_result);
+             if health_check_result == HealthCheckResult::Critical {
+                 liveness_failures.fetch_add(1, Ordering::Relaxed);
+                 critical_checks.fetch_add(1, Ordering::Relaxed);
+             } else {
+                 liveness_failures.store(0, Ordering::Relaxed);
+                 critical_checks.store(0, Ordering::Relaxed);
+             }
+
              event::health_check(service_event_metadata,
                                  health_check_result,
                                  health_check_hook_status);
@@ -234,8 +346,14 @@ impl State {
              *service_health_result.lock()
                                    .expect("Could not unlock service_health_result") =
                  health_check_result;
-             gateway_state.lock_gsw()
-                          .set_health_of(service_group.deref().clone(), health_check_result);
+             *service_readiness_result.lock()
+                                      .expect("Could not unlock service_readiness_result") =
+                 readiness_result;
+             {
+                 let mut gateway_state = gateway_state.lock_gsw();
+                 gateway_state.set_health_of(service_group.deref().clone(), health_check_result);
+                 gateway_state.set_readiness_of(service_group.deref().clone(), readiness_result);
+             }
 
              let interval = if health_check_result == HealthCheckResult::Ok {
                  // routine health check
@@ -273,10 +391,6 @@ impl State {
     /// * `GatewayState::inner` (write)
     pub fn check_repeatedly_gsw(self) -> impl Future<Item = (), Error = ()> {
         future::loop_fn(self, move |state| {
-            // TODO (CM): If we wanted to keep track of how many times
-            // a health check has failed in the past X executions, or
-            // do similar historical tracking, here's where we'd do
-            // it.
             let service_group = state.service_group.clone();
             state.clone().single_iteration_gsw().then(move |res| {
                                                     if res.is_ok() {
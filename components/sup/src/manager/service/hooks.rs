@@ -1,5 +1,6 @@
 #[cfg(windows)]
 use super::pipe_hook_client::PipeHookClient;
+use crate::event;
 use habitat_common::{error::Result,
                      outputln,
                      templating::{hooks::{self,
@@ -22,6 +23,14 @@ use std::{self,
 
 static LOGKEY: &str = "HK";
 
+/// Publish an event if a hook did not exit successfully.
+fn report_failure<H: Hook>(pkg: &Pkg, status: ExitStatus) {
+    let code = status.code();
+    if code != Some(0) {
+        event::hook_failed(pkg, H::file_name(), code);
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct StandardStreams {
     pub stdout: Option<String>,
@@ -69,7 +78,8 @@ impl Hook for FileUpdatedHook {
                           stderr_log_path: hooks::stderr_log_path::<Self>(package_name), }
     }
 
-    fn handle_exit<'a>(&self, _: &Pkg, _: &'a HookOutput, status: ExitStatus) -> Self::ExitValue {
+    fn handle_exit<'a>(&self, pkg: &Pkg, _: &'a HookOutput, status: ExitStatus) -> Self::ExitValue {
+        report_failure::<Self>(pkg, status);
         status.success()
     }
 
@@ -175,6 +185,7 @@ impl Hook for InitHook {
     }
 
     fn handle_exit<'a>(&self, pkg: &Pkg, _: &'a HookOutput, status: ExitStatus) -> Self::ExitValue {
+        report_failure::<Self>(pkg, status);
         let pkg_name = &pkg.name;
         match status.code() {
             Some(0) => true,
@@ -263,6 +274,7 @@ impl Hook for PostRunHook {
     }
 
     fn handle_exit<'a>(&self, pkg: &Pkg, _: &'a HookOutput, status: ExitStatus) -> Self::ExitValue {
+        report_failure::<Self>(pkg, status);
         match status.code() {
             Some(code) => ExitCode(code),
             None => {
@@ -306,6 +318,7 @@ impl Hook for ReloadHook {
     }
 
     fn handle_exit<'a>(&self, pkg: &Pkg, _: &'a HookOutput, status: ExitStatus) -> Self::ExitValue {
+        report_failure::<Self>(pkg, status);
         let pkg_name = &pkg.name;
         match status.code() {
             Some(0) => ExitCode(0),
@@ -349,6 +362,7 @@ impl Hook for ReconfigureHook {
     }
 
     fn handle_exit<'a>(&self, pkg: &Pkg, _: &'a HookOutput, status: ExitStatus) -> Self::ExitValue {
+        report_failure::<Self>(pkg, status);
         match status.code() {
             Some(code) => ExitCode(code),
             None => {
@@ -417,6 +431,7 @@ impl Hook for SuitabilityHook {
                        hook_output: &'a HookOutput,
                        status: ExitStatus)
                        -> Self::ExitValue {
+        report_failure::<Self>(pkg, status);
         let pkg_name = &pkg.name;
         match status.code() {
             Some(0) => {
@@ -469,6 +484,7 @@ impl Hook for PostStopHook {
     }
 
     fn handle_exit<'a>(&self, pkg: &Pkg, _: &'a HookOutput, status: ExitStatus) -> Self::ExitValue {
+        report_failure::<Self>(pkg, status);
         let pkg_name = &pkg.name;
         match status.code() {
             Some(0) => true,
@@ -624,6 +640,56 @@ impl HookTable {
         changed
     }
 
+    /// Lists the hooks whose content would change if they were compiled with `pending` instead
+    /// of `current`. Nothing is written.
+    pub fn changed_by<T>(&self, current: &T, pending: &T) -> Result<Vec<&'static str>>
+        where T: Serialize
+    {
+        let mut changed = Vec::new();
+        if let Some(ref hook) = self.file_updated {
+            Self::push_if_changed(&mut changed, hook, current, pending)?;
+        }
+        if let Some(ref hook) = self.health_check {
+            Self::push_if_changed(&mut changed, hook.as_ref(), current, pending)?;
+        }
+        if let Some(ref hook) = self.init {
+            Self::push_if_changed(&mut changed, hook.as_ref(), current, pending)?;
+        }
+        if let Some(ref hook) = self.reload {
+            Self::push_if_changed(&mut changed, hook, current, pending)?;
+        }
+        if let Some(ref hook) = self.reconfigure {
+            Self::push_if_changed(&mut changed, hook, current, pending)?;
+        }
+        if let Some(ref hook) = self.suitability {
+            Self::push_if_changed(&mut changed, hook, current, pending)?;
+        }
+        if let Some(ref hook) = self.run {
+            Self::push_if_changed(&mut changed, hook, current, pending)?;
+        }
+        if let Some(ref hook) = self.post_run {
+            Self::push_if_changed(&mut changed, hook.as_ref(), current, pending)?;
+        }
+        if let Some(ref hook) = self.post_stop {
+            Self::push_if_changed(&mut changed, hook.as_ref(), current, pending)?;
+        }
+        Ok(changed)
+    }
+
+    fn push_if_changed<H, T>(changed: &mut Vec<&'static str>,
+                             hook: &H,
+                             current: &T,
+                             pending: &T)
+                             -> Result<()>
+        where H: Hook,
+              T: Serialize
+    {
+        if hook.changed_by(current, pending)? {
+            changed.push(H::file_name());
+        }
+        Ok(())
+    }
+
     fn compile_one<H, T>(&self, hook: &H, service_group: &str, ctx: &T) -> bool
         where H: Hook,
               T: Serialize
@@ -667,7 +733,8 @@ mod tests {
                                  PackageInstall},
                        service::{ServiceBind,
                                  ServiceGroup}};
-    use std::{convert,
+    use std::{borrow::Cow,
+              convert,
               fs,
               io::BufReader,
               iter,
@@ -772,7 +839,7 @@ mod tests {
 
         let bindings = iter::empty::<&ServiceBind>();
 
-        RenderContext::new(service_group, sys, pkg, cfg, ring, bindings)
+        RenderContext::new(service_group, sys, pkg, Cow::Borrowed(cfg), ring, bindings)
     }
 
     ////////////////////////////////////////////////////////////////////////
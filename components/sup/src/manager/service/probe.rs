@@ -0,0 +1,502 @@
+//! Declarative health checks, run in place of a `health_check` hook.
+//!
+//! Many services are healthy as long as they answer an HTTP request or
+//! accept a TCP connection. A probe lets that be configured when the
+//! service is loaded, rather than written as a hook. An `exec` probe
+//! runs a command, as the service's user and with its package's
+//! environment, whose exit code is interpreted exactly like that of a
+//! `health_check` hook.
+
+use super::{health::{HealthCheckHookStatus,
+                     HealthCheckResult},
+            ProcessOutput,
+            StandardStreams};
+use crate::{PRODUCT,
+            VERSION};
+use habitat_common::{outputln,
+                     templating::package::Pkg};
+#[cfg(windows)]
+use habitat_core::os::process::windows_child::Child;
+use habitat_core::service::ServiceGroup;
+use habitat_http_client::ApiClient;
+use habitat_sup_protocol::{net::{self,
+                                 ErrCode,
+                                 NetErr},
+                           types::{self as proto,
+                                   HealthCheckProbeKind}};
+use regex::Regex;
+#[cfg(unix)]
+use std::process::{Child,
+                   Command,
+                   Stdio};
+use std::{io::Read,
+          net::{TcpStream,
+                ToSocketAddrs},
+          sync::mpsc,
+          thread,
+          time::{Duration,
+                 Instant}};
+
+static LOGKEY: &str = "PR";
+
+/// How long to wait for a probe if no timeout is given.
+const DEFAULT_TIMEOUT: u64 = 5;
+/// How often to check whether an `exec` probe's command has finished.
+const EXEC_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum HealthCheckProbe {
+    /// The service is healthy if a GET request to `url` responds with
+    /// `expected_status`, and a body matching `expected_body`, if
+    /// given.
+    Http {
+        url: String,
+        #[serde(default = "default_expected_status")]
+        expected_status: u16,
+        expected_body: Option<String>,
+        #[serde(default = "default_timeout")]
+        timeout: u64,
+    },
+    /// The service is healthy if a TCP connection to `address` can be
+    /// opened.
+    Tcp {
+        address: String,
+        #[serde(default = "default_timeout")]
+        timeout: u64,
+    },
+    /// The health of the service is given by the exit code of
+    /// `command`.
+    Exec {
+        command: Vec<String>,
+        #[serde(default = "default_timeout")]
+        timeout: u64,
+    },
+}
+
+fn default_expected_status() -> u16 { 200 }
+
+fn default_timeout() -> u64 { DEFAULT_TIMEOUT }
+
+impl HealthCheckProbe {
+    /// Validate a probe given in a `SvcLoad` request.
+    pub fn from_proto(probe: proto::HealthCheckProbe) -> Result<Self, NetErr> {
+        let timeout = probe.timeout.unwrap_or(DEFAULT_TIMEOUT);
+        let kind = probe.kind
+                        .and_then(HealthCheckProbeKind::from_i32)
+                        .unwrap_or(HealthCheckProbeKind::Http);
+        let target = || {
+            probe.target.clone().ok_or_else(|| {
+                                    net::err(ErrCode::InvalidPayload,
+                                             format!("A {} health check probe requires a target",
+                                                     kind))
+                                })
+        };
+        match kind {
+            HealthCheckProbeKind::Http => {
+                if let Some(ref expected_body) = probe.expected_body {
+                    Regex::new(expected_body).map_err(|e| {
+                                                 net::err(ErrCode::InvalidPayload,
+                                                          format!("Invalid expected body: {}", e))
+                                             })?;
+                }
+                let expected_status = match probe.expected_status {
+                    Some(status) if status < 100 || status > 599 => {
+                        return Err(net::err(ErrCode::InvalidPayload,
+                                            format!("Invalid expected status: {}", status)));
+                    }
+                    Some(status) => status as u16,
+                    None => default_expected_status(),
+                };
+                Ok(HealthCheckProbe::Http { url: target()?,
+                                            expected_status,
+                                            expected_body: probe.expected_body,
+                                            timeout })
+            }
+            HealthCheckProbeKind::Tcp => {
+                Ok(HealthCheckProbe::Tcp { address: target()?,
+                                           timeout })
+            }
+            HealthCheckProbeKind::Exec => {
+                if probe.command.is_empty() {
+                    return Err(net::err(ErrCode::InvalidPayload,
+                                        "An exec health check probe requires a command"));
+                }
+                Ok(HealthCheckProbe::Exec { command: probe.command,
+                                            timeout })
+            }
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        match *self {
+            HealthCheckProbe::Http { timeout, .. }
+            | HealthCheckProbe::Tcp { timeout, .. }
+            | HealthCheckProbe::Exec { timeout, .. } => Duration::from_secs(timeout),
+        }
+    }
+
+    /// Run the probe, blocking until it completes or times out. An
+    /// `exec` probe runs as the package's service user.
+    pub fn run(&self,
+               service_group: &ServiceGroup,
+               pkg: &Pkg,
+               svc_encrypted_password: Option<&str>)
+               -> HealthCheckHookStatus {
+        let start = Instant::now();
+        let timeout = self.timeout();
+        let result = match self {
+            HealthCheckProbe::Http { url,
+                                     expected_status,
+                                     expected_body,
+                                     .. } => {
+                http_get(url, *expected_status, expected_body.as_ref(), timeout)
+            }
+            HealthCheckProbe::Tcp { address, .. } => tcp_connect(address, timeout),
+            HealthCheckProbe::Exec { command, .. } => {
+                return match exec(command, pkg, svc_encrypted_password, timeout) {
+                    Ok(output) => HealthCheckHookStatus::Ran(output, start.elapsed()),
+                    Err(e) => {
+                        outputln!(preamble service_group, "Health check probe failed: {}", e);
+                        HealthCheckHookStatus::FailedToRun(start.elapsed())
+                    }
+                };
+            }
+        };
+        let health = match result {
+            Ok(()) => HealthCheckResult::Ok,
+            Err(e) => {
+                outputln!(preamble service_group, "Health check probe failed: {}", e);
+                HealthCheckResult::Critical
+            }
+        };
+        HealthCheckHookStatus::Probed(health, start.elapsed())
+    }
+}
+
+fn http_get(url: &str,
+            expected_status: u16,
+            expected_body: Option<&String>,
+            timeout: Duration)
+            -> Result<(), String> {
+    let client =
+        ApiClient::with_timeout(url, PRODUCT, VERSION, None, timeout).map_err(|e| e.to_string())?;
+    let expected_body = match expected_body {
+        Some(pattern) => Some(Regex::new(pattern).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    let mut response = client.get("").send().map_err(|e| e.to_string())?;
+    let body = response.text().map_err(|e| e.to_string())?;
+    let status = response.status();
+    if status.as_u16() != expected_status {
+        return Err(format!("expected status {}, got {}", expected_status, status));
+    }
+    match expected_body {
+        Some(ref pattern) if !pattern.is_match(&body) => {
+            Err(format!("response body does not match '{}'", pattern))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn tcp_connect(address: &str, timeout: Duration) -> Result<(), String> {
+    let addrs = address.to_socket_addrs().map_err(|e| e.to_string())?;
+    let mut last_error = format!("{} did not resolve to any addresses", address);
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(_) => return Ok(()),
+            Err(e) => last_error = format!("{}: {}", addr, e),
+        }
+    }
+    Err(last_error)
+}
+
+/// Run `command`, killing it if it has not finished within `timeout`.
+/// Its output is read as it is written, so that a command writing more
+/// than a pipe holds is not blocked on it.
+fn exec(command: &[String],
+        pkg: &Pkg,
+        svc_encrypted_password: Option<&str>,
+        timeout: Duration)
+        -> Result<ProcessOutput, String> {
+    let (program, args) = command.split_first()
+                                 .ok_or_else(|| "no command given".to_string())?;
+    let mut child = spawn(program, args, pkg, svc_encrypted_password).map_err(|e| {
+                                                                         format!("{}: {}",
+                                                                                 program, e)
+                                                                     })?;
+    let stdout = child.stdout.take().map(read_to_string_on_thread);
+    let stderr = child.stderr.take().map(read_to_string_on_thread);
+    let deadline = Instant::now() + timeout;
+    let exit_status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            kill(&mut child);
+            child.wait().ok();
+            return Err(format!("{} did not finish within {:?}", program, timeout));
+        }
+        thread::sleep(EXEC_POLL_INTERVAL);
+    };
+    // Anything still holding the pipes open, like a process the command
+    // left running in the background, is not waited for past the
+    // deadline.
+    let remaining = || {
+        let now = Instant::now();
+        if now < deadline {
+            deadline - now
+        } else {
+            Duration::from_secs(0)
+        }
+    };
+    let stdout = stdout.and_then(|reader| reader.recv_timeout(remaining()).ok())
+                       .and_then(|output| output);
+    let stderr = stderr.and_then(|reader| reader.recv_timeout(remaining()).ok())
+                       .and_then(|output| output);
+    Ok(ProcessOutput::from_raw(StandardStreams { stdout, stderr }, exit_status))
+}
+
+#[cfg(unix)]
+fn spawn(program: &str,
+         args: &[String],
+         pkg: &Pkg,
+         _: Option<&str>)
+         -> Result<Child, String> {
+    use habitat_core::os::users;
+    use std::{io,
+              os::unix::process::CommandExt};
+
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+       .stdin(Stdio::null())
+       .stdout(Stdio::piped())
+       .stderr(Stdio::piped());
+    for (key, val) in pkg.env.iter() {
+        cmd.env(key, val);
+    }
+    // As with hooks, the command runs as the service user if we can
+    // SETUID/SETGID, and as ourselves otherwise.
+    if users::can_run_services_as_svc_user() {
+        let uid = users::get_uid_by_name(&pkg.svc_user).ok_or_else(|| {
+                      format!("No uid for user '{}' could be found", pkg.svc_user)
+                  })?;
+        let gid = users::get_gid_by_name(&pkg.svc_group).ok_or_else(|| {
+                      format!("No gid for group '{}' could be found", pkg.svc_group)
+                  })?;
+        cmd.uid(uid).gid(gid);
+    }
+    unsafe {
+        // Run in its own process group, so that terminal signals sent
+        // to a Supervisor running in the foreground don't reach it, and
+        // so that it can be killed along with anything it started.
+        cmd.pre_exec(|| {
+               if libc::setpgid(0, 0) == 0 {
+                   Ok(())
+               } else {
+                   Err(io::Error::last_os_error())
+               }
+           });
+    }
+    cmd.spawn().map_err(|e| e.to_string())
+}
+
+#[cfg(windows)]
+fn spawn(program: &str,
+         args: &[String],
+         pkg: &Pkg,
+         svc_encrypted_password: Option<&str>)
+         -> Result<Child, String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    Child::spawn(program,
+                 &args,
+                 &pkg.env.to_hash_map(),
+                 &pkg.svc_user,
+                 svc_encrypted_password).map_err(|e| e.to_string())
+}
+
+/// Kill a command that has run past its timeout, along with the
+/// process group it leads, so that nothing it started outlives it.
+#[cfg(unix)]
+fn kill(child: &mut Child) {
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(windows)]
+fn kill(child: &mut Child) { child.kill().ok(); }
+
+/// Read `stream` to its end on another thread, so that the child
+/// writing to it never blocks on a full pipe.
+fn read_to_string_on_thread<R>(mut stream: R) -> mpsc::Receiver<Option<String>>
+    where R: Read + Send + 'static
+{
+    let (tx, rx) = mpsc::channel();
+    let reader = move || {
+        let mut output = String::new();
+        tx.send(stream.read_to_string(&mut output).ok().map(|_| output))
+          .ok();
+    };
+    if let Err(e) = thread::Builder::new().name("probe-output".to_string())
+                                          .spawn(reader)
+    {
+        debug!("Unable to read the output of a health check probe, {}", e);
+    }
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use habitat_core::package::{PackageIdent,
+                                PackageInstall};
+    use std::{net::TcpListener,
+              path::PathBuf,
+              str::FromStr};
+
+    fn service_group() -> ServiceGroup { ServiceGroup::from_str("probe.default").unwrap() }
+
+    fn pkg() -> Pkg {
+        let pg_id = PackageIdent::new("testing", "probe", Some("1.0.0"), Some("20170712000000"));
+        let pkg_install = PackageInstall::new_from_parts(pg_id,
+                                                         PathBuf::from("/tmp"),
+                                                         PathBuf::from("/tmp"),
+                                                         PathBuf::from("/tmp"));
+        Pkg::from_install(&pkg_install).unwrap()
+    }
+
+    #[test]
+    fn from_proto_requires_a_target() {
+        let probe = proto::HealthCheckProbe { kind: Some(HealthCheckProbeKind::Tcp as i32),
+                                              ..Default::default() };
+        assert!(HealthCheckProbe::from_proto(probe).is_err());
+    }
+
+    #[test]
+    fn from_proto_rejects_an_invalid_body_pattern() {
+        let probe = proto::HealthCheckProbe { target: Some("http://localhost".to_string()),
+                                              expected_body: Some("(".to_string()),
+                                              ..Default::default() };
+        assert!(HealthCheckProbe::from_proto(probe).is_err());
+    }
+
+    #[test]
+    fn from_proto_rejects_an_invalid_expected_status() {
+        for status in &[99, 600, 65_736] {
+            let probe = proto::HealthCheckProbe { target: Some("http://localhost".to_string()),
+                                                  expected_status: Some(*status),
+                                                  ..Default::default() };
+            assert!(HealthCheckProbe::from_proto(probe).is_err());
+        }
+    }
+
+    #[test]
+    fn from_proto_fills_in_defaults() {
+        let probe = proto::HealthCheckProbe { target: Some("http://localhost".to_string()),
+                                              ..Default::default() };
+        assert_eq!(HealthCheckProbe::from_proto(probe).unwrap(),
+                   HealthCheckProbe::Http { url:             "http://localhost".to_string(),
+                                            expected_status: 200,
+                                            expected_body:   None,
+                                            timeout:         DEFAULT_TIMEOUT, });
+    }
+
+    #[test]
+    fn probes_round_trip_through_toml() {
+        let probe = HealthCheckProbe::Exec { command: vec!["true".to_string()],
+                                             timeout: 3, };
+        let toml = toml::to_string(&probe).unwrap();
+        assert!(toml.contains(r#"kind = "exec""#));
+        assert_eq!(toml::from_str::<HealthCheckProbe>(&toml).unwrap(), probe);
+    }
+
+    #[test]
+    fn tcp_probe_is_ok_when_the_port_is_open() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let probe = HealthCheckProbe::Tcp { address: listener.local_addr().unwrap().to_string(),
+                                            timeout: 1, };
+        match probe.run(&service_group(), &pkg(), None) {
+            HealthCheckHookStatus::Probed(result, _) => assert_eq!(result, HealthCheckResult::Ok),
+            _ => panic!("expected the probe to report a result"),
+        }
+    }
+
+    #[test]
+    fn tcp_probe_is_critical_when_the_port_is_closed() {
+        let address = TcpListener::bind("127.0.0.1:0").unwrap()
+                                                      .local_addr()
+                                                      .unwrap()
+                                                      .to_string();
+        let probe = HealthCheckProbe::Tcp { address,
+                                            timeout: 1 };
+        match probe.run(&service_group(), &pkg(), None) {
+            HealthCheckHookStatus::Probed(result, _) => {
+                assert_eq!(result, HealthCheckResult::Critical)
+            }
+            _ => panic!("expected the probe to report a result"),
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn exec_probe_reports_the_exit_status() {
+        let probe = HealthCheckProbe::Exec { command: vec!["sh".to_string(),
+                                                           "-c".to_string(),
+                                                           "echo hi; exit 1".to_string()],
+                                             timeout: 5, };
+        match probe.run(&service_group(), &pkg(), None) {
+            HealthCheckHookStatus::Ran(output, _) => {
+                assert_eq!(output.exit_status().code(), Some(1));
+                assert_eq!(output.standard_streams().stdout, Some("hi\n".to_string()));
+            }
+            _ => panic!("expected the command to run"),
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn exec_probe_reads_more_output_than_a_pipe_holds() {
+        let probe = HealthCheckProbe::Exec { command: vec!["sh".to_string(),
+                                                           "-c".to_string(),
+                                                           "head -c 200000 /dev/zero | tr '\\0' x"
+                                                           .to_string()],
+                                             timeout: 5, };
+        match probe.run(&service_group(), &pkg(), None) {
+            HealthCheckHookStatus::Ran(output, _) => {
+                assert_eq!(output.exit_status().code(), Some(0));
+                assert_eq!(output.standard_streams().stdout.map(|s| s.len()), Some(200_000));
+            }
+            _ => panic!("expected the command to run"),
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn exec_probe_times_out() {
+        let probe = HealthCheckProbe::Exec { command: vec!["sleep".to_string(), "10".to_string()],
+                                             timeout: 0, };
+        match probe.run(&service_group(), &pkg(), None) {
+            HealthCheckHookStatus::FailedToRun(_) => (),
+            _ => panic!("expected the command to time out"),
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn exec_probe_timeout_kills_what_the_command_started() {
+        let pid_file = std::env::temp_dir().join(format!("probe-{}.pid", std::process::id()));
+        let script = format!("sleep 10 & echo $! > {}; wait", pid_file.display());
+        let probe = HealthCheckProbe::Exec { command: vec!["sh".to_string(),
+                                                           "-c".to_string(),
+                                                           script],
+                                             timeout: 1, };
+        probe.run(&service_group(), &pkg(), None);
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        std::fs::remove_file(&pid_file).ok();
+
+        // Once killed, the orphaned `sleep` is gone, or a zombie until it is reaped.
+        let status = std::fs::read_to_string(format!("/proc/{}/status", pid.trim()));
+        assert!(status.map(|s| s.contains("State:\tZ")).unwrap_or(true));
+    }
+}
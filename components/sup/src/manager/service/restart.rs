@@ -0,0 +1,294 @@
+//! Deciding whether, and when, to restart a service whose process has
+//! exited.
+//!
+//! By default a process is restarted straight away, however often it
+//! exits. A policy with a backoff makes each restart within its window
+//! wait twice as long as the one before it (up to a maximum), and a
+//! policy with a retry limit considers a service that needs more
+//! restarts than that to be crash-looping, and leaves it down.
+//!
+//! An `on-failure` policy leaves a process down once it exits
+//! successfully. The Supervisor asks the Launcher, which reaps service
+//! processes, how the process exited; an exit it can't confirm was
+//! successful, such as one reported by an older Launcher which doesn't
+//! answer that question, counts as a failure.
+
+use habitat_sup_protocol::types as proto;
+pub use habitat_sup_protocol::types::RestartCondition;
+use std::{collections::VecDeque,
+          time::{Duration,
+                 Instant}};
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(default)]
+pub struct RestartPolicy {
+    pub condition: RestartCondition,
+    /// The number of restarts allowed within `window` before the
+    /// service is crash-looping. Zero allows unlimited restarts.
+    pub max_retries: u32,
+    /// The period, in seconds, over which restarts are counted.
+    pub window: u64,
+    /// The delay, in seconds, before the first restart. Zero restarts
+    /// the process straight away.
+    pub backoff: u64,
+    /// The longest delay, in seconds, before a restart.
+    pub backoff_max: u64,
+    /// The percentage of each delay that is randomized.
+    pub backoff_jitter: u32,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self { condition:      RestartCondition::Always,
+               max_retries:    0,
+               window:         600,
+               backoff:        0,
+               backoff_max:    60,
+               backoff_jitter: 0, }
+    }
+}
+
+impl RestartPolicy {
+    /// Override any settings present in a `SvcLoad` request's policy.
+    pub fn merge(&mut self, other: proto::RestartPolicy) {
+        if let Some(condition) = other.condition.and_then(RestartCondition::from_i32) {
+            self.condition = condition;
+        }
+        if let Some(max_retries) = other.max_retries {
+            self.max_retries = max_retries;
+        }
+        if let Some(window) = other.window {
+            self.window = window;
+        }
+        if let Some(backoff) = other.backoff {
+            self.backoff = backoff;
+        }
+        if let Some(backoff_max) = other.backoff_max {
+            self.backoff_max = backoff_max;
+        }
+        if let Some(backoff_jitter) = other.backoff_jitter {
+            self.backoff_jitter = backoff_jitter.min(100);
+        }
+    }
+
+    /// The delay before restarting after `attempt` earlier restarts
+    /// within the window. `random` is a number in `[0, 1)` that
+    /// determines how much of the jitter is taken off the delay.
+    fn delay(&self, attempt: u32, random: f64) -> Duration {
+        let secs = self.backoff
+                       .saturating_mul(2u64.saturating_pow(attempt))
+                       .min(self.backoff_max);
+        let millis = secs.saturating_mul(1000);
+        let jitter = millis as f64 * f64::from(self.backoff_jitter.min(100)) / 100.0 * random;
+        Duration::from_millis(millis - jitter as u64)
+    }
+}
+
+/// What to do about a process that has exited.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RestartDecision {
+    /// Restart the process once the backoff delay has passed.
+    RestartAt(Instant),
+    /// The process has been restarted too often; leave it down.
+    CrashLooping,
+    /// The policy says the process should not be restarted.
+    StayDown,
+}
+
+/// The restart history of a service. This outlives any one `Service`,
+/// since restarting a service replaces it with a new one.
+#[derive(Debug, Default)]
+pub struct RestartTracker {
+    /// When the process was restarted, oldest first, within the
+    /// policy's window.
+    restarts: VecDeque<Instant>,
+    /// The response to the process's exit, if it has exited since it
+    /// was last started.
+    decision: Option<RestartDecision>,
+}
+
+impl RestartTracker {
+    /// Record that the process was found to have exited at `now`,
+    /// unsuccessfully if `failed`, returning the decision about what
+    /// to do if this exit has not already been recorded.
+    pub fn process_exited(&mut self,
+                          policy: &RestartPolicy,
+                          failed: bool,
+                          now: Instant)
+                          -> Option<RestartDecision> {
+        if self.decision.is_some() {
+            return None;
+        }
+        let decision = self.decide(policy, failed, now, rand::random());
+        self.decision = Some(decision);
+        self.decision
+    }
+
+    /// Whether the process has exited and its restart delay has passed.
+    pub fn restart_due(&self, now: Instant) -> bool {
+        match self.decision {
+            Some(RestartDecision::RestartAt(at)) => now >= at,
+            _ => false,
+        }
+    }
+
+    /// Record that the process has been started again.
+    pub fn process_started(&mut self) { self.decision = None; }
+
+    /// Whether the process exited too often to be restarted again.
+    pub fn crash_looping(&self) -> bool { self.decision == Some(RestartDecision::CrashLooping) }
+
+    /// The number of restarts within the policy's window.
+    pub fn restarts(&self) -> u32 { self.restarts.len() as u32 }
+
+    fn decide(&mut self,
+              policy: &RestartPolicy,
+              failed: bool,
+              now: Instant,
+              random: f64)
+              -> RestartDecision {
+        match policy.condition {
+            RestartCondition::Never => return RestartDecision::StayDown,
+            RestartCondition::OnFailure if !failed => return RestartDecision::StayDown,
+            _ => {}
+        }
+        let window = Duration::from_secs(policy.window);
+        while let Some(&oldest) = self.restarts.front() {
+            if now.duration_since(oldest) < window {
+                break;
+            }
+            self.restarts.pop_front();
+        }
+        if policy.max_retries > 0 && self.restarts() >= policy.max_retries {
+            return RestartDecision::CrashLooping;
+        }
+        let delay = policy.delay(self.restarts(), random);
+        self.restarts.push_back(now);
+        RestartDecision::RestartAt(now + delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RestartPolicy {
+        RestartPolicy { condition:      RestartCondition::Always,
+                        max_retries:    3,
+                        window:         60,
+                        backoff:        2,
+                        backoff_max:    5,
+                        backoff_jitter: 50, }
+    }
+
+    #[test]
+    fn delay_doubles_up_to_the_maximum() {
+        let policy = policy();
+        assert_eq!(policy.delay(0, 0.0), Duration::from_secs(2));
+        assert_eq!(policy.delay(1, 0.0), Duration::from_secs(4));
+        assert_eq!(policy.delay(2, 0.0), Duration::from_secs(5));
+        assert_eq!(policy.delay(100, 0.0), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn jitter_shortens_the_delay() {
+        let policy = policy();
+        assert_eq!(policy.delay(0, 0.5), Duration::from_millis(1500));
+        assert!(policy.delay(1, 0.999) > Duration::from_secs(2));
+    }
+
+    #[test]
+    fn never_leaves_the_process_down() {
+        let mut policy = policy();
+        policy.condition = RestartCondition::Never;
+        let mut tracker = RestartTracker::default();
+
+        assert_eq!(tracker.decide(&policy, true, Instant::now(), 0.0),
+                   RestartDecision::StayDown);
+    }
+
+    #[test]
+    fn on_failure_leaves_the_process_down_after_a_successful_exit() {
+        let mut policy = policy();
+        policy.condition = RestartCondition::OnFailure;
+        let mut tracker = RestartTracker::default();
+        let now = Instant::now();
+
+        assert_eq!(tracker.decide(&policy, false, now, 0.0),
+                   RestartDecision::StayDown);
+        assert_eq!(tracker.decide(&policy, true, now, 0.0),
+                   RestartDecision::RestartAt(now + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn too_many_restarts_within_the_window_is_crash_looping() {
+        let policy = policy();
+        let mut tracker = RestartTracker::default();
+        let start = Instant::now();
+
+        assert_eq!(tracker.decide(&policy, true, start, 0.0),
+                   RestartDecision::RestartAt(start + Duration::from_secs(2)));
+        let now = start + Duration::from_secs(10);
+        assert_eq!(tracker.decide(&policy, true, now, 0.0),
+                   RestartDecision::RestartAt(now + Duration::from_secs(4)));
+        tracker.decide(&policy, true, start + Duration::from_secs(20), 0.0);
+        assert_eq!(tracker.decide(&policy, true, start + Duration::from_secs(30), 0.0),
+                   RestartDecision::CrashLooping);
+    }
+
+    #[test]
+    fn restarts_outside_the_window_are_forgotten() {
+        let policy = policy();
+        let mut tracker = RestartTracker::default();
+        let start = Instant::now();
+
+        for secs in &[0, 10, 20] {
+            tracker.decide(&policy, true, start + Duration::from_secs(*secs), 0.0);
+        }
+        let now = start + Duration::from_secs(65);
+        assert_eq!(tracker.decide(&policy, true, now, 0.0),
+                   RestartDecision::RestartAt(now + Duration::from_secs(5)));
+        assert_eq!(tracker.restarts(), 3);
+    }
+
+    #[test]
+    fn an_exit_is_only_decided_once() {
+        let mut policy = policy();
+        policy.backoff_jitter = 0;
+        let mut tracker = RestartTracker::default();
+        let now = Instant::now();
+
+        assert!(tracker.process_exited(&policy, true, now).is_some());
+        assert!(tracker.process_exited(&policy, true, now).is_none());
+        assert!(!tracker.restart_due(now));
+        assert!(tracker.restart_due(now + Duration::from_secs(2)));
+
+        tracker.process_started();
+        assert!(!tracker.restart_due(now + Duration::from_secs(2)));
+        assert!(tracker.process_exited(&policy, true, now).is_some());
+    }
+
+    #[test]
+    fn the_default_policy_restarts_immediately_without_limit() {
+        let policy = RestartPolicy::default();
+        let mut tracker = RestartTracker::default();
+        let start = Instant::now();
+
+        for secs in 0..100 {
+            let now = start + Duration::from_secs(secs);
+            assert_eq!(tracker.decide(&policy, true, now, 0.5), RestartDecision::RestartAt(now));
+        }
+    }
+
+    #[test]
+    fn merge_overrides_only_the_given_settings() {
+        let mut policy = RestartPolicy::default();
+        policy.merge(proto::RestartPolicy { condition: Some(RestartCondition::Never as i32),
+                                            backoff_jitter: Some(250),
+                                            ..Default::default() });
+
+        assert_eq!(policy.condition, RestartCondition::Never);
+        assert_eq!(policy.backoff_jitter, 100);
+        assert_eq!(policy.max_retries, RestartPolicy::default().max_retries);
+    }
+}
@@ -1,5 +1,8 @@
 use super::{BindingMode,
+            HealthCheckProbe,
+            RestartPolicy,
             Topology,
+            UpdateBatchSize,
             UpdateStrategy};
 use crate::error::{Error,
                    Result};
@@ -88,6 +91,38 @@ pub fn deserialize_application_environment<'de, D>(
     }
 }
 
+fn serialize_releases<S>(releases: &[PackageIdent], s: S) -> result::Result<S::Ok, S::Error>
+    where S: serde::Serializer
+{
+    s.collect_seq(releases.iter().map(ToString::to_string))
+}
+
+fn deserialize_releases<'de, D>(d: D) -> result::Result<Vec<PackageIdent>, D::Error>
+    where D: serde::Deserializer<'de>
+{
+    let releases: Vec<String> = Vec::deserialize(d)?;
+    releases.iter()
+            .map(|r| PackageIdent::from_str(r).map_err(serde::de::Error::custom))
+            .collect()
+}
+
+fn serialize_release<S>(release: &Option<PackageIdent>, s: S) -> result::Result<S::Ok, S::Error>
+    where S: serde::Serializer
+{
+    match release {
+        Some(release) => s.serialize_some(&release.to_string()),
+        None => s.serialize_none(),
+    }
+}
+
+fn deserialize_release<'de, D>(d: D) -> result::Result<Option<PackageIdent>, D::Error>
+    where D: serde::Deserializer<'de>
+{
+    let release: Option<String> = Option::deserialize(d)?;
+    release.map(|r| PackageIdent::from_str(&r).map_err(serde::de::Error::custom))
+           .transpose()
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[serde(default = "ServiceSpec::deserialization_base")]
 pub struct ServiceSpec {
@@ -108,14 +143,45 @@ pub struct ServiceSpec {
     pub desired_state: DesiredState,
     pub shutdown_timeout: Option<ShutdownTimeout>,
     pub svc_encrypted_password: Option<String>,
-    // it is important that the health check interval
-    // is the last field to be serialized because it
-    // is serialized as a table. Individual values
-    // serialized after the health check interval will
+    pub liveness_threshold: u32,
+    /// How long, in seconds, an updated service must stay healthy
+    /// before the update is kept. Zero disables automatic rollback.
+    pub rollback_window: u64,
+    /// Releases that failed after an update and were rolled back.
+    /// They are never run or updated to again.
+    #[serde(serialize_with = "serialize_releases",
+            deserialize_with = "deserialize_releases")]
+    pub blocked_releases: Vec<PackageIdent>,
+    /// The release the service was last rolled back to. It runs in
+    /// place of a blocked release.
+    #[serde(serialize_with = "serialize_release",
+            deserialize_with = "deserialize_release",
+            skip_serializing_if = "Option::is_none")]
+    pub rolled_back_to: Option<PackageIdent>,
+    /// How many members the `batched` update strategy updates at a
+    /// time.
+    #[serde(with = "serde_string")]
+    pub update_batch_size: UpdateBatchSize,
+    /// How long, in seconds, the canary of a `batched` update must stay
+    /// healthy before the rest of the group updates. Zero skips the
+    /// canary.
+    pub update_canary_soak: u64,
+    /// Under the `leader` topology, the zone a leader is preferred in.
+    pub election_preferred_zone: Option<String>,
+    /// Under the `leader` topology, how many zones the alive members
+    /// must span for an election to have quorum. Zero ignores zones.
+    pub election_min_zones: u32,
+    // it is important that the restart policy, health check and
+    // readiness probes and health check interval are the last fields to be
+    // serialized because they are serialized as tables.
+    // Individual values serialized after them will
     // break the parser.
     // Note that there is an issue to ultimately fix this:
     // https://github.com/habitat-sh/habitat/issues/6469
-    // and eliminate the need to keep this field last.
+    // and eliminate the need to keep these fields last.
+    pub restart_policy:        RestartPolicy,
+    pub health_check_probe:    Option<HealthCheckProbe>,
+    pub readiness_probe:       Option<HealthCheckProbe>,
     pub health_check_interval: HealthCheckInterval,
 }
 
@@ -132,8 +198,19 @@ impl ServiceSpec {
                binding_mode: BindingMode::Strict,
                config_from: None,
                desired_state: DesiredState::default(),
+               restart_policy: RestartPolicy::default(),
+               health_check_probe: None,
+               readiness_probe: None,
                health_check_interval: HealthCheckInterval::default(),
                svc_encrypted_password: None,
+               liveness_threshold: 0,
+               rollback_window: 0,
+               blocked_releases: Vec::new(),
+               rolled_back_to: None,
+               update_batch_size: UpdateBatchSize::default(),
+               update_canary_soak: 0,
+               election_preferred_zone: None,
+               election_min_zones: 0,
                shutdown_timeout: None }
     }
 
@@ -278,6 +355,33 @@ impl ServiceSpec {
         if let Some(shutdown_timeout) = svc_load.shutdown_timeout {
             self.shutdown_timeout = Some(ShutdownTimeout::from(shutdown_timeout));
         }
+        if let Some(restart_policy) = svc_load.restart_policy {
+            self.restart_policy.merge(restart_policy);
+        }
+        if let Some(probe) = svc_load.health_check_probe {
+            self.health_check_probe = Some(HealthCheckProbe::from_proto(probe)?);
+        }
+        if let Some(probe) = svc_load.readiness_probe {
+            self.readiness_probe = Some(HealthCheckProbe::from_proto(probe)?);
+        }
+        if let Some(threshold) = svc_load.liveness_threshold {
+            self.liveness_threshold = threshold;
+        }
+        if let Some(rollback_window) = svc_load.rollback_window {
+            self.rollback_window = rollback_window;
+        }
+        if let Some(batch_size) = svc_load.update_batch_size {
+            self.update_batch_size = UpdateBatchSize::from_str(&batch_size)?;
+        }
+        if let Some(soak) = svc_load.update_canary_soak {
+            self.update_canary_soak = soak;
+        }
+        if let Some(zone) = svc_load.election_preferred_zone {
+            self.election_preferred_zone = Some(zone);
+        }
+        if let Some(min_zones) = svc_load.election_min_zones {
+            self.election_min_zones = min_zones;
+        }
         Ok(self)
     }
 }
@@ -321,7 +425,8 @@ mod test {
                                  HealthCheckInterval}};
 
     use super::*;
-    use crate::error::Error::*;
+    use crate::{error::Error::*,
+                manager::service::RestartCondition};
 
     fn file_from_str<P: AsRef<Path>>(path: P, content: &str) {
         fs::create_dir_all(
@@ -379,6 +484,69 @@ mod test {
                    HealthCheckInterval::from_str("5").unwrap());
     }
 
+    #[test]
+    fn service_spec_from_str_restart_policy() {
+        let toml = r#"
+            ident = "origin/name/1.2.3/20170223130020"
+
+            [restart_policy]
+            condition = "never"
+            backoff = 5
+            "#;
+        let spec = ServiceSpec::from_str(toml).unwrap();
+
+        assert_eq!(spec.restart_policy.condition, RestartCondition::Never);
+        assert_eq!(spec.restart_policy.backoff, 5);
+        assert_eq!(spec.restart_policy.max_retries,
+                   RestartPolicy::default().max_retries);
+    }
+
+    #[test]
+    fn service_spec_from_str_blocked_releases() {
+        let toml = r#"
+            ident = "origin/name"
+            rollback_window = 120
+            blocked_releases = ["origin/name/1.2.4/20170301120000"]
+            rolled_back_to = "origin/name/1.2.2/20170201120000"
+            "#;
+        let spec = ServiceSpec::from_str(toml).unwrap();
+
+        assert_eq!(spec.rollback_window, 120);
+        assert_eq!(spec.blocked_releases,
+                   vec![PackageIdent::from_str("origin/name/1.2.4/20170301120000").unwrap()]);
+        assert_eq!(spec.rolled_back_to,
+                   Some(PackageIdent::from_str("origin/name/1.2.2/20170201120000").unwrap()));
+    }
+
+    #[test]
+    fn service_spec_from_str_batched_update() {
+        let toml = r#"
+            ident = "origin/name"
+            update_strategy = "batched"
+            update_batch_size = "3"
+            update_canary_soak = 300
+            "#;
+        let spec = ServiceSpec::from_str(toml).unwrap();
+
+        assert_eq!(spec.update_strategy, UpdateStrategy::Batched);
+        assert_eq!(spec.update_batch_size, UpdateBatchSize::Count(3));
+        assert_eq!(spec.update_canary_soak, 300);
+    }
+
+    #[test]
+    fn service_spec_from_str_election_policy() {
+        let toml = r#"
+            ident = "origin/name"
+            topology = "leader"
+            election_preferred_zone = "us-east-1a"
+            election_min_zones = 2
+            "#;
+        let spec = ServiceSpec::from_str(toml).unwrap();
+
+        assert_eq!(spec.election_preferred_zone, Some(String::from("us-east-1a")));
+        assert_eq!(spec.election_min_zones, 2);
+    }
+
     #[test]
     fn service_spec_from_str_missing_ident() {
         let toml = r#""#;
@@ -433,6 +601,8 @@ mod test {
 
     #[test]
     fn service_spec_to_toml_string() {
+        let blocked = PackageIdent::from_str("origin/name/1.2.4/20170301120000").unwrap();
+        let rolled_back_to = PackageIdent::from_str("origin/name/1.2.2/20170201120000").unwrap();
         let spec =
             ServiceSpec { ident:
                               PackageIdent::from_str("origin/name/1.2.3/20170223130020").unwrap(),
@@ -448,10 +618,28 @@ mod test {
                 ServiceBind::from_str("db:postgres.app@acmecorp").unwrap(),
             ],
                           binding_mode:            BindingMode::Relaxed,
+                          restart_policy:          RestartPolicy { condition:
+                                                                       RestartCondition::OnFailure,
+                                                                   max_retries: 3,
+                                                                   ..Default::default() },
+                          health_check_probe:
+                              Some(HealthCheckProbe::Tcp { address: String::from("localhost:6379"),
+                                                           timeout: 2, }),
+                          readiness_probe:
+                              Some(HealthCheckProbe::Tcp { address: String::from("localhost:6380"),
+                                                           timeout: 1, }),
                           health_check_interval:   HealthCheckInterval::from_str("123").unwrap(),
                           config_from:             Some(PathBuf::from("/only/for/development")),
                           desired_state:           DesiredState::Down,
                           svc_encrypted_password:  None,
+                          liveness_threshold:      3,
+                          rollback_window:         300,
+                          blocked_releases:        vec![blocked],
+                          rolled_back_to:          Some(rolled_back_to),
+                          update_batch_size:       UpdateBatchSize::Percent(25),
+                          update_canary_soak:      600,
+                          election_preferred_zone: Some(String::from("us-east-1a")),
+                          election_min_zones:      2,
                           shutdown_timeout:        Some(ShutdownTimeout::from_str("10").unwrap()), };
         let toml = spec.to_toml_string().unwrap();
 
@@ -471,6 +659,22 @@ mod test {
         assert!(toml.contains(r#"secs = 123"#));
         assert!(toml.contains(r#"nanos = 0"#));
         assert!(toml.contains(r#"shutdown_timeout = 10"#));
+        assert!(toml.contains(r#"[restart_policy]"#));
+        assert!(toml.contains(r#"condition = "on-failure""#));
+        assert!(toml.contains(r#"max_retries = 3"#));
+        assert!(toml.contains(r#"[health_check_probe]"#));
+        assert!(toml.contains(r#"kind = "tcp""#));
+        assert!(toml.contains(r#"address = "localhost:6379""#));
+        assert!(toml.contains(r#"[readiness_probe]"#));
+        assert!(toml.contains(r#"address = "localhost:6380""#));
+        assert!(toml.contains(r#"liveness_threshold = 3"#));
+        assert!(toml.contains(r#"rollback_window = 300"#));
+        assert!(toml.contains(r#"blocked_releases = ["origin/name/1.2.4/20170301120000"]"#));
+        assert!(toml.contains(r#"rolled_back_to = "origin/name/1.2.2/20170201120000""#));
+        assert!(toml.contains(r#"update_batch_size = "25%""#));
+        assert!(toml.contains(r#"update_canary_soak = 600"#));
+        assert!(toml.contains(r#"election_preferred_zone = "us-east-1a""#));
+        assert!(toml.contains(r#"election_min_zones = 2"#));
     }
 
     #[test]
@@ -623,10 +827,21 @@ mod test {
                 ServiceBind::from_str("db:postgres.app@acmecorp").unwrap(),
             ],
                           binding_mode:            BindingMode::Relaxed,
+                          restart_policy:          RestartPolicy::default(),
+                          health_check_probe:      None,
+                          readiness_probe:         None,
                           health_check_interval:   HealthCheckInterval::from_str("23").unwrap(),
                           config_from:             Some(PathBuf::from("/only/for/development")),
                           desired_state:           DesiredState::Down,
                           svc_encrypted_password:  None,
+                          liveness_threshold:      0,
+                          rollback_window:         0,
+                          blocked_releases:        Vec::new(),
+                          rolled_back_to:          None,
+                          update_batch_size:       UpdateBatchSize::default(),
+                          update_canary_soak:      0,
+                          election_preferred_zone: None,
+                          election_min_zones:      0,
                           shutdown_timeout:        Some(ShutdownTimeout::default()), };
         spec.to_file(&path).unwrap();
         let toml = string_from_file(path);
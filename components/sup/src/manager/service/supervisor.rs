@@ -39,6 +39,9 @@ pub struct Supervisor {
     pub state:         ProcessState,
     pub state_entered: Timespec,
     pid:               Option<Pid>,
+    /// The pid of a process found to have exited, until its exit
+    /// status is collected.
+    exited_pid:        Option<Pid>,
     pid_file:          PathBuf,
 }
 
@@ -48,32 +51,47 @@ impl Supervisor {
                      state:         ProcessState::Down,
                      state_entered: time::get_time(),
                      pid:           None,
+                     exited_pid:    None,
                      pid_file:      fs::svc_pid_file(service_group.service()), }
     }
 
     /// Check if the child process is running
     pub fn check_process(&mut self) -> bool {
-        self.pid = self.pid
-                       .or_else(|| read_pid(&self.pid_file))
-                       .and_then(|pid| {
-                           if process::is_alive(pid) {
-                               Some(pid)
-                           } else {
-                               debug!("Could not find a live process with PID: {:?}", pid);
-                               None
-                           }
-                       });
+        let pid = self.pid.or_else(|| read_pid(&self.pid_file));
+        self.pid = pid.and_then(|pid| {
+                          if process::is_alive(pid) {
+                              Some(pid)
+                          } else {
+                              debug!("Could not find a live process with PID: {:?}", pid);
+                              None
+                          }
+                      });
+        if self.pid.is_none() && pid.is_some() {
+            self.exited_pid = pid;
+        }
 
         if self.pid.is_some() {
             self.change_state(ProcessState::Up);
         } else {
-            self.change_state(ProcessState::Down);
+            // A crash-looping process stays that way until it is
+            // started again.
+            if self.state != ProcessState::CrashLooping {
+                self.change_state(ProcessState::Down);
+            }
             self.cleanup_pidfile();
         }
 
         self.pid.is_some()
     }
 
+    /// The pid of the process which last exited, if it hasn't been
+    /// taken already.
+    pub fn take_exited_pid(&mut self) -> Option<Pid> { self.exited_pid.take() }
+
+    /// Record that the process has exited more often than its restart
+    /// policy allows, and will not be restarted.
+    pub fn crash_looping(&mut self) { self.change_state(ProcessState::CrashLooping); }
+
     // NOTE: the &self argument is only used to get access to
     // self.preamble, and even then only for Linux :/
     #[cfg(unix)]
@@ -179,7 +197,7 @@ impl Supervisor {
                              time::get_time() - self.state_entered);
         let healthy = match self.state {
             ProcessState::Up => true,
-            ProcessState::Down => false,
+            ProcessState::Down | ProcessState::CrashLooping => false,
         };
         (healthy, status)
     }
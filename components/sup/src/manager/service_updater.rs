@@ -1,4 +1,5 @@
-use crate::{census::CensusRing,
+use crate::{census::{CensusGroup,
+                     CensusRing},
             manager::{periodic::Periodic,
                       service::{Service,
                                 Topology,
@@ -46,6 +47,9 @@ enum RollingState {
 enum LeaderState {
     Polling(Receiver<PackageInstall>, Sender<()>),
     Waiting,
+    /// A follower rolled back the release we updated to, so the
+    /// rollout stops until we are running another release.
+    Halted(PackageIdent),
 }
 
 /// Current package update state of a follower in a leader-follower
@@ -53,10 +57,55 @@ enum LeaderState {
 enum FollowerState {
     /// Waiting to be told to update
     Waiting,
+    /// Waiting for the update leader, the canary of a `batched` update,
+    /// to stay healthy on `release` for the canary soak period
+    Soaking {
+        release: PackageIdent,
+        since:   Instant,
+    },
     /// Currently updating
     Updating(Receiver<PackageInstall>, Sender<()>),
 }
 
+/// The progress of a service's update rollout, as seen by this member.
+/// Served by the HTTP gateway.
+#[derive(Clone, Debug, Serialize)]
+pub struct RolloutStatus {
+    pub strategy: UpdateStrategy,
+    pub phase: RolloutPhase,
+    /// The release being rolled out: the update leader's, or this
+    /// member's own if there is no update leader.
+    pub release: String,
+    /// Alive members of the service group running `release`
+    pub updated: usize,
+    /// Alive members of the service group
+    pub members: usize,
+    /// How many members update at a time under the `batched` strategy
+    pub batch_size: Option<usize>,
+    pub canary_soak: u64,
+    /// Seconds left before the canary has soaked, if it is soaking
+    pub canary_remaining: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RolloutPhase {
+    /// The update leader is being elected
+    Electing,
+    /// Polling for a newer release
+    Polling,
+    /// The update leader is waiting for its followers to update
+    WaitingForFollowers,
+    /// The rollout stopped because a member rolled the release back
+    Halted,
+    /// A follower waiting for its turn to update
+    Waiting,
+    /// A follower waiting for the canary to soak
+    Canary,
+    /// A follower installing the release
+    Updating,
+}
+
 /// The ServiceUpdater is in charge of updating a Service when a more recent version of a package
 /// has been published to a depot or installed to the local package cache.
 /// To use an update strategy, the supervisor must be configured to watch a depot for new versions.
@@ -88,7 +137,7 @@ impl ServiceUpdater {
                     });
                 true
             }
-            UpdateStrategy::Rolling => {
+            UpdateStrategy::Rolling | UpdateStrategy::Batched => {
                 self.states
                     .entry(service.service_group.clone())
                     .or_insert(UpdaterState::Rolling(RollingState::AwaitingElection));
@@ -115,7 +164,7 @@ impl ServiceUpdater {
                                             dead.");
                                 }
                             }
-                            LeaderState::Waiting => {}
+                            LeaderState::Waiting | LeaderState::Halted(_) => {}
                         }
                     }
                     RollingState::Follower(fs) => {
@@ -126,7 +175,7 @@ impl ServiceUpdater {
                                             dead.");
                                 }
                             }
-                            FollowerState::Waiting => {}
+                            FollowerState::Waiting | FollowerState::Soaking { .. } => {}
                         }
                     }
                     _ => {}
@@ -246,6 +295,17 @@ impl ServiceUpdater {
                     LeaderState::Waiting => {
                         match census_ring.census_group_for(&service.service_group) {
                             Some(census_group) => {
+                                let release = &service.pkg.ident;
+                                if let Some(member) =
+                                    census_group.members()
+                                                .find(|cm| cm.blocked_releases.contains(release))
+                                {
+                                    outputln!("Rolling update to {} halted; {} rolled it back",
+                                              release,
+                                              member.member_id);
+                                    *state = LeaderState::Halted(release.clone());
+                                    return None;
+                                }
                                 if census_group.members()
                                                .any(|cm| cm.pkg != census_group.me().unwrap().pkg)
                                 {
@@ -264,6 +324,13 @@ impl ServiceUpdater {
                             }
                         }
                     }
+                    LeaderState::Halted(ref release) => {
+                        if service.pkg.ident == *release {
+                            return None;
+                        }
+                        debug!("Update leader no longer running the halted release");
+                        *state = LeaderState::Waiting;
+                    }
                 }
                 if ident.is_some() {
                     *state = LeaderState::Waiting;
@@ -283,7 +350,36 @@ impl ServiceUpdater {
                                             debug!("We're not in an update");
                                             return None;
                                         }
-                                        if leader.pkg != peer.pkg {
+                                        if leader.pkg
+                                                 .as_ref()
+                                                 .map_or(false, |pkg| {
+                                                     service.blocked_releases.contains(pkg)
+                                                 })
+                                        {
+                                            debug!("We're in an update to a release we rolled \
+                                                    back");
+                                            return None;
+                                        }
+                                        if service.update_strategy == UpdateStrategy::Batched {
+                                            let release = match leader.pkg {
+                                                Some(ref release) => release.clone(),
+                                                None => return None,
+                                            };
+                                            if service.update_canary_soak > 0 {
+                                                debug!("We're in an update; soaking the canary");
+                                                let since = Instant::now();
+                                                *state = FollowerState::Soaking { release, since };
+                                                return None;
+                                            }
+                                            let batch_size = service.update_batch_size;
+                                            if !census_group.earlier_batches_updated(&release,
+                                                                                     batch_size)
+                                            {
+                                                debug!("We're in an update but it's not our \
+                                                        batch's turn");
+                                                return None;
+                                            }
+                                        } else if leader.pkg != peer.pkg {
                                             debug!("We're in an update but it's not our turn");
                                             return None;
                                         }
@@ -303,6 +399,47 @@ impl ServiceUpdater {
                             }
                         }
                     }
+                    FollowerState::Soaking { ref release,
+                                             ref mut since, } => {
+                        let release = release.clone();
+                        let census_group =
+                            match census_ring.census_group_for(&service.service_group) {
+                                Some(census_group) => census_group,
+                                None => {
+                                    panic!("Expected census list to have service group '{}'!",
+                                           &*service.service_group)
+                                }
+                            };
+                        let leader = match census_group.update_leader() {
+                            Some(leader) => leader,
+                            None => return None,
+                        };
+                        if leader.pkg.as_ref() != Some(&release) {
+                            debug!("The canary is no longer running {}", release);
+                            *state = FollowerState::Waiting;
+                            return None;
+                        }
+                        if !leader.healthy() {
+                            debug!("The canary is not healthy; restarting its soak");
+                            *since = Instant::now();
+                            return None;
+                        }
+                        if since.elapsed() < Duration::from_secs(service.update_canary_soak) {
+                            return None;
+                        }
+                        if !census_group.earlier_batches_updated(&release,
+                                                                 service.update_batch_size)
+                        {
+                            debug!("The canary has soaked but it's not our batch's turn");
+                            return None;
+                        }
+                        debug!("The canary has soaked and it's our batch's turn");
+                        let (kill_tx, kill_rx) = channel();
+                        let rx = Worker::new(service).start(&service.service_group,
+                                                            Some(release),
+                                                            kill_rx);
+                        *state = FollowerState::Updating(rx, kill_tx);
+                    }
                     FollowerState::Updating(ref mut rx, ref mut kill_tx) => {
                         match census_ring.census_group_for(&service.service_group) {
                             Some(census_group) => {
@@ -338,6 +475,64 @@ impl ServiceUpdater {
         }
         ident
     }
+
+    /// The progress of the given service's update rollout, or `None`
+    /// if the service is not updated by the `ServiceUpdater`.
+    pub fn rollout_status(&self,
+                          service: &Service,
+                          census_ring: &CensusRing)
+                          -> Option<RolloutStatus> {
+        let mut canary_remaining = None;
+        let phase = match self.states.get(&service.service_group)? {
+            UpdaterState::AtOnce(..) => RolloutPhase::Polling,
+            UpdaterState::Rolling(RollingState::AwaitingElection)
+            | UpdaterState::Rolling(RollingState::InElection) => RolloutPhase::Electing,
+            UpdaterState::Rolling(RollingState::Leader(state)) => {
+                match state {
+                    LeaderState::Polling(..) => RolloutPhase::Polling,
+                    LeaderState::Waiting => RolloutPhase::WaitingForFollowers,
+                    LeaderState::Halted(_) => RolloutPhase::Halted,
+                }
+            }
+            UpdaterState::Rolling(RollingState::Follower(state)) => {
+                match state {
+                    FollowerState::Waiting => RolloutPhase::Waiting,
+                    FollowerState::Soaking { since, .. } => {
+                        let soak = Duration::from_secs(service.update_canary_soak);
+                        canary_remaining = Some(soak.checked_sub(since.elapsed())
+                                                    .unwrap_or_default()
+                                                    .as_secs());
+                        RolloutPhase::Canary
+                    }
+                    FollowerState::Updating(..) => RolloutPhase::Updating,
+                }
+            }
+        };
+
+        let census_group = census_ring.census_group_for(&service.service_group);
+        let release = census_group.and_then(CensusGroup::update_leader)
+                                  .and_then(|leader| leader.pkg.clone())
+                                  .unwrap_or_else(|| service.pkg.ident.clone());
+        let alive_members: Vec<_> =
+            census_group.map(|cg| cg.members().filter(|cm| cm.alive()).collect())
+                        .unwrap_or_default();
+        let updated = alive_members.iter()
+                                   .filter(|cm| cm.pkg.as_ref() == Some(&release))
+                                   .count();
+        let batch_size = if service.update_strategy == UpdateStrategy::Batched {
+            Some(service.update_batch_size.of(alive_members.len()))
+        } else {
+            None
+        };
+        Some(RolloutStatus { strategy: service.update_strategy,
+                             phase,
+                             release: release.to_string(),
+                             updated,
+                             members: alive_members.len(),
+                             batch_size,
+                             canary_soak: service.update_canary_soak,
+                             canary_remaining })
+    }
 }
 
 habitat_core::env_config_duration!(
@@ -358,6 +553,7 @@ struct Worker {
     spec_ident:  PackageIdent,
     builder_url: String,
     channel:     ChannelIdent,
+    blocked:     Vec<PackageIdent>,
 }
 
 impl Periodic for Worker {
@@ -379,7 +575,8 @@ impl Worker {
         Worker { current:     service.pkg.ident.clone(),
                  spec_ident:  service.spec_ident.clone(),
                  builder_url: service.bldr_url.clone(),
-                 channel:     service.channel.clone(), }
+                 channel:     service.channel.clone(),
+                 blocked:     service.blocked_releases.clone(), }
     }
 
     /// Start a new update worker.
@@ -509,7 +706,10 @@ impl Worker {
                                          &self.channel)
                 {
                     Ok(maybe_newer_package) => {
-                        if self.current < *maybe_newer_package.ident() {
+                        if self.blocked.contains(maybe_newer_package.ident()) {
+                            debug!("Package found {} was rolled back; not updating to it",
+                                   maybe_newer_package.ident());
+                        } else if self.current < *maybe_newer_package.ident() {
                             outputln!("Updating from {} to {}",
                                       self.current,
                                       maybe_newer_package.ident());
@@ -552,7 +752,8 @@ mod tests {
                                                                  .expect("Can't parse ident!"),
                  spec_ident:  "core/testing".parse().expect("Can't parse ident!"),
                  builder_url: String::from("https://bldr.habitat.sh"),
-                 channel:     ChannelIdent::stable(), }
+                 channel:     ChannelIdent::stable(),
+                 blocked:     Vec::new(), }
     }
 
     #[test]
@@ -0,0 +1,92 @@
+//! The Supervisor config file.
+//!
+//! Settings that would otherwise have to be passed to `hab sup run` every time can be kept in
+//! `/hab/sup/default/config/sup.toml`. Each key is named for its flag, so `--gossip-ping-ms 500`
+//! is `gossip_ping_ms = 500`. Flags given on the command line win over the file, and a missing
+//! file is the same as an empty one.
+
+use crate::error::{Error,
+                   Result};
+use std::{fs,
+          io,
+          path::{Path,
+                 PathBuf}};
+use toml;
+
+/// The name of the Supervisor config file, in the `config` directory of the Supervisor root.
+pub const SUP_CONFIG_FILE: &str = "sup.toml";
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SupConfigFile {
+    pub rumor_ttl:                   Option<i64>,
+    pub gossip_ping_ms:              Option<i64>,
+    pub gossip_pingreq_ms:           Option<i64>,
+    pub gossip_pingreq_targets:      Option<usize>,
+    pub gossip_suspicion_periods:    Option<i64>,
+    pub gossip_period_ms:            Option<i64>,
+    pub gossip_fanout:               Option<usize>,
+    pub gossip_departure_timeout_ms: Option<i64>,
+}
+
+impl SupConfigFile {
+    /// The path of the config file of the default Supervisor.
+    pub fn path() -> PathBuf {
+        habitat_sup_protocol::sup_root(None).join("config")
+                                            .join(SUP_CONFIG_FILE)
+    }
+
+    /// Read the config file at `path`. If there is no file, every setting is left unset.
+    ///
+    /// # Errors
+    ///
+    /// * Returns `Error::SupConfigFileIO` if the file exists but can't be read
+    /// * Returns `Error::SupConfigFileParse` if the file isn't valid, or has keys we don't know
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(contents) => {
+                toml::from_str(&contents).map_err(|err| {
+                                             Error::SupConfigFileParse(path.to_path_buf(), err)
+                                         })
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(Error::SupConfigFileIO(path.to_path_buf(), err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn missing_file_sets_nothing() {
+        let dir = TempDir::new().expect("Could not create tempdir");
+        let config = SupConfigFile::load(dir.path().join(SUP_CONFIG_FILE)).unwrap();
+        assert_eq!(config, SupConfigFile::default());
+    }
+
+    #[test]
+    fn load_reads_the_settings() {
+        let dir = TempDir::new().expect("Could not create tempdir");
+        let path = dir.path().join(SUP_CONFIG_FILE);
+        fs::write(&path, "gossip_ping_ms = 500\ngossip_fanout = 3\n").unwrap();
+        let config = SupConfigFile::load(&path).unwrap();
+        assert_eq!(config.gossip_ping_ms, Some(500));
+        assert_eq!(config.gossip_fanout, Some(3));
+        assert_eq!(config.gossip_pingreq_ms, None);
+    }
+
+    #[test]
+    fn load_rejects_unknown_keys() {
+        let dir = TempDir::new().expect("Could not create tempdir");
+        let path = dir.path().join(SUP_CONFIG_FILE);
+        fs::write(&path, "gossip_ping = 500\n").unwrap();
+        match SupConfigFile::load(&path) {
+            Err(Error::SupConfigFileParse(p, _)) => assert_eq!(p, path),
+            other => panic!("Expected a parse error, got {:?}", other),
+        }
+    }
+}